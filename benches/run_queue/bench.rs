@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+use std::mem::replace;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+criterion_main!(run_queues);
+criterion_group!(run_queues, run_processes);
+
+/// Number of processes in the run queue(s).
+const PROCESSES: usize = 1024;
+/// Number of threads to run the benchmarks with.
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+pub fn run_processes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Running processes");
+    for threads in THREADS {
+        group.bench_with_input(
+            BenchmarkId::new("Single", threads),
+            &threads,
+            |b, &threads| b.iter_custom(|iters| run(&Single::new(), threads, iters)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Stealing", threads),
+            &threads,
+            |b, &threads| b.iter_custom(|iters| run(&Stealing::new(threads), threads, iters)),
+        );
+    }
+    group.finish();
+}
+
+/// Run `iters` processes on each of the `threads`, returns the time it took
+/// the slowest thread.
+fn run<S: Scheduler>(scheduler: &S, threads: usize, iters: u64) -> Duration {
+    for id in 0..PROCESSES {
+        scheduler.add(0, Process::new(id));
+    }
+
+    let barrier = Barrier::new(threads);
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|worker| {
+                let barrier = &barrier;
+                s.spawn(move || {
+                    barrier.wait();
+                    let start = Instant::now();
+                    for _ in 0..iters {
+                        // There are more processes than threads, but another
+                        // thread can move all processes to a run queue we
+                        // already checked, so retry until we get one.
+                        let mut process = loop {
+                            if let Some(process) = scheduler.remove(worker) {
+                                break process;
+                            }
+                        };
+                        process.run();
+                        scheduler.add(worker, process);
+                    }
+                    start.elapsed()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .max()
+            .unwrap()
+    })
+}
+
+/// Scheduler using one or more run queues.
+trait Scheduler: Sync {
+    /// Add `process` to the scheduler, from `worker`.
+    fn add(&self, worker: usize, process: Process);
+
+    /// Remove the next process to run for `worker`.
+    fn remove(&self, worker: usize) -> Option<Process>;
+}
+
+/// A single run queue shared by all workers.
+struct Single {
+    run_queue: RunQueue,
+}
+
+impl Single {
+    fn new() -> Single {
+        Single {
+            run_queue: RunQueue::empty(),
+        }
+    }
+}
+
+impl Scheduler for Single {
+    fn add(&self, _: usize, process: Process) {
+        self.run_queue.add(process)
+    }
+
+    fn remove(&self, _: usize) -> Option<Process> {
+        self.run_queue.remove()
+    }
+}
+
+/// Copy of `FAIRNESS_CHECK_INTERVAL` from the shared scheduler in `heph-rt`.
+const FAIRNESS_CHECK_INTERVAL: usize = 61;
+
+/// A run queue per worker, stealing from the other workers if a worker's run
+/// queue is empty. Every `FAIRNESS_CHECK_INTERVAL` processes a worker runs the
+/// process that should run first from all run queues. Processes are added to
+/// the run queues in a round-robin fashion.
+struct Stealing {
+    run_queues: Box<[RunQueue]>,
+    dispatches: Box<[AtomicUsize]>,
+    next_queue: AtomicUsize,
+}
+
+impl Stealing {
+    fn new(workers: usize) -> Stealing {
+        Stealing {
+            run_queues: (0..workers).map(|_| RunQueue::empty()).collect(),
+            dispatches: (0..workers).map(|_| AtomicUsize::new(0)).collect(),
+            next_queue: AtomicUsize::new(0),
+        }
+    }
+
+    fn steal_first(&self, worker: usize) -> Option<Process> {
+        let n = self.run_queues.len();
+        let mut best = self.run_queues[worker].peek().map(|key| (worker, key));
+        for i in 1..n {
+            let index = (worker + i) % n;
+            if let Some(key) = self.run_queues[index].peek() {
+                if best.is_none_or(|(_, best_key)| key < best_key) {
+                    best = Some((index, key));
+                }
+            }
+        }
+        match best {
+            Some((index, _)) if index != worker => self.run_queues[index].remove(),
+            _ => None,
+        }
+    }
+}
+
+impl Scheduler for Stealing {
+    fn add(&self, _: usize, process: Process) {
+        let n = self.next_queue.fetch_add(1, atomic::Ordering::Relaxed);
+        self.run_queues[n % self.run_queues.len()].add(process)
+    }
+
+    fn remove(&self, worker: usize) -> Option<Process> {
+        let n = self.run_queues.len();
+        let dispatches = self.dispatches[worker].fetch_add(1, atomic::Ordering::Relaxed);
+        if n > 1 && dispatches % FAIRNESS_CHECK_INTERVAL == FAIRNESS_CHECK_INTERVAL - 1 {
+            if let Some(process) = self.steal_first(worker) {
+                return Some(process);
+            }
+        }
+        for i in 0..n {
+            if let Some(process) = self.run_queues[(worker + i) % n].remove() {
+                return Some(process);
+            }
+        }
+        None
+    }
+}
+
+/// Simplified version of a process, ordered by fair runtime like the real
+/// `ProcessData`.
+#[derive(Debug, Eq, PartialEq)]
+struct Process {
+    id: usize,
+    fair_runtime: Duration,
+}
+
+impl Process {
+    const fn new(id: usize) -> Process {
+        Process {
+            id,
+            fair_runtime: Duration::ZERO,
+        }
+    }
+
+    fn run(&mut self) {
+        self.fair_runtime += Duration::from_nanos((self.id % 100) as u64);
+    }
+}
+
+impl Ord for Process {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.fair_runtime).cmp(&(self.fair_runtime))
+    }
+}
+
+impl PartialOrd for Process {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Copy of the `RunQueue` from the shared scheduler in `heph-rt`.
+struct RunQueue {
+    root: Mutex<Branch>,
+}
+
+type Branch = Option<Box<Node>>;
+
+struct Node {
+    process: Process,
+    left: Branch,
+    right: Branch,
+}
+
+impl RunQueue {
+    fn empty() -> RunQueue {
+        RunQueue {
+            root: Mutex::new(None),
+        }
+    }
+
+    fn add(&self, process: Process) {
+        let mut next_node = &mut *self.root.lock().unwrap();
+        loop {
+            match next_node {
+                Some(node) => {
+                    if node.process < process {
+                        next_node = &mut node.left
+                    } else {
+                        next_node = &mut node.right
+                    }
+                }
+                None => {
+                    *next_node = Some(Box::new(Node {
+                        process,
+                        left: None,
+                        right: None,
+                    }));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the fair runtime of the next process to run.
+    fn peek(&self) -> Option<Duration> {
+        let mut next_node = &*self.root.lock().unwrap();
+        loop {
+            match next_node {
+                Some(node) if node.left.is_none() => return Some(node.process.fair_runtime),
+                Some(node) => next_node = &node.left,
+                None => return None,
+            }
+        }
+    }
+
+    fn remove(&self) -> Option<Process> {
+        let mut next_node = &mut *self.root.lock().unwrap();
+        loop {
+            match next_node {
+                Some(node) if node.left.is_none() => {
+                    let right_node = node.right.take();
+                    return replace(next_node, right_node).map(|node| node.process);
+                }
+                Some(node) => next_node = &mut node.left,
+                None => return None,
+            }
+        }
+    }
+}
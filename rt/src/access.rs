@@ -18,7 +18,9 @@ use heph::actor_ref::ActorRef;
 use heph::supervisor::Supervisor;
 use mio::{event, Interest};
 
+use crate::blocking::Blocking;
 use crate::process::ProcessId;
+use crate::registry::{LookupError, RegisterError, Registered};
 use crate::spawn::{ActorOptions, AddActorError, FutureOptions, PrivateSpawn, Spawn};
 use crate::trace::{self, Trace};
 use crate::{shared, RuntimeRef};
@@ -66,6 +68,9 @@ mod private {
         where
             S: event::Source + ?Sized;
 
+        /// Returns the current time, used for deadlines.
+        fn now(&self) -> Instant;
+
         /// Add a deadline.
         fn add_deadline(&mut self, deadline: Instant);
 
@@ -81,6 +86,13 @@ mod private {
         /// Returns the CPU the thread is bound to, if any.
         fn cpu(&self) -> Option<usize>;
 
+        /// Returns `true` if the runtime is shutting down, see
+        /// [`RuntimeRef::shutdown`].
+        fn is_shutting_down(&self) -> bool;
+
+        /// Wake `waker` once the runtime starts shutting down.
+        fn wake_on_shutdown(&mut self, waker: task::Waker);
+
         /// Start timing an event if tracing is enabled, see [`trace::start`].
         fn start_trace(&self) -> Option<trace::EventTiming>;
 
@@ -152,14 +164,18 @@ impl PrivateAccess for ThreadLocal {
     where
         S: event::Source + ?Sized,
     {
-        self.rt.register(source, self.pid.into(), interest)
+        self.rt.register_source(source, self.pid.into(), interest)
     }
 
     fn reregister<S>(&mut self, source: &mut S, interest: Interest) -> io::Result<()>
     where
         S: event::Source + ?Sized,
     {
-        self.rt.reregister(source, self.pid.into(), interest)
+        self.rt.reregister_source(source, self.pid.into(), interest)
+    }
+
+    fn now(&self) -> Instant {
+        self.rt.now()
     }
 
     fn add_deadline(&mut self, deadline: Instant) {
@@ -182,6 +198,14 @@ impl PrivateAccess for ThreadLocal {
         self.rt.cpu()
     }
 
+    fn is_shutting_down(&self) -> bool {
+        self.rt.internals.shared.is_shutting_down()
+    }
+
+    fn wake_on_shutdown(&mut self, waker: task::Waker) {
+        self.rt.internals.shared.wake_on_shutdown(waker)
+    }
+
     fn start_trace(&self) -> Option<trace::EventTiming> {
         self.rt.start_trace()
     }
@@ -231,6 +255,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
 }
@@ -240,6 +265,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
     fn try_spawn_setup<ArgFn, E>(
@@ -294,6 +320,45 @@ impl ThreadSafe {
     {
         self.rt.spawn_future(future, options)
     }
+
+    /// Run the blocking function `f` on a separate thread pool.
+    ///
+    /// See [`RuntimeRef::spawn_blocking`] for more documentation.
+    pub fn spawn_blocking<F, T>(&mut self, f: F) -> Blocking<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.rt.spawn_blocking(f)
+    }
+
+    /// Register `actor_ref` under `name`.
+    ///
+    /// See [`RuntimeRef::register`] for more documentation.
+    pub fn register<M>(&mut self, name: &str, actor_ref: ActorRef<M>) -> Result<(), RegisterError>
+    where
+        M: Send + 'static,
+    {
+        self.rt.actor_registry().register(name, actor_ref)
+    }
+
+    /// Lookup the actor registered under `name`.
+    ///
+    /// See [`RuntimeRef::lookup`] for more documentation.
+    pub fn lookup<M>(&self, name: &str) -> Result<ActorRef<M>, LookupError>
+    where
+        M: Send + 'static,
+    {
+        self.rt.actor_registry().lookup(name)
+    }
+
+    /// Receive a [`Registered`] message when an actor is (re)registered under
+    /// `name`.
+    ///
+    /// See [`RuntimeRef::watch_registrations`] for more documentation.
+    pub fn watch_registrations(&mut self, name: &str, actor_ref: ActorRef<Registered>) {
+        self.rt.actor_registry().watch(name, actor_ref)
+    }
 }
 
 impl Access for ThreadSafe {}
@@ -321,6 +386,10 @@ impl PrivateAccess for ThreadSafe {
         self.rt.reregister(source, self.pid.into(), interest)
     }
 
+    fn now(&self) -> Instant {
+        self.rt.now()
+    }
+
     fn add_deadline(&mut self, deadline: Instant) {
         self.rt.add_deadline(self.pid, deadline)
     }
@@ -341,6 +410,14 @@ impl PrivateAccess for ThreadSafe {
         None
     }
 
+    fn is_shutting_down(&self) -> bool {
+        self.rt.is_shutting_down()
+    }
+
+    fn wake_on_shutdown(&mut self, waker: task::Waker) {
+        self.rt.wake_on_shutdown(waker)
+    }
+
     fn start_trace(&self) -> Option<trace::EventTiming> {
         self.rt.start_trace()
     }
@@ -361,6 +438,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
 }
@@ -370,6 +448,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
     fn try_spawn_setup<ArgFn, E>(
@@ -443,6 +522,34 @@ impl Sync {
     {
         self.rt.spawn_future(future, options)
     }
+
+    /// Register `actor_ref` under `name`.
+    ///
+    /// See [`RuntimeRef::register`] for more documentation.
+    pub fn register<M>(&mut self, name: &str, actor_ref: ActorRef<M>) -> Result<(), RegisterError>
+    where
+        M: Send + 'static,
+    {
+        self.rt.actor_registry().register(name, actor_ref)
+    }
+
+    /// Lookup the actor registered under `name`.
+    ///
+    /// See [`RuntimeRef::lookup`] for more documentation.
+    pub fn lookup<M>(&self, name: &str) -> Result<ActorRef<M>, LookupError>
+    where
+        M: Send + 'static,
+    {
+        self.rt.actor_registry().lookup(name)
+    }
+
+    /// Receive a [`Registered`] message when an actor is (re)registered under
+    /// `name`.
+    ///
+    /// See [`RuntimeRef::watch_registrations`] for more documentation.
+    pub fn watch_registrations(&mut self, name: &str, actor_ref: ActorRef<Registered>) {
+        self.rt.actor_registry().watch(name, actor_ref)
+    }
 }
 
 impl<S, NA> Spawn<S, NA, ThreadSafe> for Sync
@@ -450,6 +557,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
 }
@@ -459,6 +567,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
     fn try_spawn_setup<ArgFn, E>(
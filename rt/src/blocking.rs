@@ -0,0 +1,309 @@
+//! Module to run blocking functions, see [`RuntimeRef::spawn_blocking`].
+//!
+//! Actors and futures should never block, as that blocks the entire worker
+//! thread, preventing any other actor or future from running. However some
+//! APIs, e.g. file I/O, compression or (legacy) libraries, are only available
+//! as blocking functions. To run those functions they can be spawned on a
+//! separate thread pool using `spawn_blocking`, which returns a [`Blocking`]
+//! future that resolves once the function has run.
+//!
+//! The thread pool starts threads as needed, up to a maximum number of threads
+//! set using [`Setup::blocking_threads`]. If all threads are busy the functions
+//! are queued until a thread is available. Threads that have been idle for a
+//! while are stopped.
+//!
+//! The number of queued functions and the number of threads are logged as part
+//! of the runtime metrics, see [`Signal::User2`].
+//!
+//! [`RuntimeRef::spawn_blocking`]: crate::RuntimeRef::spawn_blocking
+//! [`Setup::blocking_threads`]: crate::Setup::blocking_threads
+//! [`Signal::User2`]: crate::Signal::User2
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use heph::actor;
+//! use heph_rt::ThreadLocal;
+//!
+//! async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+//!     // Reading a file blocks the thread, so we run it on the blocking thread
+//!     // pool instead.
+//!     let result = ctx
+//!         .runtime()
+//!         .spawn_blocking(|| std::fs::read_to_string("Cargo.toml"))
+//!         .await;
+//!     match result {
+//!         Ok(Ok(contents)) => println!("read {} bytes", contents.len()),
+//!         Ok(Err(err)) => eprintln!("error reading file: {err}"),
+//!         Err(err) => eprintln!("error running blocking function: {err}"),
+//!     }
+//! }
+//! # _ = actor; // Silence dead code warnings.
+//! ```
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{self, Poll};
+use std::time::Duration;
+use std::{fmt, io, thread};
+
+use heph_inbox::oneshot::{new_oneshot, RecvOnce};
+use log::{error, trace};
+
+use crate::process::panic_message;
+
+/// Default maximum number of threads in the pool, see
+/// [`Setup::blocking_threads`].
+///
+/// [`Setup::blocking_threads`]: crate::Setup::blocking_threads
+pub(crate) const DEFAULT_THREADS: usize = 16;
+
+/// Time after which an idle thread is stopped.
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+/// Function to run on the thread pool.
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Bounded pool of threads running blocking functions.
+#[derive(Debug)]
+pub(crate) struct Pool {
+    shared: Arc<Shared>,
+}
+
+/// Data shared between the [`Pool`] and its threads.
+#[derive(Debug)]
+struct Shared {
+    /// Maximum number of threads to start.
+    max_threads: usize,
+    state: Mutex<State>,
+    /// Used to wake idle threads when a job is added or the pool is stopped.
+    condvar: Condvar,
+}
+
+struct State {
+    /// Functions waiting to be run.
+    queue: VecDeque<Job>,
+    /// Number of running threads.
+    threads: usize,
+    /// Number of threads waiting for a job.
+    idle: usize,
+    /// Whether or not the pool is stopped, see [`Pool`]'s `Drop`
+    /// implementation.
+    stopped: bool,
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("queue", &self.queue.len())
+            .field("threads", &self.threads)
+            .field("idle", &self.idle)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+/// Metrics for [`Pool`].
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    /// Number of functions waiting to be run.
+    pub(crate) queued: usize,
+    /// Number of running threads.
+    pub(crate) threads: usize,
+    /// Number of idle threads.
+    pub(crate) idle: usize,
+}
+
+impl Pool {
+    /// Create a new pool, which starts at most `max_threads` threads.
+    pub(crate) fn new(max_threads: usize) -> Pool {
+        debug_assert!(max_threads != 0);
+        Pool {
+            shared: Arc::new(Shared {
+                max_threads,
+                state: Mutex::new(State {
+                    queue: VecDeque::new(),
+                    threads: 0,
+                    idle: 0,
+                    stopped: false,
+                }),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Run `f` on the pool, returning a future that resolves to its result.
+    pub(crate) fn spawn<F, T>(&self, f: F) -> Blocking<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = new_oneshot();
+        let job = Box::new(move || {
+            let result = catch_unwind(AssertUnwindSafe(f));
+            // If the receiver is dropped no one is interested in the result.
+            let _ = sender.try_send(result);
+        });
+
+        let mut state = self.shared.lock();
+        state.queue.push_back(job);
+        // Start a new thread if there are not enough idle threads to run all
+        // queued jobs.
+        if state.queue.len() > state.idle && state.threads < self.shared.max_threads {
+            match self.shared.clone().start_thread() {
+                Ok(()) => state.threads += 1,
+                // No thread to run the job, so it would be queued forever.
+                Err(err) if state.threads == 0 => {
+                    drop(state.queue.pop_back());
+                    return Blocking {
+                        result: receiver.recv_once(),
+                        spawn_error: Some(err),
+                    };
+                }
+                Err(err) => error!("failed to start blocking thread: {err}"),
+            }
+        }
+        drop(state);
+        self.shared.condvar.notify_one();
+
+        Blocking {
+            result: receiver.recv_once(),
+            spawn_error: None,
+        }
+    }
+
+    /// Returns metrics about the pool.
+    pub(crate) fn metrics(&self) -> Metrics {
+        let state = self.shared.lock();
+        Metrics {
+            queued: state.queue.len(),
+            threads: state.threads,
+            idle: state.idle,
+        }
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // Let the threads finish the queued jobs and then stop.
+        self.shared.lock().stopped = true;
+        self.shared.condvar.notify_all();
+    }
+}
+
+impl Shared {
+    /// Start a new thread running jobs.
+    fn start_thread(self: Arc<Self>) -> io::Result<()> {
+        thread::Builder::new()
+            .name("heph-blocking".to_owned())
+            .spawn(move || self.run())
+            .map(|_| ())
+    }
+
+    /// Run jobs until the pool is stopped or the thread was idle for
+    /// [`KEEP_ALIVE`].
+    fn run(&self) {
+        trace!("starting blocking thread");
+        let mut state = self.lock();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                job();
+                state = self.lock();
+                continue;
+            }
+
+            if state.stopped {
+                break;
+            }
+
+            state.idle += 1;
+            let (s, res) = match self.condvar.wait_timeout(state, KEEP_ALIVE) {
+                Ok(res) => res,
+                Err(err) => err.into_inner(),
+            };
+            state = s;
+            state.idle -= 1;
+            if res.timed_out() && state.queue.is_empty() {
+                break;
+            }
+        }
+        state.threads -= 1;
+        trace!("stopping blocking thread");
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            // Jobs are run without holding the lock, so the state is always
+            // valid and we can ignore the poisoning.
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+/// [`Future`] representing a function running on the blocking thread pool.
+///
+/// See [`RuntimeRef::spawn_blocking`].
+///
+/// [`RuntimeRef::spawn_blocking`]: crate::RuntimeRef::spawn_blocking
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Blocking<T> {
+    result: RecvOnce<thread::Result<T>>,
+    /// Error starting a thread to run the function, if any.
+    spawn_error: Option<io::Error>,
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if let Some(err) = self.spawn_error.take() {
+            return Poll::Ready(Err(Error::Spawn(err)));
+        }
+        match Pin::new(&mut self.result).poll(ctx) {
+            Poll::Ready(Some(Ok(value))) => Poll::Ready(Ok(value)),
+            Poll::Ready(Some(Err(panic))) => {
+                let msg = panic_message(&*panic).to_owned();
+                Poll::Ready(Err(Error::Panic(msg)))
+            }
+            Poll::Ready(None) => Poll::Ready(Err(Error::Stopped)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Error returned by [`Blocking`].
+#[derive(Debug)]
+pub enum Error {
+    /// The function panicked, contains the panic message.
+    Panic(String),
+    /// The thread pool stopped before the function was run.
+    Stopped,
+    /// No thread could be started to run the function.
+    Spawn(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Panic(msg) => write!(f, "blocking function panicked: {msg}"),
+            Error::Stopped => f.pad("blocking thread pool stopped"),
+            Error::Spawn(err) => write!(f, "failed to start blocking thread: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Panic(_) | Error::Stopped => None,
+            Error::Spawn(err) => Some(err),
+        }
+    }
+}
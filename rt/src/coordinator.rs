@@ -8,21 +8,34 @@
 //! Most of the time the coordinator is polling new events to handle, i.e.
 //! waiting for something to happen. Such an event can be one of the following:
 //! * An incoming process signal, which it relays to all registers actors and
-//!   (sync) worker threads.
+//!   (sync) worker threads. Signals send using [`RuntimeHandle::send_signal`]
+//!   are relayed in the same way.
 //! * A (sync) worker thread stopping because all actors have finished running,
 //!   the worker hit an error or the thread panicked.
+//! * The runtime shutting down, see [`RuntimeRef::shutdown`]. It relays a
+//!   terminate signal to all registered actors and stops waiting on the sync
+//!   worker threads once the deadline has passed.
+//!
+//! If a poll budget is set (see [`Setup::poll_budget`]) the coordinator also
+//! wakes up periodically to check the progress of the worker threads, logging
+//! a warning if a process is blocking a worker thread.
 //!
 //! [worker threads]: crate::worker
 //! [sync worker threads]: crate::sync_worker
+//! [`RuntimeHandle::send_signal`]: crate::RuntimeHandle::send_signal
+//! [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
+//! [`Setup::poll_budget`]: crate::Setup::poll_budget
 
+use std::cmp::min;
+use std::collections::BTreeMap;
 use std::env::consts::ARCH;
 use std::os::unix::process::parent_id;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{fmt, io, process};
 
-use heph::actor_ref::{ActorGroup, Delivery};
-use log::{as_debug, as_display, debug, error, info, trace};
+use heph::actor_ref::{ActorGroup, ActorRef, DeadLetter, Delivery};
+use log::{as_debug, as_display, debug, error, info, trace, warn};
 use mio::event::Event;
 use mio::{Events, Interest, Poll, Registry, Token};
 use mio_signals::{SignalSet, Signals};
@@ -38,6 +51,11 @@ use crate::{
 
 /// Token used to receive process signals.
 const SIGNAL: Token = Token(usize::MAX);
+/// Token used to wake the coordinator, when the runtime is shutting down or to
+/// relay signals send using [`RuntimeHandle::send_signal`].
+///
+/// [`RuntimeHandle::send_signal`]: crate::RuntimeHandle::send_signal
+const WAKER: Token = Token(usize::MAX - 1);
 
 /// Coordinator responsible for coordinating the Heph runtime.
 #[derive(Debug)]
@@ -49,6 +67,11 @@ pub(super) struct Coordinator {
     signals: Signals,
     /// Internals shared between the coordinator and all workers.
     internals: Arc<shared::RuntimeInternals>,
+    /// Whether or not to log the inbox metrics of all registered actors.
+    log_inbox_metrics: bool,
+    /// Poll budget used to check if workers are blocked, see
+    /// [`Coordinator::check_workers`].
+    poll_budget: Option<Duration>,
 
     // Data used in [`Metrics`].
     /// Start time, used to calculate [`Metrics`]'s uptime.
@@ -74,16 +97,28 @@ impl Coordinator {
         app_name: Box<str>,
         worker_wakers: Box<[&'static ThreadWaker]>,
         trace_log: Option<Arc<trace::SharedLog>>,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        log_inbox_metrics: bool,
+        blocking_threads: usize,
+        poll_budget: Option<Duration>,
     ) -> io::Result<Coordinator> {
         let poll = Poll::new()?;
         // NOTE: on Linux this MUST be created before starting the worker
         // threads.
         let signals = setup_signals(poll.registry())?;
+        let waker = mio::Waker::new(poll.registry(), WAKER)?;
 
         let setup = shared::RuntimeInternals::setup()?;
         let internals = Arc::new_cyclic(|shared_internals| {
             let waker_id = waker::init(shared_internals.clone());
-            setup.complete(waker_id, worker_wakers, trace_log)
+            setup.complete(
+                waker_id,
+                worker_wakers,
+                trace_log,
+                dead_letters,
+                Some(waker),
+                blocking_threads,
+            )
         });
 
         let (host_os, host_name) = host_info()?;
@@ -96,6 +131,8 @@ impl Coordinator {
             poll,
             signals,
             internals,
+            log_inbox_metrics,
+            poll_budget,
             start: Instant::now(),
         })
     }
@@ -114,17 +151,29 @@ impl Coordinator {
         mut self,
         mut workers: Vec<worker::Handle>,
         mut sync_workers: Vec<SyncWorker>,
+        dead_letters_actor: Option<usize>,
         mut signal_refs: ActorGroup<Signal>,
         mut trace_log: Option<trace::CoordinatorLog>,
     ) -> Result<(), rt::Error> {
         self.pre_run(&mut workers, &mut sync_workers, &mut trace_log)?;
 
         let mut events = Events::with_capacity(16);
+        // Whether or not the runtime is shutting down and the deadline for it,
+        // see `RuntimeRef::shutdown`. The deadline is set to `None` once it
+        // has passed.
+        let mut shutting_down = false;
+        let mut shutdown_deadline = None;
         loop {
             let timing = trace::start(&trace_log);
             // Process OS events.
+            let shutdown_timeout =
+                shutdown_deadline.map(|d: Instant| d.saturating_duration_since(Instant::now()));
+            let timeout = match (shutdown_timeout, self.poll_budget) {
+                (Some(shutdown_timeout), Some(budget)) => Some(min(shutdown_timeout, budget)),
+                (shutdown_timeout, budget) => shutdown_timeout.or(budget),
+            };
             self.poll
-                .poll(&mut events, None)
+                .poll(&mut events, timeout)
                 .map_err(|err| rt::Error::coordinator(Error::Polling(err)))?;
             trace::finish_rt(trace_log.as_mut(), timing, "Polling for OS events", &[]);
 
@@ -147,6 +196,25 @@ impl Coordinator {
                             self.log_metrics(&workers, &sync_workers, &signal_refs, &mut trace_log);
                         }
                     }
+                    WAKER => {
+                        if !shutting_down {
+                            if let Some(deadline) = self.internals.shutdown_deadline() {
+                                shutting_down = true;
+                                shutdown_deadline = Some(deadline);
+                                debug!("relaying terminate signal to actors for shutdown");
+                                signal_refs.remove_disconnected();
+                                let _ = signal_refs.try_send(Signal::Terminate, Delivery::ToAll);
+                            }
+                        }
+
+                        let mut log_metrics = false;
+                        for signal in self.internals.take_signals() {
+                            log_metrics |= relay_signal(signal, &mut workers, &mut signal_refs);
+                        }
+                        if log_metrics {
+                            self.log_metrics(&workers, &sync_workers, &signal_refs, &mut trace_log);
+                        }
+                    }
                     token if token.0 < SYNC_WORKER_ID_START => {
                         let timing = trace::start(&trace_log);
                         handle_worker_event(&mut workers, event)?;
@@ -172,8 +240,31 @@ impl Coordinator {
             }
             trace::finish_rt(trace_log.as_mut(), timing, "Handling OS events", &[]);
 
-            // Once all (sync) worker threads are done running we can return.
-            if workers.is_empty() && sync_workers.is_empty() {
+            self.check_workers(&workers, &mut trace_log);
+
+            if shutdown_deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                // Sync actors can't be stopped, so we stop waiting for them.
+                for sync_worker in sync_workers.drain(..) {
+                    warn!(
+                        sync_worker_id = sync_worker.id();
+                        "sync actor still running after shutdown deadline, not waiting for it",
+                    );
+                }
+                shutdown_deadline = None;
+            }
+
+            // Once all (sync) worker threads are done running we can return. We
+            // don't wait for the dead-letter actor as its inbox is only
+            // disconnected once all actors are dropped.
+            if workers.is_empty()
+                && sync_workers
+                    .iter()
+                    .all(|sync_worker| Some(sync_worker.id()) == dead_letters_actor)
+            {
+                let stopped = self.internals.take_stopped();
+                if !stopped.is_empty() {
+                    return Err(rt::Error::shutdown_deadline(stopped));
+                }
                 return Ok(());
             }
         }
@@ -224,6 +315,38 @@ impl Coordinator {
         Ok(())
     }
 
+    /// Check if any of the `workers` is blocked by a process running longer
+    /// than the poll budget, logging a warning if so.
+    fn check_workers(
+        &self,
+        workers: &[worker::Handle],
+        trace_log: &mut Option<trace::CoordinatorLog>,
+    ) {
+        let Some(budget) = self.poll_budget else {
+            return;
+        };
+        for worker in workers {
+            let timing = trace::start(&*trace_log);
+            if let Some(blocked) = worker.blocked(budget) {
+                warn!(
+                    worker_id = worker.id(), pid = blocked.pid.0, name = blocked.name,
+                    elapsed = as_debug!(blocked.elapsed), budget = as_debug!(budget);
+                    "worker thread blocked by process running longer than the poll budget",
+                );
+                trace::finish_rt(
+                    trace_log.as_mut(),
+                    timing,
+                    "Detected blocked worker thread",
+                    &[
+                        ("worker_id", &worker.id()),
+                        ("id", &blocked.pid.0),
+                        ("name", &blocked.name),
+                    ],
+                );
+            }
+        }
+    }
+
     /// Log metrics about the coordinator and runtime.
     fn log_metrics<'c, 'l>(
         &'c self,
@@ -252,6 +375,9 @@ impl Coordinator {
             shared_scheduler_inactive = shared_metrics.scheduler_inactive,
             shared_timers_total = shared_metrics.timers_total,
             shared_timers_next = as_debug!(shared_metrics.timers_next),
+            blocking_queue_depth = shared_metrics.blocking.queued,
+            blocking_threads = shared_metrics.blocking.threads,
+            blocking_threads_idle = shared_metrics.blocking.idle,
             process_signals = as_debug!(SIGNAL_SET),
             process_signal_receivers = signal_refs.len(),
             cpu_time = as_debug!(cpu_usage(libc::CLOCK_THREAD_CPUTIME_ID)),
@@ -260,10 +386,45 @@ impl Coordinator {
             trace_counter = trace_metrics.map_or(0, |m| m.counter);
             "coordinator metrics",
         );
+        if self.log_inbox_metrics {
+            for (name, metrics) in self.internals.actor_registry().inbox_metrics() {
+                info!(
+                    target: "metrics",
+                    actor_name = name,
+                    inbox_len = metrics.len,
+                    inbox_capacity = metrics.capacity,
+                    inbox_high_watermark = metrics.high_watermark;
+                    "actor inbox metrics",
+                );
+            }
+        }
+        let mut slow_polls = BTreeMap::new();
+        for worker in workers {
+            for (name, count) in worker.slow_polls() {
+                *slow_polls.entry(name).or_insert(0) += count;
+            }
+        }
+        for (name, count) in slow_polls {
+            info!(
+                target: "metrics",
+                actor_name = name,
+                slow_polls = count;
+                "actor slow poll metrics",
+            );
+        }
         trace::finish_rt(trace_log.as_mut(), timing, "Printing runtime metrics", &[]);
     }
 }
 
+impl Drop for Coordinator {
+    fn drop(&mut self) {
+        // Once the coordinator is done, either because the runtime stopped or
+        // because it was never started, no processes will run anymore. Ensure
+        // `RuntimeHandle`s no longer accept new processes.
+        self.internals.mark_stopped();
+    }
+}
+
 /// Set of signals we're listening for.
 const SIGNAL_SET: SignalSet = SignalSet::all();
 
@@ -319,31 +480,7 @@ fn relay_signals(
     loop {
         match signals.receive() {
             Ok(Some(signal)) => {
-                let signal = Signal::from_mio(signal);
-                if let Signal::User2 = signal {
-                    log_metrics = true;
-                }
-
-                debug!(signal = as_debug!(signal); "relaying process signal to worker threads");
-                for worker in workers.iter_mut() {
-                    if let Err(err) = worker.send_signal(signal) {
-                        // NOTE: if the worker is unable to receive a message
-                        // it's likely already shutdown or is shutting down.
-                        // Rather than returning the error here and stopping the
-                        // coordinator (which was the case previously) we log
-                        // the error and instead wait until the worker thread
-                        // stopped returning that error instead, which is likely
-                        // more useful (i.e. it has the reason why the worker
-                        // thread stopped).
-                        error!(
-                            signal = as_debug!(signal), worker_id = worker.id();
-                            "failed to send process signal to worker: {err}",
-                        );
-                    }
-                }
-
-                debug!(signal = as_debug!(signal); "relaying process signal to actors");
-                let _ = signal_refs.try_send(signal, Delivery::ToAll);
+                log_metrics |= relay_signal(Signal::from_mio(signal), workers, signal_refs);
             }
             Ok(None) => break,
             Err(err) => {
@@ -355,6 +492,34 @@ fn relay_signals(
     log_metrics
 }
 
+/// Relay `signal` to the `workers` and `signal_refs`.
+/// Returns `true` if the signal is `SIGUSR2`, see [`relay_signals`].
+fn relay_signal(
+    signal: Signal,
+    workers: &mut [worker::Handle],
+    signal_refs: &mut ActorGroup<Signal>,
+) -> bool {
+    debug!(signal = as_debug!(signal); "relaying process signal to worker threads");
+    for worker in workers.iter_mut() {
+        if let Err(err) = worker.send_signal(signal) {
+            // NOTE: if the worker is unable to receive a message it's likely
+            // already shutdown or is shutting down. Rather than returning the
+            // error here and stopping the coordinator (which was the case
+            // previously) we log the error and instead wait until the worker
+            // thread stopped returning that error instead, which is likely more
+            // useful (i.e. it has the reason why the worker thread stopped).
+            error!(
+                signal = as_debug!(signal), worker_id = worker.id();
+                "failed to send process signal to worker: {err}",
+            );
+        }
+    }
+
+    debug!(signal = as_debug!(signal); "relaying process signal to actors");
+    let _ = signal_refs.try_send(signal, Delivery::ToAll);
+    matches!(signal, Signal::User2)
+}
+
 /// Handle an `event` for a worker.
 fn handle_worker_event(workers: &mut Vec<worker::Handle>, event: &Event) -> Result<(), rt::Error> {
     if let Ok(i) = workers.binary_search_by_key(&event.token().0, worker::Handle::id) {
@@ -35,6 +35,12 @@ enum ErrorInner {
     StartSyncActor(io::Error),
     /// Panic in a synchronous actor thread.
     SyncActorPanic(StringError),
+
+    /// Processes still running after the shutdown deadline, see
+    /// [`RuntimeRef::shutdown`].
+    ///
+    /// [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
+    ShutdownDeadline(Vec<&'static str>),
 }
 
 impl Error {
@@ -97,6 +103,27 @@ impl Error {
         }
     }
 
+    pub(super) const fn shutdown_deadline(stopped: Vec<&'static str>) -> Error {
+        Error {
+            inner: ErrorInner::ShutdownDeadline(stopped),
+        }
+    }
+
+    /// Returns the names of the actors and futures that were still running
+    /// after the shutdown deadline passed and were stopped, see
+    /// [`RuntimeRef::shutdown`].
+    ///
+    /// Returns an empty slice if the error is not caused by the shutdown
+    /// deadline passing.
+    ///
+    /// [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
+    pub fn stopped_processes(&self) -> &[&'static str] {
+        match self.inner {
+            ErrorInner::ShutdownDeadline(ref stopped) => stopped,
+            _ => &[],
+        }
+    }
+
     pub(super) fn sync_actor_panic(err: Box<dyn Any + Send + 'static>) -> Error {
         let msg = convert_panic(err);
         Error {
@@ -160,6 +187,13 @@ impl fmt::Display for Error {
             SyncActorPanic(ref err) => {
                 write!(f, "{desc}: panic in synchronous actor thread: {err}")
             }
+            ShutdownDeadline(ref stopped) => {
+                write!(
+                    f,
+                    "{desc}: stopped processes still running after shutdown deadline: {}",
+                    stopped.join(", ")
+                )
+            }
         }
     }
 }
@@ -177,6 +211,7 @@ impl std::error::Error for Error {
             Worker(ref err) => Some(err),
             // All `StringError`.
             Setup(ref err) | WorkerPanic(ref err) | SyncActorPanic(ref err) => Some(err),
+            ShutdownDeadline(..) => None,
         }
     }
 }
@@ -9,6 +9,9 @@
 //! - [`RuntimeRef`] is a reference to a running runtime, used for example to
 //!   spawn new actors.
 //!
+//! Furthermore [`RuntimeHandle`] can be used to spawn thread-safe actors from
+//! any thread, also after the runtime is started.
+//!
 //! [Heph crate]: heph
 //!
 //! ## Running Heph's runtime
@@ -122,8 +125,11 @@
 //!
 //! ## Features
 //!
-//! This crate has one optional: `test`. The `test` feature will enable the
-//! `test` module which adds testing facilities.
+//! This crate has two optional features: `test` and `tls`. The `test` feature
+//! will enable the `test` module which adds testing facilities. The `tls`
+//! feature enables the `net::tls` module, adding TLS support using [rustls].
+//!
+//! [rustls]: https://docs.rs/rustls
 
 #![feature(
     async_iterator,
@@ -171,6 +177,9 @@ compile_error!("Heph currently only supports 64 bit architectures.");
 
 /// A macro to try an I/O function.
 ///
+/// If the task context is passed as second argument the task is woken after
+/// injected `WouldBlock` errors, see `test::NetFaults`.
+///
 /// Note that this is used in the net and pipe modules and has to be defined
 /// before use.
 macro_rules! try_io {
@@ -184,6 +193,19 @@ macro_rules! try_io {
             }
         }
     };
+    ($op: expr, $ctx: expr) => {
+        loop {
+            match $op {
+                Ok(ok) => break Poll::Ready(Ok(ok)),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, $ctx);
+                    break Poll::Pending;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    };
 }
 
 use std::convert::TryInto;
@@ -196,12 +218,12 @@ use std::{io, task};
 
 use ::log::{as_debug, debug, warn};
 use heph::actor::{self, NewActor, SyncActor};
-use heph::actor_ref::{ActorGroup, ActorRef};
+use heph::actor_ref::{ActorGroup, ActorRef, DeadLetter};
 use heph::supervisor::{Supervisor, SyncSupervisor};
-use heph_inbox as inbox;
 use mio::{event, Interest, Token};
 
 pub mod access;
+pub mod blocking;
 pub mod bytes;
 pub(crate) mod channel;
 mod coordinator;
@@ -211,6 +233,7 @@ pub mod log;
 pub mod net;
 pub mod pipe;
 mod process;
+pub mod registry;
 mod setup;
 pub(crate) mod shared;
 mod signal;
@@ -238,7 +261,11 @@ pub use signal::Signal;
 
 use coordinator::Coordinator;
 use local::waker::MAX_THREADS;
-use spawn::{ActorOptions, AddActorError, FutureOptions, PrivateSpawn, Spawn, SyncActorOptions};
+use process::Inbox;
+use registry::{LookupError, RegisterError, Registered};
+use spawn::{
+    ActorOptions, AddActorError, FutureOptions, PrivateSpawn, Spawn, SpawnError, SyncActorOptions,
+};
 use sync_worker::SyncWorker;
 
 pub(crate) const SYNC_WORKER_ID_START: usize = 10000;
@@ -283,6 +310,9 @@ pub struct Runtime {
     workers: Vec<worker::Handle>,
     /// Synchronous actor threads.
     sync_actors: Vec<SyncWorker>,
+    /// Id of the synchronous actor that receives the dead letters, see
+    /// [`Runtime::spawn_dead_letters_actor`].
+    dead_letters_actor: Option<usize>,
     /// List of actor references that want to receive process signals.
     signals: ActorGroup<Signal>,
     /// Trace log.
@@ -325,6 +355,7 @@ impl Runtime {
         S: Supervisor<NA> + Send + std::marker::Sync + 'static,
         NA: NewActor<RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
         NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
         NA::Message: Send,
     {
         Spawn::try_spawn(self, supervisor, new_actor, arg, options)
@@ -344,6 +375,7 @@ impl Runtime {
         S: Supervisor<NA> + Send + std::marker::Sync + 'static,
         NA: NewActor<Error = !, RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
         NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
         NA::Message: Send,
     {
         Spawn::spawn(self, supervisor, new_actor, arg, options)
@@ -390,6 +422,80 @@ impl Runtime {
             .map_err(Error::start_sync_actor)
     }
 
+    /// Spawn a synchronous actor that receives all [`DeadLetter`]s, i.e. the
+    /// messages that couldn't be delivered.
+    ///
+    /// This is an alternative to [`Setup::dead_letters`] for when the
+    /// dead-letter actor should run on the runtime. See the [`dead_letter`]
+    /// module for more information.
+    ///
+    /// # Notes
+    ///
+    /// Only actors spawned after calling this send dead letters, so this should
+    /// be called before spawning any other actors.
+    ///
+    /// As the runtime (and all actors spawned on it) holds on to the actor's
+    /// reference the inbox of the dead-letter actor is never disconnected
+    /// while the runtime is running. Because of this the runtime doesn't wait
+    /// for the dead-letter actor to stop, it stops once all other actors are
+    /// done.
+    ///
+    /// # Panics
+    ///
+    /// This panics if a dead-letter actor is already set, using this method or
+    /// [`Setup::dead_letters`].
+    ///
+    /// [`DeadLetter`]: heph::actor_ref::DeadLetter
+    /// [`dead_letter`]: heph::actor_ref::dead_letter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor::SyncContext;
+    /// use heph::actor_ref::DeadLetter;
+    /// use heph::supervisor::NoSupervisor;
+    /// use heph_rt::spawn::SyncActorOptions;
+    /// use heph_rt::{self as rt, Runtime};
+    ///
+    /// fn dead_letters(mut ctx: SyncContext<DeadLetter, rt::Sync>) {
+    ///     while let Ok(letter) = ctx.receive_next() {
+    ///         eprintln!("failed to deliver a '{}': {}", letter.message_type, letter.reason);
+    ///     }
+    /// }
+    ///
+    /// let mut runtime = Runtime::new()?;
+    /// let options = SyncActorOptions::default().with_name("dead letters".to_owned());
+    /// let _ = runtime.spawn_dead_letters_actor(NoSupervisor, dead_letters as fn(_), (), options)?;
+    /// // Spawn the other actors...
+    /// runtime.start()?;
+    /// # Ok::<(), rt::Error>(())
+    /// ```
+    pub fn spawn_dead_letters_actor<S, A>(
+        &mut self,
+        supervisor: S,
+        actor: A,
+        arg: A::Argument,
+        options: SyncActorOptions,
+    ) -> Result<ActorRef<DeadLetter>, Error>
+    where
+        S: SyncSupervisor<A> + Send + 'static,
+        A: SyncActor<Message = DeadLetter, RuntimeAccess = Sync> + Send + 'static,
+        A::Argument: Send + 'static,
+    {
+        assert!(
+            self.coordinator.shared_internals().dead_letters().is_none(),
+            "dead-letter actor already set"
+        );
+        let actor_ref = self.spawn_sync_actor(supervisor, actor, arg, options)?;
+        // NOTE: we checked above that the dead-letter actor is not yet set.
+        let _ = self
+            .coordinator
+            .shared_internals()
+            .set_dead_letters(actor_ref.clone());
+        self.dead_letters_actor = self.sync_actors.last().map(SyncWorker::id);
+        Ok(actor_ref)
+    }
+
     /// Spawn a thread-safe [`Future`].
     ///
     /// See [`RuntimeRef::spawn_future`] for more documentation.
@@ -436,6 +542,14 @@ impl Runtime {
         self.signals.add(actor_ref);
     }
 
+    /// Returns a handle to the runtime, that can be used after the runtime is
+    /// started.
+    ///
+    /// See [`RuntimeHandle`] for more information.
+    pub fn handle(&self) -> RuntimeHandle {
+        RuntimeHandle::new(self.coordinator.shared_internals().clone())
+    }
+
     /// Run the runtime.
     ///
     /// This will wait until all spawned workers have finished, which happens
@@ -448,8 +562,13 @@ impl Runtime {
             workers = self.workers.len(), sync_actors = self.sync_actors.len();
             "starting Heph runtime"
         );
-        self.coordinator
-            .run(self.workers, self.sync_actors, self.signals, self.trace_log)
+        self.coordinator.run(
+            self.workers,
+            self.sync_actors,
+            self.dead_letters_actor,
+            self.signals,
+            self.trace_log,
+        )
     }
 }
 
@@ -458,6 +577,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
 }
@@ -467,6 +587,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
     fn try_spawn_setup<ArgFn, E>(
@@ -485,6 +606,170 @@ where
     }
 }
 
+/// Handle to a [`Runtime`] that can be used from any thread.
+///
+/// [`Runtime::start`] consumes the runtime and blocks until it's done, so the
+/// runtime can't be used to spawn actors after it's started. A handle, created
+/// using [`Runtime::handle`], can. It can be used to spawn thread-safe actors
+/// and futures, send signals and shutdown the runtime from any thread, e.g.
+/// from callbacks of a C library or from threads of another thread pool.
+///
+/// # Notes
+///
+/// The runtime keeps running while any handle is alive, even if all actors and
+/// futures are done. The runtime only stops once all handles are dropped and
+/// all actors and futures are done, or when it's [shutdown].
+///
+/// [shutdown]: RuntimeHandle::shutdown
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::thread;
+///
+/// use heph::actor;
+/// use heph::supervisor::NoSupervisor;
+/// use heph_rt::spawn::ActorOptions;
+/// use heph_rt::{Runtime, ThreadSafe};
+///
+/// async fn actor(_: actor::Context<!, ThreadSafe>, msg: &'static str) {
+///     println!("{msg}");
+/// }
+///
+/// let runtime = Runtime::new().unwrap();
+/// let mut handle = runtime.handle();
+///
+/// let thread = thread::spawn(move || {
+///     // Spawn an actor on the started runtime from another thread.
+///     let actor = actor as fn(_, _) -> _;
+///     let msg = "Hello from another thread";
+///     handle.spawn(NoSupervisor, actor, msg, ActorOptions::default()).unwrap();
+///     // Dropping the handle allows the runtime to stop.
+///     drop(handle);
+/// });
+///
+/// runtime.start().unwrap();
+/// thread.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RuntimeHandle {
+    /// A shared reference to the runtime's internals.
+    internals: Arc<shared::RuntimeInternals>,
+}
+
+impl RuntimeHandle {
+    fn new(internals: Arc<shared::RuntimeInternals>) -> RuntimeHandle {
+        internals.add_handle();
+        RuntimeHandle { internals }
+    }
+
+    /// Attempt to spawn a new thread-safe actor.
+    ///
+    /// This returns an error if the runtime is shutting down or has stopped,
+    /// as the actor would never run. See the [`Spawn`] trait for more
+    /// information about the arguments.
+    pub fn try_spawn<S, NA>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> Result<ActorRef<NA::Message>, SpawnError<NA::Error>>
+    where
+        S: Supervisor<NA> + Send + std::marker::Sync + 'static,
+        NA: NewActor<RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
+        NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
+        NA::Message: Send,
+    {
+        self.check_running()?;
+        let res = self
+            .internals
+            .spawn_setup(supervisor, new_actor, |_| Ok(arg), options)
+            .map_err(|err| match err {
+                AddActorError::NewActor(err) => SpawnError::NewActor(err),
+                AddActorError::<_, !>::ArgFn(_) => unreachable!(),
+            });
+        // The runtime might already be running, ensure a worker runs the actor.
+        self.internals.wake_workers(1);
+        res
+    }
+
+    /// Spawn a new thread-safe actor.
+    ///
+    /// See [`RuntimeHandle::try_spawn`] for more information.
+    pub fn spawn<S, NA>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> Result<ActorRef<NA::Message>, SpawnError<!>>
+    where
+        S: Supervisor<NA> + Send + std::marker::Sync + 'static,
+        NA: NewActor<Error = !, RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
+        NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
+        NA::Message: Send,
+    {
+        self.try_spawn(supervisor, new_actor, arg, options)
+    }
+
+    /// Spawn a thread-safe [`Future`].
+    ///
+    /// This returns an error if the runtime is shutting down or has stopped,
+    /// as the future would never run. See [`RuntimeRef::spawn_future`] for
+    /// more documentation.
+    pub fn spawn_future<Fut>(
+        &mut self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> Result<(), SpawnError<!>>
+    where
+        Fut: Future<Output = ()> + Send + std::marker::Sync + 'static,
+    {
+        self.check_running()?;
+        self.internals.spawn_future(future, options);
+        self.internals.wake_workers(1);
+        Ok(())
+    }
+
+    /// Returns an error if the runtime is shutting down or has stopped.
+    fn check_running<E>(&self) -> Result<(), SpawnError<E>> {
+        if self.internals.is_shutting_down() || self.internals.is_stopped() {
+            Err(SpawnError::Stopped)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Send `signal` to all actors that want to receive [process signals], as
+    /// if the process received the signal.
+    ///
+    /// [process signals]: Signal
+    pub fn send_signal(&self, signal: Signal) {
+        self.internals.send_signal(signal)
+    }
+
+    /// Shutdown the runtime, see [`RuntimeRef::shutdown`].
+    pub fn shutdown(&self, deadline: Instant) {
+        self.internals.shutdown(deadline)
+    }
+}
+
+impl Clone for RuntimeHandle {
+    fn clone(&self) -> RuntimeHandle {
+        RuntimeHandle::new(self.internals.clone())
+    }
+}
+
+impl Drop for RuntimeHandle {
+    fn drop(&mut self) {
+        self.internals.remove_handle();
+    }
+}
+
 /// A reference to a [`Runtime`].
 ///
 /// This reference refers to the thread-local runtime, and thus can't be shared
@@ -547,6 +832,7 @@ impl RuntimeRef {
         S: Supervisor<NA> + Send + std::marker::Sync + 'static,
         NA: NewActor<RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
         NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
         NA::Message: Send,
     {
         Spawn::try_spawn(self, supervisor, new_actor, arg, options)
@@ -566,6 +852,7 @@ impl RuntimeRef {
         S: Supervisor<NA> + Send + std::marker::Sync + 'static,
         NA: NewActor<Error = !, RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
         NA::Actor: Send + std::marker::Sync + 'static,
+        NA::Argument: Send + std::marker::Sync,
         NA::Message: Send,
     {
         Spawn::spawn(self, supervisor, new_actor, arg, options)
@@ -599,6 +886,24 @@ impl RuntimeRef {
         self.internals.shared.spawn_future(future, options)
     }
 
+    /// Run the blocking function `f` on a separate thread pool.
+    ///
+    /// Returns a [`Blocking`] future that resolves to the result of `f`, or an
+    /// error if `f` panicked. The maximum number of threads in the pool can be
+    /// set using [`Setup::blocking_threads`], if all threads are busy `f` is
+    /// queued until one is available.
+    ///
+    /// See the [`blocking`] module for more information and an example.
+    ///
+    /// [`Blocking`]: blocking::Blocking
+    pub fn spawn_blocking<F, T>(&mut self, f: F) -> blocking::Blocking<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.internals.shared.spawn_blocking(f)
+    }
+
     /// Receive [process signals] as messages.
     ///
     /// This adds the `actor_ref` to the list of actor references that will
@@ -612,8 +917,83 @@ impl RuntimeRef {
             .add_unique(actor_ref)
     }
 
+    /// Shutdown the runtime, forcefully stopping all processes still running
+    /// after `deadline`.
+    ///
+    /// This does the following:
+    ///  * Sends [`Signal::Terminate`] to all actors that want to receive process
+    ///    signals, see [`RuntimeRef::receive_signals`] and
+    ///    [`Runtime::receive_signals`].
+    ///  * Stops all [`TcpServer`]s from accepting new connections.
+    ///  * Waits for all actors and futures to complete, like normal.
+    ///  * Once `deadline` has passed, it drops all actors and futures that are
+    ///    still running, logging a warning for each, and stops the runtime.
+    ///    In this case [`Runtime::start`] returns an error, the names of the
+    ///    stopped actors and futures are available using
+    ///    [`Error::stopped_processes`].
+    ///
+    /// Once the deadline has passed the runtime no longer waits for the threads
+    /// running synchronous actors, as those can't be stopped.
+    ///
+    /// Calling this while the runtime is already shutting down does nothing,
+    /// the deadline is not changed.
+    ///
+    /// # Panics
+    ///
+    /// This panics if called on the [test runtime], as it's shared between all
+    /// tests.
+    ///
+    /// [test runtime]: crate::test
+    ///
+    /// [`TcpServer`]: crate::net::TcpServer
+    pub fn shutdown(&mut self, deadline: Instant) {
+        self.internals.shared.shutdown(deadline)
+    }
+
+    /// Register `actor_ref` under `name` in the runtime-wide registry of
+    /// named actors.
+    ///
+    /// Registering fails if another actor, that is still running, is already
+    /// registered under `name`. The same actor can register itself again,
+    /// e.g. after it was restarted. Actors are automatically unregistered once
+    /// they stop.
+    ///
+    /// As the registry is shared between all threads of the runtime the message
+    /// type must be `Send`. See the [`registry`] module for more information.
+    pub fn register<M>(&mut self, name: &str, actor_ref: ActorRef<M>) -> Result<(), RegisterError>
+    where
+        M: Send + 'static,
+    {
+        self.internals
+            .shared
+            .actor_registry()
+            .register(name, actor_ref)
+    }
+
+    /// Lookup the actor registered under `name`, see [`RuntimeRef::register`].
+    ///
+    /// This returns an error if no running actor is registered under `name` or
+    /// if the registered actor doesn't have message type `M`.
+    pub fn lookup<M>(&self, name: &str) -> Result<ActorRef<M>, LookupError>
+    where
+        M: Send + 'static,
+    {
+        self.internals.shared.actor_registry().lookup(name)
+    }
+
+    /// Receive a [`Registered`] message when an actor is (re)registered under
+    /// `name`, see [`RuntimeRef::register`].
+    ///
+    /// [`Registered`]: registry::Registered
+    pub fn watch_registrations(&mut self, name: &str, actor_ref: ActorRef<Registered>) {
+        self.internals
+            .shared
+            .actor_registry()
+            .watch(name, actor_ref)
+    }
+
     /// Register an `event::Source`, see [`mio::Registry::register`].
-    pub(crate) fn register<S>(
+    pub(crate) fn register_source<S>(
         &mut self,
         source: &mut S,
         token: Token,
@@ -630,7 +1010,7 @@ impl RuntimeRef {
     }
 
     /// Reregister an `event::Source`, see [`mio::Registry::reregister`].
-    pub(crate) fn reregister<S>(
+    pub(crate) fn reregister_source<S>(
         &mut self,
         source: &mut S,
         token: Token,
@@ -677,6 +1057,16 @@ impl RuntimeRef {
         self.internals.timers.borrow_mut().add(pid, deadline);
     }
 
+    /// Returns the current time, see [`local::Clock`].
+    fn now(&self) -> Instant {
+        self.internals.clock.now()
+    }
+
+    /// Add a deadline for a shared process.
+    fn add_deadline_shared(&mut self, pid: ProcessId, deadline: Instant) {
+        self.internals.shared.add_deadline(pid, deadline);
+    }
+
     /// Remove a deadline.
     fn remove_deadline(&mut self, pid: ProcessId, deadline: Instant) {
         ::log::trace!(pid = pid.0, deadline = as_debug!(deadline); "removing deadline");
@@ -754,9 +1144,15 @@ where
         debug!(pid = pid.0, name = name; "spawning thread-local actor");
 
         // Create our actor context and our actor with it.
-        let (manager, sender, receiver) = inbox::Manager::new_small_channel();
-        let actor_ref = ActorRef::local(sender);
-        let mut ctx = actor::Context::new(receiver, ThreadLocal::new(pid, self.clone()));
+        let dead_letters = self.internals.shared.dead_letters();
+        let (inbox, receiver, actor_ref) = Inbox::new(
+            name,
+            dead_letters,
+            options.inbox_capacity(),
+            options.overflow_policy(),
+        );
+        let rt = ThreadLocal::new(pid, self.clone());
+        let mut ctx = actor::Context::new(receiver, inbox.state(), rt);
         // Create our actor argument, running any setup required by the caller.
         let arg = arg_fn(&mut ctx).map_err(AddActorError::ArgFn)?;
         let actor = new_actor.new(ctx, arg).map_err(AddActorError::NewActor)?;
@@ -767,7 +1163,7 @@ where
             supervisor,
             new_actor,
             actor,
-            manager,
+            inbox,
             options.is_ready(),
         );
 
@@ -780,6 +1176,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
 }
@@ -789,6 +1186,7 @@ where
     S: Supervisor<NA> + Send + std::marker::Sync + 'static,
     NA: NewActor<RuntimeAccess = ThreadSafe> + Send + std::marker::Sync + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
+    NA::Argument: Send + std::marker::Sync,
     NA::Message: Send,
 {
     fn try_spawn_setup<ArgFn, E>(
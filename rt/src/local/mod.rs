@@ -1,8 +1,13 @@
 //! Module with shared runtime internals.
 
+#[cfg(any(test, feature = "test"))]
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
+#[cfg(any(test, feature = "test"))]
+use std::time::Duration;
+use std::time::Instant;
 
 use heph::actor_ref::ActorGroup;
 use mio::Poll;
@@ -32,6 +37,8 @@ pub(super) struct RuntimeInternals {
     pub(super) poll: RefCell<Poll>,
     /// Timers, deadlines and timeouts.
     pub(crate) timers: RefCell<Timers>,
+    /// Source of the current time, used by the timers.
+    pub(crate) clock: Clock,
     /// Actor references to relay received `Signal`s to.
     pub(super) signal_receivers: RefCell<ActorGroup<Signal>>,
     /// CPU affinity of the worker thread, or `None` if not set.
@@ -57,9 +64,88 @@ impl RuntimeInternals {
             scheduler: RefCell::new(Scheduler::new()),
             poll: RefCell::new(poll),
             timers: RefCell::new(Timers::new()),
+            clock: Clock::Real,
             signal_receivers: RefCell::new(ActorGroup::empty()),
             cpu,
             trace_log: RefCell::new(trace_log),
         }
     }
+
+    /// Create a local runtime internals for a simulation, using a seeded
+    /// scheduler and a virtual clock starting at `start`.
+    #[cfg(any(test, feature = "test"))]
+    pub(super) fn new_simulation(
+        shared_internals: Arc<shared::RuntimeInternals>,
+        waker_id: WakerId,
+        poll: Poll,
+        seed: u64,
+        start: Instant,
+    ) -> RuntimeInternals {
+        RuntimeInternals {
+            id: NonZeroUsize::new(usize::MAX).unwrap(),
+            shared: shared_internals,
+            waker_id,
+            scheduler: RefCell::new(Scheduler::new_seeded(seed)),
+            poll: RefCell::new(poll),
+            timers: RefCell::new(Timers::new()),
+            clock: Clock::Virtual(Cell::new(start)),
+            signal_receivers: RefCell::new(ActorGroup::empty()),
+            cpu: None,
+            trace_log: RefCell::new(None),
+        }
+    }
+}
+
+/// Source of the current time.
+#[derive(Debug)]
+pub(crate) enum Clock {
+    /// The monotonic clock of the OS, see [`Instant::now`].
+    Real,
+    /// The monotonic clock of the OS, plus an offset that can be advanced.
+    /// Used by the test runtimes, see [`test::advance_time`].
+    ///
+    /// [`test::advance_time`]: crate::test::advance_time
+    #[cfg(any(test, feature = "test"))]
+    Offset(Cell<Duration>),
+    /// Virtual time, only advanced explicitly. Used in simulations, see
+    /// [`test::Simulation`].
+    ///
+    /// [`test::Simulation`]: crate::test::Simulation
+    #[cfg(any(test, feature = "test"))]
+    Virtual(Cell<Instant>),
+}
+
+impl Clock {
+    /// Returns the current time.
+    pub(crate) fn now(&self) -> Instant {
+        match self {
+            Clock::Real => Instant::now(),
+            #[cfg(any(test, feature = "test"))]
+            Clock::Offset(offset) => Instant::now() + offset.get(),
+            #[cfg(any(test, feature = "test"))]
+            Clock::Virtual(now) => now.get(),
+        }
+    }
+
+    /// Advance the clock to `time`, if `time` is later than the current time.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the clock is the real clock.
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn advance_to(&self, time: Instant) {
+        match self {
+            Clock::Real => panic!("can't change the time of the real clock"),
+            Clock::Offset(offset) => {
+                if let Some(diff) = time.checked_duration_since(self.now()) {
+                    offset.set(offset.get() + diff);
+                }
+            }
+            Clock::Virtual(now) => {
+                if time > now.get() {
+                    now.set(time);
+                }
+            }
+        }
+    }
 }
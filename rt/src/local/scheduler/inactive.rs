@@ -84,6 +84,12 @@ impl Inactive {
             process
         })
     }
+
+    /// Remove all processes, adding them to `processes`.
+    pub(super) fn drain(&mut self, processes: &mut Vec<Pin<Box<ProcessData>>>) {
+        self.root.drain(processes);
+        self.length = 0;
+    }
 }
 
 struct Branch {
@@ -169,6 +175,16 @@ impl Branch {
         self.branches[w_pid & LEVEL_MASK] = Some(branch.into());
     }
 
+    fn drain(&mut self, processes: &mut Vec<Pin<Box<ProcessData>>>) {
+        for node in &mut self.branches {
+            match Pointer::take_process(node) {
+                Some(Ok(process)) => processes.push(process),
+                Some(Err(mut branch)) => branch.drain(processes),
+                None => {}
+            }
+        }
+    }
+
     fn remove(&mut self, pid: ProcessId, w_pid: usize) -> Option<Pin<Box<ProcessData>>> {
         let node = &mut self.branches[w_pid & LEVEL_MASK];
         match Pointer::take_process(node) {
@@ -5,17 +5,20 @@
 //! [`RuntimeRef::try_spawn_local`]: crate::RuntimeRef::try_spawn_local
 
 use std::collections::BinaryHeap;
+#[cfg(any(test, feature = "test"))]
+use std::collections::{BTreeMap, HashMap};
 use std::future::Future;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
 
 use heph::actor::NewActor;
 use heph::supervisor::Supervisor;
-use heph_inbox::Manager;
 use log::{debug, trace};
 
-use crate::process::{self, ActorProcess, FutureProcess, ProcessId};
+use crate::process::{self, ActorProcess, FutureProcess, Inbox, ProcessId};
 use crate::spawn::options::Priority;
+#[cfg(any(test, feature = "test"))]
+use crate::test::Rng;
 use crate::{ptr_as_usize, ThreadLocal};
 
 mod inactive;
@@ -29,7 +32,7 @@ type ProcessData = process::ProcessData<dyn process::Process>;
 #[derive(Debug)]
 pub(crate) struct Scheduler {
     /// Processes that are ready to run.
-    ready: BinaryHeap<Pin<Box<ProcessData>>>,
+    ready: Ready,
     /// Processes that are not ready to run.
     inactive: Inactive,
 }
@@ -38,7 +41,22 @@ impl Scheduler {
     /// Create a new `Scheduler`.
     pub(crate) fn new() -> Scheduler {
         Scheduler {
-            ready: BinaryHeap::new(),
+            ready: Ready::Priority(BinaryHeap::new()),
+            inactive: Inactive::empty(),
+        }
+    }
+
+    /// Create a new `Scheduler` that runs the ready processes in a random
+    /// order, determined by `seed`. Used in simulations.
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn new_seeded(seed: u64) -> Scheduler {
+        Scheduler {
+            ready: Ready::Seeded(Seeded {
+                processes: BTreeMap::new(),
+                order: HashMap::new(),
+                next_order: 0,
+                rng: Rng::new(seed),
+            }),
             inactive: Inactive::empty(),
         }
     }
@@ -82,6 +100,7 @@ impl Scheduler {
             Box::pin(FutureProcess::<Fut, ThreadLocal>::new(future)),
         ));
         debug!(pid = process.as_ref().id().0; "spawning thread-local future");
+        self.ready.spawned(process.as_ref().id());
         self.ready.push(process)
     }
 
@@ -102,11 +121,28 @@ impl Scheduler {
         self.ready.pop()
     }
 
+    /// Remove all processes from the scheduler, both ready and inactive.
+    pub(crate) fn drain(&mut self) -> Vec<Pin<Box<ProcessData>>> {
+        let mut processes = Vec::new();
+        while let Some(process) = self.ready.pop() {
+            processes.push(process);
+        }
+        self.inactive.drain(&mut processes);
+        processes
+    }
+
     /// Add back a process that was previously removed via
     /// [`Scheduler::next_process`].
     pub(crate) fn add_process(&mut self, process: Pin<Box<ProcessData>>) {
         self.inactive.add(process);
     }
+
+    /// Mark the process with `pid`, previously removed via
+    /// [`Scheduler::next_process`], as complete.
+    pub(crate) fn complete(&mut self, pid: ProcessId) {
+        trace!(pid = pid.0; "removing process");
+        self.ready.completed(pid);
+    }
 }
 
 /// A handle to add a process to the scheduler.
@@ -133,7 +169,7 @@ impl<'s> AddActor<'s> {
         supervisor: S,
         new_actor: NA,
         actor: NA::Actor,
-        inbox: Manager<NA::Message>,
+        inbox: Inbox<NA::Message>,
         is_ready: bool,
     ) where
         S: Supervisor<NA> + 'static,
@@ -147,10 +183,12 @@ impl<'s> AddActor<'s> {
             priority,
             Box::pin(ActorProcess::new(supervisor, new_actor, actor, inbox)),
         );
+        let pid = self.pid();
         let AddActor {
             scheduler,
             mut alloc,
         } = self;
+        scheduler.ready.spawned(pid);
         let process: Pin<_> = unsafe {
             let _ = alloc.write(process);
             // Safe because we write into the allocation above.
@@ -163,3 +201,99 @@ impl<'s> AddActor<'s> {
         }
     }
 }
+
+/// Processes that are ready to run.
+#[derive(Debug)]
+enum Ready {
+    /// Processes are run based on their priority and fair runtime, see the
+    /// `Ord` implementation of [`ProcessData`].
+    Priority(BinaryHeap<Pin<Box<ProcessData>>>),
+    /// Processes are run in a seeded random order.
+    #[cfg(any(test, feature = "test"))]
+    Seeded(Seeded),
+}
+
+/// Ready processes in a simulation, see [`Scheduler::new_seeded`].
+#[cfg(any(test, feature = "test"))]
+#[derive(Debug)]
+struct Seeded {
+    /// Processes that are ready to run, ordered by when they were spawned.
+    ///
+    /// We can't use the `ProcessId` for the order as it's based on the
+    /// address of the process, which isn't the same each time the simulation
+    /// runs.
+    processes: BTreeMap<u64, Pin<Box<ProcessData>>>,
+    /// Spawn order for all processes that haven't completed yet.
+    order: HashMap<ProcessId, u64>,
+    /// Order of the next process to spawn.
+    next_order: u64,
+    rng: Rng,
+}
+
+impl Ready {
+    fn len(&self) -> usize {
+        match self {
+            Ready::Priority(ready) => ready.len(),
+            #[cfg(any(test, feature = "test"))]
+            Ready::Seeded(seeded) => seeded.processes.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark the process with `pid` as spawned, must be called before
+    /// [`Ready::push`] is called for the process.
+    #[cfg_attr(not(any(test, feature = "test")), allow(unused_variables))]
+    fn spawned(&mut self, pid: ProcessId) {
+        match self {
+            Ready::Priority(..) => {}
+            #[cfg(any(test, feature = "test"))]
+            Ready::Seeded(seeded) => {
+                let _ = seeded.order.insert(pid, seeded.next_order);
+                seeded.next_order += 1;
+            }
+        }
+    }
+
+    /// Mark the process with `pid` as completed, see [`Ready::spawned`].
+    #[cfg_attr(not(any(test, feature = "test")), allow(unused_variables))]
+    fn completed(&mut self, pid: ProcessId) {
+        match self {
+            Ready::Priority(..) => {}
+            #[cfg(any(test, feature = "test"))]
+            Ready::Seeded(seeded) => {
+                let _ = seeded.order.remove(&pid);
+            }
+        }
+    }
+
+    fn push(&mut self, process: Pin<Box<ProcessData>>) {
+        match self {
+            Ready::Priority(ready) => ready.push(process),
+            #[cfg(any(test, feature = "test"))]
+            Ready::Seeded(seeded) => {
+                let pid = process.as_ref().id();
+                let order = seeded.order[&pid];
+                let _ = seeded.processes.insert(order, process);
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Pin<Box<ProcessData>>> {
+        match self {
+            Ready::Priority(ready) => ready.pop(),
+            #[cfg(any(test, feature = "test"))]
+            Ready::Seeded(seeded) => {
+                if seeded.processes.is_empty() {
+                    return None;
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                let n = (seeded.rng.next() % seeded.processes.len() as u64) as usize;
+                let order = *seeded.processes.keys().nth(n).unwrap();
+                seeded.processes.remove(&order)
+            }
+        }
+    }
+}
@@ -9,7 +9,7 @@ use std::rc::Rc;
 use heph::actor::{self, NewActor};
 use heph::supervisor::NoSupervisor;
 
-use crate::local::scheduler::{ProcessData, Scheduler};
+use crate::local::scheduler::{ProcessData, Ready, Scheduler};
 use crate::process::{Process, ProcessId, ProcessResult};
 use crate::spawn::options::Priority;
 use crate::test::{self, init_local_actor_with_inbox, AssertUnmoved};
@@ -231,6 +231,36 @@ fn add_process_marked_ready() {
     assert_eq!(process.as_ref().id(), pid);
 }
 
+#[test]
+fn seeded_complete() {
+    let mut scheduler = Scheduler::new_seeded(123);
+
+    let actor_entry = scheduler.add_actor();
+    let pid = actor_entry.pid();
+    let new_actor = simple_actor as fn(_) -> _;
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    actor_entry.add(
+        Priority::NORMAL,
+        NoSupervisor,
+        new_actor,
+        actor,
+        inbox,
+        true,
+    );
+
+    let process = scheduler.next_process().unwrap();
+    assert_eq!(process.as_ref().id(), pid);
+    scheduler.complete(pid);
+    drop(process);
+
+    // Completed processes shouldn't be kept around.
+    let Ready::Seeded(seeded) = &scheduler.ready else {
+        panic!("expected a seeded scheduler");
+    };
+    assert!(seeded.order.is_empty());
+    assert!(!scheduler.has_process());
+}
+
 #[test]
 fn scheduler_run_order() {
     async fn order_actor(
@@ -101,6 +101,27 @@ impl Timers {
         timers + self.overflow.len()
     }
 
+    /// Returns the number of timers with a deadline after `now`.
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn pending(&self, now: Instant) -> usize {
+        let (second, first) = self.slots.split_at(self.index as usize);
+        let iter = first.iter().chain(second.iter());
+        let mut pending = 0;
+        for (n, slot) in iter.enumerate() {
+            let slot_epoch = self.epoch + Duration::from_nanos(n as u64 * u64::from(NS_PER_SLOT));
+            pending += slot
+                .iter()
+                .filter(|timer| slot_epoch + Duration::from_nanos(u64::from(timer.deadline)) > now)
+                .count();
+        }
+        pending
+            + self
+                .overflow
+                .iter()
+                .filter(|timer| timer.deadline > now)
+                .count()
+    }
+
     /// Returns the next deadline, if any.
     pub(crate) fn next(&mut self) -> Option<Instant> {
         match self.cached_next_deadline {
@@ -1,6 +1,6 @@
 //! Network related types.
 //!
-//! The network module support two types of protocols:
+//! The network module support three types of protocols:
 //!
 //! * [Transmission Control Protocol] (TCP) module provides three main types:
 //!   * A [TCP stream] between a local and a remote socket.
@@ -8,12 +8,27 @@
 //!   * A [TCP server], listens for connections and starts a new actor for each.
 //! * [User Datagram Protocol] (UDP) only provides a single socket type:
 //!   * [`UdpSocket`].
+//! * [Unix Domain Sockets] (UDS) module provides four main types:
+//!   * A [Unix stream] between a local and a remote socket.
+//!   * A [Unix listening socket], a socket used to listen for connections.
+//!   * A [Unix datagram socket].
+//!   * A [Unix server], listens for connections and starts a new actor for
+//!     each.
+//!
+//! In addition the Transport Layer Security (TLS) module, `tls`, provides a TLS
+//! stream on top of TCP and a way to perform the TLS handshake in the TCP
+//! server. The module requires the `tls` feature.
 //!
 //! [Transmission Control Protocol]: crate::net::tcp
 //! [TCP stream]: crate::net::TcpStream
 //! [TCP listening socket]: crate::net::TcpListener
 //! [TCP server]: crate::net::TcpServer
 //! [User Datagram Protocol]: crate::net::udp
+//! [Unix Domain Sockets]: crate::net::uds
+//! [Unix stream]: crate::net::UnixStream
+//! [Unix listening socket]: crate::net::UnixListener
+//! [Unix datagram socket]: crate::net::UnixDatagram
+//! [Unix server]: crate::net::UnixServer
 //!
 //! # I/O with Heph's socket
 //!
@@ -44,18 +59,28 @@
 //! [bound]: crate::Bound
 //! [`Bound`]: crate::Bound
 
-use std::io;
 use std::net::SocketAddr;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::RawFd;
+#[cfg(target_os = "linux")]
+use std::ptr;
+use std::{io, task};
 
-use socket2::SockAddr;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 
 pub mod tcp;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod udp;
+pub mod uds;
 
 #[doc(no_inline)]
 pub use tcp::{TcpListener, TcpServer, TcpStream};
 #[doc(no_inline)]
 pub use udp::UdpSocket;
+#[doc(no_inline)]
+pub use uds::{UnixDatagram, UnixListener, UnixServer, UnixStream};
+
 /// Convert a `socket2:::SockAddr` into a `std::net::SocketAddr`.
 #[allow(clippy::needless_pass_by_value)]
 fn convert_address(address: SockAddr) -> io::Result<SocketAddr> {
@@ -67,3 +92,73 @@ fn convert_address(address: SockAddr) -> io::Result<SocketAddr> {
         )),
     }
 }
+
+/// Create a new non-blocking socket of type `ty` for the address family of
+/// `address`.
+fn new_socket(address: SocketAddr, ty: Type, protocol: Protocol) -> io::Result<Socket> {
+    let domain = Domain::for_address(address);
+    #[cfg(any(target_os = "freebsd", target_os = "linux"))]
+    let ty = ty.nonblocking();
+    let socket = Socket::new(domain, ty, Some(protocol))?;
+    // For OSs that don't support `SOCK_NONBLOCK`.
+    #[cfg(not(any(target_os = "freebsd", target_os = "linux")))]
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// Set the socket `option` at `level` to `value`, for options not supported by
+/// socket2.
+#[cfg(target_os = "linux")]
+fn set_socket_option(
+    socket: RawFd,
+    level: libc::c_int,
+    option: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    let res = unsafe {
+        libc::setsockopt(
+            socket,
+            level,
+            option,
+            ptr::addr_of!(value).cast(),
+            size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    match res {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Get the value of the socket `option` at `level`, for options not supported
+/// by socket2.
+#[cfg(target_os = "linux")]
+fn socket_option(
+    socket: RawFd,
+    level: libc::c_int,
+    option: libc::c_int,
+) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = size_of::<libc::c_int>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            socket,
+            level,
+            option,
+            ptr::addr_of_mut!(value).cast(),
+            &mut len,
+        )
+    };
+    match res {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(value),
+    }
+}
+
+/// Wake the task in `ctx` if `err` is an injected `WouldBlock` error, as no
+/// readiness event will wake it. See `test::NetFaults`.
+#[cfg_attr(not(any(test, feature = "test")), allow(unused_variables))]
+fn wake_after_fault(err: &io::Error, ctx: &task::Context<'_>) {
+    #[cfg(any(test, feature = "test"))]
+    crate::test::wake_after_fault(err, ctx);
+}
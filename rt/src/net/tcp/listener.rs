@@ -4,13 +4,19 @@ use std::async_iter::AsyncIterator;
 use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::time::Duration;
 
 use heph::actor;
 use mio::{net, Interest};
+use socket2::{Protocol, Socket, TcpKeepalive, Type};
 
-use crate::net::TcpStream;
+#[cfg(target_os = "linux")]
+use crate::net::set_socket_option;
+use crate::net::{new_socket, TcpStream};
 use crate::{self as rt, Bound};
 
 /// A TCP socket listener.
@@ -186,7 +192,23 @@ impl TcpListener {
     where
         RT: rt::Access,
     {
-        let mut socket = net::TcpListener::bind(address)?;
+        TcpListener::bind_with(ctx, address, TcpListenerOptions::default())
+    }
+
+    /// Same as [`TcpListener::bind`], but sets the socket `options` before
+    /// binding.
+    ///
+    /// See [`TcpListenerOptions`] for the available options.
+    pub fn bind_with<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        address: SocketAddr,
+        options: TcpListenerOptions,
+    ) -> io::Result<TcpListener>
+    where
+        RT: rt::Access,
+    {
+        let socket = options.bind(address)?;
+        let mut socket = net::TcpListener::from_std(socket.into());
         ctx.runtime().register(&mut socket, Interest::READABLE)?;
         Ok(TcpListener { socket })
     }
@@ -260,6 +282,194 @@ impl TcpListener {
     }
 }
 
+/// Options used to create a [`TcpListener`], see [`TcpListener::bind_with`].
+///
+/// All options are set before the listener is bound. Options that are not set
+/// use the OS's default. Accepted streams inherit the buffer sizes, linger and
+/// keepalive options from the listener.
+///
+/// # Examples
+///
+/// Using a larger backlog and allowing multiple listeners to bind to the same
+/// port.
+///
+/// ```
+/// use heph_rt::net::tcp::listener::TcpListenerOptions;
+///
+/// let options = TcpListenerOptions::default()
+///     .with_backlog(4096)
+///     .with_reuse_port(true);
+/// # drop(options); // Silence unused variable warning.
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct TcpListenerOptions {
+    backlog: libc::c_int,
+    reuse_port: bool,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    linger: Option<Duration>,
+    keepalive: Option<TcpKeepalive>,
+    only_v6: Option<bool>,
+    #[cfg(target_os = "linux")]
+    fast_open: Option<u32>,
+}
+
+impl TcpListenerOptions {
+    /// Set the maximum length of the queue of pending connections, defaults to
+    /// 1024.
+    ///
+    /// The OS may silently limit the backlog, e.g. Linux limits it to
+    /// `/proc/sys/net/core/somaxconn`.
+    pub fn with_backlog(mut self, backlog: u32) -> Self {
+        self.backlog = libc::c_int::try_from(backlog).unwrap_or(libc::c_int::MAX);
+        self
+    }
+
+    /// Set the value of the `SO_REUSEPORT` option, allowing multiple sockets
+    /// to bind to the same address and port.
+    pub const fn with_reuse_port(mut self, reuse_port: bool) -> Self {
+        self.reuse_port = reuse_port;
+        self
+    }
+
+    /// Set the size of the send buffer, setting the `SO_SNDBUF` option.
+    pub const fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the size of the receive buffer, setting the `SO_RCVBUF` option.
+    pub const fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the value of the `SO_LINGER` option for accepted streams.
+    ///
+    /// See [`TcpStreamOptions::with_linger`] for more information.
+    ///
+    /// [`TcpStreamOptions::with_linger`]: crate::net::tcp::stream::TcpStreamOptions::with_linger
+    pub const fn with_linger(mut self, duration: Duration) -> Self {
+        self.linger = Some(duration);
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`) for accepted streams, sending
+    /// the first probe after the connection was idle for `time`
+    /// (`TCP_KEEPIDLE`).
+    pub fn with_keepalive_time(mut self, time: Duration) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_time(time),
+        );
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`) for accepted streams, using an
+    /// `interval` between probes (`TCP_KEEPINTVL`).
+    pub fn with_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_interval(interval),
+        );
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`) for accepted streams, sending
+    /// at most `retries` probes before dropping the connection
+    /// (`TCP_KEEPCNT`).
+    pub fn with_keepalive_retries(mut self, retries: u32) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_retries(retries),
+        );
+        self
+    }
+
+    /// Set the value of the `IPV6_V6ONLY` option, only valid for IPv6
+    /// addresses.
+    ///
+    /// If enabled an IPv6 listener bound to the unspecified address (`::`) will
+    /// not accept IPv4 connections.
+    pub const fn with_only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Enable TCP Fast Open, setting the `TCP_FASTOPEN` option to the maximum
+    /// length of the queue of pending Fast Open requests.
+    ///
+    /// This allows clients to send data in the SYN packet, which is available
+    /// to read as soon as the stream is accepted.
+    ///
+    /// This is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub const fn with_fast_open(mut self, queue_length: u32) -> Self {
+        self.fast_open = Some(queue_length);
+        self
+    }
+
+    /// Create a new socket with the options set, bound to `address` and
+    /// listening for connections.
+    fn bind(&self, address: SocketAddr) -> io::Result<Socket> {
+        let socket = new_socket(address, Type::STREAM, Protocol::TCP)?;
+        // Allow the address to be reused after the listener is closed, same as
+        // the standard library does.
+        socket.set_reuse_address(true)?;
+        if self.reuse_port {
+            socket.set_reuse_port(true)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(duration) = self.linger {
+            socket.set_linger(Some(duration))?;
+        }
+        if let Some(keepalive) = &self.keepalive {
+            socket.set_tcp_keepalive(keepalive)?;
+        }
+        if let Some(only_v6) = self.only_v6 {
+            socket.set_only_v6(only_v6)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(queue_length) = self.fast_open {
+            let queue_length = libc::c_int::try_from(queue_length).unwrap_or(libc::c_int::MAX);
+            set_socket_option(
+                socket.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN,
+                queue_length,
+            )?;
+        }
+        socket.bind(&address.into())?;
+        socket.listen(self.backlog)?;
+        Ok(socket)
+    }
+}
+
+impl Default for TcpListenerOptions {
+    fn default() -> TcpListenerOptions {
+        TcpListenerOptions {
+            backlog: 1024,
+            reuse_port: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            linger: None,
+            keepalive: None,
+            only_v6: None,
+            #[cfg(target_os = "linux")]
+            fast_open: None,
+        }
+    }
+}
+
 /// An unbound [`TcpStream`].
 ///
 /// The stream first has to be bound to an actor (using [`bind_to`]), before it
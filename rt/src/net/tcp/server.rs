@@ -16,9 +16,11 @@ use log::warn;
 use log::{as_display, debug};
 use mio::net::TcpListener;
 use mio::Interest;
-use socket2::{Domain, Protocol, Socket, Type};
+use socket2::{Protocol, Socket, Type};
 
-use crate::net::TcpStream;
+#[cfg(feature = "tls")]
+use crate::net::tls::{server::Accept, ServerConfig, TlsStream};
+use crate::net::{new_socket, TcpStream};
 use crate::spawn::{ActorOptions, AddActorError, PrivateSpawn, Spawn};
 use crate::{self as rt, PrivateAccess, Signal};
 
@@ -88,16 +90,7 @@ where
 }
 
 fn new_listener(address: SocketAddr, backlog: libc::c_int) -> io::Result<Socket> {
-    // Create a new non-blocking socket.
-    let domain = Domain::for_address(address);
-    let ty = Type::STREAM;
-    #[cfg(any(target_os = "freebsd", target_os = "linux"))]
-    let ty = ty.nonblocking();
-    let protocol = Protocol::TCP;
-    let socket = Socket::new(domain, ty, Some(protocol))?;
-    // For OSs that don't support `SOCK_NONBLOCK`.
-    #[cfg(not(any(target_os = "freebsd", target_os = "linux")))]
-    socket.set_nonblocking(true)?;
+    let socket = new_socket(address, Type::STREAM, Protocol::TCP)?;
 
     // Allow the other worker threads and processes to reuse the address and
     // port we're binding to. This allow reload the process without dropping
@@ -139,7 +132,10 @@ impl<S, NA> Clone for Setup<S, NA> {
 /// Graceful shutdown is done by sending it a [`Terminate`] message, see below
 /// for an example. The TCP server can also handle (shutdown) process signals,
 /// see "Example 2 my ip" (in the examples directory of the source code) for an
-/// example of that.
+/// example of that. Finally the TCP server stops when the runtime is shutting
+/// down, see [`RuntimeRef::shutdown`].
+///
+/// [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
 ///
 /// # Examples
 ///
@@ -370,6 +366,7 @@ impl<S, NA> Clone for Setup<S, NA> {
 ///     S: Supervisor<NA> + Send + Sync + Clone + 'static,
 ///     NA: NewActor<Argument = (TcpStream, SocketAddr), Error = !, RuntimeAccess = ThreadSafe> + Send + Sync + Clone + 'static,
 ///     NA::Actor: Send + Sync + 'static,
+///     NA::Argument: Send + Sync,
 ///     NA::Message: Send,
 /// {
 ///     fn decide(&mut self, err: server::Error<!>) -> SupervisorStrategy<()> {
@@ -476,6 +473,39 @@ where
     }
 }
 
+#[cfg(feature = "tls")]
+impl<S, NA> TcpServer<S, Accept<NA>>
+where
+    S: Supervisor<Accept<NA>> + Clone + 'static,
+    NA: NewActor<Argument = (TlsStream, SocketAddr), Error = !> + Clone + 'static,
+    <NA::Actor as Actor>::Error: From<io::Error>,
+{
+    /// Create a new [server setup] that performs a TLS handshake for each
+    /// accepted connection, before starting the actor to handle it.
+    ///
+    /// Arguments are the same as for [`TcpServer::setup`], with the addition of
+    /// `config`, the TLS configuration used in the handshake. This includes
+    /// the certificate(s) to use, which can be selected based on the server
+    /// name indication (SNI) send by the client, and the application protocols
+    /// to negotiate (ALPN). See the [`tls`] module for more information.
+    ///
+    /// The actors are supervised by `supervisor` as if they accept a
+    /// [`TcpStream`]. Failed handshakes can't be retried, see [`Accept`] for
+    /// more information.
+    ///
+    /// [server setup]: Setup
+    /// [`tls`]: crate::net::tls
+    pub fn setup_tls(
+        address: SocketAddr,
+        supervisor: S,
+        new_actor: NA,
+        config: Arc<ServerConfig>,
+        options: ActorOptions,
+    ) -> io::Result<Setup<S, Accept<NA>>> {
+        TcpServer::setup(address, supervisor, Accept::new(new_actor, config), options)
+    }
+}
+
 impl<S, NA> Actor for TcpServer<S, NA>
 where
     S: Supervisor<NA> + Clone + 'static,
@@ -496,6 +526,8 @@ where
             // Set the waker of the inbox to ensure we get run when we receive a
             // message.
             this.ctx.register_inbox_waker(ctx.waker());
+            // And to ensure we get run when the runtime is shutting down.
+            this.ctx.runtime().wake_on_shutdown(ctx.waker().clone());
             this.set_waker = true
         }
 
@@ -509,7 +541,8 @@ where
         // however that there is still a race condition between our last call to
         // `accept` and the time the file descriptor is actually closed,
         // currently we can't avoid this.
-        let should_stop = this.ctx.try_receive_next().is_ok();
+        let should_stop =
+            this.ctx.try_receive_next().is_ok() || this.ctx.runtime_ref().is_shutting_down();
 
         loop {
             let (mut stream, addr) = match this.listener.accept() {
@@ -545,7 +578,7 @@ where
         }
 
         if should_stop {
-            debug!("TCP server received shutdown message, stopping");
+            debug!("TCP server received shutdown message or runtime is shutting down, stopping");
             Poll::Ready(Ok(()))
         } else {
             Poll::Pending
@@ -9,17 +9,23 @@ use std::future::Future;
 use std::io::{self, IoSlice};
 use std::net::{Shutdown, SocketAddr};
 use std::num::NonZeroUsize;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::time::Duration;
 
 #[cfg(target_os = "linux")]
 use log::warn;
 use mio::{net, Interest};
 
 use heph::actor;
-use socket2::SockRef;
+use socket2::{Protocol, SockRef, Socket, TcpKeepalive, Type};
 
 use crate::bytes::{Bytes, BytesVectored, MaybeUninitSlice};
+use crate::net::new_socket;
+#[cfg(target_os = "linux")]
+use crate::net::set_socket_option;
 use crate::{self as rt, Bound};
 
 /// A non-blocking TCP stream between a local socket and a remote socket.
@@ -69,7 +75,23 @@ impl TcpStream {
     where
         RT: rt::Access,
     {
-        let mut socket = net::TcpStream::connect(address)?;
+        TcpStream::connect_with(ctx, address, TcpStreamOptions::default())
+    }
+
+    /// Same as [`TcpStream::connect`], but sets the socket `options` before
+    /// connecting.
+    ///
+    /// See [`TcpStreamOptions`] for the available options.
+    pub fn connect_with<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        address: SocketAddr,
+        options: TcpStreamOptions,
+    ) -> io::Result<Connect>
+    where
+        RT: rt::Access,
+    {
+        let socket = options.connect(address)?;
+        let mut socket = net::TcpStream::from_std(socket.into());
         ctx.runtime()
             .register(&mut socket, Interest::READABLE | Interest::WRITABLE)?;
         Ok(Connect {
@@ -138,6 +160,11 @@ impl TcpStream {
     /// [kind]: io::Error::kind
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        let buf = {
+            crate::test::tcp_fault()?;
+            &buf[..crate::test::partial_write(buf.len())]
+        };
         SockRef::from(&self.socket).send(buf)
     }
 
@@ -166,6 +193,13 @@ impl TcpStream {
     /// [kind]: io::Error::kind
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        {
+            crate::test::tcp_fault()?;
+            if let Some(bufs) = crate::test::partial_write_vectored(bufs) {
+                return SockRef::from(&self.socket).send_vectored(&bufs);
+            }
+        }
         SockRef::from(&self.socket).send_vectored(bufs)
     }
 
@@ -241,6 +275,8 @@ impl TcpStream {
             buf.has_spare_capacity(),
             "called `TcpStream::try_recv with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::tcp_fault()?;
         SockRef::from(&self.socket)
             .recv(buf.as_bytes())
             .map(|read| {
@@ -347,6 +383,8 @@ impl TcpStream {
             bufs.has_spare_capacity(),
             "called `TcpStream::try_recv_vectored` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::tcp_fault()?;
         let res = SockRef::from(&self.socket)
             .recv_vectored(MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()));
         match res {
@@ -398,6 +436,8 @@ impl TcpStream {
             buf.has_spare_capacity(),
             "called `TcpStream::try_peek with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::tcp_fault()?;
         SockRef::from(&self.socket)
             .peek(buf.as_bytes())
             .map(|read| {
@@ -428,6 +468,8 @@ impl TcpStream {
             bufs.has_spare_capacity(),
             "called `TcpStream::try_peek_vectored` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::tcp_fault()?;
         let res = SockRef::from(&self.socket).recv_vectored_with_flags(
             MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()),
             libc::MSG_PEEK,
@@ -464,6 +506,8 @@ impl TcpStream {
     where
         F: FileSend,
     {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::tcp_fault()?;
         SockRef::from(&self.socket).sendfile(file, offset, length)
     }
 
@@ -545,6 +589,161 @@ impl TcpStream {
     }
 }
 
+/// Options used to create a [`TcpStream`], see [`TcpStream::connect_with`].
+///
+/// All options are set before the stream is connected. Options that are not
+/// set use the OS's default.
+///
+/// # Examples
+///
+/// Using keepalive probes and connecting from a specific local address.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph_rt::net::tcp::stream::TcpStreamOptions;
+///
+/// let options = TcpStreamOptions::default()
+///     .with_local_address("127.0.0.1:0".parse().unwrap())
+///     .with_keepalive_time(Duration::from_secs(60))
+///     .with_keepalive_interval(Duration::from_secs(10))
+///     .with_keepalive_retries(3);
+/// # drop(options); // Silence unused variable warning.
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct TcpStreamOptions {
+    local_address: Option<SocketAddr>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    linger: Option<Duration>,
+    keepalive: Option<TcpKeepalive>,
+    only_v6: Option<bool>,
+    #[cfg(target_os = "linux")]
+    fast_open: bool,
+}
+
+impl TcpStreamOptions {
+    /// Bind the socket to the local `address` before connecting. Use a port of
+    /// zero to let the OS select a port.
+    pub const fn with_local_address(mut self, address: SocketAddr) -> Self {
+        self.local_address = Some(address);
+        self
+    }
+
+    /// Set the size of the send buffer, setting the `SO_SNDBUF` option.
+    pub const fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the size of the receive buffer, setting the `SO_RCVBUF` option.
+    pub const fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the value of the `SO_LINGER` option.
+    ///
+    /// When the stream is dropped the OS will block for at most `duration`
+    /// while sending any unsent data. A `duration` of zero resets the
+    /// connection, discarding all unsent data.
+    pub const fn with_linger(mut self, duration: Duration) -> Self {
+        self.linger = Some(duration);
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`), sending the first probe after
+    /// the connection was idle for `time` (`TCP_KEEPIDLE`).
+    pub fn with_keepalive_time(mut self, time: Duration) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_time(time),
+        );
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`), using an `interval` between
+    /// probes (`TCP_KEEPINTVL`).
+    pub fn with_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_interval(interval),
+        );
+        self
+    }
+
+    /// Enable keepalive probes (`SO_KEEPALIVE`), sending at most `retries`
+    /// probes before dropping the connection (`TCP_KEEPCNT`).
+    pub fn with_keepalive_retries(mut self, retries: u32) -> Self {
+        self.keepalive = Some(
+            self.keepalive
+                .unwrap_or_else(TcpKeepalive::new)
+                .with_retries(retries),
+        );
+        self
+    }
+
+    /// Set the value of the `IPV6_V6ONLY` option, only valid for IPv6
+    /// addresses.
+    pub const fn with_only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Enable TCP Fast Open, setting the `TCP_FASTOPEN_CONNECT` option.
+    ///
+    /// This allows data to be send in the SYN packet when reconnecting to a
+    /// server that supports TCP Fast Open, see [`TcpListenerOptions`]. Note
+    /// that this makes the connect complete before the handshake is done.
+    ///
+    /// This is only available on Linux.
+    ///
+    /// [`TcpListenerOptions`]: crate::net::tcp::listener::TcpListenerOptions
+    #[cfg(target_os = "linux")]
+    pub const fn with_fast_open(mut self, enable: bool) -> Self {
+        self.fast_open = enable;
+        self
+    }
+
+    /// Create a new socket with the options set and start connecting to
+    /// `address`.
+    fn connect(&self, address: SocketAddr) -> io::Result<Socket> {
+        let socket = new_socket(address, Type::STREAM, Protocol::TCP)?;
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(duration) = self.linger {
+            socket.set_linger(Some(duration))?;
+        }
+        if let Some(keepalive) = &self.keepalive {
+            socket.set_tcp_keepalive(keepalive)?;
+        }
+        if let Some(only_v6) = self.only_v6 {
+            socket.set_only_v6(only_v6)?;
+        }
+        #[cfg(target_os = "linux")]
+        if self.fast_open {
+            let fd = socket.as_raw_fd();
+            set_socket_option(fd, libc::IPPROTO_TCP, libc::TCP_FASTOPEN_CONNECT, 1)?;
+        }
+        if let Some(local_address) = self.local_address {
+            socket.bind(&local_address.into())?;
+        }
+
+        match socket.connect(&address.into()) {
+            // Connection is still in progress, see `Connect`.
+            Err(err) if err.raw_os_error() != Some(libc::EINPROGRESS) => Err(err),
+            Ok(()) | Err(_) => Ok(socket),
+        }
+    }
+}
+
 /// The [`Future`] behind [`TcpStream::connect`].
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
@@ -634,9 +833,9 @@ pub struct Send<'a, 'b> {
 impl<'a, 'b> Future for Send<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Send { stream, buf } = Pin::into_inner(self);
-        try_io!(stream.try_send(buf))
+        try_io!(stream.try_send(buf), ctx)
     }
 }
 
@@ -651,7 +850,7 @@ pub struct SendAll<'a, 'b> {
 impl<'a, 'b> Future for SendAll<'a, 'b> {
     type Output = io::Result<()>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let SendAll { stream, buf } = Pin::into_inner(self);
         loop {
             match stream.try_send(buf) {
@@ -662,7 +861,10 @@ impl<'a, 'b> Future for SendAll<'a, 'b> {
                     // Try to send some more bytes.
                     continue;
                 }
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
                 Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => break Poll::Ready(Err(err)),
             }
@@ -681,9 +883,9 @@ pub struct SendVectored<'a, 'b> {
 impl<'a, 'b> Future for SendVectored<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let SendVectored { stream, bufs } = Pin::into_inner(self);
-        try_io!(stream.try_send_vectored(bufs))
+        try_io!(stream.try_send_vectored(bufs), ctx)
     }
 }
 
@@ -698,13 +900,16 @@ pub struct SendVectoredAll<'a, 'b> {
 impl<'a, 'b> Future for SendVectoredAll<'a, 'b> {
     type Output = io::Result<()>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let SendVectoredAll { stream, bufs } = Pin::into_inner(self);
         while !bufs.is_empty() {
             match stream.try_send_vectored(bufs) {
                 Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
                 Ok(n) => IoSlice::advance_slices(bufs, n),
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    return Poll::Pending;
+                }
                 Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => return Poll::Ready(Err(err)),
             }
@@ -727,9 +932,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Recv { stream, buf } = Pin::into_inner(self);
-        try_io!(stream.try_recv(&mut *buf))
+        try_io!(stream.try_recv(&mut *buf), ctx)
     }
 }
 
@@ -747,9 +952,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Peek { stream, buf } = Pin::into_inner(self);
-        try_io!(stream.try_peek(&mut *buf))
+        try_io!(stream.try_peek(&mut *buf), ctx)
     }
 }
 
@@ -768,7 +973,7 @@ where
 {
     type Output = io::Result<()>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvN { stream, buf, left } = Pin::into_inner(self);
         loop {
             match stream.try_recv(&mut *buf) {
@@ -779,7 +984,10 @@ where
                     // Try to read some more bytes.
                     continue;
                 }
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
                 Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => break Poll::Ready(Err(err)),
             }
@@ -801,9 +1009,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvVectored { stream, bufs } = Pin::into_inner(self);
-        try_io!(stream.try_recv_vectored(&mut *bufs))
+        try_io!(stream.try_recv_vectored(&mut *bufs), ctx)
     }
 }
 
@@ -822,7 +1030,7 @@ where
 {
     type Output = io::Result<()>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvNVectored { stream, bufs, left } = Pin::into_inner(self);
         loop {
             match stream.try_recv_vectored(&mut *bufs) {
@@ -833,7 +1041,10 @@ where
                     // Try to read some more bytes.
                     continue;
                 }
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
                 Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => break Poll::Ready(Err(err)),
             }
@@ -855,9 +1066,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let PeekVectored { stream, bufs } = Pin::into_inner(self);
-        try_io!(stream.try_peek_vectored(&mut *bufs))
+        try_io!(stream.try_peek_vectored(&mut *bufs), ctx)
     }
 }
 
@@ -877,10 +1088,10 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         #[rustfmt::skip]
         let SendFile { stream, file, offset, length } = Pin::into_inner(self);
-        try_io!(stream.try_send_file(*file, *offset, *length))
+        try_io!(stream.try_send_file(*file, *offset, *length), ctx)
     }
 }
 
@@ -902,7 +1113,7 @@ where
 {
     type Output = io::Result<()>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         #[rustfmt::skip]
         let SendFileAll { stream, file, start, end } = Pin::into_inner(self);
         loop {
@@ -923,7 +1134,10 @@ where
                         }
                     }
                 }
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
                 Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue, // Try again.
                 Err(err) => break Poll::Ready(Err(err)),
             }
@@ -0,0 +1,34 @@
+//! Transport Layer Security (TLS) related types.
+//!
+//! TLS support is build on top of the TCP types and uses [rustls]. This module
+//! requires the `tls` feature.
+//!
+//! Two main types are provided:
+//!
+//!  * [`TlsStream`] represents a single TLS connection, for both the client
+//!    and server side of the connection.
+//!  * [`server::Accept`] is a [`NewActor`] that performs the TLS handshake
+//!    before starting the actor to handle the connection, see
+//!    [`TcpServer::setup_tls`] to create a [`TcpServer`] that uses it.
+//!
+//! All configuration, such as the certificates to use or the protocols to
+//! support, is done using rustls' [`ClientConfig`] and [`ServerConfig`]. For
+//! example to select the server's certificate based on the server name
+//! indication (SNI) send by the client use [`ResolvesServerCertUsingSni`], and
+//! to negotiate the application protocol (ALPN) set
+//! [`ServerConfig::alpn_protocols`]. The negotiated values are available using
+//! [`TlsStream::server_name`] and [`TlsStream::alpn_protocol`].
+//!
+//! [rustls]: https://docs.rs/rustls
+//! [`NewActor`]: heph::actor::NewActor
+//! [`TcpServer::setup_tls`]: crate::net::TcpServer::setup_tls
+//! [`TcpServer`]: crate::net::TcpServer
+//! [`ResolvesServerCertUsingSni`]: rustls::server::ResolvesServerCertUsingSni
+
+pub mod server;
+pub mod stream;
+
+#[doc(no_inline)]
+pub use rustls::{self, ClientConfig, ServerConfig, ServerName};
+#[doc(no_inline)]
+pub use stream::TlsStream;
@@ -0,0 +1,177 @@
+//! Module with [`Accept`] and related types.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self, Poll};
+use std::{fmt, io, mem};
+
+use heph::actor::{self, Actor, NewActor};
+use rustls::ServerConfig;
+
+use crate::net::tls::stream::{Handshake, TlsStream};
+use crate::net::TcpStream;
+
+/// [`NewActor`] implementation that performs the TLS handshake before
+/// starting the actor to handle the connection.
+///
+/// This wraps a `NewActor` implementation that accepts a [`TlsStream`] and
+/// turns it into one that accepts a [`TcpStream`], which allows it to be used
+/// with [`TcpServer`]. See [`TcpServer::setup_tls`] for a convenient way to
+/// create a TCP server that uses it.
+///
+/// If the handshake fails the error is converted into the error of the actor
+/// created by `NA` and passed to the supervisor. The `TcpStream` is consumed by
+/// the handshake, so the supervisor has no stream to restart the actor with and
+/// the handshake can't be retried. Supervisors should return
+/// [`SupervisorStrategy::Stop`] for these errors, the client is expected to
+/// open a new connection instead. As the actor is created after the handshake,
+/// creating it can't fail, i.e. `NA::Error` must be `!`, which is the case for
+/// asynchronous functions.
+///
+/// [`SupervisorStrategy::Stop`]: heph::supervisor::SupervisorStrategy::Stop
+/// [`TcpServer`]: crate::net::TcpServer
+/// [`TcpServer::setup_tls`]: crate::net::TcpServer::setup_tls
+#[derive(Debug)]
+pub struct Accept<NA> {
+    /// `NewActor` used to create the actor once the handshake is complete.
+    new_actor: NA,
+    /// TLS configuration used in the handshake.
+    config: Arc<ServerConfig>,
+}
+
+impl<NA> Accept<NA> {
+    /// Create a new `Accept`, using `config` in the TLS handshake and
+    /// `new_actor` to start the actor handling the connection.
+    pub const fn new(new_actor: NA, config: Arc<ServerConfig>) -> Accept<NA> {
+        Accept { new_actor, config }
+    }
+}
+
+impl<NA: Clone> Clone for Accept<NA> {
+    fn clone(&self) -> Accept<NA> {
+        Accept {
+            new_actor: self.new_actor.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<NA> NewActor for Accept<NA>
+where
+    NA: NewActor<Argument = (TlsStream, SocketAddr), Error = !> + Clone,
+    <NA::Actor as Actor>::Error: From<io::Error>,
+{
+    type Message = NA::Message;
+    type Argument = (TcpStream, SocketAddr);
+    type Actor = AcceptActor<NA>;
+    type Error = !;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        (stream, address): Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        // NOTE: the `TcpStream` is already bound to the actor by `TcpServer`.
+        let state = match TlsStream::server(stream, self.config.clone()) {
+            Ok(handshake) => State::Handshake {
+                ctx,
+                new_actor: self.new_actor.clone(),
+                address,
+                handshake,
+            },
+            Err(err) => State::Failed(Some(err)),
+        };
+        Ok(AcceptActor { state })
+    }
+}
+
+/// The [`Actor`] behind [`Accept`].
+///
+/// First completes the TLS handshake and then runs the actor created by `NA`.
+pub struct AcceptActor<NA: NewActor> {
+    state: State<NA>,
+}
+
+// The size of the `Running` state depends on `NA::Actor`, which clippy doesn't
+// take into account.
+#[allow(clippy::large_enum_variant)]
+enum State<NA: NewActor> {
+    /// Performing the TLS handshake.
+    Handshake {
+        ctx: actor::Context<NA::Message, NA::RuntimeAccess>,
+        new_actor: NA,
+        address: SocketAddr,
+        handshake: Handshake,
+    },
+    /// Running the actor created by `NA`.
+    Running(NA::Actor),
+    /// Failed to setup the TLS connection.
+    Failed(Option<io::Error>),
+    /// Temporary state used while starting the actor.
+    Starting,
+}
+
+impl<NA: NewActor> fmt::Debug for AcceptActor<NA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state {
+            State::Handshake { .. } => "handshake",
+            State::Running(_) => "running",
+            State::Failed(_) => "failed",
+            State::Starting => "starting",
+        };
+        f.debug_struct("AcceptActor")
+            .field("state", &state)
+            .finish()
+    }
+}
+
+impl<NA> Actor for AcceptActor<NA>
+where
+    NA: NewActor<Argument = (TlsStream, SocketAddr), Error = !>,
+    <NA::Actor as Actor>::Error: From<io::Error>,
+{
+    type Error = <NA::Actor as Actor>::Error;
+
+    fn try_poll(
+        self: Pin<&mut Self>,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // Safety: we only move the `State::Handshake` fields, `NA::Actor` in
+        // `State::Running` is never moved.
+        let this = unsafe { Pin::into_inner_unchecked(self) };
+        loop {
+            match &mut this.state {
+                State::Handshake { handshake, .. } => {
+                    let stream = match Pin::new(handshake).poll(ctx) {
+                        Poll::Ready(Ok(stream)) => stream,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    let State::Handshake {
+                        ctx,
+                        mut new_actor,
+                        address,
+                        ..
+                    } = mem::replace(&mut this.state, State::Starting)
+                    else {
+                        unreachable!()
+                    };
+                    this.state = match new_actor.new(ctx, (stream, address)) {
+                        Ok(actor) => State::Running(actor),
+                        Err(err) => err,
+                    };
+                }
+                // Safety: see the comment above, `actor` is never moved.
+                State::Running(actor) => return unsafe { Pin::new_unchecked(actor) }.try_poll(ctx),
+                State::Failed(err) => match err.take() {
+                    Some(err) => return Poll::Ready(Err(err.into())),
+                    None => panic!("polled `tls::server::AcceptActor` after completion"),
+                },
+                State::Starting => panic!("polled `tls::server::AcceptActor` after completion"),
+            }
+        }
+    }
+}
@@ -0,0 +1,788 @@
+//! Module with [`TlsStream`] and related types.
+
+use std::future::Future;
+use std::io::{self, IoSlice, Read, Write};
+use std::mem::MaybeUninit;
+use std::net::{Shutdown, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self, Poll};
+
+use heph::actor;
+use rustls::{ClientConfig, ClientConnection, Connection, ProtocolVersion, ServerConfig};
+use rustls::{ServerConnection, ServerName};
+
+use crate::bytes::{Bytes, BytesVectored};
+use crate::net::tcp::{self, TcpStream};
+use crate::{self as rt, Bound};
+
+/// A non-blocking TLS stream between a local socket and a remote socket.
+///
+/// A `TlsStream` can be created using [`TlsStream::connect`] to connect to a
+/// server, or from an existing [`TcpStream`] using [`TlsStream::client`] or
+/// [`TlsStream::server`] for the client and server side respectively. In all
+/// cases the TLS handshake is completed before the stream is returned.
+///
+/// # Notes
+///
+/// Bytes send are encrypted and buffered before they're written to the
+/// underlying TCP stream. If the TCP stream is not ready to accept more bytes
+/// the bytes remain buffered, they're written on the next call to any of the
+/// send or receive methods. Use [`TlsStream::flush`] to ensure all buffered
+/// bytes are written, [`TlsStream::send_all`] and
+/// [`TlsStream::send_vectored_all`] do this automatically.
+///
+/// If the peer closes the connection without sending a `close_notify` alert
+/// the receive methods return an [`io::ErrorKind::UnexpectedEof`] error,
+/// rather than `Ok(0)`. Use [`TlsStream::close`] to close the connection
+/// cleanly.
+///
+/// # Examples
+///
+/// Sending `Hello world!` to a peer.
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+/// use std::sync::Arc;
+///
+/// use heph::actor;
+/// use heph_rt::net::tls::{ClientConfig, TlsStream};
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>, config: Arc<ClientConfig>) -> io::Result<()> {
+///     let address = "127.0.0.1:12345".parse().unwrap();
+///     let server_name = "localhost".try_into().unwrap();
+///     let mut stream = TlsStream::connect(&mut ctx, address, config, server_name)?.await?;
+///     stream.send_all(b"Hello world!").await?;
+///     stream.close().await
+/// }
+/// #
+/// # _ = actor; // Silence dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct TlsStream {
+    /// Underlying TCP connection.
+    stream: TcpStream,
+    /// TLS state of the connection.
+    tls: Connection,
+}
+
+impl TlsStream {
+    /// Create a new TLS stream, issuing a non-blocking connect to the
+    /// specified `address` and performing the TLS handshake.
+    ///
+    /// The `server_name` is used to verify the certificate of the server and
+    /// is send to the server using the server name indication (SNI) extension.
+    ///
+    /// # Notes
+    ///
+    /// The stream is also [bound] to the actor that owns the `actor::Context`,
+    /// which means the actor will be run every time the stream is ready to read
+    /// or write.
+    ///
+    /// [bound]: crate::Bound
+    pub fn connect<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        address: SocketAddr,
+        config: Arc<ClientConfig>,
+        server_name: ServerName,
+    ) -> io::Result<Connect>
+    where
+        RT: rt::Access,
+    {
+        let tls = ClientConnection::new(config, server_name).map_err(tls_error)?;
+        TcpStream::connect(ctx, address).map(|connect| Connect {
+            connect,
+            tls: Some(tls.into()),
+            handshake: None,
+        })
+    }
+
+    /// Perform the client side of the TLS handshake on `stream`.
+    ///
+    /// See [`TlsStream::connect`] for the meaning of `server_name`.
+    pub fn client(
+        stream: TcpStream,
+        config: Arc<ClientConfig>,
+        server_name: ServerName,
+    ) -> io::Result<Handshake> {
+        ClientConnection::new(config, server_name)
+            .map(|tls| Handshake::new(stream, tls.into()))
+            .map_err(tls_error)
+    }
+
+    /// Perform the server side of the TLS handshake on `stream`.
+    pub fn server(stream: TcpStream, config: Arc<ServerConfig>) -> io::Result<Handshake> {
+        ServerConnection::new(config)
+            .map(|tls| Handshake::new(stream, tls.into()))
+            .map_err(tls_error)
+    }
+
+    /// Returns the socket address of the remote peer of this TLS connection.
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// Returns the socket address of the local half of this TLS connection.
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// Returns the application protocol negotiated using ALPN, if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.tls.alpn_protocol()
+    }
+
+    /// Returns the TLS protocol version used.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.tls.protocol_version()
+    }
+
+    /// Returns the server name send by the client using the server name
+    /// indication (SNI) extension.
+    ///
+    /// Always returns `None` for the client side of the connection.
+    pub fn server_name(&self) -> Option<&str> {
+        match &self.tls {
+            Connection::Server(tls) => tls.server_name(),
+            Connection::Client(_) => None,
+        }
+    }
+
+    /// Attempt to send bytes in `buf` to the peer.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`TlsStream::send`] or [`TlsStream::send_all`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Don't buffer more bytes if we can't write the currently buffered
+        // bytes.
+        self.try_flush()?;
+        let n = self.tls.writer().write(buf)?;
+        self.try_flush_buffered().map(|()| n)
+    }
+
+    /// Send the bytes in `buf` to the peer.
+    ///
+    /// Return the number of bytes written. This may we fewer then the length of
+    /// `buf`. To ensure that all bytes are written use [`TlsStream::send_all`].
+    pub fn send<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Send<'a, 'b> {
+        Send { stream: self, buf }
+    }
+
+    /// Send the all bytes in `buf` to the peer.
+    ///
+    /// If this fails to send all bytes (this happens if a write returns
+    /// `Ok(0)`) this will return [`io::ErrorKind::WriteZero`].
+    pub fn send_all<'a, 'b>(&'a mut self, buf: &'b [u8]) -> SendAll<'a, 'b> {
+        SendAll { stream: self, buf }
+    }
+
+    /// Attempt to send bytes in `bufs` to the peer.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`TlsStream::send_vectored`] or [`TlsStream::send_vectored_all`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.try_flush()?;
+        let n = self.tls.writer().write_vectored(bufs)?;
+        self.try_flush_buffered().map(|()| n)
+    }
+
+    /// Send the bytes in `bufs` to the peer.
+    ///
+    /// Return the number of bytes written. This may we fewer then the length of
+    /// `bufs`. To ensure that all bytes are written use
+    /// [`TlsStream::send_vectored_all`].
+    pub fn send_vectored<'a, 'b>(
+        &'a mut self,
+        bufs: &'b mut [IoSlice<'b>],
+    ) -> SendVectored<'a, 'b> {
+        SendVectored { stream: self, bufs }
+    }
+
+    /// Send the all bytes in `bufs` to the peer.
+    ///
+    /// If this fails to send all bytes (this happens if a write returns
+    /// `Ok(0)`) this will return [`io::ErrorKind::WriteZero`].
+    pub fn send_vectored_all<'a, 'b>(
+        &'a mut self,
+        bufs: &'b mut [IoSlice<'b>],
+    ) -> SendVectoredAll<'a, 'b> {
+        SendVectoredAll { stream: self, bufs }
+    }
+
+    /// Attempt to write all buffered bytes to the underlying TCP stream.
+    ///
+    /// If not all bytes can currently be written this will return an error
+    /// with the [kind] set to [`ErrorKind::WouldBlock`]. Most users should
+    /// prefer to use [`TlsStream::flush`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_flush(&mut self) -> io::Result<()> {
+        while self.tls.wants_write() {
+            if self.tls.write_tls(&mut Io(&mut self.stream))? == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Write all buffered bytes to the underlying TCP stream.
+    pub fn flush(&mut self) -> Flush<'_> {
+        Flush { stream: self }
+    }
+
+    /// Same as [`TlsStream::try_flush`], but doesn't return an error if not
+    /// all bytes can be written at this time.
+    fn try_flush_buffered(&mut self) -> io::Result<()> {
+        match self.try_flush() {
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Attempt to receive message(s) from the stream, writing them into `buf`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`TlsStream::recv`] or [`TlsStream::recv_n`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv<B>(&mut self, mut buf: B) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `TlsStream::try_recv with an empty buffer"
+        );
+        self.read_plaintext(buf.as_bytes()).inspect(|&read| {
+            // Safety: just read the bytes.
+            unsafe { buf.update_length(read) }
+        })
+    }
+
+    /// Receive messages from the stream, writing them into `buf`.
+    pub fn recv<'a, B>(&'a mut self, buf: B) -> Recv<'a, B>
+    where
+        B: Bytes,
+    {
+        Recv { stream: self, buf }
+    }
+
+    /// Receive at least `n` bytes from the stream, writing them into `buf`.
+    ///
+    /// This returns a [`Future`] that receives at least `n` bytes from a
+    /// `TlsStream` and writes them into buffer `B`, or returns
+    /// [`io::ErrorKind::UnexpectedEof`] if less then `n` bytes could be read.
+    pub fn recv_n<'a, B>(&'a mut self, buf: B, n: usize) -> RecvN<'a, B>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.spare_capacity() >= n,
+            "called `TlsStream::recv_n` with a buffer smaller then `n`"
+        );
+        RecvN {
+            stream: self,
+            buf,
+            left: n,
+        }
+    }
+
+    /// Attempt to receive message(s) from the stream, writing them into `bufs`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`TlsStream::recv_vectored`] or [`TlsStream::recv_n_vectored`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_vectored<B>(&mut self, mut bufs: B) -> io::Result<usize>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.has_spare_capacity(),
+            "called `TlsStream::try_recv_vectored` with empty buffers"
+        );
+        let mut read = 0;
+        for buf in bufs.as_bufs().as_mut() {
+            if buf.is_empty() {
+                continue;
+            }
+            match self.read_plaintext(buf) {
+                Ok(n) => {
+                    read += n;
+                    if n < buf.len() {
+                        // No more bytes available.
+                        break;
+                    }
+                }
+                Err(err) if read == 0 => return Err(err),
+                // Return the bytes we did read, the error will be returned in
+                // the next call (if it's not a `WouldBlock` error).
+                Err(_) => break,
+            }
+        }
+        // Safety: just read the bytes.
+        unsafe { bufs.update_lengths(read) }
+        Ok(read)
+    }
+
+    /// Receive messages from the stream, writing them into `bufs`.
+    pub fn recv_vectored<B>(&mut self, bufs: B) -> RecvVectored<'_, B>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.has_spare_capacity(),
+            "called `TlsStream::recv_vectored` with empty buffers"
+        );
+        RecvVectored { stream: self, bufs }
+    }
+
+    /// Receive at least `n` bytes from the stream, writing them into `bufs`.
+    pub fn recv_n_vectored<B>(&mut self, bufs: B, n: usize) -> RecvNVectored<'_, B>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.spare_capacity() >= n,
+            "called `TlsStream::recv_n_vectored` with a buffer smaller then `n`"
+        );
+        RecvNVectored {
+            stream: self,
+            bufs,
+            left: n,
+        }
+    }
+
+    /// Read plaintext bytes into `buf`, reading TLS records from the
+    /// underlying TCP stream if no bytes are available.
+    fn read_plaintext(&mut self, buf: &mut [MaybeUninit<u8>]) -> io::Result<usize> {
+        // Write any bytes still buffered, e.g. from a previous send. Errors
+        // will be returned by the next call to a send method.
+        let _ = self.try_flush();
+        // Rustls only reads into initialised bytes.
+        buf.fill(MaybeUninit::new(0));
+        // Safety: initialised all bytes above.
+        let buf = unsafe { &mut *(buf as *mut [MaybeUninit<u8>] as *mut [u8]) };
+        loop {
+            match self.tls.reader().read(buf) {
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+            // No plaintext bytes available, read another TLS record. If this
+            // hits the end of the TCP stream the next call to `read` above
+            // will return `Ok(0)` or an `UnexpectedEof` error.
+            let _ = self.read_tls()?;
+        }
+    }
+
+    /// Read TLS records from the underlying TCP stream and process them.
+    ///
+    /// Returns the number of bytes read, 0 meaning the peer closed the
+    /// connection.
+    fn read_tls(&mut self) -> io::Result<usize> {
+        let n = self.tls.read_tls(&mut Io(&mut self.stream))?;
+        if let Err(err) = self.tls.process_new_packets() {
+            // Attempt to send the alert describing the error to the peer, but
+            // return the original error.
+            let _ = self.try_flush();
+            return Err(tls_error(err));
+        }
+        Ok(n)
+    }
+
+    /// Attempt to complete the TLS handshake.
+    fn try_handshake(&mut self) -> io::Result<()> {
+        loop {
+            self.try_flush()?;
+            if !self.tls.is_handshaking() {
+                return Ok(());
+            }
+            if self.read_tls()? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed during TLS handshake",
+                ));
+            }
+        }
+    }
+
+    /// Sends a `close_notify` alert to the peer, closing the TLS connection,
+    /// and shuts down the writing half of the underlying TCP stream.
+    ///
+    /// This allows the peer to determine the connection was closed cleanly.
+    /// Bytes can still be received after this is called.
+    pub fn close(&mut self) -> Close<'_> {
+        self.tls.send_close_notify();
+        Close { stream: self }
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
+        self.stream.take_error()
+    }
+}
+
+/// Convert a TLS error into an I/O error.
+#[allow(clippy::needless_pass_by_value)]
+fn tls_error(err: rustls::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Wrapper around [`TcpStream`] that implements [`Read`] and [`Write`] so it
+/// can be used by rustls.
+struct Io<'a>(&'a mut TcpStream);
+
+impl<'a> Read for Io<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.0.socket).read(buf)
+    }
+}
+
+impl<'a> Write for Io<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.try_send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.0.try_send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::connect`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Connect {
+    connect: tcp::stream::Connect,
+    /// TLS state, `None` once the TCP stream is connected.
+    tls: Option<Connection>,
+    /// Handshake, `Some` once the TCP stream is connected.
+    handshake: Option<Handshake>,
+}
+
+impl Future for Connect {
+    type Output = io::Result<TlsStream>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let Some(tls) = this.tls.take() {
+            match Pin::new(&mut this.connect).poll(ctx) {
+                Poll::Ready(Ok(stream)) => this.handshake = Some(Handshake::new(stream, tls)),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => {
+                    this.tls = Some(tls);
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        match this.handshake.as_mut() {
+            Some(handshake) => Pin::new(handshake).poll(ctx),
+            None => panic!("polled `tls::stream::Connect` after completion"),
+        }
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::client`] and [`TlsStream::server`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Handshake {
+    stream: Option<TlsStream>,
+}
+
+impl Handshake {
+    const fn new(stream: TcpStream, tls: Connection) -> Handshake {
+        Handshake {
+            stream: Some(TlsStream { stream, tls }),
+        }
+    }
+}
+
+impl Future for Handshake {
+    type Output = io::Result<TlsStream>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match self.stream.as_mut() {
+            Some(stream) => match try_io!(stream.try_handshake(), ctx) {
+                Poll::Ready(Ok(())) => Poll::Ready(Ok(self.stream.take().unwrap())),
+                Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+                Poll::Pending => Poll::Pending,
+            },
+            None => panic!("polled `tls::stream::Handshake` after completion"),
+        }
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::send`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Send<'a, 'b> {
+    stream: &'a mut TlsStream,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for Send<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Send { stream, buf } = Pin::into_inner(self);
+        try_io!(stream.try_send(buf), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::send_all`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendAll<'a, 'b> {
+    stream: &'a mut TlsStream,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for SendAll<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendAll { stream, buf } = Pin::into_inner(self);
+        while !buf.is_empty() {
+            match stream.try_send(buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => *buf = &buf[n..],
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    return Poll::Pending;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        // Ensure all bytes are actually send.
+        try_io!(stream.try_flush(), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::send_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendVectored<'a, 'b> {
+    stream: &'a mut TlsStream,
+    bufs: &'b mut [IoSlice<'b>],
+}
+
+impl<'a, 'b> Future for SendVectored<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendVectored { stream, bufs } = Pin::into_inner(self);
+        try_io!(stream.try_send_vectored(bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::send_vectored_all`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendVectoredAll<'a, 'b> {
+    stream: &'a mut TlsStream,
+    bufs: &'b mut [IoSlice<'b>],
+}
+
+impl<'a, 'b> Future for SendVectoredAll<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendVectoredAll { stream, bufs } = Pin::into_inner(self);
+        while !bufs.is_empty() {
+            match stream.try_send_vectored(bufs) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => IoSlice::advance_slices(bufs, n),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    return Poll::Pending;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        // Ensure all bytes are actually send.
+        try_io!(stream.try_flush(), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::flush`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Flush<'a> {
+    stream: &'a mut TlsStream,
+}
+
+impl<'a> Future for Flush<'a> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Flush { stream } = Pin::into_inner(self);
+        try_io!(stream.try_flush(), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::close`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Close<'a> {
+    stream: &'a mut TlsStream,
+}
+
+impl<'a> Future for Close<'a> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Close { stream } = Pin::into_inner(self);
+        match try_io!(stream.try_flush(), ctx) {
+            Poll::Ready(Ok(())) => Poll::Ready(stream.stream.shutdown(Shutdown::Write)),
+            poll => poll,
+        }
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::recv`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Recv<'b, B> {
+    stream: &'b mut TlsStream,
+    buf: B,
+}
+
+impl<'b, B> Future for Recv<'b, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Recv { stream, buf } = Pin::into_inner(self);
+        try_io!(stream.try_recv(&mut *buf), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::recv_n`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvN<'b, B> {
+    stream: &'b mut TlsStream,
+    buf: B,
+    left: usize,
+}
+
+impl<'b, B> Future for RecvN<'b, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvN { stream, buf, left } = Pin::into_inner(self);
+        loop {
+            match stream.try_recv(&mut *buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) if n >= *left => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *left -= n;
+                    // Try to read some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::recv_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvVectored<'b, B> {
+    stream: &'b mut TlsStream,
+    bufs: B,
+}
+
+impl<'b, B> Future for RecvVectored<'b, B>
+where
+    B: BytesVectored + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvVectored { stream, bufs } = Pin::into_inner(self);
+        try_io!(stream.try_recv_vectored(&mut *bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`TlsStream::recv_n_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvNVectored<'b, B> {
+    stream: &'b mut TlsStream,
+    bufs: B,
+    left: usize,
+}
+
+impl<'b, B> Future for RecvNVectored<'b, B>
+where
+    B: BytesVectored + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvNVectored { stream, bufs, left } = Pin::into_inner(self);
+        loop {
+            match stream.try_recv_vectored(&mut *bufs) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) if n >= *left => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *left -= n;
+                    // Try to read some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    crate::net::wake_after_fault(err, ctx);
+                    break Poll::Pending;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+impl<RT: rt::Access> Bound<RT> for TlsStream {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        self.stream.bind_to(ctx)
+    }
+}
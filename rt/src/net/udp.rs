@@ -11,18 +11,26 @@ use std::fmt;
 use std::future::Future;
 use std::io::{self, IoSlice};
 use std::marker::PhantomData;
-use std::net::SocketAddr;
+#[cfg(target_os = "linux")]
+use std::mem::{self, size_of, MaybeUninit};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
+#[cfg(target_os = "linux")]
+use std::ptr;
 use std::task::{self, Poll};
 
 use heph::actor;
 #[cfg(target_os = "linux")]
 use log::warn;
 use mio::{net, Interest};
-use socket2::{SockAddr, SockRef};
+use socket2::{Protocol, SockAddr, SockRef, Socket, Type};
 
 use crate::bytes::{Bytes, BytesVectored, MaybeUninitSlice};
-use crate::net::convert_address;
+use crate::net::{convert_address, new_socket};
+#[cfg(target_os = "linux")]
+use crate::net::{set_socket_option, socket_option};
 use crate::{self as rt, Bound};
 
 /// The unconnected mode of an [`UdpSocket`].
@@ -54,6 +62,18 @@ pub enum Connected {}
 /// Both unconnected and connected sockets have three main operations send,
 /// receive and peek, all these methods return a [`Future`].
 ///
+/// On Linux multiple datagrams can be send or received using a single system
+/// call, see [`UdpSocket::send_to_many`] and [`UdpSocket::recv_from_many`] (or
+/// [`UdpSocket::send_many`] and [`UdpSocket::recv_many`] for connected
+/// sockets). Connected sockets can further reduce the number of system calls
+/// using UDP segmentation offload, see [`UdpSocket::set_gso_segment_size`] and
+/// [`UdpSocket::set_gro`].
+///
+/// Sockets can also receive multicast datagrams after joining a multicast
+/// group using [`UdpSocket::join_multicast_v4`] or
+/// [`UdpSocket::join_multicast_v6`]. Sending broadcast datagrams requires the
+/// `SO_BROADCAST` option, see [`UdpSocket::set_broadcast`].
+///
 /// [connected]: UdpSocket::connect
 ///
 /// # Examples
@@ -159,7 +179,23 @@ impl UdpSocket {
     where
         RT: rt::Access,
     {
-        let mut socket = net::UdpSocket::bind(local)?;
+        UdpSocket::bind_with(ctx, local, UdpSocketOptions::default())
+    }
+
+    /// Same as [`UdpSocket::bind`], but sets the socket `options` before
+    /// binding.
+    ///
+    /// See [`UdpSocketOptions`] for the available options.
+    pub fn bind_with<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        local: SocketAddr,
+        options: UdpSocketOptions,
+    ) -> io::Result<UdpSocket<Unconnected>>
+    where
+        RT: rt::Access,
+    {
+        let socket = options.bind(local)?;
+        let mut socket = net::UdpSocket::from_std(socket.into());
         ctx.runtime()
             .register(&mut socket, Interest::READABLE | Interest::WRITABLE)?;
         #[cfg(target_os = "linux")]
@@ -175,6 +211,83 @@ impl UdpSocket {
     }
 }
 
+/// Options used to create an [`UdpSocket`], see [`UdpSocket::bind_with`].
+///
+/// All options are set before the socket is bound. Options that are not set
+/// use the OS's default.
+///
+/// # Examples
+///
+/// Using a larger receive buffer and allowing multiple sockets to bind to the
+/// same port.
+///
+/// ```
+/// use heph_rt::net::udp::UdpSocketOptions;
+///
+/// let options = UdpSocketOptions::default()
+///     .with_recv_buffer_size(1024 * 1024)
+///     .with_reuse_port(true);
+/// # drop(options); // Silence unused variable warning.
+/// ```
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct UdpSocketOptions {
+    reuse_port: bool,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    only_v6: Option<bool>,
+}
+
+impl UdpSocketOptions {
+    /// Set the value of the `SO_REUSEPORT` option, allowing multiple sockets
+    /// to bind to the same address and port.
+    pub const fn with_reuse_port(mut self, reuse_port: bool) -> Self {
+        self.reuse_port = reuse_port;
+        self
+    }
+
+    /// Set the size of the send buffer, setting the `SO_SNDBUF` option.
+    pub const fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the size of the receive buffer, setting the `SO_RCVBUF` option.
+    pub const fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the value of the `IPV6_V6ONLY` option, only valid for IPv6
+    /// addresses.
+    ///
+    /// If enabled a socket bound to the unspecified IPv6 address (`::`) will
+    /// not receive IPv4 datagrams.
+    pub const fn with_only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Create a new socket with the options set, bound to `address`.
+    fn bind(&self, address: SocketAddr) -> io::Result<Socket> {
+        let socket = new_socket(address, Type::DGRAM, Protocol::UDP)?;
+        if self.reuse_port {
+            socket.set_reuse_port(true)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(only_v6) = self.only_v6 {
+            socket.set_only_v6(only_v6)?;
+        }
+        socket.bind(&address.into())?;
+        Ok(socket)
+    }
+}
+
 impl<M> UdpSocket<M> {
     /// Connects the UDP socket by setting the default destination and limiting
     /// packets that are read, written and peeked to the `remote` address.
@@ -198,6 +311,145 @@ impl<M> UdpSocket<M> {
     pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
         self.socket.take_error()
     }
+
+    /// Sets the value of the `SO_BROADCAST` option for this socket.
+    ///
+    /// When enabled, this socket is allowed to send packets to a broadcast
+    /// address.
+    pub fn set_broadcast(&mut self, broadcast: bool) -> io::Result<()> {
+        self.socket.set_broadcast(broadcast)
+    }
+
+    /// Gets the value of the `SO_BROADCAST` option for this socket.
+    pub fn broadcast(&mut self) -> io::Result<bool> {
+        self.socket.broadcast()
+    }
+
+    /// Join the IPv4 multicast group `multiaddr`, using the `IP_ADD_MEMBERSHIP`
+    /// option.
+    ///
+    /// `interface` is the address of the local interface with which the system
+    /// should join the multicast group. If it's equal to
+    /// [`Ipv4Addr::UNSPECIFIED`] then an appropriate interface is chosen by the
+    /// system.
+    pub fn join_multicast_v4(
+        &mut self,
+        multiaddr: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        self.socket.join_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Leave the IPv4 multicast group `multiaddr`, using the
+    /// `IP_DROP_MEMBERSHIP` option.
+    ///
+    /// For more information about this option, see
+    /// [`UdpSocket::join_multicast_v4`].
+    pub fn leave_multicast_v4(
+        &mut self,
+        multiaddr: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        self.socket.leave_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Join the IPv6 multicast group `multiaddr`, using the
+    /// `IPV6_ADD_MEMBERSHIP` option.
+    ///
+    /// `interface` is the index of the local interface with which the system
+    /// should join the multicast group. If it's zero then an appropriate
+    /// interface is chosen by the system.
+    pub fn join_multicast_v6(&mut self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.socket.join_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Leave the IPv6 multicast group `multiaddr`, using the
+    /// `IPV6_DROP_MEMBERSHIP` option.
+    ///
+    /// For more information about this option, see
+    /// [`UdpSocket::join_multicast_v6`].
+    pub fn leave_multicast_v6(&mut self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.socket.leave_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Sets the value of the `IP_MULTICAST_IF` option for this socket.
+    ///
+    /// Specifies the address of the local interface used to send outgoing
+    /// IPv4 multicast datagrams. If it's equal to [`Ipv4Addr::UNSPECIFIED`]
+    /// then an appropriate interface is chosen by the system.
+    pub fn set_multicast_if_v4(&mut self, interface: Ipv4Addr) -> io::Result<()> {
+        SockRef::from(&self.socket).set_multicast_if_v4(&interface)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_IF` option for this socket.
+    pub fn multicast_if_v4(&mut self) -> io::Result<Ipv4Addr> {
+        SockRef::from(&self.socket).multicast_if_v4()
+    }
+
+    /// Sets the value of the `IPV6_MULTICAST_IF` option for this socket.
+    ///
+    /// Specifies the index of the local interface used to send outgoing IPv6
+    /// multicast datagrams. If it's zero then an appropriate interface is
+    /// chosen by the system.
+    pub fn set_multicast_if_v6(&mut self, interface: u32) -> io::Result<()> {
+        SockRef::from(&self.socket).set_multicast_if_v6(interface)
+    }
+
+    /// Gets the value of the `IPV6_MULTICAST_IF` option for this socket.
+    pub fn multicast_if_v6(&mut self) -> io::Result<u32> {
+        SockRef::from(&self.socket).multicast_if_v6()
+    }
+
+    /// Sets the value of the `IP_MULTICAST_TTL` option for this socket.
+    ///
+    /// Indicates the time-to-live value of outgoing IPv4 multicast datagrams,
+    /// defaults to 1 which means the datagrams will not leave the local
+    /// network.
+    pub fn set_multicast_ttl_v4(&mut self, ttl: u32) -> io::Result<()> {
+        self.socket.set_multicast_ttl_v4(ttl)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_TTL` option for this socket.
+    pub fn multicast_ttl_v4(&mut self) -> io::Result<u32> {
+        self.socket.multicast_ttl_v4()
+    }
+
+    /// Sets the value of the `IPV6_MULTICAST_HOPS` option for this socket.
+    ///
+    /// The IPv6 version of [`UdpSocket::set_multicast_ttl_v4`].
+    pub fn set_multicast_hops_v6(&mut self, hops: u32) -> io::Result<()> {
+        SockRef::from(&self.socket).set_multicast_hops_v6(hops)
+    }
+
+    /// Gets the value of the `IPV6_MULTICAST_HOPS` option for this socket.
+    pub fn multicast_hops_v6(&mut self) -> io::Result<u32> {
+        SockRef::from(&self.socket).multicast_hops_v6()
+    }
+
+    /// Sets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    ///
+    /// If enabled, outgoing IPv4 multicast datagrams are looped back to the
+    /// local sockets that joined the multicast group.
+    pub fn set_multicast_loop_v4(&mut self, multicast_loop: bool) -> io::Result<()> {
+        self.socket.set_multicast_loop_v4(multicast_loop)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    pub fn multicast_loop_v4(&mut self) -> io::Result<bool> {
+        self.socket.multicast_loop_v4()
+    }
+
+    /// Sets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    ///
+    /// The IPv6 version of [`UdpSocket::set_multicast_loop_v4`].
+    pub fn set_multicast_loop_v6(&mut self, multicast_loop: bool) -> io::Result<()> {
+        self.socket.set_multicast_loop_v6(multicast_loop)
+    }
+
+    /// Gets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    pub fn multicast_loop_v6(&mut self) -> io::Result<bool> {
+        self.socket.multicast_loop_v6()
+    }
 }
 
 impl UdpSocket<Unconnected> {
@@ -210,6 +462,8 @@ impl UdpSocket<Unconnected> {
     /// [kind]: io::Error::kind
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_send_to(&mut self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         self.socket.send_to(buf, target)
     }
 
@@ -236,6 +490,8 @@ impl UdpSocket<Unconnected> {
         bufs: &[IoSlice<'_>],
         target: SocketAddr,
     ) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket).send_to_vectored(bufs, &target.into())
     }
 
@@ -271,6 +527,8 @@ impl UdpSocket<Unconnected> {
             buf.has_spare_capacity(),
             "called `UdpSocket::try_recv_from` with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket)
             .recv_from(buf.as_bytes())
             .and_then(|(read, address)| {
@@ -307,6 +565,8 @@ impl UdpSocket<Unconnected> {
             bufs.has_spare_capacity(),
             "called `UdpSocket::try_recv_from` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         let res = SockRef::from(&self.socket)
             .recv_from_vectored(MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()));
         match res {
@@ -346,6 +606,8 @@ impl UdpSocket<Unconnected> {
             buf.has_spare_capacity(),
             "called `UdpSocket::try_peek_from` with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket)
             .peek_from(buf.as_bytes())
             .and_then(|(read, address)| {
@@ -383,6 +645,8 @@ impl UdpSocket<Unconnected> {
             bufs.has_spare_capacity(),
             "called `UdpSocket::try_peek_from_vectored` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         let res = SockRef::from(&self.socket).recv_from_vectored_with_flags(
             MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()),
             libc::MSG_PEEK,
@@ -408,6 +672,82 @@ impl UdpSocket<Unconnected> {
     {
         PeekFromVectored { socket: self, bufs }
     }
+
+    /// Attempt to send multiple datagrams, each buffer to its accompanying
+    /// address, using a single system call.
+    ///
+    /// Returns the number of datagrams send, which may be fewer then the length
+    /// of `bufs`.
+    ///
+    /// If no datagrams can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UdpSocket::send_to_many`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_send_to_many(&mut self, bufs: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
+        let targets: Vec<SockAddr> = bufs.iter().map(|(_, target)| (*target).into()).collect();
+        let bufs: Vec<&[u8]> = bufs.iter().map(|(buf, _)| *buf).collect();
+        send_many(self.socket.as_raw_fd(), &bufs, &targets)
+    }
+
+    /// Send multiple datagrams, each buffer to its accompanying address, using
+    /// a single system call. Returns a [`Future`] that on success returns the
+    /// number of datagrams send (`io::Result<usize>`).
+    ///
+    /// This uses `sendmmsg(2)` and is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn send_to_many<'a, 'b>(
+        &'a mut self,
+        bufs: &'b [(&'b [u8], SocketAddr)],
+    ) -> SendToMany<'a, 'b> {
+        SendToMany { socket: self, bufs }
+    }
+
+    /// Attempt to receive multiple datagrams, one per buffer in `bufs`, using a
+    /// single system call.
+    ///
+    /// Returns the address from whence the datagram came for each received
+    /// datagram, the first address belonging to the first buffer etc. Buffers
+    /// after the number of returned addresses are not modified.
+    ///
+    /// If no datagrams can currently be received this will return an error with
+    /// the [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to
+    /// use [`UdpSocket::recv_from_many`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_recv_from_many<B>(&mut self, bufs: &mut [B]) -> io::Result<Vec<SocketAddr>>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            bufs.iter().all(Bytes::has_spare_capacity),
+            "called `UdpSocket::try_recv_from_many` with an empty buffer"
+        );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
+        let mut addresses = Vec::with_capacity(bufs.len());
+        _ = recv_many(self.socket.as_raw_fd(), bufs, Some(&mut addresses))?;
+        Ok(addresses)
+    }
+
+    /// Receives multiple datagrams, one per buffer in `bufs`, using a single
+    /// system call. Returns a [`Future`] that on success returns the address
+    /// from whence each received datagram came (`io::Result<Vec<SocketAddr>>`).
+    ///
+    /// This uses `recvmmsg(2)` and is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn recv_from_many<'a, 'b, B>(&'a mut self, bufs: &'b mut [B]) -> RecvFromMany<'a, 'b, B>
+    where
+        B: Bytes,
+    {
+        RecvFromMany { socket: self, bufs }
+    }
 }
 
 /// The [`Future`] behind [`UdpSocket::send_to`].
@@ -422,10 +762,10 @@ pub struct SendTo<'a, 'b> {
 impl<'a, 'b> Future for SendTo<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         #[rustfmt::skip]
         let SendTo { socket, buf, target } = Pin::into_inner(self);
-        try_io!(socket.try_send_to(buf, *target))
+        try_io!(socket.try_send_to(buf, *target), ctx)
     }
 }
 
@@ -441,10 +781,15 @@ pub struct SendToVectored<'a, 'b> {
 impl<'a, 'b> Future for SendToVectored<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         #[rustfmt::skip]
         let SendToVectored { socket, bufs, target } = Pin::into_inner(self);
-        try_io!(SockRef::from(&socket.socket).send_to_vectored(bufs, target))
+        let send = || {
+            #[cfg(any(test, feature = "test"))]
+            crate::test::udp_fault()?;
+            SockRef::from(&socket.socket).send_to_vectored(bufs, target)
+        };
+        try_io!(send(), ctx)
     }
 }
 
@@ -462,9 +807,9 @@ where
 {
     type Output = io::Result<(usize, SocketAddr)>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvFrom { socket, buf } = Pin::into_inner(self);
-        try_io!(socket.try_recv_from(&mut *buf))
+        try_io!(socket.try_recv_from(&mut *buf), ctx)
     }
 }
 
@@ -482,9 +827,9 @@ where
 {
     type Output = io::Result<(usize, SocketAddr)>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvFromVectored { socket, bufs } = Pin::into_inner(self);
-        try_io!(socket.try_recv_from_vectored(&mut *bufs))
+        try_io!(socket.try_recv_from_vectored(&mut *bufs), ctx)
     }
 }
 
@@ -502,9 +847,9 @@ where
 {
     type Output = io::Result<(usize, SocketAddr)>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let PeekFrom { socket, buf } = Pin::into_inner(self);
-        try_io!(socket.try_peek_from(&mut *buf))
+        try_io!(socket.try_peek_from(&mut *buf), ctx)
     }
 }
 
@@ -522,9 +867,50 @@ where
 {
     type Output = io::Result<(usize, SocketAddr)>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let PeekFromVectored { socket, bufs } = Pin::into_inner(self);
-        try_io!(socket.try_peek_from_vectored(&mut *bufs))
+        try_io!(socket.try_peek_from_vectored(&mut *bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`UdpSocket::send_to_many`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendToMany<'a, 'b> {
+    socket: &'a mut UdpSocket<Unconnected>,
+    bufs: &'b [(&'b [u8], SocketAddr)],
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b> Future for SendToMany<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendToMany { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_send_to_many(bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`UdpSocket::recv_from_many`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFromMany<'a, 'b, B> {
+    socket: &'a mut UdpSocket<Unconnected>,
+    bufs: &'b mut [B],
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b, B> Future for RecvFromMany<'a, 'b, B>
+where
+    B: Bytes,
+{
+    type Output = io::Result<Vec<SocketAddr>>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvFromMany { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_recv_from_many(bufs), ctx)
     }
 }
 
@@ -538,6 +924,8 @@ impl UdpSocket<Connected> {
     /// [kind]: io::Error::kind
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         self.socket.send(buf)
     }
 
@@ -557,6 +945,8 @@ impl UdpSocket<Connected> {
     /// [kind]: io::Error::kind
     /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
     pub fn try_send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket).send_vectored(bufs)
     }
 
@@ -587,6 +977,8 @@ impl UdpSocket<Connected> {
             buf.has_spare_capacity(),
             "called `UdpSocket::try_recv` with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket)
             .recv(buf.as_bytes())
             .map(|read| {
@@ -621,6 +1013,8 @@ impl UdpSocket<Connected> {
             bufs.has_spare_capacity(),
             "called `UdpSocket::try_recv_vectored` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         let res = SockRef::from(&self.socket)
             .recv_vectored(MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()));
         match res {
@@ -658,6 +1052,8 @@ impl UdpSocket<Connected> {
             buf.has_spare_capacity(),
             "called `UdpSocket::try_peek` with an empty buffer"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         SockRef::from(&self.socket)
             .peek(buf.as_bytes())
             .map(|read| {
@@ -693,6 +1089,8 @@ impl UdpSocket<Connected> {
             bufs.has_spare_capacity(),
             "called `UdpSocket::try_peek_vectored` with empty buffers"
         );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
         let res = SockRef::from(&self.socket).recv_vectored_with_flags(
             MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()),
             libc::MSG_PEEK,
@@ -716,6 +1114,181 @@ impl UdpSocket<Connected> {
     {
         PeekVectored { socket: self, bufs }
     }
+
+    /// Attempt to send multiple datagrams, one per buffer in `bufs`, to the
+    /// peer using a single system call.
+    ///
+    /// Returns the number of datagrams send, which may be fewer then the length
+    /// of `bufs`.
+    ///
+    /// If no datagrams can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UdpSocket::send_many`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_send_many(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
+        send_many(self.socket.as_raw_fd(), bufs, &[])
+    }
+
+    /// Send multiple datagrams, one per buffer in `bufs`, to the peer using a
+    /// single system call. Returns a [`Future`] that on success returns the
+    /// number of datagrams send (`io::Result<usize>`).
+    ///
+    /// This uses `sendmmsg(2)` and is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn send_many<'a, 'b>(&'a mut self, bufs: &'b [&'b [u8]]) -> SendMany<'a, 'b> {
+        SendMany { socket: self, bufs }
+    }
+
+    /// Attempt to receive multiple datagrams, one per buffer in `bufs`, using a
+    /// single system call.
+    ///
+    /// Returns the number of datagrams received, the first datagram is written
+    /// into the first buffer etc. Buffers after the number of received
+    /// datagrams are not modified.
+    ///
+    /// If no datagrams can currently be received this will return an error with
+    /// the [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to
+    /// use [`UdpSocket::recv_many`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_recv_many<B>(&mut self, bufs: &mut [B]) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            bufs.iter().all(Bytes::has_spare_capacity),
+            "called `UdpSocket::try_recv_many` with an empty buffer"
+        );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
+        recv_many(self.socket.as_raw_fd(), bufs, None)
+    }
+
+    /// Receives multiple datagrams, one per buffer in `bufs`, using a single
+    /// system call. Returns a [`Future`] that on success returns the number of
+    /// datagrams received (`io::Result<usize>`).
+    ///
+    /// This uses `recvmmsg(2)` and is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn recv_many<'a, 'b, B>(&'a mut self, bufs: &'b mut [B]) -> RecvMany<'a, 'b, B>
+    where
+        B: Bytes,
+    {
+        RecvMany { socket: self, bufs }
+    }
+
+    /// Set the segment size used for UDP generic segmentation offload (GSO),
+    /// setting the `UDP_SEGMENT` option.
+    ///
+    /// If set to a non-zero value the kernel (or the network card) splits
+    /// buffers send using [`UdpSocket::send`] into multiple datagrams of `size`
+    /// bytes each, only the last datagram may be smaller. This allows multiple
+    /// datagrams to be send using a single system call. A buffer may contain at
+    /// most 64 segments and must fit in a single (64 KB) UDP datagram. Setting
+    /// it to zero disables segmentation offload.
+    ///
+    /// This is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_gso_segment_size(&mut self, size: u16) -> io::Result<()> {
+        set_socket_option(
+            self.socket.as_raw_fd(),
+            libc::SOL_UDP,
+            libc::UDP_SEGMENT,
+            libc::c_int::from(size),
+        )
+    }
+
+    /// Get the value of the `UDP_SEGMENT` option on this socket.
+    ///
+    /// For more information about this option, see
+    /// [`UdpSocket::set_gso_segment_size`].
+    #[cfg(target_os = "linux")]
+    pub fn gso_segment_size(&mut self) -> io::Result<u16> {
+        socket_option(self.socket.as_raw_fd(), libc::SOL_UDP, libc::UDP_SEGMENT)
+            .map(|size| size as u16)
+    }
+
+    /// Enable or disable UDP generic receive offload (GRO), setting the
+    /// `UDP_GRO` option.
+    ///
+    /// If enabled the kernel may coalesce multiple datagrams of the same size
+    /// from the same source into a single buffer. Use
+    /// [`UdpSocket::recv_segments`] to receive these buffers along with the
+    /// size of the datagrams contained in them.
+    ///
+    /// This is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_gro(&mut self, enable: bool) -> io::Result<()> {
+        set_socket_option(
+            self.socket.as_raw_fd(),
+            libc::SOL_UDP,
+            libc::UDP_GRO,
+            libc::c_int::from(enable),
+        )
+    }
+
+    /// Get the value of the `UDP_GRO` option on this socket.
+    ///
+    /// For more information about this option, see [`UdpSocket::set_gro`].
+    #[cfg(target_os = "linux")]
+    pub fn gro(&mut self) -> io::Result<bool> {
+        socket_option(self.socket.as_raw_fd(), libc::SOL_UDP, libc::UDP_GRO)
+            .map(|enabled| enabled != 0)
+    }
+
+    /// Attempt to receive (possibly coalesced) datagrams from the socket,
+    /// writing them into `buf`.
+    ///
+    /// Returns the number of bytes read and the segment size, i.e. the size of
+    /// the datagrams in `buf`. Only the last datagram may be smaller then the
+    /// segment size. If the datagram wasn't coalesced, e.g. if
+    /// [generic receive offload] is disabled, the segment size is equal to the
+    /// number of bytes read.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UdpSocket::recv_segments`].
+    ///
+    /// [generic receive offload]: UdpSocket::set_gro
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_recv_segments<B>(&mut self, mut buf: B) -> io::Result<(usize, usize)>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UdpSocket::try_recv_segments` with an empty buffer"
+        );
+        #[cfg(any(test, feature = "test"))]
+        crate::test::udp_fault()?;
+        recv_segments(self.socket.as_raw_fd(), buf.as_bytes()).map(|(read, segment_size)| {
+            // Safety: just read the bytes.
+            unsafe { buf.update_length(read) }
+            (read, segment_size)
+        })
+    }
+
+    /// Receives (possibly coalesced) datagrams from the socket. Returns a
+    /// [`Future`] that on success returns the number of bytes read and the
+    /// segment size (`io::Result<(usize, usize)>`).
+    ///
+    /// See [`UdpSocket::try_recv_segments`] for more information.
+    #[cfg(target_os = "linux")]
+    pub fn recv_segments<B>(&mut self, buf: B) -> RecvSegments<'_, B>
+    where
+        B: Bytes,
+    {
+        RecvSegments { socket: self, buf }
+    }
 }
 
 /// The [`Future`] behind [`UdpSocket::send`].
@@ -729,9 +1302,9 @@ pub struct Send<'a, 'b> {
 impl<'a, 'b> Future for Send<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Send { socket, buf } = Pin::into_inner(self);
-        try_io!(socket.try_send(buf))
+        try_io!(socket.try_send(buf), ctx)
     }
 }
 
@@ -746,9 +1319,9 @@ pub struct SendVectored<'a, 'b> {
 impl<'a, 'b> Future for SendVectored<'a, 'b> {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let SendVectored { socket, bufs } = Pin::into_inner(self);
-        try_io!(socket.try_send_vectored(bufs))
+        try_io!(socket.try_send_vectored(bufs), ctx)
     }
 }
 
@@ -766,9 +1339,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Recv { socket, buf } = Pin::into_inner(self);
-        try_io!(socket.try_recv(&mut *buf))
+        try_io!(socket.try_recv(&mut *buf), ctx)
     }
 }
 
@@ -786,9 +1359,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let PeekVectored { socket, bufs } = Pin::into_inner(self);
-        try_io!(socket.try_peek_vectored(&mut *bufs))
+        try_io!(socket.try_peek_vectored(&mut *bufs), ctx)
     }
 }
 
@@ -806,9 +1379,9 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let Peek { socket, buf } = Pin::into_inner(self);
-        try_io!(socket.try_peek(&mut *buf))
+        try_io!(socket.try_peek(&mut *buf), ctx)
     }
 }
 
@@ -826,9 +1399,72 @@ where
 {
     type Output = io::Result<usize>;
 
-    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let RecvVectored { socket, bufs } = Pin::into_inner(self);
-        try_io!(socket.try_recv_vectored(&mut *bufs))
+        try_io!(socket.try_recv_vectored(&mut *bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`UdpSocket::send_many`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendMany<'a, 'b> {
+    socket: &'a mut UdpSocket<Connected>,
+    bufs: &'b [&'b [u8]],
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b> Future for SendMany<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendMany { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_send_many(bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`UdpSocket::recv_many`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvMany<'a, 'b, B> {
+    socket: &'a mut UdpSocket<Connected>,
+    bufs: &'b mut [B],
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, 'b, B> Future for RecvMany<'a, 'b, B>
+where
+    B: Bytes,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvMany { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_recv_many(bufs), ctx)
+    }
+}
+
+/// The [`Future`] behind [`UdpSocket::recv_segments`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvSegments<'a, B> {
+    socket: &'a mut UdpSocket<Connected>,
+    buf: B,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, B> Future for RecvSegments<'a, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<(usize, usize)>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvSegments { socket, buf } = Pin::into_inner(self);
+        try_io!(socket.try_recv_segments(&mut *buf), ctx)
     }
 }
 
@@ -846,3 +1482,150 @@ impl<M, RT: rt::Access> Bound<RT> for UdpSocket<M> {
             .reregister(&mut self.socket, Interest::READABLE | Interest::WRITABLE)
     }
 }
+
+/// Send each buffer in `bufs` as a separate datagram using `sendmmsg(2)`. If
+/// `targets` is not empty the `n`th buffer is send to the `n`th target.
+///
+/// Returns the number of datagrams send.
+// The types of the `msghdr` fields differ per libc implementation.
+#[cfg(target_os = "linux")]
+#[allow(trivial_numeric_casts)]
+fn send_many(socket: RawFd, bufs: &[&[u8]], targets: &[SockAddr]) -> io::Result<usize> {
+    debug_assert!(targets.is_empty() || targets.len() == bufs.len());
+    let mut iovecs: Vec<libc::iovec> = bufs
+        .iter()
+        .map(|buf| libc::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        })
+        .collect();
+    let mut msgs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .enumerate()
+        .map(|(i, iov)| {
+            // Safety: all zero is valid for `mmsghdr`.
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+            if let Some(target) = targets.get(i) {
+                msg.msg_hdr.msg_name = target.as_ptr() as *mut libc::c_void;
+                msg.msg_hdr.msg_namelen = target.len();
+            }
+            msg
+        })
+        .collect();
+    match unsafe { libc::sendmmsg(socket, msgs.as_mut_ptr(), msgs.len() as _, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        n => Ok(n as usize),
+    }
+}
+
+/// Receive a datagram into each buffer in `bufs` using `recvmmsg(2)`. If
+/// `addresses` is `Some` the source address of each datagram is added to it.
+///
+/// Returns the number of datagrams received.
+#[cfg(target_os = "linux")]
+#[allow(trivial_numeric_casts)]
+fn recv_many<B: Bytes>(
+    socket: RawFd,
+    bufs: &mut [B],
+    mut addresses: Option<&mut Vec<SocketAddr>>,
+) -> io::Result<usize> {
+    let mut iovecs: Vec<libc::iovec> = bufs
+        .iter_mut()
+        .map(|buf| {
+            let buf = buf.as_bytes();
+            libc::iovec {
+                iov_base: buf.as_mut_ptr().cast(),
+                iov_len: buf.len(),
+            }
+        })
+        .collect();
+    let mut names: Vec<MaybeUninit<libc::sockaddr_storage>> = if addresses.is_some() {
+        (0..bufs.len()).map(|_| MaybeUninit::uninit()).collect()
+    } else {
+        Vec::new()
+    };
+    let mut msgs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(
+            names
+                .iter_mut()
+                .map(Some)
+                .chain(std::iter::repeat_with(|| None)),
+        )
+        .map(|(iov, name)| {
+            // Safety: all zero is valid for `mmsghdr`.
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+            if let Some(name) = name {
+                msg.msg_hdr.msg_name = name.as_mut_ptr().cast();
+                msg.msg_hdr.msg_namelen = size_of::<libc::sockaddr_storage>() as _;
+            }
+            msg
+        })
+        .collect();
+    let n = unsafe {
+        libc::recvmmsg(
+            socket,
+            msgs.as_mut_ptr(),
+            msgs.len() as _,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    let n = match n {
+        -1 => return Err(io::Error::last_os_error()),
+        n => n as usize,
+    };
+    for (i, (buf, msg)) in bufs.iter_mut().zip(&msgs).take(n).enumerate() {
+        // Safety: the kernel wrote `msg_len` bytes into the buffer.
+        unsafe { buf.update_length(msg.msg_len as usize) }
+        if let Some(addresses) = addresses.as_deref_mut() {
+            // Safety: the kernel initialised the address.
+            let address = unsafe { SockAddr::new(names[i].assume_init(), msg.msg_hdr.msg_namelen) };
+            addresses.push(convert_address(address)?);
+        }
+    }
+    Ok(n)
+}
+
+/// Receive bytes into `buf`, reading the segment size from the `UDP_GRO`
+/// control message (if any).
+///
+/// Returns the number of bytes read and the segment size.
+#[cfg(target_os = "linux")]
+#[allow(trivial_numeric_casts)]
+fn recv_segments(socket: RawFd, buf: &mut [MaybeUninit<u8>]) -> io::Result<(usize, usize)> {
+    // Large enough for a single control message holding a `c_int`, using `u64`
+    // to align the buffer.
+    let mut control = [0_u64; 4];
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // Safety: all zero is valid for `msghdr`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = (control.len() * size_of::<u64>()) as _;
+    let n = match unsafe { libc::recvmsg(socket, &mut msg, 0) } {
+        -1 => return Err(io::Error::last_os_error()),
+        n => n as usize,
+    };
+    let mut segment_size = n;
+    // Safety: the kernel initialised the control messages.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_UDP && (*cmsg).cmsg_type == libc::UDP_GRO {
+                let size = ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>());
+                segment_size = size as usize;
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((n, segment_size))
+}
@@ -0,0 +1,560 @@
+//! Module with [`UnixDatagram`] and related types.
+
+use std::future::Future;
+use std::io::{self, IoSlice};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use heph::actor;
+use mio::{net, Interest};
+use socket2::SockRef;
+
+use crate::bytes::{Bytes, BytesVectored, MaybeUninitSlice};
+use crate::net::uds::{self, SocketAddr};
+use crate::{self as rt, Bound};
+
+/// A Unix datagram socket.
+///
+/// To create a socket [`UnixDatagram::bind`], [`UnixDatagram::unbound`] or
+/// [`UnixDatagram::pair`] can be used. A socket can send datagrams to any
+/// address using [`UnixDatagram::send_to`], or, once [connected], to the
+/// connected address using [`UnixDatagram::send`].
+///
+/// [connected]: UnixDatagram::connect
+///
+/// # Notes
+///
+/// The socket file is not removed when the socket is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::io;
+///
+/// use heph::actor;
+/// use heph_rt::net::UnixDatagram;
+/// use heph_rt::{self as rt};
+///
+/// const DATA: &[u8] = b"Hello, world!";
+///
+/// async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+///     where RT: rt::Access,
+/// {
+///     let (mut left, mut right) = UnixDatagram::pair(&mut ctx)?;
+///
+///     // Send a datagram.
+///     left.send(DATA).await?;
+///
+///     // And receive it on the other side.
+///     let mut buf = Vec::with_capacity(DATA.len() + 1);
+///     right.recv(&mut buf).await?;
+///     assert_eq!(buf, DATA);
+///     Ok(())
+/// }
+/// #
+/// # let actor_ref = heph_rt::test::try_spawn(
+/// #     heph_rt::test::PanicSupervisor,
+/// #     actor as fn(_) -> _,
+/// #     (),
+/// #     heph_rt::spawn::ActorOptions::default(),
+/// # ).unwrap();
+/// # heph_rt::test::join(&actor_ref, std::time::Duration::from_secs(1)).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct UnixDatagram {
+    /// Underlying Unix datagram socket, backed by Mio.
+    socket: net::UnixDatagram,
+}
+
+impl UnixDatagram {
+    /// Creates a Unix datagram socket bound to `path`.
+    ///
+    /// # Notes
+    ///
+    /// The socket is also [bound] to the actor that owns the `actor::Context`,
+    /// which means the actor will be run every time the socket is ready to be
+    /// read from or write to.
+    ///
+    /// [bound]: crate::Bound
+    pub fn bind<M, RT, P>(ctx: &mut actor::Context<M, RT>, path: P) -> io::Result<UnixDatagram>
+    where
+        RT: rt::Access,
+        P: AsRef<Path>,
+    {
+        UnixDatagram::register(ctx, net::UnixDatagram::bind(path)?)
+    }
+
+    /// Creates a Unix datagram socket which is not bound to any address.
+    ///
+    /// # Notes
+    ///
+    /// The socket is also [bound] to the actor that owns the `actor::Context`.
+    ///
+    /// [bound]: crate::Bound
+    pub fn unbound<M, RT>(ctx: &mut actor::Context<M, RT>) -> io::Result<UnixDatagram>
+    where
+        RT: rt::Access,
+    {
+        UnixDatagram::register(ctx, net::UnixDatagram::unbound()?)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    ///
+    /// # Notes
+    ///
+    /// Both sockets are [bound] to the actor that owns the `actor::Context`.
+    ///
+    /// [bound]: crate::Bound
+    pub fn pair<M, RT>(ctx: &mut actor::Context<M, RT>) -> io::Result<(UnixDatagram, UnixDatagram)>
+    where
+        RT: rt::Access,
+    {
+        let (left, right) = net::UnixDatagram::pair()?;
+        let left = UnixDatagram::register(ctx, left)?;
+        let right = UnixDatagram::register(ctx, right)?;
+        Ok((left, right))
+    }
+
+    fn register<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        mut socket: net::UnixDatagram,
+    ) -> io::Result<UnixDatagram>
+    where
+        RT: rt::Access,
+    {
+        ctx.runtime()
+            .register(&mut socket, Interest::READABLE | Interest::WRITABLE)?;
+        Ok(UnixDatagram { socket })
+    }
+
+    /// Connects the socket to the socket at `path`, setting the default
+    /// destination and limiting datagrams that are received to those from
+    /// `path`.
+    pub fn connect<P>(&mut self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.socket.connect(path)
+    }
+
+    /// Returns the sockets local address.
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        SockRef::from(&self.socket)
+            .local_addr()
+            .map(SocketAddr::from)
+    }
+
+    /// Returns the address of the peer, if the socket is connected.
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        SockRef::from(&self.socket)
+            .peer_addr()
+            .map(SocketAddr::from)
+    }
+
+    /// Attempt to send data to the given `target` address.
+    ///
+    /// If the buffer currently can't be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::send_to`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        SockRef::from(&self.socket).send_to(buf, target.as_sock_addr())
+    }
+
+    /// Sends data to the given `target` address. Returns a [`Future`] that on
+    /// success returns the number of bytes written (`io::Result<usize>`).
+    pub fn send_to<'a, 'b>(&'a mut self, buf: &'b [u8], target: &'b SocketAddr) -> SendTo<'a, 'b> {
+        SendTo {
+            socket: self,
+            buf,
+            target,
+        }
+    }
+
+    /// Attempt to send data to the connected peer.
+    ///
+    /// If the buffer currently can't be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::send`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        SockRef::from(&self.socket).send(buf)
+    }
+
+    /// Sends data to the connected peer. Returns a [`Future`] that on success
+    /// returns the number of bytes written (`io::Result<usize>`).
+    pub fn send<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Send<'a, 'b> {
+        Send { socket: self, buf }
+    }
+
+    /// Attempt to send the bytes in `bufs` to the connected peer.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::send_vectored`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        SockRef::from(&self.socket).send_vectored(bufs)
+    }
+
+    /// Send the bytes in `bufs` to the connected peer.
+    pub fn send_vectored<'a, 'b>(
+        &'a mut self,
+        bufs: &'b mut [IoSlice<'b>],
+    ) -> SendVectored<'a, 'b> {
+        SendVectored { socket: self, bufs }
+    }
+
+    /// Attempt to send the bytes in `buf` and the file descriptors `fds` to the
+    /// connected peer.
+    ///
+    /// See [`UnixStream::try_send_fds`] for more information.
+    ///
+    /// [`UnixStream::try_send_fds`]: crate::net::UnixStream::try_send_fds
+    pub fn try_send_fds(&mut self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        uds::send_fds(self.socket.as_raw_fd(), &[IoSlice::new(buf)], fds)
+    }
+
+    /// Send the bytes in `buf` and the file descriptors `fds` to the connected
+    /// peer.
+    pub fn send_fds<'a, 'b>(&'a mut self, buf: &'b [u8], fds: &'b [RawFd]) -> SendFds<'a, 'b> {
+        SendFds {
+            socket: self,
+            buf,
+            fds,
+        }
+    }
+
+    /// Attempt to receive data from the socket, writing them into `buf`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::recv_from`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_from<B>(&mut self, mut buf: B) -> io::Result<(usize, SocketAddr)>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixDatagram::try_recv_from` with an empty buffer"
+        );
+        SockRef::from(&self.socket)
+            .recv_from(buf.as_bytes())
+            .map(|(read, address)| {
+                // Safety: just read the bytes.
+                unsafe { buf.update_length(read) }
+                (read, SocketAddr::from(address))
+            })
+    }
+
+    /// Receives data from the socket. Returns a [`Future`] that on success
+    /// returns the number of bytes read and the address from whence the data
+    /// came (`io::Result<(usize, SocketAddr>`).
+    pub fn recv_from<B>(&mut self, buf: B) -> RecvFrom<'_, B>
+    where
+        B: Bytes,
+    {
+        RecvFrom { socket: self, buf }
+    }
+
+    /// Attempt to receive data from the connected peer, writing them into
+    /// `buf`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::recv`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv<B>(&mut self, mut buf: B) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixDatagram::try_recv` with an empty buffer"
+        );
+        SockRef::from(&self.socket)
+            .recv(buf.as_bytes())
+            .inspect(|&read| {
+                // Safety: just read the bytes.
+                unsafe { buf.update_length(read) }
+            })
+    }
+
+    /// Receives data from the connected peer. Returns a [`Future`] that on
+    /// success returns the number of bytes read (`io::Result<usize>`).
+    pub fn recv<B>(&mut self, buf: B) -> Recv<'_, B>
+    where
+        B: Bytes,
+    {
+        Recv { socket: self, buf }
+    }
+
+    /// Attempt to receive data from the connected peer, writing them into
+    /// `bufs`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixDatagram::recv_vectored`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_vectored<B>(&mut self, mut bufs: B) -> io::Result<usize>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.has_spare_capacity(),
+            "called `UnixDatagram::try_recv_vectored` with empty buffers"
+        );
+        let res = SockRef::from(&self.socket)
+            .recv_vectored(MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()));
+        match res {
+            Ok((read, _)) => {
+                // Safety: just read the bytes.
+                unsafe { bufs.update_lengths(read) }
+                Ok(read)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Receives data from the connected peer, writing them into `bufs`.
+    pub fn recv_vectored<B>(&mut self, bufs: B) -> RecvVectored<'_, B>
+    where
+        B: BytesVectored,
+    {
+        RecvVectored { socket: self, bufs }
+    }
+
+    /// Attempt to receive data from the socket, writing them into `buf`, and
+    /// file descriptors, writing them into `fds`.
+    ///
+    /// See [`UnixStream::try_recv_fds`] for more information.
+    ///
+    /// [`UnixStream::try_recv_fds`]: crate::net::UnixStream::try_recv_fds
+    pub fn try_recv_fds<B>(
+        &mut self,
+        mut buf: B,
+        fds: &mut Vec<OwnedFd>,
+    ) -> io::Result<(usize, SocketAddr)>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixDatagram::try_recv_fds` with an empty buffer"
+        );
+        uds::recv_fds(self.socket.as_raw_fd(), buf.as_bytes(), fds).map(|(read, address)| {
+            // Safety: just read the bytes.
+            unsafe { buf.update_length(read) }
+            (read, address)
+        })
+    }
+
+    /// Receives data from the socket, writing them into `buf`, and file
+    /// descriptors, writing them into `fds`.
+    pub fn recv_fds<'a, 'f, B>(
+        &'a mut self,
+        buf: B,
+        fds: &'f mut Vec<OwnedFd>,
+    ) -> RecvFds<'a, 'f, B>
+    where
+        B: Bytes,
+    {
+        RecvFds {
+            socket: self,
+            buf,
+            fds,
+        }
+    }
+
+    /// Shuts down the read, write, or both halves of this socket.
+    pub fn shutdown(&mut self, how: Shutdown) -> io::Result<()> {
+        self.socket.shutdown(how)
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
+        self.socket.take_error()
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::send_to`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendTo<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b [u8],
+    target: &'b SocketAddr,
+}
+
+impl<'a, 'b> Future for SendTo<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        #[rustfmt::skip]
+        let SendTo { socket, buf, target } = Pin::into_inner(self);
+        try_io!(socket.try_send_to(buf, target))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::send`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Send<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for Send<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Send { socket, buf } = Pin::into_inner(self);
+        try_io!(socket.try_send(buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::send_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendVectored<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    bufs: &'b mut [IoSlice<'b>],
+}
+
+impl<'a, 'b> Future for SendVectored<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendVectored { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_send_vectored(bufs))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::send_fds`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendFds<'a, 'b> {
+    socket: &'a mut UnixDatagram,
+    buf: &'b [u8],
+    fds: &'b [RawFd],
+}
+
+impl<'a, 'b> Future for SendFds<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendFds { socket, buf, fds } = Pin::into_inner(self);
+        try_io!(socket.try_send_fds(buf, fds))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::recv_from`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFrom<'a, B> {
+    socket: &'a mut UnixDatagram,
+    buf: B,
+}
+
+impl<'a, B> Future for RecvFrom<'a, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<(usize, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvFrom { socket, buf } = Pin::into_inner(self);
+        try_io!(socket.try_recv_from(&mut *buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::recv`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Recv<'a, B> {
+    socket: &'a mut UnixDatagram,
+    buf: B,
+}
+
+impl<'a, B> Future for Recv<'a, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Recv { socket, buf } = Pin::into_inner(self);
+        try_io!(socket.try_recv(&mut *buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::recv_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvVectored<'a, B> {
+    socket: &'a mut UnixDatagram,
+    bufs: B,
+}
+
+impl<'a, B> Future for RecvVectored<'a, B>
+where
+    B: BytesVectored + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvVectored { socket, bufs } = Pin::into_inner(self);
+        try_io!(socket.try_recv_vectored(&mut *bufs))
+    }
+}
+
+/// The [`Future`] behind [`UnixDatagram::recv_fds`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFds<'a, 'f, B> {
+    socket: &'a mut UnixDatagram,
+    buf: B,
+    fds: &'f mut Vec<OwnedFd>,
+}
+
+impl<'a, 'f, B> Future for RecvFds<'a, 'f, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<(usize, SocketAddr)>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvFds { socket, buf, fds } = Pin::into_inner(self);
+        try_io!(socket.try_recv_fds(&mut *buf, fds))
+    }
+}
+
+impl<RT: rt::Access> Bound<RT> for UnixDatagram {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        ctx.runtime()
+            .reregister(&mut self.socket, Interest::READABLE | Interest::WRITABLE)
+    }
+}
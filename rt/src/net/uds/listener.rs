@@ -0,0 +1,215 @@
+//! Module with [`UnixListener`] and related types.
+
+use std::async_iter::AsyncIterator;
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use heph::actor;
+use mio::{net, Interest};
+use socket2::SockRef;
+
+use crate::net::uds::{SocketAddr, UnixStream};
+use crate::{self as rt, Bound};
+
+/// A Unix socket listener.
+///
+/// A listener can be created using [`UnixListener::bind`]. After it is created
+/// there are two ways to accept incoming [`UnixStream`]s:
+///
+///  * [`accept`] accepts a single connection, or
+///  * [`incoming`] which returns stream of incoming connections.
+///
+/// [`accept`]: UnixListener::accept
+/// [`incoming`]: UnixListener::incoming
+///
+/// # Notes
+///
+/// The socket file is not removed when the listener is dropped.
+///
+/// # Examples
+///
+/// Accepting a single [`UnixStream`], using [`UnixListener::accept`].
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+/// use std::path::PathBuf;
+///
+/// use heph::actor;
+/// use heph_rt::net::UnixListener;
+/// use heph_rt::ThreadLocal;
+/// use log::info;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) -> io::Result<()> {
+///     // Create a new listener.
+///     let mut listener = UnixListener::bind(&mut ctx, path)?;
+///
+///     // Accept a connection.
+///     let (unbound_stream, peer_address) = listener.accept().await?;
+///     info!("accepted connection from: {peer_address:?}");
+///
+///     // Next we need to bind the stream to this actor.
+///     let mut stream = unbound_stream.bind_to(&mut ctx)?;
+///     stream.send_all(b"Hello world!").await
+/// }
+/// #
+/// # _ = actor; // Silence dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct UnixListener {
+    /// The underlying Unix listener, backed by Mio.
+    socket: net::UnixListener,
+}
+
+impl UnixListener {
+    /// Creates a new `UnixListener` bound to the socket at `path`.
+    ///
+    /// # Notes
+    ///
+    /// The listener is also [bound] to the actor that owns the
+    /// `actor::Context`, which means the actor will be run every time the
+    /// listener has a connection ready to be accepted.
+    ///
+    /// [bound]: crate::Bound
+    pub fn bind<M, RT, P>(ctx: &mut actor::Context<M, RT>, path: P) -> io::Result<UnixListener>
+    where
+        RT: rt::Access,
+        P: AsRef<Path>,
+    {
+        let mut socket = net::UnixListener::bind(path)?;
+        ctx.runtime().register(&mut socket, Interest::READABLE)?;
+        Ok(UnixListener { socket })
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        SockRef::from(&self.socket)
+            .local_addr()
+            .map(SocketAddr::from)
+    }
+
+    /// Attempts to accept a new incoming [`UnixStream`].
+    ///
+    /// If an accepted Unix stream is returned, the remote address of the peer
+    /// is returned along with it.
+    ///
+    /// If no streams are currently queued this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixListener::accept`].
+    ///
+    /// See the [`UnixListener`] documentation for an example.
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_accept(&mut self) -> io::Result<(UnboundUnixStream, SocketAddr)> {
+        let (socket, _) = self.socket.accept()?;
+        let address = SockRef::from(&socket).peer_addr()?;
+        let stream = UnboundUnixStream {
+            stream: UnixStream { socket },
+        };
+        Ok((stream, SocketAddr::from(address)))
+    }
+
+    /// Accepts a new incoming [`UnixStream`].
+    ///
+    /// If an accepted Unix stream is returned, the remote address of the peer
+    /// is returned along with it.
+    ///
+    /// See the [`UnixListener`] documentation for an example.
+    pub fn accept(&mut self) -> Accept<'_> {
+        Accept {
+            listener: Some(self),
+        }
+    }
+
+    /// Returns a stream that iterates over the [`UnixStream`]s being received
+    /// on this listener.
+    pub fn incoming(&mut self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
+        self.socket.take_error()
+    }
+}
+
+/// An unbound [`UnixStream`].
+///
+/// The stream first has to be bound to an actor (using [`bind_to`]), before it
+/// can be used.
+///
+/// [`bind_to`]: UnboundUnixStream::bind_to
+#[derive(Debug)]
+pub struct UnboundUnixStream {
+    stream: UnixStream,
+}
+
+impl UnboundUnixStream {
+    /// Bind this Unix stream to the actor's `ctx`, allowing it to be used.
+    pub fn bind_to<M, RT>(mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<UnixStream>
+    where
+        RT: rt::Access,
+    {
+        ctx.runtime()
+            .register(
+                &mut self.stream.socket,
+                Interest::READABLE | Interest::WRITABLE,
+            )
+            .map(|()| self.stream)
+    }
+}
+
+/// The [`Future`] behind [`UnixListener::accept`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Accept<'a> {
+    listener: Option<&'a mut UnixListener>,
+}
+
+impl<'a> Future for Accept<'a> {
+    type Output = io::Result<(UnboundUnixStream, SocketAddr)>;
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match self.listener {
+            Some(ref mut listener) => try_io!(listener.try_accept()).map(|res| {
+                // Only remove the listener if we return a stream.
+                self.listener = None;
+                res
+            }),
+            None => panic!("polled Accept after it return Poll::Ready"),
+        }
+    }
+}
+
+/// The [`AsyncIterator`] behind [`UnixListener::incoming`].
+#[derive(Debug)]
+#[must_use = "AsyncIterators do nothing unless polled"]
+pub struct Incoming<'a> {
+    listener: &'a mut UnixListener,
+}
+
+impl<'a> AsyncIterator for Incoming<'a> {
+    type Item = io::Result<(UnboundUnixStream, SocketAddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        try_io!(self.listener.try_accept()).map(Some)
+    }
+}
+
+impl<RT: rt::Access> Bound<RT> for UnixListener {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        ctx.runtime()
+            .reregister(&mut self.socket, Interest::READABLE)
+    }
+}
@@ -0,0 +1,289 @@
+//! Unix Domain Socket (UDS) related types.
+//!
+//! Four main types are provided:
+//!
+//!  * [`UnixListener`] listens for incoming connections.
+//!  * [`UnixStream`] represents a single Unix stream connection.
+//!  * [`UnixDatagram`] is a Unix datagram socket.
+//!  * [`UnixServer`] is an [`Actor`] that listens for incoming connections and
+//!    starts a new actor for each.
+//!
+//! In addition to sending bytes, both [`UnixStream`] and [`UnixDatagram`] can
+//! pass file descriptors to the peer (using `SCM_RIGHTS`), see for example
+//! [`UnixStream::send_fds`] and [`UnixStream::recv_fds`]. The credentials of
+//! the peer process can be retrieved using [`UnixStream::peer_cred`].
+//!
+//! [`Actor`]: heph::actor::Actor
+
+use std::ffi::OsStr;
+use std::io::{self, IoSlice};
+use std::mem::{self, size_of, size_of_val, MaybeUninit};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::{fmt, ptr};
+
+use socket2::SockAddr;
+
+pub mod datagram;
+pub mod listener;
+pub mod server;
+pub mod stream;
+
+#[doc(no_inline)]
+pub use datagram::UnixDatagram;
+#[doc(no_inline)]
+pub use listener::UnixListener;
+#[doc(no_inline)]
+pub use server::UnixServer;
+#[doc(no_inline)]
+pub use stream::UnixStream;
+
+/// Address of a Unix socket.
+#[derive(Clone)]
+pub struct SocketAddr {
+    inner: SockAddr,
+}
+
+impl SocketAddr {
+    /// Create a `SocketAddr` from a path.
+    pub fn from_pathname<P>(path: P) -> io::Result<SocketAddr>
+    where
+        P: AsRef<Path>,
+    {
+        SockAddr::unix(path).map(|inner| SocketAddr { inner })
+    }
+
+    /// Returns the contents of this address if it is a pathname address.
+    pub fn as_pathname(&self) -> Option<&Path> {
+        match self.path_bytes() {
+            Some(path) if !path.is_empty() && path[0] != 0 => {
+                // Remove the null byte, if any.
+                let len = path.iter().position(|b| *b == 0).unwrap_or(path.len());
+                Some(Path::new(OsStr::from_bytes(&path[..len])))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the address is unnamed.
+    pub fn is_unnamed(&self) -> bool {
+        self.path_bytes().unwrap_or_default().is_empty()
+    }
+
+    /// Returns the bytes in `sun_path`, `None` if the address is not a Unix
+    /// address.
+    fn path_bytes(&self) -> Option<&[u8]> {
+        if self.inner.family() != libc::AF_UNIX as libc::sa_family_t {
+            return None;
+        }
+        let address = self.inner.as_ptr().cast::<libc::sockaddr_un>();
+        // Safety: checked the family above, so the storage is a `sockaddr_un`.
+        let path = unsafe { ptr::addr_of!((*address).sun_path) };
+        let offset = path as usize - address as usize;
+        let len = (self.inner.len() as usize).saturating_sub(offset);
+        // Safety: `len` bytes of `sun_path` are initialised by the OS.
+        Some(unsafe { std::slice::from_raw_parts(path.cast::<u8>(), len) })
+    }
+
+    /// Returns the address as a `socket2::SockAddr`.
+    pub(in crate::net) const fn as_sock_addr(&self) -> &SockAddr {
+        &self.inner
+    }
+}
+
+impl From<SockAddr> for SocketAddr {
+    fn from(inner: SockAddr) -> SocketAddr {
+        SocketAddr { inner }
+    }
+}
+
+impl fmt::Debug for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = self.as_pathname() {
+            write!(f, "{path:?} (pathname)")
+        } else if self.is_unnamed() {
+            f.write_str("(unnamed)")
+        } else {
+            f.write_str("(abstract)")
+        }
+    }
+}
+
+/// Credentials of a process connected to a Unix socket.
+///
+/// See [`UnixStream::peer_cred`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UCred {
+    /// User id of the process.
+    pub uid: libc::uid_t,
+    /// Group id of the process.
+    pub gid: libc::gid_t,
+    /// Process id of the process, only available on Linux.
+    pub pid: Option<libc::pid_t>,
+}
+
+/// Returns the credentials of the process connected to `socket`.
+#[cfg(target_os = "linux")]
+fn peer_cred(socket: RawFd) -> io::Result<UCred> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            socket,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            ptr::addr_of_mut!(cred).cast(),
+            &mut len,
+        )
+    };
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(UCred {
+            uid: cred.uid,
+            gid: cred.gid,
+            pid: Some(cred.pid),
+        })
+    }
+}
+
+/// Returns the credentials of the process connected to `socket`.
+#[cfg(not(target_os = "linux"))]
+fn peer_cred(socket: RawFd) -> io::Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    if unsafe { libc::getpeereid(socket, &mut uid, &mut gid) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(UCred {
+            uid,
+            gid,
+            pid: None,
+        })
+    }
+}
+
+/// Flags used in `sendmsg(2)`, don't raise `SIGPIPE` (where supported).
+#[cfg(target_os = "linux")]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(target_os = "linux"))]
+const SEND_FLAGS: libc::c_int = 0;
+
+/// Flags used in `recvmsg(2)`, set `O_CLOEXEC` on the received file
+/// descriptors (where supported).
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+const RECV_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+const RECV_FLAGS: libc::c_int = 0;
+
+/// Returns the size of the control message buffer needed to hold `fds` file
+/// descriptors, in number of `u64`s (to align the buffer).
+fn control_len(fds: usize) -> usize {
+    // Safety: `CMSG_SPACE` only does some arithmetic.
+    let space = unsafe { libc::CMSG_SPACE((fds * size_of::<RawFd>()) as libc::c_uint) };
+    (space as usize).div_ceil(size_of::<u64>())
+}
+
+/// Send the bytes in `bufs` and the file descriptors `fds` using a
+/// `SCM_RIGHTS` control message.
+// The types of the `msghdr` and `cmsghdr` fields differ per OS.
+#[allow(trivial_numeric_casts)]
+fn send_fds(socket: RawFd, bufs: &[IoSlice<'_>], fds: &[RawFd]) -> io::Result<usize> {
+    let mut control = vec![0_u64; control_len(fds.len())];
+    // Safety: all zero is valid for `msghdr`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    // `IoSlice` is guaranteed to be ABI compatible with `iovec`.
+    msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = bufs.len() as _;
+    if !fds.is_empty() {
+        let size = size_of_val(fds);
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = (control.len() * size_of::<u64>()) as _;
+        // Safety: `control` is large enough to hold a single control message
+        // with all file descriptors.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(size as libc::c_uint) as _;
+            ptr::copy_nonoverlapping(fds.as_ptr().cast::<u8>(), libc::CMSG_DATA(cmsg), size);
+        }
+    }
+    match unsafe { libc::sendmsg(socket, &msg, SEND_FLAGS) } {
+        -1 => Err(io::Error::last_os_error()),
+        n => Ok(n as usize),
+    }
+}
+
+/// Receive bytes into `buf` and file descriptors, send using a `SCM_RIGHTS`
+/// control message, into the spare capacity of `fds`.
+///
+/// Returns the number of bytes read and the address of the peer. Returns an
+/// error if the peer sent more file descriptors than fit in `fds`, in which
+/// case the received bytes and file descriptors are dropped.
+#[allow(trivial_numeric_casts)]
+fn recv_fds(
+    socket: RawFd,
+    buf: &mut [MaybeUninit<u8>],
+    fds: &mut Vec<OwnedFd>,
+) -> io::Result<(usize, SocketAddr)> {
+    let fds_len = fds.len();
+    let max_fds = fds.capacity() - fds_len;
+    let mut control = vec![0_u64; control_len(max_fds)];
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // Safety: `SockAddr::init` gives us storage for the address.
+    let (n, address) = unsafe {
+        SockAddr::init(|storage, len| {
+            let mut msg: libc::msghdr = mem::zeroed();
+            msg.msg_name = storage.cast();
+            msg.msg_namelen = *len;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            if max_fds != 0 {
+                msg.msg_control = control.as_mut_ptr().cast();
+                msg.msg_controllen = (control.len() * size_of::<u64>()) as _;
+            }
+            let n = match libc::recvmsg(socket, &mut msg, RECV_FLAGS) {
+                -1 => return Err(io::Error::last_os_error()),
+                n => n as usize,
+            };
+            *len = msg.msg_namelen;
+
+            // Take ownership of all received file descriptors.
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data = libc::CMSG_DATA(cmsg);
+                    let size = (*cmsg).cmsg_len as usize - (data as usize - cmsg as usize);
+                    for i in 0..size / size_of::<RawFd>() {
+                        let fd = ptr::read_unaligned(data.cast::<RawFd>().add(i));
+                        fds.push(OwnedFd::from_raw_fd(fd));
+                        #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+                        if libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+                            return Err(io::Error::last_os_error());
+                        }
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+            if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+                // The OS closed the file descriptors that didn't fit.
+                fds.truncate(fds_len);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "received more file descriptors than fit in the buffer",
+                ));
+            }
+            Ok(n)
+        })?
+    };
+    Ok((n, SocketAddr::from(address)))
+}
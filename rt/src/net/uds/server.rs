@@ -0,0 +1,402 @@
+//! Module with [`UnixServer`] and related types.
+
+use std::convert::TryFrom;
+use std::os::unix::net;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self, Poll};
+use std::{fmt, io};
+
+use heph::actor::{self, Actor, NewActor};
+use heph::messages::Terminate;
+use heph::supervisor::Supervisor;
+use log::{as_debug, debug};
+use mio::net::UnixListener;
+use mio::Interest;
+use socket2::SockRef;
+
+use crate::net::uds::{SocketAddr, UnixStream};
+use crate::spawn::{ActorOptions, AddActorError, PrivateSpawn, Spawn};
+use crate::{self as rt, PrivateAccess, Signal};
+
+/// A intermediate structure that implements [`NewActor`], creating
+/// [`UnixServer`].
+///
+/// See [`UnixServer::setup`] to create this and [`UnixServer`] for examples.
+#[derive(Debug)]
+pub struct Setup<S, NA> {
+    /// All fields are in an `Arc` to allow `Setup` to cheaply be cloned and
+    /// still be `Send` and `Sync` for use in the setup function of `Runtime`.
+    inner: Arc<SetupInner<S, NA>>,
+}
+
+#[derive(Debug)]
+struct SetupInner<S, NA> {
+    /// Listening socket, shared by all servers. Unix sockets can't be bound
+    /// to the same address multiple times, so each server uses a duplicate of
+    /// this socket.
+    listener: net::UnixListener,
+    /// Address of the `listener`.
+    address: SocketAddr,
+    /// Supervisor for all actors created by `NewActor`.
+    supervisor: S,
+    /// NewActor used to create an actor for each connection.
+    new_actor: NA,
+    /// Options used to spawn the actor.
+    options: ActorOptions,
+}
+
+impl<S, NA> Setup<S, NA> {
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> &SocketAddr {
+        &self.inner.address
+    }
+}
+
+impl<S, NA> NewActor for Setup<S, NA>
+where
+    S: Supervisor<NA> + Clone + 'static,
+    NA: NewActor<Argument = (UnixStream, SocketAddr)> + Clone + 'static,
+    NA::RuntimeAccess: rt::Access + Spawn<S, NA, NA::RuntimeAccess>,
+{
+    type Message = Message;
+    type Argument = ();
+    type Actor = UnixServer<S, NA>;
+    type Error = io::Error;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        mut ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        _: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let this = &*self.inner;
+        let mut listener = UnixListener::from_std(this.listener.try_clone()?);
+        ctx.runtime().register(&mut listener, Interest::READABLE)?;
+        Ok(UnixServer {
+            ctx,
+            set_waker: false,
+            listener,
+            supervisor: this.supervisor.clone(),
+            new_actor: this.new_actor.clone(),
+            options: this.options.clone(),
+        })
+    }
+}
+
+impl<S, NA> Clone for Setup<S, NA> {
+    fn clone(&self) -> Setup<S, NA> {
+        Setup {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An actor that starts a new actor for each accepted Unix stream connection.
+///
+/// This is the Unix socket equivalent of [`TcpServer`], see it for more
+/// information about running the server as thread-local or thread-safe actor.
+///
+/// Unlike TCP sockets, Unix sockets can't be bound to the same address
+/// multiple times. Instead [`UnixServer::setup`] binds the socket once and all
+/// servers created from the same setup accept connections from the same
+/// socket.
+///
+/// [`TcpServer`]: crate::net::TcpServer
+///
+/// # Graceful shutdown
+///
+/// Graceful shutdown is done by sending it a [`Terminate`] message. The Unix
+/// server can also handle (shutdown) process signals. Finally the Unix server
+/// stops when the runtime is shutting down, see [`RuntimeRef::shutdown`].
+///
+/// [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
+///
+/// # Notes
+///
+/// The socket file is not removed when the server stops. Binding fails if the
+/// file already exists, so stale socket files must be removed before calling
+/// [`UnixServer::setup`].
+///
+/// # Examples
+///
+/// The following example is a Unix server that writes "Hello World" to the
+/// connection, using the server as a thread-local actor.
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::{env, fs, io};
+///
+/// # use heph::messages::Terminate;
+/// use heph::actor::{self, NewActor};
+/// use heph::supervisor::{Supervisor, SupervisorStrategy};
+/// use heph_rt::net::uds::{server, SocketAddr, UnixServer, UnixStream};
+/// use heph_rt::spawn::ActorOptions;
+/// use heph_rt::spawn::options::Priority;
+/// use heph_rt::{self as rt, Runtime, RuntimeRef, ThreadLocal};
+/// use log::error;
+///
+/// fn main() -> Result<(), rt::Error> {
+///     // The path to listen on, removing any stale socket file.
+///     let path = env::temp_dir().join("heph_unix_server_doc.sock");
+///     let _ = fs::remove_file(&path);
+///     // Create our Unix server.
+///     let new_actor = conn_actor as fn(_, _, _) -> _;
+///     // Wait for the `UnixStream` to become ready before running the actor.
+///     let options = ActorOptions::default().mark_ready(false);
+///     let server = UnixServer::setup(&path, conn_supervisor, new_actor, options)
+///         .map_err(rt::Error::setup)?;
+///
+///     // Create and start the Heph runtime.
+///     let mut runtime = Runtime::new()?;
+///     runtime.run_on_workers(move |runtime_ref| setup(runtime_ref, server))?;
+///     runtime.start()
+/// }
+///
+/// /// In this setup function we'll spawn the Unix server.
+/// fn setup<S, NA>(mut runtime_ref: RuntimeRef, server: server::Setup<S, NA>) -> io::Result<()>
+/// where
+///     S: Supervisor<NA> + Clone + 'static,
+///     NA: NewActor<Argument = (UnixStream, SocketAddr), Error = !, RuntimeAccess = ThreadLocal> + Clone + 'static,
+/// {
+///     let options = ActorOptions::default().with_priority(Priority::LOW);
+///     # let actor_ref =
+///     runtime_ref.try_spawn_local(ServerSupervisor, server, (), options)?;
+///     # actor_ref.try_send(Terminate).unwrap();
+///     Ok(())
+/// }
+///
+/// /// Our supervisor for the Unix server.
+/// #[derive(Copy, Clone, Debug)]
+/// struct ServerSupervisor;
+///
+/// impl<S, NA> Supervisor<server::Setup<S, NA>> for ServerSupervisor
+/// where
+///     // Trait bounds needed by `server::Setup`.
+///     S: Supervisor<NA> + Clone + 'static,
+///     NA: NewActor<Argument = (UnixStream, SocketAddr), Error = !, RuntimeAccess = ThreadLocal> + Clone + 'static,
+/// {
+///     fn decide(&mut self, err: server::Error<!>) -> SupervisorStrategy<()> {
+///         use server::Error::*;
+///         match err {
+///             // When we hit an error accepting a connection we'll drop the old
+///             // server and create a new one.
+///             Accept(err) => {
+///                 error!("error accepting new connection: {err}");
+///                 SupervisorStrategy::Restart(())
+///             }
+///             // Async function never return an error creating a new actor.
+///             NewActor(_) => unreachable!(),
+///         }
+///     }
+///
+///     fn decide_on_restart_error(&mut self, err: io::Error) -> SupervisorStrategy<()> {
+///         // If we can't create a new server we'll stop.
+///         error!("error restarting the Unix server: {err}");
+///         SupervisorStrategy::Stop
+///     }
+///
+///     fn second_restart_error(&mut self, _: io::Error) {
+///         // We don't restart a second time, so this will never be called.
+///         unreachable!();
+///     }
+/// }
+///
+/// /// `conn_actor`'s supervisor.
+/// fn conn_supervisor(err: io::Error) -> SupervisorStrategy<(UnixStream, SocketAddr)> {
+///     error!("error handling connection: {err}");
+///     SupervisorStrategy::Stop
+/// }
+///
+/// /// The actor responsible for a single Unix stream.
+/// async fn conn_actor(_: actor::Context<!, ThreadLocal>, mut stream: UnixStream, address: SocketAddr) -> io::Result<()> {
+/// #   drop(address); // Silence dead code warnings.
+///     stream.send_all(b"Hello World").await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UnixServer<S, NA: NewActor> {
+    /// Actor context in which this actor is running.
+    ctx: actor::Context<Message, NA::RuntimeAccess>,
+    /// Whether or not we set the waker for the inbox.
+    set_waker: bool,
+    /// The underlying Unix listener, backed by Mio.
+    listener: UnixListener,
+    /// Supervisor for all actors created by `NewActor`.
+    supervisor: S,
+    /// `NewActor` used to create an actor for each connection.
+    new_actor: NA,
+    /// Options used to spawn the actor.
+    options: ActorOptions,
+}
+
+impl<S, NA> UnixServer<S, NA>
+where
+    S: Supervisor<NA> + Clone + 'static,
+    NA: NewActor<Argument = (UnixStream, SocketAddr)> + Clone + 'static,
+{
+    /// Create a new [server setup].
+    ///
+    /// Arguments:
+    /// * `path`: the path of the socket to listen on.
+    /// * `supervisor`: the [`Supervisor`] used to supervise each started actor,
+    /// * `new_actor`: the [`NewActor`] implementation to start each actor,
+    ///   and
+    /// * `options`: the actor options used to spawn the new actors.
+    ///
+    /// [server setup]: Setup
+    pub fn setup<P>(
+        path: P,
+        supervisor: S,
+        new_actor: NA,
+        options: ActorOptions,
+    ) -> io::Result<Setup<S, NA>>
+    where
+        P: AsRef<Path>,
+    {
+        let listener = net::UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        let address = SocketAddr::from(SockRef::from(&listener).local_addr()?);
+        Ok(Setup {
+            inner: Arc::new(SetupInner {
+                listener,
+                address,
+                supervisor,
+                new_actor,
+                options,
+            }),
+        })
+    }
+}
+
+impl<S, NA> Actor for UnixServer<S, NA>
+where
+    S: Supervisor<NA> + Clone + 'static,
+    NA: NewActor<Argument = (UnixStream, SocketAddr)> + Clone + 'static,
+    NA::RuntimeAccess: rt::Access + Spawn<S, NA, NA::RuntimeAccess>,
+{
+    type Error = Error<NA::Error>;
+
+    fn try_poll(
+        self: Pin<&mut Self>,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // Safety: This is safe because only the `actor::Context` and
+        // `set_waker` are mutably borrowed and both are `Unpin`.
+        let this = unsafe { Pin::into_inner_unchecked(self) };
+
+        if !this.set_waker {
+            // Set the waker of the inbox to ensure we get run when we receive a
+            // message.
+            this.ctx.register_inbox_waker(ctx.waker());
+            // And to ensure we get run when the runtime is shutting down.
+            this.ctx.runtime().wake_on_shutdown(ctx.waker().clone());
+            this.set_waker = true
+        }
+
+        // See if we need to shutdown.
+        //
+        // Like the `TcpServer` we first accept all pending connections and
+        // start actors for them, as the other servers sharing the socket might
+        // be stopping as well.
+        let should_stop =
+            this.ctx.try_receive_next().is_ok() || this.ctx.runtime_ref().is_shutting_down();
+
+        loop {
+            let mut stream = match this.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue, // Try again.
+                Err(err) => return Poll::Ready(Err(Error::Accept(err))),
+            };
+            let addr = match SockRef::from(&stream).peer_addr() {
+                Ok(addr) => SocketAddr::from(addr),
+                Err(err) => return Poll::Ready(Err(Error::Accept(err))),
+            };
+            debug!(remote_address = as_debug!(addr); "UnixServer accepted connection");
+
+            let setup_actor = move |ctx: &mut actor::Context<NA::Message, NA::RuntimeAccess>| {
+                ctx.runtime()
+                    .register(&mut stream, Interest::READABLE | Interest::WRITABLE)?;
+                Ok((UnixStream { socket: stream }, addr))
+            };
+            let res = this.ctx.try_spawn_setup(
+                this.supervisor.clone(),
+                this.new_actor.clone(),
+                setup_actor,
+                this.options.clone(),
+            );
+            if let Err(err) = res {
+                return Poll::Ready(Err(err.into()));
+            }
+        }
+
+        if should_stop {
+            debug!("Unix server received shutdown message or runtime is shutting down, stopping");
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// The message type used by [`UnixServer`].
+///
+/// The message implements [`From`]`<`[`Terminate`]`>` and
+/// [`TryFrom`]`<`[`Signal`]`>` for the message, allowing for graceful shutdown.
+#[derive(Debug)]
+pub struct Message {
+    // Allow for future expansion.
+    _inner: (),
+}
+
+impl From<Terminate> for Message {
+    fn from(_: Terminate) -> Message {
+        Message { _inner: () }
+    }
+}
+
+impl TryFrom<Signal> for Message {
+    type Error = ();
+
+    /// Converts [`Signal::Interrupt`], [`Signal::Terminate`] and
+    /// [`Signal::Quit`], fails for all other signals (by returning `Err(())`).
+    fn try_from(signal: Signal) -> Result<Self, Self::Error> {
+        match signal {
+            Signal::Interrupt | Signal::Terminate | Signal::Quit => Ok(Message { _inner: () }),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Error returned by the [`UnixServer`] actor.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Error accepting Unix stream.
+    Accept(io::Error),
+    /// Error creating a new actor to handle the Unix stream.
+    NewActor(E),
+}
+
+// Not part of the public API.
+#[doc(hidden)]
+impl<E> From<AddActorError<E, io::Error>> for Error<E> {
+    fn from(err: AddActorError<E, io::Error>) -> Error<E> {
+        match err {
+            AddActorError::NewActor(err) => Error::NewActor(err),
+            AddActorError::ArgFn(err) => Error::Accept(err),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match self {
+            Accept(ref err) => write!(f, "error accepting Unix stream: {err}"),
+            NewActor(ref err) => write!(f, "error creating new actor: {err}"),
+        }
+    }
+}
@@ -0,0 +1,769 @@
+//! Module with [`UnixStream`] and related types.
+
+use std::future::Future;
+use std::io::{self, IoSlice};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use heph::actor;
+use mio::{net, Interest};
+use socket2::SockRef;
+
+use crate::bytes::{Bytes, BytesVectored, MaybeUninitSlice};
+use crate::net::uds::{self, SocketAddr, UCred};
+use crate::{self as rt, Bound};
+
+/// A non-blocking Unix stream between a local socket and a remote socket.
+///
+/// # Examples
+///
+/// Sending `Hello world!` to a peer.
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+///
+/// use heph::actor;
+/// use heph_rt::net::UnixStream;
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+///     let mut stream = UnixStream::connect(&mut ctx, "/tmp/my_app.sock")?.await?;
+///     stream.send_all(b"Hello world!").await
+/// }
+/// #
+/// # _ = actor; // Silence dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct UnixStream {
+    /// Underlying Unix stream, backed by Mio.
+    pub(in crate::net) socket: net::UnixStream,
+}
+
+impl UnixStream {
+    /// Create a new Unix stream and issues a non-blocking connect to the
+    /// socket at `path`.
+    ///
+    /// # Notes
+    ///
+    /// The stream is also [bound] to the actor that owns the `actor::Context`,
+    /// which means the actor will be run every time the stream is ready to read
+    /// or write.
+    ///
+    /// [bound]: crate::Bound
+    pub fn connect<M, RT, P>(ctx: &mut actor::Context<M, RT>, path: P) -> io::Result<Connect>
+    where
+        RT: rt::Access,
+        P: AsRef<Path>,
+    {
+        let mut socket = net::UnixStream::connect(path)?;
+        ctx.runtime()
+            .register(&mut socket, Interest::READABLE | Interest::WRITABLE)?;
+        Ok(Connect {
+            socket: Some(socket),
+        })
+    }
+
+    /// Creates an unnamed pair of connected streams.
+    ///
+    /// # Notes
+    ///
+    /// Both streams are [bound] to the actor that owns the `actor::Context`.
+    ///
+    /// [bound]: crate::Bound
+    pub fn pair<M, RT>(ctx: &mut actor::Context<M, RT>) -> io::Result<(UnixStream, UnixStream)>
+    where
+        RT: rt::Access,
+    {
+        let (mut left, mut right) = net::UnixStream::pair()?;
+        let interest = Interest::READABLE | Interest::WRITABLE;
+        ctx.runtime().register(&mut left, interest)?;
+        ctx.runtime().register(&mut right, interest)?;
+        Ok((UnixStream { socket: left }, UnixStream { socket: right }))
+    }
+
+    /// Returns the socket address of the remote peer of this connection.
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        SockRef::from(&self.socket)
+            .peer_addr()
+            .map(SocketAddr::from)
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        SockRef::from(&self.socket)
+            .local_addr()
+            .map(SocketAddr::from)
+    }
+
+    /// Returns the credentials of the process connected to this stream.
+    ///
+    /// On Linux this uses `SO_PEERCRED`, on other OSs `getpeereid(3)` (which
+    /// doesn't provide the process id).
+    pub fn peer_cred(&mut self) -> io::Result<UCred> {
+        uds::peer_cred(self.socket.as_raw_fd())
+    }
+
+    /// Attempt to send bytes in `buf` to the peer.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::send`] or [`UnixStream::send_all`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        SockRef::from(&self.socket).send(buf)
+    }
+
+    /// Send the bytes in `buf` to the peer.
+    ///
+    /// Return the number of bytes written. This may we fewer then the length of
+    /// `buf`. To ensure that all bytes are written use [`UnixStream::send_all`].
+    pub fn send<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Send<'a, 'b> {
+        Send { stream: self, buf }
+    }
+
+    /// Send the all bytes in `buf` to the peer.
+    ///
+    /// If this fails to send all bytes (this happens if a write returns
+    /// `Ok(0)`) this will return [`io::ErrorKind::WriteZero`].
+    pub fn send_all<'a, 'b>(&'a mut self, buf: &'b [u8]) -> SendAll<'a, 'b> {
+        SendAll { stream: self, buf }
+    }
+
+    /// Attempt to send bytes in `bufs` to the peer.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::send_vectored`] or [`UnixStream::send_vectored_all`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        SockRef::from(&self.socket).send_vectored(bufs)
+    }
+
+    /// Send the bytes in `bufs` to the peer.
+    ///
+    /// Return the number of bytes written. This may we fewer then the length of
+    /// `bufs`. To ensure that all bytes are written use
+    /// [`UnixStream::send_vectored_all`].
+    pub fn send_vectored<'a, 'b>(
+        &'a mut self,
+        bufs: &'b mut [IoSlice<'b>],
+    ) -> SendVectored<'a, 'b> {
+        SendVectored { stream: self, bufs }
+    }
+
+    /// Send the all bytes in `bufs` to the peer.
+    ///
+    /// If this fails to send all bytes (this happens if a write returns
+    /// `Ok(0)`) this will return [`io::ErrorKind::WriteZero`].
+    pub fn send_vectored_all<'a, 'b>(
+        &'a mut self,
+        bufs: &'b mut [IoSlice<'b>],
+    ) -> SendVectoredAll<'a, 'b> {
+        SendVectoredAll { stream: self, bufs }
+    }
+
+    /// Attempt to send the bytes in `buf` and the file descriptors `fds` to
+    /// the peer.
+    ///
+    /// The file descriptors are send using a `SCM_RIGHTS` control message and
+    /// are passed along with the first byte of `buf`, thus `buf` should not be
+    /// empty. Note that the file descriptors are duplicated, the caller remains
+    /// responsible for closing its copies.
+    ///
+    /// If no bytes can currently be send this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::send_fds`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_fds(&mut self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        uds::send_fds(self.socket.as_raw_fd(), &[IoSlice::new(buf)], fds)
+    }
+
+    /// Send the bytes in `buf` and the file descriptors `fds` to the peer.
+    ///
+    /// See [`UnixStream::try_send_fds`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(never_type)]
+    ///
+    /// use std::fs::File;
+    /// use std::io;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// use heph::actor;
+    /// use heph_rt::net::UnixStream;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+    ///     let mut stream = UnixStream::connect(&mut ctx, "/tmp/my_app.sock")?.await?;
+    ///
+    ///     // Pass an opened file to the peer.
+    ///     let file = File::open("Cargo.toml")?;
+    ///     stream.send_fds(b"file", &[file.as_raw_fd()]).await?;
+    ///     Ok(())
+    /// }
+    /// #
+    /// # _ = actor; // Silence dead code warnings.
+    /// ```
+    pub fn send_fds<'a, 'b>(&'a mut self, buf: &'b [u8], fds: &'b [RawFd]) -> SendFds<'a, 'b> {
+        SendFds {
+            stream: self,
+            buf,
+            fds,
+        }
+    }
+
+    /// Attempt to receive message(s) from the stream, writing them into `buf`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::recv`] or [`UnixStream::recv_n`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv<B>(&mut self, mut buf: B) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixStream::try_recv with an empty buffer"
+        );
+        SockRef::from(&self.socket)
+            .recv(buf.as_bytes())
+            .inspect(|&read| {
+                // Safety: just read the bytes.
+                unsafe { buf.update_length(read) }
+            })
+    }
+
+    /// Receive messages from the stream, writing them into `buf`.
+    pub fn recv<'a, B>(&'a mut self, buf: B) -> Recv<'a, B>
+    where
+        B: Bytes,
+    {
+        Recv { stream: self, buf }
+    }
+
+    /// Receive at least `n` bytes from the stream, writing them into `buf`.
+    ///
+    /// This returns a [`Future`] that receives at least `n` bytes from a
+    /// `UnixStream` and writes them into buffer `B`, or returns
+    /// [`io::ErrorKind::UnexpectedEof`] if less then `n` bytes could be read.
+    pub fn recv_n<'a, B>(&'a mut self, buf: B, n: usize) -> RecvN<'a, B>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.spare_capacity() >= n,
+            "called `UnixStream::recv_n` with a buffer smaller then `n`"
+        );
+        RecvN {
+            stream: self,
+            buf,
+            left: n,
+        }
+    }
+
+    /// Attempt to receive message(s) from the stream, writing them into `bufs`.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::recv_vectored`] or [`UnixStream::recv_n_vectored`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_vectored<B>(&mut self, mut bufs: B) -> io::Result<usize>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.has_spare_capacity(),
+            "called `UnixStream::try_recv_vectored` with empty buffers"
+        );
+        let res = SockRef::from(&self.socket)
+            .recv_vectored(MaybeUninitSlice::as_socket2(bufs.as_bufs().as_mut()));
+        match res {
+            Ok((read, _)) => {
+                // Safety: just read the bytes.
+                unsafe { bufs.update_lengths(read) }
+                Ok(read)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Receive messages from the stream, writing them into `bufs`.
+    pub fn recv_vectored<B>(&mut self, bufs: B) -> RecvVectored<'_, B>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.has_spare_capacity(),
+            "called `UnixStream::recv_vectored` with empty buffers"
+        );
+        RecvVectored { stream: self, bufs }
+    }
+
+    /// Receive at least `n` bytes from the stream, writing them into `bufs`.
+    pub fn recv_n_vectored<B>(&mut self, bufs: B, n: usize) -> RecvNVectored<'_, B>
+    where
+        B: BytesVectored,
+    {
+        debug_assert!(
+            bufs.spare_capacity() >= n,
+            "called `UnixStream::recv_n_vectored` with a buffer smaller then `n`"
+        );
+        RecvNVectored {
+            stream: self,
+            bufs,
+            left: n,
+        }
+    }
+
+    /// Attempt to receive bytes from the stream, writing them into `buf`, and
+    /// file descriptors, writing them into `fds`.
+    ///
+    /// At most `fds.capacity() - fds.len()` file descriptors are received, if
+    /// the peer sends more file descriptors they're closed by the OS and this
+    /// returns an error (dropping the received bytes and file descriptors). The
+    /// received file descriptors have the close-on-exec flag set.
+    ///
+    /// If no bytes can currently be received this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UnixStream::recv_fds`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_fds<B>(&mut self, mut buf: B, fds: &mut Vec<OwnedFd>) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixStream::try_recv_fds with an empty buffer"
+        );
+        uds::recv_fds(self.socket.as_raw_fd(), buf.as_bytes(), fds).map(|(read, _)| {
+            // Safety: just read the bytes.
+            unsafe { buf.update_length(read) }
+            read
+        })
+    }
+
+    /// Receive bytes from the stream, writing them into `buf`, and file
+    /// descriptors, writing them into `fds`.
+    ///
+    /// See [`UnixStream::try_recv_fds`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(never_type)]
+    ///
+    /// use std::fs::File;
+    /// use std::io;
+    ///
+    /// use heph::actor;
+    /// use heph_rt::net::UnixStream;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+    ///     let mut stream = UnixStream::connect(&mut ctx, "/tmp/my_app.sock")?.await?;
+    ///
+    ///     let mut buf = Vec::with_capacity(128);
+    ///     // Receive at most one file descriptor.
+    ///     let mut fds = Vec::with_capacity(1);
+    ///     stream.recv_fds(&mut buf, &mut fds).await?;
+    ///     if let Some(fd) = fds.pop() {
+    ///         let file = File::from(fd);
+    ///         println!("received file: {file:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// #
+    /// # _ = actor; // Silence dead code warnings.
+    /// ```
+    pub fn recv_fds<'a, 'f, B>(
+        &'a mut self,
+        buf: B,
+        fds: &'f mut Vec<OwnedFd>,
+    ) -> RecvFds<'a, 'f, B>
+    where
+        B: Bytes,
+    {
+        RecvFds {
+            stream: self,
+            buf,
+            fds,
+        }
+    }
+
+    /// Attempt to receive messages from the stream, writing them into `buf`,
+    /// without removing that data from the queue. On success, returns the
+    /// number of bytes peeked.
+    pub fn try_peek<B>(&mut self, mut buf: B) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `UnixStream::try_peek with an empty buffer"
+        );
+        SockRef::from(&self.socket)
+            .peek(buf.as_bytes())
+            .inspect(|&read| {
+                // Safety: just read the bytes.
+                unsafe { buf.update_length(read) }
+            })
+    }
+
+    /// Receive messages from the stream, writing them into `buf`, without
+    /// removing that data from the queue. On success, returns the number of
+    /// bytes peeked.
+    pub fn peek<'a, B>(&'a mut self, buf: B) -> Peek<'a, B>
+    where
+        B: Bytes,
+    {
+        Peek { stream: self, buf }
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This function will cause all pending and future I/O on the specified
+    /// portions to return immediately with an appropriate value (see the
+    /// documentation of [`Shutdown`]).
+    pub fn shutdown(&mut self, how: Shutdown) -> io::Result<()> {
+        self.socket.shutdown(how)
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
+        self.socket.take_error()
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::connect`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Connect {
+    socket: Option<net::UnixStream>,
+}
+
+impl Future for Connect {
+    type Output = io::Result<UnixStream>;
+
+    #[track_caller]
+    fn poll(mut self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // See `tcp::stream::Connect` for an explanation of how this works.
+        match self.socket.take() {
+            Some(socket) => {
+                // If we hit an error while connecting return that error.
+                if let Ok(Some(err)) | Err(err) = socket.take_error() {
+                    return Poll::Ready(Err(err));
+                }
+
+                // If we can get a peer address it means the stream is
+                // connected.
+                match socket.peer_addr() {
+                    Ok(..) => Poll::Ready(Ok(UnixStream { socket })),
+                    // `NotConnected` (`ENOTCONN`) means the socket not yet
+                    // connected, but still working on it.
+                    Err(err)
+                        if err.kind() == io::ErrorKind::NotConnected
+                            || err.raw_os_error() == Some(libc::EINPROGRESS) =>
+                    {
+                        // Socket is not (yet) connected but haven't hit an
+                        // error either. So we return `Pending` and wait for
+                        // another event.
+                        self.socket = Some(socket);
+                        Poll::Pending
+                    }
+                    Err(err) => Poll::Ready(Err(err)),
+                }
+            }
+            None => panic!("polled `uds::stream::Connect` after completion"),
+        }
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::send`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Send<'a, 'b> {
+    stream: &'a mut UnixStream,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for Send<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Send { stream, buf } = Pin::into_inner(self);
+        try_io!(stream.try_send(buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::send_all`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendAll<'a, 'b> {
+    stream: &'a mut UnixStream,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for SendAll<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendAll { stream, buf } = Pin::into_inner(self);
+        loop {
+            match stream.try_send(buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) if buf.len() <= n => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *buf = &buf[n..];
+                    // Try to send some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::send_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendVectored<'a, 'b> {
+    stream: &'a mut UnixStream,
+    bufs: &'b mut [IoSlice<'b>],
+}
+
+impl<'a, 'b> Future for SendVectored<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendVectored { stream, bufs } = Pin::into_inner(self);
+        try_io!(stream.try_send_vectored(bufs))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::send_vectored_all`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendVectoredAll<'a, 'b> {
+    stream: &'a mut UnixStream,
+    bufs: &'b mut [IoSlice<'b>],
+}
+
+impl<'a, 'b> Future for SendVectoredAll<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendVectoredAll { stream, bufs } = Pin::into_inner(self);
+        while !bufs.is_empty() {
+            match stream.try_send_vectored(bufs) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => IoSlice::advance_slices(bufs, n),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::send_fds`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendFds<'a, 'b> {
+    stream: &'a mut UnixStream,
+    buf: &'b [u8],
+    fds: &'b [RawFd],
+}
+
+impl<'a, 'b> Future for SendFds<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendFds { stream, buf, fds } = Pin::into_inner(self);
+        try_io!(stream.try_send_fds(buf, fds))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::recv`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Recv<'b, B> {
+    stream: &'b mut UnixStream,
+    buf: B,
+}
+
+impl<'b, B> Future for Recv<'b, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Recv { stream, buf } = Pin::into_inner(self);
+        try_io!(stream.try_recv(&mut *buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::peek`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Peek<'b, B> {
+    stream: &'b mut UnixStream,
+    buf: B,
+}
+
+impl<'b, B> Future for Peek<'b, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Peek { stream, buf } = Pin::into_inner(self);
+        try_io!(stream.try_peek(&mut *buf))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::recv_n`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvN<'b, B> {
+    stream: &'b mut UnixStream,
+    buf: B,
+    left: usize,
+}
+
+impl<'b, B> Future for RecvN<'b, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvN { stream, buf, left } = Pin::into_inner(self);
+        loop {
+            match stream.try_recv(&mut *buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) if n >= *left => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *left -= n;
+                    // Try to read some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::recv_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvVectored<'b, B> {
+    stream: &'b mut UnixStream,
+    bufs: B,
+}
+
+impl<'b, B> Future for RecvVectored<'b, B>
+where
+    B: BytesVectored + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvVectored { stream, bufs } = Pin::into_inner(self);
+        try_io!(stream.try_recv_vectored(&mut *bufs))
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::recv_n_vectored`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvNVectored<'b, B> {
+    stream: &'b mut UnixStream,
+    bufs: B,
+    left: usize,
+}
+
+impl<'b, B> Future for RecvNVectored<'b, B>
+where
+    B: BytesVectored + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvNVectored { stream, bufs, left } = Pin::into_inner(self);
+        loop {
+            match stream.try_recv_vectored(&mut *bufs) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) if n >= *left => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *left -= n;
+                    // Try to read some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+/// The [`Future`] behind [`UnixStream::recv_fds`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvFds<'a, 'f, B> {
+    stream: &'a mut UnixStream,
+    buf: B,
+    fds: &'f mut Vec<OwnedFd>,
+}
+
+impl<'a, 'f, B> Future for RecvFds<'a, 'f, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvFds { stream, buf, fds } = Pin::into_inner(self);
+        try_io!(stream.try_recv_fds(&mut *buf, fds))
+    }
+}
+
+impl<RT: rt::Access> Bound<RT> for UnixStream {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        ctx.runtime()
+            .reregister(&mut self.socket, Interest::READABLE | Interest::WRITABLE)
+    }
+}
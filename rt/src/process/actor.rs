@@ -4,9 +4,13 @@
 use std::any::Any;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 use heph::actor::{self, Actor, NewActor};
+use heph::actor_ref::monitor::ActorMonitors;
+use heph::actor_ref::{ActorRef, DeadLetter, DownReason, InboxState, OverflowPolicy};
 use heph::supervisor::{Supervisor, SupervisorStrategy};
 use heph_inbox::{Manager, Receiver};
 use log::error;
@@ -24,9 +28,18 @@ pub(crate) struct ActorProcess<S, NA: NewActor> {
     new_actor: NA,
     /// The inbox of the actor, used in creating a new [`actor::Context`]
     /// if the actor is restarted.
-    inbox: Manager<NA::Message>,
-    /// The running actor.
-    actor: NA::Actor,
+    inbox: Inbox<NA::Message>,
+    /// The running actor, `None` if the actor was stopped to restart it (and
+    /// restarting failed or is delayed).
+    actor: Option<NA::Actor>,
+    /// If the actor is restarted after a delay, see
+    /// [`SupervisorStrategy::RestartAfter`], this is the time at which to
+    /// create the new actor and the argument to create it with.
+    restart: Option<(Instant, NA::Argument)>,
+    /// Monitors of the actor.
+    ///
+    /// NOTE: must be the last field, see [`ActorMonitors`].
+    monitors: ActorMonitors,
 }
 
 impl<S, NA> ActorProcess<S, NA>
@@ -36,17 +49,20 @@ where
     NA::RuntimeAccess: RuntimeSupport,
 {
     /// Create a new `ActorProcess`.
-    pub(crate) const fn new(
+    pub(crate) fn new(
         supervisor: S,
         new_actor: NA,
         actor: NA::Actor,
-        inbox: Manager<NA::Message>,
+        inbox: Inbox<NA::Message>,
     ) -> ActorProcess<S, NA> {
+        let monitors = ActorMonitors::new(inbox.state());
         ActorProcess {
             supervisor,
             new_actor,
             inbox,
-            actor,
+            actor: Some(actor),
+            restart: None,
+            monitors,
         }
     }
 
@@ -72,7 +88,11 @@ where
                     Err(err) => self.handle_restart_error(runtime_ref, pid, err),
                 }
             }
-            SupervisorStrategy::Stop => ProcessResult::Complete,
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(runtime_ref, pid, arg, delay);
+                ProcessResult::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Error),
             _ => unreachable!(),
         }
     }
@@ -85,6 +105,7 @@ where
         pid: ProcessId,
         panic: Box<dyn Any + Send + 'static>,
     ) -> ProcessResult {
+        let msg = panic_message(&*panic).to_owned();
         match self.supervisor.decide_on_panic(panic) {
             SupervisorStrategy::Restart(arg) => {
                 match self.create_new_actor(runtime_ref, pid, arg) {
@@ -98,7 +119,11 @@ where
                     Err(err) => self.handle_restart_error(runtime_ref, pid, err),
                 }
             }
-            SupervisorStrategy::Stop => ProcessResult::Complete,
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(runtime_ref, pid, arg, delay);
+                ProcessResult::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Panic(msg)),
             _ => unreachable!(),
         }
     }
@@ -122,15 +147,45 @@ where
                     Err(err) => {
                         // Let the supervisor know.
                         self.supervisor.second_restart_error(err);
-                        ProcessResult::Complete
+                        self.stop(DownReason::Error)
                     }
                 }
             }
-            SupervisorStrategy::Stop => ProcessResult::Complete,
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(runtime_ref, pid, arg, delay);
+                ProcessResult::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Error),
             _ => unreachable!(),
         }
     }
 
+    /// Marks the actor as stopped because of `reason`.
+    fn stop(&mut self, reason: DownReason) -> ProcessResult {
+        self.monitors.stopped(reason);
+        ProcessResult::Complete
+    }
+
+    /// Restarts the actor, like [`ActorProcess::create_new_actor`], but only
+    /// after `delay` has passed.
+    ///
+    /// The old actor is stopped now, the new actor is created once the
+    /// runtime's timer for the process expires. Any errors creating it are
+    /// handled at that point.
+    fn restart_after(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+        arg: NA::Argument,
+        delay: Duration,
+    ) {
+        // Stop the old actor, see `create_new_actor`.
+        unsafe { Pin::new_unchecked(&mut self.actor) }.set(None);
+        let deadline = NA::RuntimeAccess::current_time(runtime_ref) + delay;
+        NA::RuntimeAccess::add_deadline(runtime_ref, pid, deadline);
+        self.restart = Some((deadline, arg));
+    }
+
     /// Creates a new actor and, if successful, replaces the old actor with it.
     fn create_new_actor(
         &mut self,
@@ -138,14 +193,15 @@ where
         pid: ProcessId,
         arg: NA::Argument,
     ) -> Result<(), NA::Error> {
-        let receiver = self.inbox.new_receiver().expect(
+        let receiver = self.inbox.manager.new_receiver().expect(
             "failed to create new receiver for actor's inbox. Was the `actor::Context` leaked?",
         );
-        let ctx = NA::RuntimeAccess::new_context(pid, receiver, runtime_ref);
+        let ctx = NA::RuntimeAccess::new_context(pid, receiver, self.inbox.state(), runtime_ref);
         self.new_actor.new(ctx, arg).map(|actor| {
             // We pin the actor here to ensure its dropped in place when
             // replacing it with out new actor.
-            unsafe { Pin::new_unchecked(&mut self.actor) }.set(actor)
+            unsafe { Pin::new_unchecked(&mut self.actor) }.set(Some(actor));
+            self.monitors.restarted();
         })
     }
 }
@@ -163,14 +219,53 @@ where
     fn run(self: Pin<&mut Self>, runtime_ref: &mut RuntimeRef, pid: ProcessId) -> ProcessResult {
         // This is safe because we're not moving the actor.
         let this = unsafe { Pin::get_unchecked_mut(self) };
-        // The actor need to be called with `Pin`. So we're undoing the previous
-        // operation, still ensuring that the actor is not moved.
-        let mut actor = unsafe { Pin::new_unchecked(&mut this.actor) };
 
         let waker = NA::RuntimeAccess::new_task_waker(runtime_ref, pid);
         let mut task_ctx = task::Context::from_waker(&waker);
+
+        // Check if the supervisor (tree) wants to restart or stop the actor,
+        // e.g. because one of the actor's siblings failed.
+        match this.supervisor.poll_tree(&mut task_ctx) {
+            Poll::Ready(SupervisorStrategy::Restart(arg)) => {
+                // NOTE: this overrules a delayed restart, which deadline will
+                // cause a spurious wake up, but that's fine.
+                this.restart = None;
+                // Stop the old actor first, dropping its `actor::Context`,
+                // so we can create a new receiver for the inbox.
+                unsafe { Pin::new_unchecked(&mut this.actor) }.set(None);
+                if let Err(err) = this.create_new_actor(runtime_ref, pid, arg) {
+                    return this.handle_restart_error(runtime_ref, pid, err);
+                }
+            }
+            Poll::Ready(SupervisorStrategy::RestartAfter(arg, delay)) => {
+                this.restart_after(runtime_ref, pid, arg, delay);
+                return ProcessResult::Pending;
+            }
+            Poll::Ready(SupervisorStrategy::Stop) => return this.stop(DownReason::Stopped),
+            Poll::Ready(_) => unreachable!(),
+            Poll::Pending => {}
+        }
+
+        // Check if the actor should be restarted after a delay.
+        if let Some((restart_at, _)) = this.restart {
+            if restart_at > NA::RuntimeAccess::current_time(runtime_ref) {
+                return ProcessResult::Pending;
+            }
+            let (_, arg) = this.restart.take().unwrap();
+            if let Err(err) = this.create_new_actor(runtime_ref, pid, arg) {
+                return this.handle_restart_error(runtime_ref, pid, err);
+            }
+        }
+
+        // The actor need to be called with `Pin`. So we're undoing the previous
+        // operation, still ensuring that the actor is not moved.
+        let mut actor = match unsafe { Pin::new_unchecked(&mut this.actor) }.as_pin_mut() {
+            Some(actor) => actor,
+            // Restarting the actor failed and the supervisor decided to stop.
+            None => return this.stop(DownReason::Stopped),
+        };
         match catch_unwind(AssertUnwindSafe(|| actor.as_mut().try_poll(&mut task_ctx))) {
-            Ok(Poll::Ready(Ok(()))) => ProcessResult::Complete,
+            Ok(Poll::Ready(Ok(()))) => this.stop(DownReason::Normal),
             Ok(Poll::Ready(Err(err))) => this.handle_actor_error(runtime_ref, pid, err),
             Ok(Poll::Pending) => ProcessResult::Pending,
             Err(panic) => {
@@ -183,6 +278,58 @@ where
     }
 }
 
+/// Inbox of an actor.
+pub(crate) struct Inbox<M> {
+    /// Used to create new receivers for the inbox.
+    manager: Manager<M>,
+    /// State of the inbox, shared between the actor and its references.
+    state: Arc<InboxState>,
+}
+
+impl<M> Inbox<M> {
+    /// Create a new inbox for the actor with `name`, returning the receiving
+    /// side for the actor and a reference to the actor.
+    pub(crate) fn new(
+        name: &'static str,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> (Inbox<M>, Receiver<M>, ActorRef<M>) {
+        Inbox::with_state(capacity, InboxState::new(name, dead_letters, overflow))
+    }
+
+    /// Same as [`Inbox::new`], but includes the undeliverable messages in the
+    /// [`DeadLetter`]s, see [`DeadLetter::message`].
+    pub(crate) fn new_send(
+        name: &'static str,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> (Inbox<M>, Receiver<M>, ActorRef<M>)
+    where
+        M: Send + 'static,
+    {
+        let state = InboxState::new_send::<M>(name, dead_letters, overflow);
+        Inbox::with_state(capacity, state)
+    }
+
+    fn with_state(capacity: usize, state: Arc<InboxState>) -> (Inbox<M>, Receiver<M>, ActorRef<M>) {
+        let (manager, sender, receiver) = Manager::new_channel(capacity);
+        let actor_ref = ActorRef::local(sender, state.clone());
+        (Inbox { manager, state }, receiver, actor_ref)
+    }
+
+    /// Returns the manager of the inbox.
+    pub(crate) const fn manager(&self) -> &Manager<M> {
+        &self.manager
+    }
+
+    /// Returns the state of the inbox.
+    pub(crate) fn state(&self) -> Arc<InboxState> {
+        self.state.clone()
+    }
+}
+
 /// Trait to support different kind of runtime access, e.g. [`ThreadSafe`] and
 /// [`ThreadLocal`], within the same implementation of [`ActorProcess`].
 pub(crate) trait RuntimeSupport {
@@ -190,6 +337,7 @@ pub(crate) trait RuntimeSupport {
     fn new_context<M>(
         pid: ProcessId,
         inbox: Receiver<M>,
+        inbox_state: Arc<InboxState>,
         runtime_ref: &mut RuntimeRef,
     ) -> actor::Context<M, Self>
     where
@@ -197,32 +345,58 @@ pub(crate) trait RuntimeSupport {
 
     /// Schedule the actor with `pid` for running (used after restart).
     fn mark_ready(runtime_ref: &mut RuntimeRef, pid: ProcessId);
+
+    /// Returns the current time (used in delayed restarts).
+    fn current_time(runtime_ref: &RuntimeRef) -> Instant;
+
+    /// Add a deadline for the actor with `pid` (used in delayed restarts).
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant);
 }
 
 impl RuntimeSupport for ThreadLocal {
     fn new_context<M>(
         pid: ProcessId,
         inbox: Receiver<M>,
+        inbox_state: Arc<InboxState>,
         runtime_ref: &mut RuntimeRef,
     ) -> actor::Context<M, ThreadLocal> {
-        actor::Context::new(inbox, ThreadLocal::new(pid, runtime_ref.clone()))
+        let rt = ThreadLocal::new(pid, runtime_ref.clone());
+        actor::Context::new(inbox, inbox_state, rt)
     }
 
     fn mark_ready(runtime_ref: &mut RuntimeRef, pid: ProcessId) {
         runtime_ref.mark_ready_local(pid)
     }
+
+    fn current_time(runtime_ref: &RuntimeRef) -> Instant {
+        runtime_ref.now()
+    }
+
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant) {
+        runtime_ref.add_deadline(pid, deadline)
+    }
 }
 
 impl RuntimeSupport for ThreadSafe {
     fn new_context<M>(
         pid: ProcessId,
         inbox: Receiver<M>,
+        inbox_state: Arc<InboxState>,
         runtime_ref: &mut RuntimeRef,
     ) -> actor::Context<M, ThreadSafe> {
-        actor::Context::new(inbox, ThreadSafe::new(pid, runtime_ref.clone_shared()))
+        let rt = ThreadSafe::new(pid, runtime_ref.clone_shared());
+        actor::Context::new(inbox, inbox_state, rt)
     }
 
     fn mark_ready(runtime_ref: &mut RuntimeRef, pid: ProcessId) {
         runtime_ref.mark_ready_shared(pid)
     }
+
+    fn current_time(runtime_ref: &RuntimeRef) -> Instant {
+        runtime_ref.internals.shared.now()
+    }
+
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant) {
+        runtime_ref.add_deadline_shared(pid, deadline)
+    }
 }
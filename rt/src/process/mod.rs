@@ -1,7 +1,7 @@
 //! Module containing the `Process` trait, related types and implementations.
 
 use std::any::Any;
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::fmt;
 use std::pin::Pin;
 use std::time::{Duration, Instant};
@@ -17,7 +17,7 @@ mod future;
 #[cfg(test)]
 mod tests;
 
-pub(crate) use actor::ActorProcess;
+pub(crate) use actor::{ActorProcess, Inbox};
 pub(crate) use future::FutureProcess;
 
 /// Process id, or pid for short, is an identifier for a process in an
@@ -99,7 +99,7 @@ pub(crate) enum ProcessResult {
 
 /// Attempts to extract a message from a panic, defaulting to `<unknown>`.
 /// Note: be sure to derefence the `Box`!
-fn panic_message<'a>(panic: &'a (dyn Any + Send + 'static)) -> &'a str {
+pub(crate) fn panic_message<'a>(panic: &'a (dyn Any + Send + 'static)) -> &'a str {
     match panic.downcast_ref::<&'static str>() {
         Some(s) => s,
         None => match panic.downcast_ref::<String>() {
@@ -150,6 +150,17 @@ impl<P: ?Sized> ProcessData<P> {
     }
 }
 
+impl<P: ?Sized> ProcessData<P> {
+    /// Returns the key used to order the processes, the process with the
+    /// highest key should run first.
+    ///
+    /// Processes with the lowest fair runtime come first, using the priority
+    /// to break ties.
+    pub(crate) fn order_key(&self) -> (Reverse<Duration>, Priority) {
+        (Reverse(self.fair_runtime), self.priority)
+    }
+}
+
 impl<P: Process + ?Sized> ProcessData<P> {
     /// Returns the name of the process.
     pub(crate) fn name(self: Pin<&Self>) -> &'static str {
@@ -189,9 +200,7 @@ impl<P: ?Sized> PartialEq for ProcessData<P> {
 
 impl<P: ?Sized> Ord for ProcessData<P> {
     fn cmp(&self, other: &Self) -> Ordering {
-        (other.fair_runtime)
-            .cmp(&(self.fair_runtime))
-            .then_with(|| self.priority.cmp(&other.priority))
+        self.order_key().cmp(&other.order_key())
     }
 }
 
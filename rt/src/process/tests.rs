@@ -4,18 +4,21 @@ use std::cmp::Ordering;
 use std::future::{pending, Pending};
 use std::mem::size_of;
 use std::pin::Pin;
-use std::sync::atomic::{self, AtomicBool};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
 use std::sync::Arc;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use heph::actor::{self, Actor, NewActor};
-use heph::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
+use heph::actor_ref::{Down, DownReason};
+use heph::supervisor::{
+    NoSupervisor, RestartStrategy, Supervisor, SupervisorStrategy, SupervisorTree,
+};
 use mio::Token;
 
 use crate::process::{ActorProcess, FutureProcess, Process, ProcessData, ProcessId, ProcessResult};
 use crate::spawn::options::Priority;
-use crate::test::{self, init_local_actor_with_inbox, AssertUnmoved, TEST_PID};
+use crate::test::{self, init_local_actor_with_inbox, probe, AssertUnmoved, TEST_PID};
 use crate::{RuntimeRef, ThreadLocal, ThreadSafe};
 
 #[test]
@@ -256,6 +259,189 @@ fn restarting_erroneous_actor_process() {
     assert_eq!(res, ProcessResult::Complete);
 }
 
+#[test]
+fn restarting_actor_process_after_delay() {
+    const DELAY: Duration = Duration::from_millis(50);
+
+    // Create our actor.
+    let created = Arc::new(AtomicUsize::new(0));
+    let new_actor = CountingNewActor(error_actor as fn(_, _) -> _, created.clone());
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor.clone(), true).unwrap();
+    assert_eq!(created.load(atomic::Ordering::Relaxed), 1);
+
+    // Create our process.
+    let supervisor = |_| SupervisorStrategy::RestartAfter(false, DELAY);
+    let process = ActorProcess::new(supervisor, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    // In the first call to run the actor should return an error. Then it should
+    // be restarted, but not run until the delay has passed.
+    let mut runtime_ref = test::runtime();
+    let start = Instant::now();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+
+    // The new actor shouldn't be created before the delay has passed, i.e. it
+    // shouldn't receive the message.
+    actor_ref.try_send(()).unwrap();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(created.load(atomic::Ordering::Relaxed), 1);
+
+    // After the delay the restarted actor should receive the message and
+    // return `Ok`.
+    sleep(DELAY);
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    assert!(start.elapsed() >= DELAY);
+    assert_eq!(created.load(atomic::Ordering::Relaxed), 2);
+}
+
+/// [`NewActor`] that counts the number of actors it created.
+#[derive(Clone)]
+struct CountingNewActor<NA>(NA, Arc<AtomicUsize>);
+
+impl<NA: NewActor> NewActor for CountingNewActor<NA> {
+    type Message = NA::Message;
+    type Argument = NA::Argument;
+    type Actor = NA::Actor;
+    type Error = NA::Error;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        arg: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let _ = self.1.fetch_add(1, atomic::Ordering::Relaxed);
+        self.0.new(ctx, arg)
+    }
+}
+
+#[test]
+fn monitored_actor_process() {
+    // Create our actor.
+    let new_actor = error_actor as fn(_, _) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, true).unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    // Create our process.
+    let supervisor = |_| SupervisorStrategy::Restart(false);
+    let process = ActorProcess::new(supervisor, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    // In the first call to run the actor should return an error and be
+    // restarted.
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+    let reason = DownReason::Restarted;
+    assert_eq!(probe.received(), [Down { reason }]);
+
+    // The monitor should only be notified of the actor stopping once the
+    // process is dropped.
+    actor_ref.try_send(()).unwrap();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    assert_eq!(probe.received().len(), 1);
+    drop(process);
+    let reason = DownReason::Normal;
+    assert_eq!(probe.received()[1..], [Down { reason }]);
+}
+
+async fn panic_actor(_: actor::Context<(), ThreadLocal>) {
+    panic!("oops")
+}
+
+#[test]
+fn monitored_panicking_actor_process() {
+    // Create our actor.
+    let new_actor = panic_actor as fn(_) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    // Create our process.
+    let process = ActorProcess::new(NoSupervisor, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    drop(process);
+    let reason = DownReason::Panic("oops".to_owned());
+    assert_eq!(probe.received(), [Down { reason }]);
+}
+
+async fn counting_actor(
+    mut ctx: actor::Context<bool, ThreadLocal>,
+    starts: Arc<AtomicUsize>,
+) -> Result<(), ()> {
+    let _ = starts.fetch_add(1, atomic::Ordering::SeqCst);
+    match ctx.receive_next().await {
+        Ok(true) => Err(()),
+        Ok(false) | Err(_) => Ok(()),
+    }
+}
+
+#[test]
+fn supervisor_tree_actor_process() {
+    let tree = SupervisorTree::new(
+        "test",
+        RestartStrategy::OneForAll,
+        1,
+        Duration::from_secs(60),
+    );
+    let new_actor = counting_actor as fn(_, _) -> _;
+    let supervisor = |_| SupervisorStrategy::Restart(Arc::new(AtomicUsize::new(0)));
+
+    let starts1 = Arc::new(AtomicUsize::new(0));
+    let (actor, inbox, actor_ref1) =
+        init_local_actor_with_inbox(new_actor, starts1.clone()).unwrap();
+    let supervisor1 = tree.child(supervisor, starts1.clone());
+    let mut process1 = Box::pin(ActorProcess::new(supervisor1, new_actor, actor, inbox));
+
+    let starts2 = Arc::new(AtomicUsize::new(0));
+    let (actor, inbox, _actor_ref2) =
+        init_local_actor_with_inbox(new_actor, starts2.clone()).unwrap();
+    let supervisor2 = tree.child(supervisor, starts2.clone());
+    let mut process2 = Box::pin(ActorProcess::new(supervisor2, new_actor, actor, inbox));
+    assert_eq!(tree.len(), 2);
+
+    let mut runtime_ref = test::runtime();
+    let res = process1.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+    let res = process2.as_mut().run(&mut runtime_ref, ProcessId(1));
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(starts1.load(atomic::Ordering::SeqCst), 1);
+    assert_eq!(starts2.load(atomic::Ordering::SeqCst), 1);
+
+    // Failing the first actor should restart it (with the argument returned by
+    // the supervisor).
+    actor_ref1.try_send(true).unwrap();
+    let res = process1.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(starts1.load(atomic::Ordering::SeqCst), 1);
+
+    // The second actor should be restarted by the tree, using its original
+    // argument.
+    let res = process2.as_mut().run(&mut runtime_ref, ProcessId(1));
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(starts2.load(atomic::Ordering::SeqCst), 2);
+
+    // Failing again exceeds the restart limit, stopping both actors.
+    actor_ref1.try_send(true).unwrap();
+    let res = process1.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    let res = process2.as_mut().run(&mut runtime_ref, ProcessId(1));
+    assert_eq!(res, ProcessResult::Complete);
+
+    drop(process1);
+    drop(process2);
+    assert!(tree.is_empty());
+}
+
 struct TestAssertUnmovedNewActor;
 
 impl NewActor for TestAssertUnmovedNewActor {
@@ -0,0 +1,284 @@
+//! Registry of named actors.
+//!
+//! Actors can be registered under a name using [`RuntimeRef::register`], after
+//! which other actors can find them using [`RuntimeRef::lookup`]. This removes
+//! the need to pass actor references around in arguments just so that distant
+//! actors can find each other.
+//!
+//! The registry is shared between all threads of the runtime, so it can be
+//! accessed using [`ThreadLocal`], [`ThreadSafe`] and [`Sync`] runtime access.
+//!
+//! Lookups are type-checked: looking up an actor using the wrong message type
+//! returns [`LookupError::InvalidType`]. Once a registered actor stops (i.e.
+//! all of its actor references are disconnected) it's automatically
+//! unregistered.
+//!
+//! Actors that are restarted keep the same actor reference, but will likely
+//! register themselves again when they are started. Using
+//! [`RuntimeRef::watch_registrations`] an actor can receive a [`Registered`]
+//! message whenever a name becomes (re)registered.
+//!
+//! [`RuntimeRef::register`]: crate::RuntimeRef::register
+//! [`RuntimeRef::lookup`]: crate::RuntimeRef::lookup
+//! [`RuntimeRef::watch_registrations`]: crate::RuntimeRef::watch_registrations
+//! [`ThreadLocal`]: crate::ThreadLocal
+//! [`ThreadSafe`]: crate::ThreadSafe
+//! [`Sync`]: crate::Sync
+//!
+//! # Examples
+//!
+//! Registering an actor and looking it up from another actor.
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use heph::actor;
+//! use heph_rt::ThreadLocal;
+//!
+//! async fn logger(mut ctx: actor::Context<String, ThreadLocal>) {
+//!     // Register ourselves so other actors can find us.
+//!     let actor_ref = ctx.actor_ref();
+//!     if let Err(err) = ctx.runtime().register("logger", actor_ref) {
+//!         eprintln!("failed to register logger: {err}");
+//!         return;
+//!     }
+//!
+//!     while let Ok(msg) = ctx.receive_next().await {
+//!         println!("log: {msg}");
+//!     }
+//! }
+//!
+//! async fn worker(mut ctx: actor::Context<!, ThreadLocal>) {
+//!     // Find the logger actor by its name.
+//!     if let Ok(logger) = ctx.runtime().lookup::<String>("logger") {
+//!         let _ = logger.send("Hello world".to_owned()).await;
+//!     }
+//! }
+//! # let _ = (logger, worker);
+//! ```
+
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+use heph::actor_ref::{ActorRef, InboxMetrics};
+
+/// Message send to watchers when a name becomes (re)registered, see
+/// [`RuntimeRef::watch_registrations`].
+///
+/// [`RuntimeRef::watch_registrations`]: crate::RuntimeRef::watch_registrations
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Registered {
+    /// The name that was registered.
+    pub name: String,
+}
+
+/// Error returned by [`RuntimeRef::register`].
+///
+/// [`RuntimeRef::register`]: crate::RuntimeRef::register
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegisterError {
+    /// The name is already registered by another running actor.
+    AlreadyRegistered,
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::AlreadyRegistered => f.pad("name already registered"),
+        }
+    }
+}
+
+/// Error returned by [`RuntimeRef::lookup`].
+///
+/// [`RuntimeRef::lookup`]: crate::RuntimeRef::lookup
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LookupError {
+    /// No running actor is registered under the name.
+    NotRegistered,
+    /// The registered actor has a different message type than the one
+    /// requested.
+    InvalidType,
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::NotRegistered => f.pad("name not registered"),
+            LookupError::InvalidType => f.pad("registered actor has a different message type"),
+        }
+    }
+}
+
+/// Registry of named actors, shared between all threads of the runtime.
+#[derive(Debug, Default)]
+pub(crate) struct ActorRegistry {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Registered actors.
+    actors: HashMap<Box<str>, Entry>,
+    /// Actors that want to receive a [`Registered`] message when a name is
+    /// (re)registered.
+    watchers: HashMap<Box<str>, Vec<ActorRef<Registered>>>,
+}
+
+/// A single registered actor.
+struct Entry {
+    /// `ActorRef<M>`.
+    actor_ref: Box<dyn RegisteredRef>,
+    /// Name of the message type `M`, used in debugging.
+    message_type: &'static str,
+}
+
+/// Type-erased [`ActorRef`].
+trait RegisteredRef: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn is_connected(&self) -> bool;
+
+    fn inbox_metrics(&self) -> InboxMetrics;
+}
+
+impl<M: Send + 'static> RegisteredRef for ActorRef<M> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_connected(&self) -> bool {
+        ActorRef::is_connected(self)
+    }
+
+    fn inbox_metrics(&self) -> InboxMetrics {
+        ActorRef::inbox_metrics(self)
+    }
+}
+
+impl ActorRegistry {
+    /// Create a new empty registry.
+    pub(crate) fn new() -> ActorRegistry {
+        ActorRegistry::default()
+    }
+
+    /// Register `actor_ref` under `name`, fails if `name` is registered by
+    /// another actor that is still running.
+    pub(crate) fn register<M>(
+        &self,
+        name: &str,
+        actor_ref: ActorRef<M>,
+    ) -> Result<(), RegisterError>
+    where
+        M: Send + 'static,
+    {
+        let watchers = {
+            let mut inner = self.lock();
+            if let Some(entry) = inner.get(name) {
+                // Allow the same actor to register itself again, e.g. after
+                // it was restarted.
+                let same_actor = match entry.actor_ref.as_any().downcast_ref::<ActorRef<M>>() {
+                    Some(registered) => registered.sends_to(&actor_ref),
+                    None => false,
+                };
+                if !same_actor {
+                    return Err(RegisterError::AlreadyRegistered);
+                }
+            }
+            let entry = Entry {
+                actor_ref: Box::new(actor_ref),
+                message_type: type_name::<M>(),
+            };
+            let _ = inner.actors.insert(name.into(), entry);
+            match inner.watchers.get_mut(name) {
+                Some(watchers) => {
+                    watchers.retain(ActorRef::is_connected);
+                    watchers.clone()
+                }
+                None => Vec::new(),
+            }
+        };
+
+        // NOTE: sending the messages without holding the lock as mapped actor
+        // references run user code.
+        for watcher in watchers {
+            let _ = watcher.try_send(Registered {
+                name: name.to_owned(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Lookup the actor registered under `name`.
+    pub(crate) fn lookup<M>(&self, name: &str) -> Result<ActorRef<M>, LookupError>
+    where
+        M: Send + 'static,
+    {
+        let mut inner = self.lock();
+        let entry = inner.get(name).ok_or(LookupError::NotRegistered)?;
+        entry
+            .actor_ref
+            .as_any()
+            .downcast_ref::<ActorRef<M>>()
+            .cloned()
+            .ok_or(LookupError::InvalidType)
+    }
+
+    /// Add `watcher` to receive a [`Registered`] message when `name` is
+    /// (re)registered.
+    pub(crate) fn watch(&self, name: &str, watcher: ActorRef<Registered>) {
+        let mut inner = self.lock();
+        let watchers = inner.watchers.entry(name.into()).or_default();
+        watchers.retain(ActorRef::is_connected);
+        watchers.push(watcher);
+    }
+
+    /// Returns the inbox metrics of all registered actors, sorted by name.
+    pub(crate) fn inbox_metrics(&self) -> Vec<(Box<str>, InboxMetrics)> {
+        let mut inner = self.lock();
+        // Unregister all stopped actors.
+        inner
+            .actors
+            .retain(|_, entry| entry.actor_ref.is_connected());
+        let mut metrics: Vec<_> = inner
+            .actors
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.actor_ref.inbox_metrics()))
+            .collect();
+        metrics.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        metrics
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        match self.inner.lock() {
+            Ok(inner) => inner,
+            // The registry itself is always in a valid state, so we can ignore
+            // the poisoning.
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+impl Inner {
+    /// Returns the actor registered under `name`, unregistering it if it's
+    /// stopped.
+    fn get(&mut self, name: &str) -> Option<&Entry> {
+        if self
+            .actors
+            .get(name)
+            .is_some_and(|entry| !entry.actor_ref.is_connected())
+        {
+            let _ = self.actors.remove(name);
+        }
+        self.actors.get(name)
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("message_type", &self.message_type)
+            .finish()
+    }
+}
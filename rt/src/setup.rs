@@ -4,14 +4,15 @@ use std::ffi::CStr;
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
 use std::path::Path;
+use std::time::Duration;
 use std::{env, fmt, io, thread};
 
-use heph::actor_ref::ActorGroup;
+use heph::actor_ref::{ActorGroup, ActorRef, DeadLetter};
 use log::{debug, warn};
 
 use crate::coordinator::Coordinator;
 use crate::trace;
-use crate::{worker, Error, Runtime, MAX_THREADS};
+use crate::{blocking, worker, Error, Runtime, MAX_THREADS};
 
 /// Setup a [`Runtime`].
 ///
@@ -30,6 +31,16 @@ pub struct Setup {
     auto_cpu_affinity: bool,
     /// Optional trace log.
     trace_log: Option<trace::CoordinatorLog>,
+    /// Optional actor to send undeliverable messages to.
+    dead_letters: Option<ActorRef<DeadLetter>>,
+    /// Whether or not to log the inbox metrics of all registered actors.
+    log_inbox_metrics: bool,
+    /// Maximum number of threads in the blocking thread pool.
+    blocking_threads: usize,
+    /// Maximum time a process may run before its considered slow.
+    poll_budget: Option<Duration>,
+    /// Whether or not to count the number of slow polls per actor.
+    count_slow_polls: bool,
 }
 
 impl Setup {
@@ -40,6 +51,11 @@ impl Setup {
             threads: 1,
             auto_cpu_affinity: false,
             trace_log: None,
+            dead_letters: None,
+            log_inbox_metrics: false,
+            blocking_threads: blocking::DEFAULT_THREADS,
+            poll_budget: None,
+            count_slow_polls: false,
         }
     }
 
@@ -141,13 +157,119 @@ impl Setup {
         }
     }
 
+    /// Send messages that couldn't be delivered to `actor_ref`.
+    ///
+    /// When sending a message to an actor spawned on the runtime fails, e.g.
+    /// because its inbox is full or the actor stopped, a [`DeadLetter`] is send
+    /// to `actor_ref`. See the [`dead_letter`] module for more information.
+    ///
+    /// To run the dead-letter actor on the runtime itself use
+    /// [`Runtime::spawn_dead_letters_actor`].
+    ///
+    /// [`dead_letter`]: heph::actor_ref::dead_letter
+    /// [`Runtime::spawn_dead_letters_actor`]: crate::Runtime::spawn_dead_letters_actor
+    ///
+    /// # Notes
+    ///
+    /// The runtime holds on to `actor_ref` until it's dropped, so the inbox of
+    /// the dead-letter actor is never disconnected while the runtime is
+    /// running.
+    pub fn dead_letters(mut self, actor_ref: ActorRef<DeadLetter>) -> Self {
+        self.dead_letters = Some(actor_ref);
+        self
+    }
+
+    /// Log the inbox metrics of all actors in the [registry] when logging the
+    /// runtime's metrics.
+    ///
+    /// The runtime logs its metrics when the process receives the `SIGUSR2`
+    /// signal. When enabled this also logs the length, capacity and high
+    /// watermark of the inbox of each named actor, see
+    /// [`ActorRef::inbox_metrics`]. This can be used to find (and alert on)
+    /// actors that can't keep up with the messages they receive.
+    ///
+    /// [registry]: crate::registry
+    /// [`ActorRef::inbox_metrics`]: heph::actor_ref::ActorRef::inbox_metrics
+    pub const fn log_inbox_metrics(mut self) -> Self {
+        self.log_inbox_metrics = true;
+        self
+    }
+
+    /// Set the maximum number of threads used to run blocking functions,
+    /// defaults to 16.
+    ///
+    /// See [`RuntimeRef::spawn_blocking`] and the [`blocking`] module.
+    ///
+    /// [`RuntimeRef::spawn_blocking`]: crate::RuntimeRef::spawn_blocking
+    pub fn blocking_threads(mut self, n: usize) -> Self {
+        assert!(
+            n != 0,
+            "Can't create zero blocking threads, one is the minimum"
+        );
+        self.blocking_threads = n;
+        self
+    }
+
+    /// Returns the maximum number of threads used to run blocking functions.
+    ///
+    /// See [`Setup::blocking_threads`].
+    pub const fn get_blocking_threads(&self) -> usize {
+        self.blocking_threads
+    }
+
+    /// Set the poll budget, disabled (`None`) by default.
+    ///
+    /// Actors and futures should never block, however a single process that
+    /// (accidentally) blocks or runs a long computation blocks all other
+    /// processes on the same worker thread. The poll budget is the maximum
+    /// time a single run of a process (i.e. a call to [`Future::poll`]) may
+    /// take before it's considered slow.
+    ///
+    /// If a process runs longer than the budget a warning is logged, including
+    /// the process' name and id, and an event is added to the trace (if
+    /// enabled, see [`Setup::enable_tracing`]). Furthermore the coordinator
+    /// checks if a worker thread is blocked (roughly) every `budget`, logging a
+    /// warning, including the process' id, while the process is still running.
+    /// This detects processes that never return, e.g. an actor stuck in an
+    /// infinite loop.
+    ///
+    /// Note that enabling this means the time is read before and after every
+    /// process run and the coordinator thread wakes up every `budget`, so
+    /// don't set it too low.
+    ///
+    /// [`Future::poll`]: std::future::Future::poll
+    pub const fn poll_budget(mut self, budget: Option<Duration>) -> Self {
+        self.poll_budget = budget;
+        self
+    }
+
+    /// Returns the poll budget.
+    ///
+    /// See [`Setup::poll_budget`].
+    pub const fn get_poll_budget(&self) -> Option<Duration> {
+        self.poll_budget
+    }
+
+    /// Count the number of slow polls per actor, i.e. the number of times an
+    /// actor ran longer than the [poll budget].
+    ///
+    /// The counts are logged, per actor name, as part of the runtime's metrics
+    /// (logged when the process receives the `SIGUSR2` signal). This is
+    /// disabled if the poll budget is.
+    ///
+    /// [poll budget]: Setup::poll_budget
+    pub const fn count_slow_polls(mut self) -> Self {
+        self.count_slow_polls = true;
+        self
+    }
+
     /// Build the runtime.
     ///
     /// This will spawn a number of worker threads (see [`Setup::num_threads`])
     /// to run all the actors.
     pub fn build(self) -> Result<Runtime, Error> {
         #[rustfmt::skip]
-        let Setup { name, threads, auto_cpu_affinity, mut trace_log } = self;
+        let Setup { name, threads, auto_cpu_affinity, mut trace_log, dead_letters, log_inbox_metrics, blocking_threads, poll_budget, count_slow_polls } = self;
         let name = name.unwrap_or_else(default_app_name).into_boxed_str();
         debug!(name = name, workers = threads; "building Heph runtime");
 
@@ -166,8 +288,16 @@ impl Setup {
         // Create the coordinator to oversee all workers.
         let thread_wakers = thread_wakers.into_boxed_slice();
         let shared_trace_log = trace_log.as_ref().map(trace::CoordinatorLog::clone_shared);
-        let coordinator = Coordinator::init(name, thread_wakers, shared_trace_log)
-            .map_err(Error::init_coordinator)?;
+        let coordinator = Coordinator::init(
+            name,
+            thread_wakers,
+            shared_trace_log,
+            dead_letters,
+            log_inbox_metrics,
+            blocking_threads,
+            poll_budget,
+        )
+        .map_err(Error::init_coordinator)?;
 
         // Spawn the worker threads.
         let workers = worker_setups
@@ -180,6 +310,8 @@ impl Setup {
                 worker_setup.start(
                     coordinator.shared_internals().clone(),
                     auto_cpu_affinity,
+                    poll_budget,
+                    count_slow_polls,
                     trace_log,
                 )
             })
@@ -197,6 +329,7 @@ impl Setup {
             coordinator,
             workers,
             sync_actors: Vec::new(),
+            dead_letters_actor: None,
             signals: ActorGroup::empty(),
             trace_log,
         })
@@ -1,25 +1,31 @@
 //! Module with shared runtime internals.
 
+#[cfg(any(test, feature = "test"))]
+use std::cell::Cell;
 use std::cmp::min;
 use std::future::Future;
+use std::mem::take;
+use std::num::NonZeroUsize;
 use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex, TryLockError};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, TryLockError};
 use std::time::{Duration, Instant};
 use std::{io, task};
 
 use heph::actor::{self, NewActor};
-use heph::actor_ref::ActorRef;
+use heph::actor_ref::{ActorRef, DeadLetter};
 use heph::supervisor::Supervisor;
-use heph_inbox as inbox;
-use log::{debug, error, trace};
+use log::{as_debug, debug, error, trace};
 use mio::unix::SourceFd;
 use mio::{event, Events, Interest, Poll, Registry, Token};
 
+use crate::blocking::{self, Blocking};
+use crate::process::Inbox;
+use crate::registry::ActorRegistry;
 use crate::spawn::{ActorOptions, AddActorError, FutureOptions};
 use crate::thread_waker::ThreadWaker;
-use crate::{trace, ProcessId, ThreadSafe};
+use crate::{trace, ProcessId, Signal, ThreadSafe};
 
 mod scheduler;
 mod timers;
@@ -51,18 +57,35 @@ impl RuntimeSetup {
         shared_id: WakerId,
         worker_wakers: Box<[&'static ThreadWaker]>,
         trace_log: Option<Arc<trace::SharedLog>>,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        coordinator_waker: Option<mio::Waker>,
+        blocking_threads: usize,
     ) -> RuntimeInternals {
         // Needed by `RuntimeInternals::wake_workers`.
         debug_assert!(worker_wakers.len() >= 1);
+        let scheduler = Scheduler::new(worker_wakers.len());
         RuntimeInternals {
             shared_id,
             worker_wakers,
             wake_worker_idx: AtomicUsize::new(0),
             poll: Mutex::new(self.poll),
             registry: self.registry,
-            scheduler: Scheduler::new(),
+            scheduler,
             timers: Timers::new(),
+            actor_registry: ActorRegistry::new(),
+            dead_letters: dead_letters.map_or_else(OnceLock::new, OnceLock::from),
             trace_log,
+            shutting_down: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            shutdown: Mutex::new(Shutdown {
+                deadline: None,
+                wakers: Vec::new(),
+                stopped: Vec::new(),
+            }),
+            coordinator_waker,
+            signals: Mutex::new(Vec::new()),
+            handles: AtomicUsize::new(0),
+            blocking: blocking::Pool::new(blocking_threads),
         }
     }
 }
@@ -86,6 +109,14 @@ pub(crate) struct RuntimeInternals {
     scheduler: Scheduler,
     /// Timers for thread-safe actors.
     timers: Timers,
+    /// Registry of named actors.
+    actor_registry: ActorRegistry,
+    /// Actor to send messages to that couldn't be delivered, see
+    /// [`Setup::dead_letters`] and [`Runtime::spawn_dead_letters_actor`].
+    ///
+    /// [`Setup::dead_letters`]: crate::Setup::dead_letters
+    /// [`Runtime::spawn_dead_letters_actor`]: crate::Runtime::spawn_dead_letters_actor
+    dead_letters: OnceLock<ActorRef<DeadLetter>>,
     /// Shared trace log.
     ///
     /// # Notes
@@ -93,6 +124,46 @@ pub(crate) struct RuntimeInternals {
     /// Prefer not to use this but use [`trace::Log`] in local internals
     /// instead.
     trace_log: Option<Arc<trace::SharedLog>>,
+    /// Whether or not the runtime is shutting down, i.e. `shutdown.deadline`
+    /// is set. Used to avoid locking `shutdown`.
+    shutting_down: AtomicBool,
+    /// Shutdown state, see [`RuntimeInternals::shutdown`].
+    shutdown: Mutex<Shutdown>,
+    /// Whether or not the runtime has stopped, see
+    /// [`RuntimeInternals::mark_stopped`].
+    stopped: AtomicBool,
+    /// Waker for the `Coordinator`, used to start the shutdown and relay
+    /// `signals`. `None` in the test runtime.
+    coordinator_waker: Option<mio::Waker>,
+    /// Signals to relay to all actors, see [`RuntimeInternals::send_signal`].
+    signals: Mutex<Vec<Signal>>,
+    /// Number of [`RuntimeHandle`]s alive, which keep the runtime running.
+    ///
+    /// [`RuntimeHandle`]: crate::RuntimeHandle
+    handles: AtomicUsize,
+    /// Thread pool for blocking functions, see
+    /// [`RuntimeInternals::spawn_blocking`].
+    blocking: blocking::Pool,
+}
+
+#[cfg(any(test, feature = "test"))]
+thread_local! {
+    /// Offset added to the current time on this thread, see
+    /// [`RuntimeInternals::now`].
+    static TIME_OFFSET: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Shutdown state of the runtime.
+#[derive(Debug)]
+struct Shutdown {
+    /// Deadline after which all remaining processes are stopped.
+    deadline: Option<Instant>,
+    /// Wakers to wake once the runtime starts shutting down, see
+    /// [`RuntimeInternals::wake_on_shutdown`].
+    wakers: Vec<task::Waker>,
+    /// Names of the processes stopped after the deadline passed, see
+    /// [`RuntimeInternals::add_stopped`].
+    stopped: Vec<&'static str>,
 }
 
 /// Metrics for [`RuntimeInternals`].
@@ -102,6 +173,7 @@ pub(crate) struct Metrics {
     pub(crate) scheduler_inactive: usize,
     pub(crate) timers_total: usize,
     pub(crate) timers_next: Option<Duration>,
+    pub(crate) blocking: blocking::Metrics,
 }
 
 impl RuntimeInternals {
@@ -112,13 +184,190 @@ impl RuntimeInternals {
         Ok(RuntimeSetup { poll, registry })
     }
 
-    /// Returns metrics about the shared scheduler and timers.
+    /// Returns metrics about the shared scheduler, timers and blocking thread
+    /// pool.
     pub(crate) fn metrics(&self) -> Metrics {
         Metrics {
             scheduler_ready: self.scheduler.ready(),
             scheduler_inactive: self.scheduler.inactive(),
             timers_total: self.timers.len(),
             timers_next: self.timers.next_timer(),
+            blocking: self.blocking.metrics(),
+        }
+    }
+
+    /// Run the blocking function `f` on the blocking thread pool.
+    pub(crate) fn spawn_blocking<F, T>(&self, f: F) -> Blocking<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.blocking.spawn(f)
+    }
+
+    /// Returns the registry of named actors.
+    pub(crate) const fn actor_registry(&self) -> &ActorRegistry {
+        &self.actor_registry
+    }
+
+    /// Returns the actor to send messages to that couldn't be delivered.
+    pub(crate) fn dead_letters(&self) -> Option<ActorRef<DeadLetter>> {
+        self.dead_letters.get().cloned()
+    }
+
+    /// Set the actor to send messages to that couldn't be delivered. Returns
+    /// an error if it's already set.
+    pub(crate) fn set_dead_letters(
+        &self,
+        actor_ref: ActorRef<DeadLetter>,
+    ) -> Result<(), ActorRef<DeadLetter>> {
+        self.dead_letters.set(actor_ref)
+    }
+
+    /// Start shutting down the runtime, stopping all processes still running
+    /// after `deadline`, see [`RuntimeRef::shutdown`].
+    ///
+    /// This wakes all workers, the coordinator and all wakers registered using
+    /// [`RuntimeInternals::wake_on_shutdown`]. If the runtime is already
+    /// shutting down this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This panics if called on the test runtime, as it's shared between all
+    /// tests.
+    ///
+    /// [`RuntimeRef::shutdown`]: crate::RuntimeRef::shutdown
+    pub(crate) fn shutdown(&self, deadline: Instant) {
+        // NOTE: the test runtime is the only runtime without a coordinator.
+        assert!(
+            self.coordinator_waker.is_some(),
+            "can't shutdown the test runtime, it's shared between all tests"
+        );
+        let mut shutdown = self.lock_shutdown();
+        if shutdown.deadline.is_some() {
+            return;
+        }
+        debug!(deadline = as_debug!(deadline); "shutting down runtime");
+        shutdown.deadline = Some(deadline);
+        self.shutting_down.store(true, Ordering::Release);
+        let wakers = take(&mut shutdown.wakers);
+        drop(shutdown);
+
+        for waker in wakers {
+            waker.wake();
+        }
+        self.wake_all_workers();
+        self.wake_coordinator();
+    }
+
+    /// Returns `true` if the runtime is shutting down.
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Acquire)
+    }
+
+    /// Mark the runtime as stopped, i.e. the worker threads no longer run any
+    /// processes.
+    pub(crate) fn mark_stopped(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the runtime has stopped, see
+    /// [`RuntimeInternals::mark_stopped`].
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    /// Returns the shutdown deadline, if the runtime is shutting down.
+    pub(crate) fn shutdown_deadline(&self) -> Option<Instant> {
+        if self.is_shutting_down() {
+            self.lock_shutdown().deadline
+        } else {
+            None
+        }
+    }
+
+    /// Record that the process with `name` was stopped because it was still
+    /// running after the shutdown deadline.
+    pub(crate) fn add_stopped(&self, name: &'static str) {
+        self.lock_shutdown().stopped.push(name);
+    }
+
+    /// Returns the names of all processes stopped after the shutdown
+    /// deadline, see [`RuntimeInternals::add_stopped`].
+    pub(crate) fn take_stopped(&self) -> Vec<&'static str> {
+        take(&mut self.lock_shutdown().stopped)
+    }
+
+    /// Wake `waker` once the runtime starts shutting down, or right away if
+    /// it's already shutting down.
+    pub(crate) fn wake_on_shutdown(&self, waker: task::Waker) {
+        let mut shutdown = self.lock_shutdown();
+        if shutdown.deadline.is_some() {
+            drop(shutdown);
+            waker.wake();
+        } else {
+            shutdown.wakers.push(waker);
+        }
+    }
+
+    /// Relay `signal` to all actors that want to receive process signals, as if
+    /// the process received it. The signal is relayed by the `Coordinator`.
+    pub(crate) fn send_signal(&self, signal: Signal) {
+        trace!(signal = as_debug!(signal); "sending signal to coordinator");
+        match self.signals.lock() {
+            Ok(mut signals) => signals.push(signal),
+            Err(err) => err.into_inner().push(signal),
+        }
+        self.wake_coordinator();
+    }
+
+    /// Returns all signals send using [`RuntimeInternals::send_signal`] that
+    /// still need to be relayed.
+    pub(crate) fn take_signals(&self) -> Vec<Signal> {
+        match self.signals.lock() {
+            Ok(mut signals) => take(&mut *signals),
+            Err(err) => take(&mut *err.into_inner()),
+        }
+    }
+
+    fn wake_coordinator(&self) {
+        if let Some(waker) = &self.coordinator_waker {
+            if let Err(err) = waker.wake() {
+                error!("error waking coordinator: {err}");
+            }
+        }
+    }
+
+    /// Keep the runtime running, see [`RuntimeHandle`].
+    ///
+    /// [`RuntimeHandle`]: crate::RuntimeHandle
+    pub(crate) fn add_handle(&self) {
+        let _ = self.handles.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Undo a previous call to [`RuntimeInternals::add_handle`], waking all
+    /// workers if this was the last handle so they can stop.
+    pub(crate) fn remove_handle(&self) {
+        if self.handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.wake_all_workers();
+        }
+    }
+
+    /// Returns `true` if the runtime should keep running, even if there are no
+    /// processes to run. This is the case if there are [`RuntimeHandle`]s
+    /// alive, unless the runtime is shutting down.
+    ///
+    /// [`RuntimeHandle`]: crate::RuntimeHandle
+    pub(crate) fn keep_running(&self) -> bool {
+        self.handles.load(Ordering::Acquire) != 0 && !self.is_shutting_down()
+    }
+
+    fn lock_shutdown(&self) -> MutexGuard<'_, Shutdown> {
+        match self.shutdown.lock() {
+            Ok(shutdown) => shutdown,
+            // The shutdown state is always valid, so we can ignore the
+            // poisoning.
+            Err(err) => err.into_inner(),
         }
     }
 
@@ -172,6 +421,44 @@ impl RuntimeInternals {
         self.registry.reregister(source, token, interest)
     }
 
+    /// Returns the current time, used by the timers.
+    ///
+    /// In the test runtime the time can be advanced per thread, see
+    /// [`test::advance_time`].
+    ///
+    /// [`test::advance_time`]: crate::test::advance_time
+    #[allow(clippy::unused_self)]
+    pub(crate) fn now(&self) -> Instant {
+        #[cfg(any(test, feature = "test"))]
+        {
+            Instant::now() + TIME_OFFSET.with(Cell::get)
+        }
+        #[cfg(not(any(test, feature = "test")))]
+        {
+            Instant::now()
+        }
+    }
+
+    /// Advance the time returned by [`RuntimeInternals::now`] on the current
+    /// thread to `time`, if `time` is later than the current time.
+    #[cfg(any(test, feature = "test"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn advance_time_to(&self, time: Instant) {
+        if let Some(offset) = time.checked_duration_since(Instant::now()) {
+            TIME_OFFSET.with(|time_offset| {
+                if offset > time_offset.get() {
+                    time_offset.set(offset);
+                }
+            });
+        }
+    }
+
+    /// Returns the number of timers that expire after [`RuntimeInternals::now`].
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn pending_timers(&self) -> usize {
+        self.timers.pending(self.now())
+    }
+
     /// See [`Timers::add`].
     pub(super) fn add_deadline(&self, pid: ProcessId, deadline: Instant) {
         self.timers.add(pid, deadline);
@@ -227,6 +514,7 @@ impl RuntimeInternals {
         NA: NewActor<RuntimeAccess = ThreadSafe> + Sync + Send + 'static,
         ArgFn: FnOnce(&mut actor::Context<NA::Message, ThreadSafe>) -> Result<NA::Argument, E>,
         NA::Actor: Send + Sync + 'static,
+        NA::Argument: Send + Sync,
         NA::Message: Send,
     {
         // Setup adding a new process to the scheduler.
@@ -236,9 +524,14 @@ impl RuntimeInternals {
         debug!(pid = pid.0, name = name; "spawning thread-safe actor");
 
         // Create our actor context and our actor with it.
-        let (manager, sender, receiver) = inbox::Manager::new_small_channel();
-        let actor_ref = ActorRef::local(sender);
-        let mut ctx = actor::Context::new(receiver, ThreadSafe::new(pid, self.clone()));
+        let (inbox, receiver, actor_ref) = Inbox::new_send(
+            name,
+            self.dead_letters(),
+            options.inbox_capacity(),
+            options.overflow_policy(),
+        );
+        let rt = ThreadSafe::new(pid, self.clone());
+        let mut ctx = actor::Context::new(receiver, inbox.state(), rt);
         let arg = arg_fn(&mut ctx).map_err(AddActorError::ArgFn)?;
         let actor = new_actor.new(ctx, arg).map_err(AddActorError::NewActor)?;
 
@@ -248,7 +541,7 @@ impl RuntimeInternals {
             supervisor,
             new_actor,
             actor,
-            manager,
+            inbox,
             options.is_ready(),
         );
 
@@ -319,13 +612,13 @@ impl RuntimeInternals {
     }
 
     /// See [`Scheduler::remove`].
-    pub(crate) fn remove_process(&self) -> Option<Pin<Box<ProcessData>>> {
-        self.scheduler.remove()
+    pub(crate) fn remove_process(&self, worker_id: NonZeroUsize) -> Option<Pin<Box<ProcessData>>> {
+        self.scheduler.remove(worker_index(worker_id))
     }
 
     /// See [`Scheduler::add_process`].
-    pub(crate) fn add_process(&self, process: Pin<Box<ProcessData>>) {
-        self.scheduler.add_process(process);
+    pub(crate) fn add_process(&self, process: Pin<Box<ProcessData>>, worker_id: NonZeroUsize) {
+        self.scheduler.add_process(process, worker_index(worker_id));
     }
 
     /// See [`Scheduler::complete`].
@@ -333,6 +626,11 @@ impl RuntimeInternals {
         self.scheduler.complete(process);
     }
 
+    /// See [`Scheduler::drain`].
+    pub(crate) fn drain_processes(&self) -> Vec<Pin<Box<ProcessData>>> {
+        self.scheduler.drain()
+    }
+
     pub(crate) fn start_trace(&self) -> Option<trace::EventTiming> {
         trace::start(&self.trace_log.as_deref())
     }
@@ -353,3 +651,10 @@ impl RuntimeInternals {
         )
     }
 }
+
+/// Returns the index of the worker with `worker_id`, used to select the run
+/// queue of the worker in the [`Scheduler`].
+const fn worker_index(worker_id: NonZeroUsize) -> usize {
+    // Worker ids start at 1, the coordinator has id 0.
+    worker_id.get() - 1
+}
@@ -161,6 +161,16 @@ impl Inactive {
         drop(catch_unwind(AssertUnwindSafe(move || drop(process))));
     }
 
+    /// Remove all processes from the tree, adding them to `processes`.
+    ///
+    /// Ready markers and the branches are left in place.
+    pub(super) fn drain(&self, processes: &mut Vec<Pin<Box<ProcessData>>>) {
+        let n = processes.len();
+        self.root.drain(processes);
+        #[allow(clippy::cast_possible_wrap)]
+        self.update_length(-((processes.len() - n) as isize));
+    }
+
     /// Update `length` with `n` added/removed processes.
     fn update_length(&self, n: isize) {
         #[allow(clippy::cast_sign_loss)]
@@ -211,6 +221,33 @@ impl Branch {
         }
     }
 
+    /// Remove all processes from this branch and its child branches, adding
+    /// them to `processes`.
+    fn drain(&self, processes: &mut Vec<Pin<Box<ProcessData>>>) {
+        for branch in &self.branches {
+            // Safety: see comment for `load` in `Inactive::complete`.
+            let ptr = branch.load(Ordering::Acquire);
+            if is_branch(ptr) && !ptr.is_null() {
+                // Safety: per the docs of `Branch.branches` once it's a branch
+                // it's immutable.
+                let branch: &Branch = unsafe { &*as_ptr(ptr).cast() };
+                branch.drain(processes);
+            } else if is_process(ptr) {
+                // Safety: see comment for `load` in `Inactive::complete`.
+                if branch
+                    .compare_exchange(ptr, ptr::null_mut(), Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    // Safety: we've just removed the process pointer from the
+                    // tree, so we have unique access to it.
+                    processes.push(unsafe { process_from_tagged(ptr) });
+                }
+                // If the pointer was changed another thread removed the
+                // process, e.g. to run it, which is fine.
+            }
+        }
+    }
+
     /// Add `process` to the tree. Returns the number of processes added/removed
     /// from the tree.
     fn add(
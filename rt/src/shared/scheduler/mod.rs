@@ -7,13 +7,13 @@
 use std::future::Future;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use heph::actor::NewActor;
 use heph::supervisor::Supervisor;
-use heph_inbox::Manager;
 use log::{debug, trace};
 
-use crate::process::{self, ActorProcess, FutureProcess, Process, ProcessId};
+use crate::process::{self, ActorProcess, FutureProcess, Inbox, Process, ProcessId};
 use crate::spawn::options::Priority;
 use crate::{ptr_as_usize, ThreadSafe};
 
@@ -27,6 +27,11 @@ use runqueue::RunQueue;
 
 pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 
+/// Number of processes a worker removes from the scheduler between checking
+/// whether the other run queues have a process that should run first, see
+/// [`Scheduler::remove`].
+const FAIRNESS_CHECK_INTERVAL: usize = 61;
+
 /// The thread-safe scheduler, responsible for scheduling processes that can run
 /// one any of the worker threads, e.g. thread-safe actors.
 ///
@@ -34,13 +39,17 @@ pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 ///
 /// There are two components to the scheduler:
 ///
-/// * [`RunQueue`]: holds the processes that are ready to run.
+/// * [`RunQueue`]s: holds the processes that are ready to run, one queue per
+///   worker thread.
 /// * [`Inactive`]: holds the inactive processes.
 ///
 /// All threads have access to both components to they can mark processes as
 /// ready to run, e.g. in the waking mechanism, and allows worker threads to run
 /// a process.
 ///
+/// Using a run queue per worker thread, rather than a single queue, avoids all
+/// worker threads contending on a single lock.
+///
 /// ## Process states
 ///
 /// Processes can be in one of the following states:
@@ -70,7 +79,8 @@ pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 ///
 /// Marking a process as ready to run is done by calling
 /// [`Scheduler::mark_ready`], this move the actor from the [`Inactive`] list to
-/// the [`RunQueue`].
+/// a [`RunQueue`]. New and newly ready processes are divided over the run
+/// queues in a round-robin fashion.
 ///
 /// If the process is not found in the [`Inactive`] a marker is placed in its
 /// place in the list. This marker ensures that the process is marked as ready
@@ -82,8 +92,17 @@ pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 /// ## Running a process
 ///
 /// A worker thread can by first removing a process from the `Scheduler` by
-/// calling [`Scheduler::remove`]. The scheduler will check if the [`RunQueue`]
-/// is non-empty and returns the highest priority process that is ready to run.
+/// calling [`Scheduler::remove`]. The scheduler removes the next process from
+/// the worker's own [`RunQueue`], in order of priority (see [`Priority`]), only
+/// stealing a process from the other workers' queues if its own queue is
+/// empty. This way a worker only takes the lock of its own run queue in the
+/// common case.
+///
+/// To ensure processes in the other queues don't wait too long behind the
+/// processes in the worker's own queue, every [`FAIRNESS_CHECK_INTERVAL`]
+/// processes the worker compares the next process in its own queue with the
+/// next process in the queues of the other workers and runs the process that
+/// should run first, stealing it if needed.
 ///
 /// If `remove` returns `Some(process)` the process must be run. Depending on
 /// the result of the process it should be added back the schduler using
@@ -93,27 +112,36 @@ pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 ///
 /// If the process was marked as ready to run while it was running, see the
 /// section above, it will not be added to the [`Inactive`] list but instead be
-/// moved to the [`RunQueue`] again.
+/// moved to the [`RunQueue`] of the worker that ran it.
 #[derive(Debug)]
 pub(super) struct Scheduler {
-    /// Processes that are ready to run.
-    ready: RunQueue,
+    /// Processes that are ready to run, one queue per worker thread.
+    ready: Box<[RunQueue]>,
+    /// Number of processes removed by each worker thread, used to determine
+    /// when to run the fairness check in [`Scheduler::remove`].
+    dispatches: Box<[AtomicUsize]>,
+    /// Index into `ready` of the queue to add the next process to, see
+    /// [`Scheduler::next_queue`].
+    next_queue: AtomicUsize,
     /// Inactive processes that are not ready to run.
     inactive: Inactive,
 }
 
 impl Scheduler {
-    /// Create a new `Scheduler`.
-    pub(super) fn new() -> Scheduler {
+    /// Create a new `Scheduler` for `workers` worker threads.
+    pub(super) fn new(workers: usize) -> Scheduler {
+        debug_assert!(workers >= 1);
         Scheduler {
-            ready: RunQueue::empty(),
+            ready: (0..workers).map(|_| RunQueue::empty()).collect(),
+            dispatches: (0..workers).map(|_| AtomicUsize::new(0)).collect(),
+            next_queue: AtomicUsize::new(0),
             inactive: Inactive::empty(),
         }
     }
 
     /// Returns the number of processes ready to run.
     pub(crate) fn ready(&self) -> usize {
-        self.ready.len()
+        self.ready.iter().map(RunQueue::len).sum()
     }
 
     /// Returns the number of inactive processes.
@@ -139,7 +167,17 @@ impl Scheduler {
     ///
     /// Once this function returns the value could already be outdated.
     pub(super) fn has_ready_process(&self) -> bool {
-        self.ready.has_process()
+        self.ready.iter().any(RunQueue::has_process)
+    }
+
+    /// Returns the run queue for `worker`.
+    fn queue(&self, worker: usize) -> &RunQueue {
+        &self.ready[worker % self.ready.len()]
+    }
+
+    /// Returns the run queue to add the next ready process to.
+    fn next_queue(&self) -> &RunQueue {
+        self.queue(self.next_queue.fetch_add(1, Ordering::Relaxed))
     }
 
     /// Add a new actor to the scheduler.
@@ -159,7 +197,7 @@ impl Scheduler {
             Box::pin(FutureProcess::<Fut, ThreadSafe>::new(future)),
         ));
         debug!(pid = process.as_ref().id().0; "spawning thread-safe future");
-        self.ready.add(process)
+        self.next_queue().add(process)
     }
 
     /// Mark the process, with `pid`, as ready to run.
@@ -169,26 +207,103 @@ impl Scheduler {
     /// Calling this with an invalid or outdated `pid` will be silently ignored.
     pub(super) fn mark_ready(&self, pid: ProcessId) {
         trace!(pid = pid.0; "marking process as ready");
-        self.inactive.mark_ready(pid, &self.ready);
+        self.inactive.mark_ready(pid, self.next_queue());
         // NOTE: if the process in currently not in the `Inactive` list it will
         // be marked as ready-to-run and `Scheduler::add_process` will add it to
         // the run queue once its done running.
     }
 
-    /// Attempts to remove a process.
+    /// Attempts to remove a process for `worker` to run.
     ///
-    /// Returns `Ok(Some(..))` if a process was successfully removed or
-    /// `Ok(None)` if no processes are available to run.
-    pub(super) fn remove(&self) -> Option<Pin<Box<ProcessData>>> {
-        self.ready.remove()
+    /// Removes the next process from the worker's own run queue, stealing a
+    /// process from the other run queues if its own queue is empty. Every
+    /// [`FAIRNESS_CHECK_INTERVAL`] calls it removes the process that should run
+    /// first from all run queues instead, see [`ProcessData::order_key`].
+    ///
+    /// Returns `Some(..)` if a process was successfully removed or `None` if
+    /// no processes are available to run.
+    ///
+    /// [`ProcessData::order_key`]: process::ProcessData::order_key
+    pub(super) fn remove(&self, worker: usize) -> Option<Pin<Box<ProcessData>>> {
+        let n = self.ready.len();
+        let worker = worker % n;
+
+        // NOTE: only `worker` updates its count, so the relaxed ordering is
+        // fine.
+        let dispatches = self.dispatches[worker].fetch_add(1, Ordering::Relaxed);
+        if n > 1 && dispatches % FAIRNESS_CHECK_INTERVAL == FAIRNESS_CHECK_INTERVAL - 1 {
+            if let Some(process) = self.steal_first(worker) {
+                return Some(process);
+            }
+        }
+
+        if let Some(process) = self.ready[worker].remove() {
+            return Some(process);
+        }
+        // Our own queue is empty, steal a process from the other workers,
+        // starting with the next worker to spread the stealing among all
+        // workers.
+        for i in 1..n {
+            if let Some(process) = self.ready[(worker + i) % n].remove() {
+                trace!(pid = process.as_ref().id().0, worker = worker; "stole process");
+                return Some(process);
+            }
+        }
+        None
+    }
+
+    /// Steals the process that should run first from the other workers' run
+    /// queues, if it should run before the next process in the run queue of
+    /// `worker`.
+    fn steal_first(&self, worker: usize) -> Option<Pin<Box<ProcessData>>> {
+        let n = self.ready.len();
+        let mut best = self.ready[worker].peek().map(|key| (worker, key));
+        for i in 1..n {
+            let index = (worker + i) % n;
+            if let Some(key) = self.ready[index].peek() {
+                if best.as_ref().is_none_or(|(_, best_key)| key > *best_key) {
+                    best = Some((index, key));
+                }
+            }
+        }
+        match best {
+            // Another worker could have removed the process in the meantime,
+            // in which case we take the next process from the queue.
+            Some((index, _)) if index != worker => {
+                let process = self.ready[index].remove()?;
+                trace!(pid = process.as_ref().id().0, worker = worker; "stole process");
+                Some(process)
+            }
+            _ => None,
+        }
     }
 
     /// Add back a process that was previously removed via
-    /// [`Scheduler::remove`] and add it to the inactive list.
-    pub(super) fn add_process(&self, process: Pin<Box<ProcessData>>) {
+    /// [`Scheduler::remove`] by `worker` and add it to the inactive list.
+    ///
+    /// If the process was marked as ready while it was running it's added to
+    /// the run queue of `worker`.
+    pub(super) fn add_process(&self, process: Pin<Box<ProcessData>>, worker: usize) {
         let pid = process.as_ref().id();
         trace!(pid = pid.0; "adding back process");
-        self.inactive.add(process, &self.ready);
+        self.inactive.add(process, self.queue(worker));
+    }
+
+    /// Remove all processes from the scheduler, both ready and inactive.
+    ///
+    /// # Notes
+    ///
+    /// Processes that are running, or are being added by another thread, are
+    /// not removed.
+    pub(super) fn drain(&self) -> Vec<Pin<Box<ProcessData>>> {
+        let mut processes = Vec::new();
+        for run_queue in self.ready.iter() {
+            while let Some(process) = run_queue.remove() {
+                processes.push(process);
+            }
+        }
+        self.inactive.drain(&mut processes);
+        processes
     }
 
     /// Mark `process` as complete, removing it from the scheduler.
@@ -224,12 +339,13 @@ impl<'s> AddActor<'s> {
         supervisor: S,
         new_actor: NA,
         actor: NA::Actor,
-        inbox: Manager<NA::Message>,
+        inbox: Inbox<NA::Message>,
         is_ready: bool,
     ) where
         S: Supervisor<NA> + Send + Sync + 'static,
         NA: NewActor<RuntimeAccess = ThreadSafe> + Send + Sync + 'static,
         NA::Actor: Send + Sync + 'static,
+        NA::Argument: Send + Sync,
         NA::Message: Send,
     {
         debug_assert!(
@@ -252,9 +368,11 @@ impl<'s> AddActor<'s> {
         };
 
         if is_ready {
-            scheduler.ready.add(process);
+            scheduler.next_queue().add(process);
         } else {
-            scheduler.add_process(process)
+            let pid = process.as_ref().id();
+            trace!(pid = pid.0; "adding process");
+            scheduler.inactive.add(process, scheduler.next_queue());
         }
     }
 }
@@ -1,6 +1,10 @@
+use std::cmp::Reverse;
 use std::mem::replace;
 use std::pin::Pin;
 use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::spawn::options::Priority;
 
 use super::ProcessData;
 
@@ -73,6 +77,23 @@ impl RunQueue {
         }
     }
 
+    /// Returns the [order key] of the next process to run, without removing
+    /// it from the queue.
+    ///
+    /// [order key]: crate::process::ProcessData::order_key
+    pub(super) fn peek(&self) -> Option<(Reverse<Duration>, Priority)> {
+        let mut next_node = &*self.root.lock().unwrap();
+        loop {
+            match next_node {
+                Some(node) if node.left.is_none() => {
+                    return Some(node.process.order_key());
+                }
+                Some(node) => next_node = &node.left,
+                None => return None,
+            }
+        }
+    }
+
     /// Remove the next process to run from the queue.
     pub(super) fn remove(&self) -> Option<Pin<Box<ProcessData>>> {
         let mut next_node = &mut *self.root.lock().unwrap();
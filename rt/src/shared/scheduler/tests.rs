@@ -8,7 +8,7 @@ use heph::actor::{self, NewActor};
 use heph::supervisor::NoSupervisor;
 
 use crate::process::{ProcessId, ProcessResult};
-use crate::shared::scheduler::{Priority, ProcessData, Scheduler};
+use crate::shared::scheduler::{Priority, ProcessData, Scheduler, FAIRNESS_CHECK_INTERVAL};
 use crate::test::{self, init_actor_with_inbox, AssertUnmoved};
 use crate::ThreadSafe;
 
@@ -31,12 +31,12 @@ async fn simple_actor(_: actor::Context<!, ThreadSafe>) {}
 
 #[test]
 fn adding_actor() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(1);
 
     // Shouldn't run any process yet, since none are added.
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
 
     // Add an actor to the scheduler.
     let actor_entry = scheduler.add_actor();
@@ -55,50 +55,50 @@ fn adding_actor() {
     // Newly added processes aren't ready by default.
     assert!(scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
 
     // After scheduling the process should be ready to run.
     scheduler.mark_ready(pid);
     assert!(scheduler.has_process());
     assert!(scheduler.has_ready_process());
-    let process = scheduler.remove().unwrap();
+    let process = scheduler.remove(0).unwrap();
     assert_eq!(process.as_ref().id(), pid);
 
     // After the process is run, and returned `ProcessResult::Complete`, it
     // should be removed.
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
 
     // Adding the process back means its not ready.
-    scheduler.add_process(process);
+    scheduler.add_process(process, 0);
     assert!(scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
 
     // Marking the same process as ready again.
     scheduler.mark_ready(pid);
     assert!(scheduler.has_process());
     assert!(scheduler.has_ready_process());
-    let process = scheduler.remove().unwrap();
+    let process = scheduler.remove(0).unwrap();
     assert_eq!(process.as_ref().id(), pid);
 }
 
 #[test]
 fn marking_unknown_pid_as_ready() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(1);
 
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
 
     // Scheduling an unknown process should do nothing.
     scheduler.mark_ready(ProcessId(0));
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
-    assert_eq!(scheduler.remove(), None);
+    assert_eq!(scheduler.remove(0), None);
 }
 
 #[test]
@@ -111,7 +111,7 @@ fn scheduler_run_order() {
         order.lock().unwrap().push(id);
     }
 
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(1);
     let mut runtime_ref = test::runtime();
 
     // The order in which the processes have been run.
@@ -134,7 +134,7 @@ fn scheduler_run_order() {
     // Run all processes, should be in order of priority (since there runtimes
     // are equal).
     for _ in 0..3 {
-        let mut process = scheduler.remove().unwrap();
+        let mut process = scheduler.remove(0).unwrap();
         assert_eq!(
             process.as_mut().run(&mut runtime_ref),
             ProcessResult::Complete
@@ -164,7 +164,7 @@ impl NewActor for TestAssertUnmovedNewActor {
 
 #[test]
 fn assert_actor_process_unmoved() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(1);
     let mut runtime_ref = test::runtime();
 
     let (actor, inbox, _) = init_actor_with_inbox(TestAssertUnmovedNewActor, ()).unwrap();
@@ -182,23 +182,23 @@ fn assert_actor_process_unmoved() {
 
     // Run the process multiple times, ensure it's not moved in the
     // process.
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
     );
-    scheduler.add_process(process);
+    scheduler.add_process(process, 0);
 
     scheduler.mark_ready(pid);
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
     );
-    scheduler.add_process(process);
+    scheduler.add_process(process, 0);
 
     scheduler.mark_ready(pid);
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
@@ -207,7 +207,7 @@ fn assert_actor_process_unmoved() {
 
 #[test]
 fn assert_future_process_unmoved() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(1);
     let mut runtime_ref = test::runtime();
 
     let future = AssertUnmoved::new(pending());
@@ -215,26 +215,132 @@ fn assert_future_process_unmoved() {
 
     // Run the process multiple times, ensure it's not moved in the
     // process.
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     let pid = process.as_ref().id();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
     );
-    scheduler.add_process(process);
+    scheduler.add_process(process, 0);
 
     scheduler.mark_ready(pid);
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
     );
-    scheduler.add_process(process);
+    scheduler.add_process(process, 0);
 
     scheduler.mark_ready(pid);
-    let mut process = scheduler.remove().unwrap();
+    let mut process = scheduler.remove(0).unwrap();
     assert_eq!(
         process.as_mut().run(&mut runtime_ref),
         ProcessResult::Pending
     );
 }
+
+#[test]
+fn work_stealing() {
+    let scheduler = Scheduler::new(2);
+
+    // Processes are divided over the run queues.
+    scheduler.add_future(pending::<()>(), Priority::NORMAL);
+    scheduler.add_future(pending::<()>(), Priority::NORMAL);
+    assert!(scheduler.ready[0].has_process());
+    assert!(scheduler.ready[1].has_process());
+    assert_eq!(scheduler.ready(), 2);
+
+    // Worker 0 runs the process in its own queue first, then steals the
+    // process from worker 1.
+    let process1 = scheduler.remove(0).unwrap();
+    assert!(!scheduler.ready[0].has_process());
+    assert!(scheduler.ready[1].has_process());
+    let process2 = scheduler.remove(0).unwrap();
+    assert!(!scheduler.has_ready_process());
+    assert_eq!(scheduler.remove(0), None);
+    assert_eq!(scheduler.remove(1), None);
+
+    // When marked as ready while running the process is added to the queue of
+    // the worker that ran it.
+    let pid = process2.as_ref().id();
+    scheduler.mark_ready(pid);
+    scheduler.add_process(process2, 0);
+    assert!(scheduler.ready[0].has_process());
+    assert!(!scheduler.ready[1].has_process());
+    let process2 = scheduler.remove(1).unwrap();
+    assert_eq!(process2.as_ref().id(), pid);
+
+    scheduler.complete(process1);
+    scheduler.complete(process2);
+}
+
+#[test]
+fn work_stealing_priority() {
+    async fn order_actor(
+        _: actor::Context<!, ThreadSafe>,
+        id: usize,
+        order: Arc<Mutex<Vec<usize>>>,
+    ) {
+        order.lock().unwrap().push(id);
+    }
+
+    let scheduler = Scheduler::new(2);
+    let mut runtime_ref = test::runtime();
+    let run_order = Arc::new(Mutex::new(Vec::new()));
+
+    // Worker 0 gets ids 0 and 2, worker 1 gets ids 1 and 3.
+    let new_actor = order_actor as fn(_, _, _) -> _;
+    let priorities = [
+        Priority::LOW,
+        Priority::LOW,
+        Priority::HIGH,
+        Priority::NORMAL,
+    ];
+    for (id, priority) in priorities.iter().enumerate() {
+        let actor_entry = scheduler.add_actor();
+        let (actor, inbox, _) = init_actor_with_inbox(new_actor, (id, run_order.clone())).unwrap();
+        actor_entry.add(*priority, NoSupervisor, new_actor, actor, inbox, true);
+    }
+
+    // Worker 1 runs its own processes in order of priority, after which it
+    // steals the processes of worker 0, again in order of priority.
+    for _ in 0..4 {
+        let mut process = scheduler.remove(1).unwrap();
+        assert_eq!(
+            process.as_mut().run(&mut runtime_ref),
+            ProcessResult::Complete
+        );
+    }
+    assert!(!scheduler.has_process());
+    assert_eq!(*run_order.lock().unwrap(), vec![3_usize, 1, 2, 0]);
+}
+
+#[test]
+fn work_stealing_fairness_check() {
+    let scheduler = Scheduler::new(2);
+
+    // Worker 0 gets the high priority processes, worker 1 the low priority
+    // ones.
+    for _ in 0..FAIRNESS_CHECK_INTERVAL {
+        scheduler.add_future(pending::<()>(), Priority::HIGH);
+        scheduler.add_future(pending::<()>(), Priority::LOW);
+    }
+    assert_eq!(scheduler.ready[0].len(), FAIRNESS_CHECK_INTERVAL);
+    assert_eq!(scheduler.ready[1].len(), FAIRNESS_CHECK_INTERVAL);
+
+    // Worker 1 runs the processes from its own queue until the fairness check,
+    // at which point it steals the higher priority process from worker 0.
+    let mut processes = Vec::new();
+    for _ in 0..FAIRNESS_CHECK_INTERVAL - 1 {
+        processes.push(scheduler.remove(1).unwrap());
+    }
+    assert_eq!(scheduler.ready[0].len(), FAIRNESS_CHECK_INTERVAL);
+    assert_eq!(scheduler.ready[1].len(), 1);
+    processes.push(scheduler.remove(1).unwrap());
+    assert_eq!(scheduler.ready[0].len(), FAIRNESS_CHECK_INTERVAL - 1);
+    assert_eq!(scheduler.ready[1].len(), 1);
+
+    for process in processes {
+        scheduler.complete(process);
+    }
+}
@@ -156,6 +156,28 @@ impl Timers {
         })
     }
 
+    /// Returns the number of timers with a deadline after `now`.
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn pending(&self, now: Instant) -> usize {
+        let (epoch_time, index) = {
+            let epoch = self.epoch.read().unwrap();
+            (epoch.time, epoch.index as usize)
+        };
+        let (second, first) = self.slots.split_at(index);
+        let iter = first.iter().chain(second.iter());
+        let mut pending = 0;
+        for (n, slot) in iter.enumerate() {
+            let slot_epoch = epoch_time + Duration::from_nanos(n as u64 * u64::from(NS_PER_SLOT));
+            let slot = slot.read().unwrap();
+            pending += slot
+                .iter()
+                .filter(|timer| slot_epoch + Duration::from_nanos(u64::from(timer.deadline)) > now)
+                .count();
+        }
+        let overflow = self.overflow.read().unwrap();
+        pending + overflow.iter().filter(|timer| timer.deadline > now).count()
+    }
+
     /// Add a new deadline.
     pub(crate) fn add(&self, pid: ProcessId, deadline: Instant) {
         // NOTE: it's possible that we call `add_timer` based on an outdated
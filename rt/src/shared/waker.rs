@@ -221,7 +221,7 @@ mod tests {
         waker.wake_by_ref();
         assert!(shared_internals.scheduler.has_process());
         assert!(shared_internals.scheduler.has_ready_process());
-        let process = shared_internals.scheduler.remove().unwrap();
+        let process = shared_internals.scheduler.remove(0).unwrap();
         assert_eq!(process.as_ref().id(), pid);
 
         // Waking a process that isn't in the scheduler should be fine.
@@ -251,7 +251,7 @@ mod tests {
         waker2.wake();
         assert!(shared_internals.scheduler.has_process());
         assert!(shared_internals.scheduler.has_ready_process());
-        let process = shared_internals.scheduler.remove().unwrap();
+        let process = shared_internals.scheduler.remove(0).unwrap();
         assert_eq!(process.as_ref().id(), pid);
     }
 
@@ -271,7 +271,7 @@ mod tests {
         });
 
         loop {
-            if let Some(process) = shared_internals.scheduler.remove() {
+            if let Some(process) = shared_internals.scheduler.remove(0) {
                 assert_eq!(process.as_ref().id(), pid);
                 shared_internals.complete(process);
                 break;
@@ -322,7 +322,14 @@ mod tests {
         Arc::new_cyclic(|shared_internals| {
             let waker_id = waker::init(shared_internals.clone());
             let worker_wakers = vec![&*test::NOOP_WAKER].into_boxed_slice();
-            setup.complete(waker_id, worker_wakers, None)
+            setup.complete(
+                waker_id,
+                worker_wakers,
+                None,
+                None,
+                None,
+                crate::blocking::DEFAULT_THREADS,
+            )
         })
     }
 
@@ -330,7 +337,7 @@ mod tests {
         let process: Pin<Box<dyn Process + Send + Sync>> = Box::pin(TestProcess);
         let process_data = Box::pin(ProcessData::new(Priority::NORMAL, process));
         let pid = process_data.as_ref().id();
-        scheduler.add_process(process_data);
+        scheduler.add_process(process_data, 0);
         pid
     }
 }
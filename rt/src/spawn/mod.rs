@@ -1,8 +1,10 @@
 //! Module with the [`Spawn`] trait.
 
+use std::{error, fmt};
+
 use heph::actor::{self, NewActor};
 use heph::actor_ref::ActorRef;
-use heph::supervisor::Supervisor;
+use heph::supervisor::{ChildSupervisor, Supervisor, SupervisorTree};
 
 pub mod options;
 
@@ -67,6 +69,90 @@ pub trait Spawn<S, NA, RT>: PrivateSpawn<S, NA, RT> {
         self.try_spawn_setup(supervisor, new_actor, |_| Ok(arg), options)
             .unwrap_or_else(|_: AddActorError<!, !>| unreachable!())
     }
+
+    /// Attempts to spawn an actor as child of a supervision `tree`.
+    ///
+    /// The `supervisor` decides whether or not the actor is restarted after an
+    /// error, the `tree` decides what happens to the actor's siblings. See
+    /// [`SupervisorTree`] for more information.
+    ///
+    /// See [`Spawn::try_spawn`] for more information about the arguments.
+    fn try_spawn_child(
+        &mut self,
+        tree: &SupervisorTree,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> Result<ActorRef<NA::Message>, NA::Error>
+    where
+        Self: PrivateSpawn<ChildSupervisor<S, NA::Argument>, NA, RT>,
+        S: Supervisor<NA>,
+        NA: NewActor<RuntimeAccess = RT>,
+        NA::Argument: Clone,
+    {
+        let supervisor = tree.child(supervisor, arg.clone());
+        self.try_spawn_setup(supervisor, new_actor, |_| Ok(arg), options)
+            .map_err(|err| match err {
+                AddActorError::NewActor(err) => err,
+                AddActorError::<_, !>::ArgFn(_) => unreachable!(),
+            })
+    }
+
+    /// Spawn an actor as child of a supervision `tree`.
+    ///
+    /// This is a convenience method for `NewActor` implementations that never
+    /// return an error, such as asynchronous functions.
+    ///
+    /// See [`Spawn::try_spawn_child`] for more information.
+    fn spawn_child(
+        &mut self,
+        tree: &SupervisorTree,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> ActorRef<NA::Message>
+    where
+        Self: PrivateSpawn<ChildSupervisor<S, NA::Argument>, NA, RT>,
+        S: Supervisor<NA>,
+        NA: NewActor<Error = !, RuntimeAccess = RT>,
+        NA::Argument: Clone,
+    {
+        let supervisor = tree.child(supervisor, arg.clone());
+        self.try_spawn_setup(supervisor, new_actor, |_| Ok(arg), options)
+            .unwrap_or_else(|_: AddActorError<!, !>| unreachable!())
+    }
+}
+
+/// Error returned by spawning an actor or future using a [`RuntimeHandle`].
+///
+/// [`RuntimeHandle`]: crate::RuntimeHandle
+#[derive(Debug)]
+pub enum SpawnError<E> {
+    /// Calling `NewActor::new` actor resulted in an error.
+    NewActor(E),
+    /// The runtime is shutting down or has stopped, the actor or future would
+    /// never run.
+    Stopped,
+}
+
+impl<E: fmt::Display> fmt::Display for SpawnError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpawnError::NewActor(err) => write!(f, "error creating actor: {err}"),
+            SpawnError::Stopped => f.write_str("runtime is stopped"),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for SpawnError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SpawnError::NewActor(err) => Some(err),
+            SpawnError::Stopped => None,
+        }
+    }
 }
 
 mod private {
@@ -9,6 +9,8 @@ use std::num::NonZeroU8;
 use std::ops::Mul;
 use std::time::Duration;
 
+use heph::actor_ref::OverflowPolicy;
+
 /// Options for [spawning] an [`Actor`].
 ///
 /// [spawning]: crate::spawn::Spawn
@@ -33,11 +35,25 @@ use std::time::Duration;
 /// let opts = ActorOptions::default().with_priority(Priority::HIGH);
 /// # drop(opts); // Silence unused variable warning.
 /// ```
+///
+/// Giving an actor a larger inbox, dropping the oldest message once it's full.
+///
+/// ```
+/// use heph::actor_ref::OverflowPolicy;
+/// use heph_rt::spawn::ActorOptions;
+///
+/// let opts = ActorOptions::default()
+///     .with_inbox_capacity(16)
+///     .with_overflow_policy(OverflowPolicy::DropOldest);
+/// # drop(opts); // Silence unused variable warning.
+/// ```
 #[derive(Clone, Debug)]
 #[must_use]
 pub struct ActorOptions {
     priority: Priority,
     ready: bool,
+    inbox_capacity: usize,
+    overflow_policy: OverflowPolicy,
 }
 
 impl ActorOptions {
@@ -72,6 +88,41 @@ impl ActorOptions {
         self.ready = ready;
         self
     }
+
+    /// Returns the capacity of the actor's inbox.
+    pub const fn inbox_capacity(&self) -> usize {
+        self.inbox_capacity
+    }
+
+    /// Set the capacity of the actor's inbox, i.e. the number of messages that
+    /// can be queued before the [overflow policy] kicks in. Defaults to 8.
+    ///
+    /// [overflow policy]: ActorOptions::with_overflow_policy
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `capacity` is zero or larger than 29, the maximum
+    /// capacity supported by the inbox.
+    pub const fn with_inbox_capacity(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity >= heph_inbox::MIN_CAP && capacity <= heph_inbox::MAX_CAP,
+            "actor inbox capacity must be between 1 and 29"
+        );
+        self.inbox_capacity = capacity;
+        self
+    }
+
+    /// Returns the policy used when the actor's inbox is full.
+    pub const fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Set the policy used when the actor's inbox is full, see
+    /// [`OverflowPolicy`]. Defaults to [`OverflowPolicy::Block`].
+    pub const fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
 }
 
 impl Default for ActorOptions {
@@ -79,10 +130,15 @@ impl Default for ActorOptions {
         ActorOptions {
             priority: Priority::default(),
             ready: true,
+            inbox_capacity: DEFAULT_INBOX_CAPACITY,
+            overflow_policy: OverflowPolicy::Block,
         }
     }
 }
 
+/// Default capacity of an actor's inbox, same as [`heph_inbox::new_small`].
+pub(crate) const DEFAULT_INBOX_CAPACITY: usize = 8;
+
 /// Priority for an actor in the scheduler.
 ///
 /// Actors with a higher priority will be scheduled to run more often and
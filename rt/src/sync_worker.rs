@@ -14,14 +14,16 @@ use std::io::{self, Write};
 use std::sync::Arc;
 use std::thread;
 
-use heph::actor::{SyncActor, SyncContext};
-use heph::actor_ref::ActorRef;
+use heph::actor::{self, SyncActor, SyncContext};
+use heph::actor_ref::monitor::ActorMonitors;
+use heph::actor_ref::{ActorRef, DownReason, OverflowPolicy};
 use heph::supervisor::{SupervisorStrategy, SyncSupervisor};
-use heph_inbox::{self as inbox, ReceiverConnected};
+use heph_inbox::ReceiverConnected;
 use log::trace;
 use mio::{unix, Interest, Registry, Token};
 
-use crate::spawn::options::SyncActorOptions;
+use crate::process::Inbox;
+use crate::spawn::options::{SyncActorOptions, DEFAULT_INBOX_CAPACITY};
 use crate::trace;
 use crate::{self as rt, shared};
 
@@ -54,14 +56,18 @@ impl SyncWorker {
         A::Argument: Send + 'static,
     {
         unix::pipe::new().and_then(|(sender, receiver)| {
-            let (manager, send, ..) = inbox::Manager::new_small_channel();
-            let actor_ref = ActorRef::local(send);
+            let (inbox, _, actor_ref) = Inbox::new_send(
+                actor::name::<A>(),
+                rt.dead_letters(),
+                DEFAULT_INBOX_CAPACITY,
+                OverflowPolicy::Block,
+            );
             let thread_name = options
                 .take_name()
                 .unwrap_or_else(|| format!("Sync actor {id}"));
             thread::Builder::new()
                 .name(thread_name)
-                .spawn(move || main(id, supervisor, actor, arg, manager, receiver, rt, trace_log))
+                .spawn(move || main(id, supervisor, actor, arg, inbox, receiver, rt, trace_log))
                 .map(|handle| (SyncWorker { id, handle, sender }, actor_ref))
         })
     }
@@ -107,7 +113,7 @@ fn main<S, A>(
     mut supervisor: S,
     actor: A,
     mut arg: A::Argument,
-    inbox: inbox::Manager<A::Message>,
+    inbox: Inbox<A::Message>,
     receiver: unix::pipe::Receiver,
     rt: Arc<shared::RuntimeInternals>,
     mut trace_log: Option<trace::Log>,
@@ -118,11 +124,12 @@ fn main<S, A>(
     let thread = thread::current();
     let name = thread.name().unwrap();
     trace!(sync_worker_id = id, name = name; "running synchronous actor");
+    let mut monitors = ActorMonitors::new(inbox.state());
     loop {
         let timing = trace::start(&trace_log);
-        let receiver = inbox.new_receiver().unwrap_or_else(inbox_failure);
+        let receiver = inbox.manager().new_receiver().unwrap_or_else(inbox_failure);
         let rt = rt::Sync::new(rt.clone(), trace_log.clone());
-        let ctx = SyncContext::new(receiver, rt);
+        let ctx = SyncContext::new(receiver, inbox.state(), rt);
         trace::finish_rt(
             trace_log.as_mut(),
             timing,
@@ -135,12 +142,26 @@ fn main<S, A>(
         trace::finish_rt(trace_log.as_mut(), timing, "running synchronous actor", &[]);
 
         match res {
-            Ok(()) => break,
+            Ok(()) => {
+                monitors.stopped(DownReason::Normal);
+                break;
+            }
             Err(err) => {
                 let timing = trace::start(&trace_log);
                 match supervisor.decide(err) {
                     SupervisorStrategy::Restart(new_arg) => {
                         trace!(sync_worker_id = id, name = name; "restarting synchronous actor");
+                        monitors.restarted();
+                        arg = new_arg;
+                        trace::finish_rt(
+                            trace_log.as_mut(),
+                            timing,
+                            "restarting synchronous actor",
+                            &[],
+                        );
+                    }
+                    SupervisorStrategy::RestartAfter(new_arg, delay) => {
+                        trace!(sync_worker_id = id, name = name; "restarting synchronous actor after {delay:?}");
                         arg = new_arg;
                         trace::finish_rt(
                             trace_log.as_mut(),
@@ -148,6 +169,8 @@ fn main<S, A>(
                             "restarting synchronous actor",
                             &[],
                         );
+                        thread::sleep(delay);
+                        monitors.restarted();
                     }
                     SupervisorStrategy::Stop => {
                         trace::finish_rt(
@@ -156,6 +179,7 @@ fn main<S, A>(
                             "stopping synchronous actor",
                             &[],
                         );
+                        monitors.stopped(DownReason::Error);
                         break;
                     }
                     _ => unreachable!(),
@@ -169,6 +193,7 @@ fn main<S, A>(
     drop(actor);
     drop(supervisor);
     drop(inbox);
+    drop(monitors);
     drop(rt);
     drop(trace_log);
     // After dropping all values let the coordinator know we're done.
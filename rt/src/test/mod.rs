@@ -24,9 +24,23 @@
 //!    * [`poll_actor`]: poll an [`Actor`].
 //!    * [`poll_future`]: poll a [`Future`].
 //!    * [`poll_next`]: poll a [`AsyncIterator`].
+//!  * Time:
+//!    * [`advance_time`], [`set_time`]: advance the time of the timers.
+//!    * [`pending_timers`], [`assert_no_pending_timers`],
+//!      [`pending_shared_timers`]: check the pending timers.
+//!  * Deterministic testing:
+//!    * [`Simulation`]: a seeded runtime using a virtual clock.
+//!  * Probes:
+//!    * [`probe`]: create an actor reference that records the messages it
+//!      receives, allowing assertions about them using [`Probe`].
+//!  * Fault injection:
+//!    * [`set_message_loss`]: set the percentage of messages lost on purpose.
+//!    * [`inject_faults`], [`inject_shared_faults`], [`set_message_faults`]:
+//!      lose, duplicate, reorder or delay messages, see [`MessageFaults`].
+//!    * [`set_net_faults`]: inject partial writes, `WouldBlock` errors and
+//!      connection resets into network I/O, see [`NetFaults`].
 //!  * Miscellaneous:
 //!    * [`size_of_actor`], [`size_of_actor_val`]: returns the size of an actor.
-//!    * [`set_message_loss`]: set the percentage of messages lost on purpose.
 //!    * [`PanicSupervisor`]: supervisor that panics when it receives an actor's
 //!      error.
 //!
@@ -51,31 +65,44 @@ use std::async_iter::AsyncIterator;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, PoisonError};
 use std::task::{self, Poll};
 use std::time::{Duration, Instant};
 use std::{io, slice, thread};
 
+use crossbeam_channel::Receiver;
 use heph::actor::{self, Actor, NewActor, SyncActor, SyncWaker};
-use heph::actor_ref::{ActorGroup, ActorRef};
+use heph::actor_ref::{ActorGroup, ActorRef, OverflowPolicy};
 use heph::supervisor::{Supervisor, SyncSupervisor};
 use heph_inbox::oneshot::new_oneshot;
-use heph_inbox::Manager;
+use log::warn;
 
+use crate::blocking;
+use crate::local::waker::WakerId;
+use crate::process::Inbox;
 use crate::shared::waker;
+use crate::spawn::options::DEFAULT_INBOX_CAPACITY;
 use crate::spawn::{ActorOptions, FutureOptions, SyncActorOptions};
 use crate::sync_worker::SyncWorker;
 use crate::thread_waker::ThreadWaker;
-use crate::worker::{Control, Worker};
+use crate::worker::{Control, Worker, WAKER};
 use crate::{
-    self as rt, shared, ProcessId, RuntimeRef, Sync, ThreadLocal, ThreadSafe, SYNC_WORKER_ID_END,
-    SYNC_WORKER_ID_START,
+    self as rt, local, shared, ProcessId, RuntimeRef, Sync, ThreadLocal, ThreadSafe,
+    SYNC_WORKER_ID_END, SYNC_WORKER_ID_START,
 };
 
+mod net;
+mod simulation;
+
 #[doc(no_inline)]
 #[cfg(feature = "test")]
 pub use heph::test::*;
+pub(crate) use net::{
+    partial_write, partial_write_vectored, tcp_fault, udp_fault, wake_after_fault,
+};
+pub use net::{set_net_faults, NetFaults};
+pub(crate) use simulation::Rng;
+pub use simulation::Simulation;
 
 pub(crate) const TEST_PID: ProcessId = ProcessId(0);
 
@@ -92,7 +119,14 @@ static SHARED_INTERNAL: LazyLock<Arc<shared::RuntimeInternals>> = LazyLock::new(
     Arc::new_cyclic(|shared_internals| {
         let waker_id = waker::init(shared_internals.clone());
         let worker_wakers = vec![&*NOOP_WAKER].into_boxed_slice();
-        setup.complete(waker_id, worker_wakers, None)
+        setup.complete(
+            waker_id,
+            worker_wakers,
+            None,
+            None,
+            None,
+            blocking::DEFAULT_THREADS,
+        )
     })
 });
 
@@ -105,24 +139,89 @@ static SHARED_INTERNAL: LazyLock<Arc<shared::RuntimeInternals>> = LazyLock::new(
 pub fn runtime() -> RuntimeRef {
     thread_local! {
         /// Per thread runtime.
-        static TEST_RT: Worker = {
+        static TEST_RT: LocalRuntime = {
             let (_, receiver) = rt::channel::new()
                 .expect("failed to create runtime channel for test module");
-            Worker::new_test(SHARED_INTERNAL.clone(), receiver)
-                .expect("failed to create local `Runtime` for test module")
+            let (waker_id, waker_events) = take_waker();
+            let worker = Worker::new_test(
+                SHARED_INTERNAL.clone(),
+                receiver,
+                Some((waker_id, waker_events.clone())),
+            )
+            .expect("failed to create local `Runtime` for test module");
+            LocalRuntime {
+                worker: Some(worker),
+                waker: Some((waker_id, waker_events)),
+            }
         };
     }
 
-    TEST_RT.with(Worker::create_ref)
+    TEST_RT.with(|rt| rt.worker.as_ref().unwrap().create_ref())
+}
+
+/// Per thread runtime, see [`runtime`].
+struct LocalRuntime {
+    /// Always `Some`, only `None` when dropped.
+    worker: Option<Worker>,
+    waker: Option<(WakerId, Receiver<ProcessId>)>,
+}
+
+impl Drop for LocalRuntime {
+    fn drop(&mut self) {
+        // Drop all processes before the waker can be reused.
+        drop(self.worker.take());
+        if let Some(waker) = self.waker.take() {
+            return_waker(waker);
+        }
+    }
+}
+
+/// Wakers available for reuse.
+///
+/// Only a limited amount of `WakerId`s can be created (see
+/// [`local::waker::MAX_THREADS`]), so we reuse them for the per thread
+/// runtimes (see [`runtime`]) and simulations.
+///
+/// [`local::waker::MAX_THREADS`]: crate::local::waker::MAX_THREADS
+static WAKERS: Mutex<Vec<(WakerId, Receiver<ProcessId>)>> = Mutex::new(Vec::new());
+
+/// Returns an unused waker, creating one if needed.
+///
+/// The returned waker is not registered with any `Poll` instance, so it can't
+/// be used to wake a polling thread.
+fn take_waker() -> (WakerId, Receiver<ProcessId>) {
+    let waker = WAKERS.lock().unwrap_or_else(PoisonError::into_inner).pop();
+    if let Some((waker_id, waker_events)) = waker {
+        // Remove wake-ups from a previous user.
+        for _ in waker_events.try_iter() {}
+        return (waker_id, waker_events);
+    }
+
+    let poll = mio::Poll::new().expect("failed to create `Poll` instance for test module");
+    let waker = mio::Waker::new(poll.registry(), WAKER)
+        .expect("failed to create `Waker` instance for test module");
+    let (waker_sender, waker_events) = crossbeam_channel::unbounded();
+    (local::waker::init(waker, waker_sender), waker_events)
+}
+
+/// Return a waker previously returned by [`take_waker`].
+fn return_waker(waker: (WakerId, Receiver<ProcessId>)) {
+    WAKERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(waker);
 }
 
 /// Sending side of a runtime channel to control the test runtime.
 type RtControl = rt::channel::Sender<Control>;
 
+/// Control channel of the *test* runtime, see [`test_runtime`].
+static TEST_RT: OnceLock<RtControl> = OnceLock::new();
+
 /// Lazily start the *test* runtime on a new thread, returning the control
 /// channel.
 fn test_runtime() -> &'static RtControl {
-    static TEST_RT: LazyLock<RtControl> = LazyLock::new(|| {
+    TEST_RT.get_or_init(|| {
         let (sender, receiver) =
             rt::channel::new().expect("failed to create runtime channel for test module");
         let _handle = thread::Builder::new()
@@ -130,16 +229,14 @@ fn test_runtime() -> &'static RtControl {
             .spawn(move || {
                 // NOTE: because we didn't indicate the runtime has started this
                 // will never stop.
-                Worker::new_test(SHARED_INTERNAL.clone(), receiver)
+                Worker::new_test(SHARED_INTERNAL.clone(), receiver, None)
                     .expect("failed to create a runtime for test module")
                     .run()
                     .expect("failed to run test runtime");
             })
             .expect("failed to start thread for test runtime");
         sender
-    });
-
-    &TEST_RT
+    })
 }
 
 /// Run function `f` on the *test* runtime.
@@ -253,7 +350,7 @@ where
     NA: NewActor<RuntimeAccess = ThreadSafe> + std::marker::Sync + Send + 'static,
     NA::Actor: Send + std::marker::Sync + 'static,
     NA::Message: Send,
-    NA::Argument: Send,
+    NA::Argument: Send + std::marker::Sync,
     NA::Error: Send,
 {
     run_on_test_runtime_wait(move |mut runtime_ref| {
@@ -355,6 +452,142 @@ pub fn join_all<M>(actors: &ActorGroup<M>, timeout: Duration) -> JoinResult {
     }
 }
 
+/// Advance the time of the test runtime of the current thread by `duration`.
+///
+/// This advances the clocks used by the timers, such as [`Timer`],
+/// [`Deadline`] and [`Interval`], without actually waiting. The time is only
+/// advanced for the current thread, i.e. for the runtime returned by
+/// [`runtime`] (used by e.g. [`init_local_actor`]) and for thread-safe actors
+/// and futures polled on the current thread (e.g. initialised using
+/// [`init_actor`]). Because tests run on their own thread this doesn't affect
+/// other tests.
+///
+/// The time of the *test* runtime (used by e.g. [`try_spawn_local`] and
+/// [`try_spawn`]) is not changed as it's shared between all tests. If the
+/// *test* runtime has pending timers a warning is logged, as those timers still
+/// expire in real time. Use [`init_local_actor`] or [`init_actor`], or a
+/// [`Simulation`], to control the time of such actors.
+///
+/// Time can only move forward.
+///
+/// [`Timer`]: crate::timer::Timer
+/// [`Deadline`]: crate::timer::Deadline
+/// [`Interval`]: crate::timer::Interval
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// #
+/// use std::pin::Pin;
+/// use std::task::Poll;
+/// use std::time::Duration;
+///
+/// use heph::actor;
+/// use heph_rt::test::{self, init_local_actor, poll_actor};
+/// use heph_rt::timer::Timer;
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+///     Timer::after(&mut ctx, Duration::from_secs(60)).await;
+/// }
+///
+/// let actor = actor as fn(_) -> _;
+/// let (actor, _) = init_local_actor(actor, ()).unwrap();
+/// let mut actor = Box::pin(actor);
+/// assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+///
+/// // No need to wait a minute.
+/// test::advance_time(Duration::from_secs(60));
+/// assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+/// ```
+pub fn advance_time(duration: Duration) {
+    let runtime_ref = runtime();
+    runtime_ref
+        .internals
+        .clock
+        .advance_to(runtime_ref.now() + duration);
+    SHARED_INTERNAL.advance_time_to(SHARED_INTERNAL.now() + duration);
+    warn_test_runtime_timers();
+}
+
+/// Set the time of the test runtime of the current thread to `time`.
+///
+/// If `time` is before the current time its time is unchanged, as time can
+/// only move forward. See [`advance_time`] for the actors and futures this
+/// affects.
+pub fn set_time(time: Instant) {
+    runtime().internals.clock.advance_to(time);
+    SHARED_INTERNAL.advance_time_to(time);
+    warn_test_runtime_timers();
+}
+
+/// Log a warning if the *test* runtime has pending timers, which are not
+/// affected by [`advance_time`] and [`set_time`].
+fn warn_test_runtime_timers() {
+    if TEST_RT.get().is_none() {
+        // Not started, so it can't have any timers.
+        return;
+    }
+    let pending = run_on_test_runtime_wait(|runtime_ref| {
+        let timers = runtime_ref.internals.timers.borrow();
+        timers.pending(runtime_ref.now()) + SHARED_INTERNAL.pending_timers()
+    });
+    if pending != 0 {
+        warn!(
+            "advancing the time doesn't affect the {pending} pending timer(s) of \
+            the test runtime (used by `try_spawn_local`, `try_spawn`, etc.)"
+        );
+    }
+}
+
+/// Returns the number of pending timers in the runtime returned by
+/// [`runtime`] for the current thread, e.g. timers created by actors
+/// initialised using [`init_local_actor`].
+///
+/// Timers that have expired are not considered pending. Because the runtime
+/// (and its time, see [`advance_time`]) is local to the current thread, the
+/// timers of other tests are not included. For the timers of thread-safe
+/// actors and the *test* runtime see [`pending_shared_timers`].
+pub fn pending_timers() -> usize {
+    let runtime_ref = runtime();
+    let timers = runtime_ref.internals.timers.borrow();
+    timers.pending(runtime_ref.now())
+}
+
+/// Returns the number of pending timers of thread-safe actors and futures
+/// (e.g. initialised using [`init_actor`]) and of the *test* runtime (used by
+/// e.g. [`try_spawn_local`]).
+///
+/// # Notes
+///
+/// These timers are shared between all tests (in the same process), so the
+/// count includes the timers of other tests running concurrently. This makes
+/// the result racy, only rely on it if no other tests create timers, e.g. in
+/// a separate test binary.
+pub fn pending_shared_timers() -> usize {
+    let test_runtime = match TEST_RT.get() {
+        Some(_) => run_on_test_runtime_wait(|runtime_ref| {
+            let timers = runtime_ref.internals.timers.borrow();
+            timers.pending(runtime_ref.now())
+        }),
+        // Not started, so it can't have any timers.
+        None => 0,
+    };
+    SHARED_INTERNAL.pending_timers() + test_runtime
+}
+
+/// Assert that no timers are pending, see [`pending_timers`].
+///
+/// # Panics
+///
+/// This panics if any timers are pending.
+#[track_caller]
+pub fn assert_no_pending_timers() {
+    let pending = pending_timers();
+    assert!(pending == 0, "expected no pending timers, got {pending}");
+}
+
 /// Initialise a thread-local actor.
 #[allow(clippy::type_complexity)]
 pub fn init_local_actor<NA>(
@@ -384,14 +617,20 @@ where
 pub(crate) fn init_local_actor_with_inbox<NA>(
     mut new_actor: NA,
     arg: NA::Argument,
-) -> Result<(NA::Actor, Manager<NA::Message>, ActorRef<NA::Message>), NA::Error>
+) -> Result<(NA::Actor, Inbox<NA::Message>, ActorRef<NA::Message>), NA::Error>
 where
     NA: NewActor<RuntimeAccess = ThreadLocal>,
 {
-    let (manager, sender, receiver) = Manager::new_small_channel();
-    let ctx = actor::Context::new(receiver, ThreadLocal::new(TEST_PID, runtime()));
+    let (inbox, receiver, actor_ref) = Inbox::new(
+        NA::name(),
+        None,
+        DEFAULT_INBOX_CAPACITY,
+        OverflowPolicy::Block,
+    );
+    let rt = ThreadLocal::new(TEST_PID, runtime());
+    let ctx = actor::Context::new(receiver, inbox.state(), rt);
     let actor = new_actor.new(ctx, arg)?;
-    Ok((actor, manager, ActorRef::local(sender)))
+    Ok((actor, inbox, actor_ref))
 }
 
 /// Initialise a thread-safe actor with access to it's inbox.
@@ -399,14 +638,20 @@ where
 pub(crate) fn init_actor_with_inbox<NA>(
     mut new_actor: NA,
     arg: NA::Argument,
-) -> Result<(NA::Actor, Manager<NA::Message>, ActorRef<NA::Message>), NA::Error>
+) -> Result<(NA::Actor, Inbox<NA::Message>, ActorRef<NA::Message>), NA::Error>
 where
     NA: NewActor<RuntimeAccess = ThreadSafe>,
 {
-    let (manager, sender, receiver) = Manager::new_small_channel();
-    let ctx = actor::Context::new(receiver, ThreadSafe::new(TEST_PID, SHARED_INTERNAL.clone()));
+    let (inbox, receiver, actor_ref) = Inbox::new(
+        NA::name(),
+        None,
+        DEFAULT_INBOX_CAPACITY,
+        OverflowPolicy::Block,
+    );
+    let rt = ThreadSafe::new(TEST_PID, SHARED_INTERNAL.clone());
+    let ctx = actor::Context::new(receiver, inbox.state(), rt);
     let actor = new_actor.new(ctx, arg)?;
-    Ok((actor, manager, ActorRef::local(sender)))
+    Ok((actor, inbox, actor_ref))
 }
 
 /// Spawn a synchronous actor.
@@ -0,0 +1,221 @@
+//! Network faults, see [`NetFaults`].
+
+use std::cmp::min;
+use std::io::{self, IoSlice};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{error, fmt, task};
+
+use getrandom::getrandom;
+use heph::test::{max_percent, roll};
+use log::{debug, warn};
+
+/// Faults to inject into the network I/O of [`TcpStream`] and [`UdpSocket`].
+///
+/// All faults are set as a percentage of the I/O operations, a number between
+/// `0` (the default, disabling the fault) and `100`. Larger numbers are
+/// treated as `100`. Use [`set_net_faults`] to start injecting the faults.
+///
+/// Faults are injected into the `try_*` methods, e.g. [`TcpStream::try_send`],
+/// and thus also into the [`Future`]s returned by methods such as
+/// [`TcpStream::send_all`] and [`UdpSocket::recv`]. When such a `Future`
+/// receives an injected [`WouldBlock`] error it wakes itself, so that it's
+/// polled again (just like it would be once the socket becomes ready).
+///
+/// [`TcpStream`]: crate::net::TcpStream
+/// [`UdpSocket`]: crate::net::UdpSocket
+/// [`TcpStream::try_send`]: crate::net::TcpStream::try_send
+/// [`Future`]: std::future::Future
+/// [`TcpStream::send_all`]: crate::net::TcpStream::send_all
+/// [`UdpSocket::recv`]: crate::net::UdpSocket::recv
+/// [`WouldBlock`]: io::ErrorKind::WouldBlock
+///
+/// # Examples
+///
+/// ```
+/// use heph_rt::test::{set_net_faults, NetFaults};
+///
+/// // Only write part of the bytes in half of the writes and return a
+/// // `WouldBlock` error for one in ten operations.
+/// set_net_faults(NetFaults::new().partial_writes(50).would_block(10));
+///
+/// // Run tests...
+///
+/// // Stop injecting faults.
+/// set_net_faults(NetFaults::new());
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NetFaults {
+    partial_writes: u8,
+    would_block: u8,
+    connection_reset: u8,
+}
+
+impl NetFaults {
+    /// Create a new configuration without any faults.
+    pub const fn new() -> NetFaults {
+        NetFaults {
+            partial_writes: 0,
+            would_block: 0,
+            connection_reset: 0,
+        }
+    }
+
+    /// Set the percentage of send operations that only write part of the
+    /// bytes.
+    ///
+    /// Only applies to [`TcpStream`], as datagrams are never partially send.
+    ///
+    /// [`TcpStream`]: crate::net::TcpStream
+    pub const fn partial_writes(mut self, percent: u8) -> NetFaults {
+        self.partial_writes = max_percent(percent);
+        self
+    }
+
+    /// Set the percentage of operations that return a [`WouldBlock`] error.
+    ///
+    /// [`WouldBlock`]: io::ErrorKind::WouldBlock
+    pub const fn would_block(mut self, percent: u8) -> NetFaults {
+        self.would_block = max_percent(percent);
+        self
+    }
+
+    /// Set the percentage of operations that return a [`ConnectionReset`]
+    /// error.
+    ///
+    /// Only applies to [`TcpStream`].
+    ///
+    /// [`ConnectionReset`]: io::ErrorKind::ConnectionReset
+    /// [`TcpStream`]: crate::net::TcpStream
+    pub const fn connection_reset(mut self, percent: u8) -> NetFaults {
+        self.connection_reset = max_percent(percent);
+        self
+    }
+
+    /// Pack the faults into a single integer, see [`NET_FAULTS`].
+    const fn to_bits(self) -> u32 {
+        u32::from_ne_bytes([
+            self.partial_writes,
+            self.would_block,
+            self.connection_reset,
+            0,
+        ])
+    }
+
+    /// Reverse of [`NetFaults::to_bits`].
+    const fn from_bits(bits: u32) -> NetFaults {
+        let [partial_writes, would_block, connection_reset, _] = bits.to_ne_bytes();
+        NetFaults {
+            partial_writes,
+            would_block,
+            connection_reset,
+        }
+    }
+}
+
+/// Faults injected into all network I/O, packed using [`NetFaults::to_bits`]
+/// so that the `try_*` methods only need a single atomic load to check them.
+static NET_FAULTS: AtomicU32 = AtomicU32::new(0);
+
+/// Set the faults to inject into the network I/O of all sockets.
+///
+/// See [`NetFaults`] for the possible faults.
+pub fn set_net_faults(faults: NetFaults) {
+    NET_FAULTS.store(faults.to_bits(), Ordering::Relaxed);
+}
+
+fn net_faults() -> NetFaults {
+    NetFaults::from_bits(NET_FAULTS.load(Ordering::Relaxed))
+}
+
+/// Returns an error if a fault should be injected into an operation on a
+/// `TcpStream`.
+pub(crate) fn tcp_fault() -> io::Result<()> {
+    let faults = net_faults();
+    if roll(faults.connection_reset) {
+        debug!("injecting connection reset");
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionReset,
+            InjectedFault,
+        ));
+    }
+    would_block_fault(faults)
+}
+
+/// Returns an error if a fault should be injected into an operation on a
+/// `UdpSocket`.
+pub(crate) fn udp_fault() -> io::Result<()> {
+    would_block_fault(net_faults())
+}
+
+fn would_block_fault(faults: NetFaults) -> io::Result<()> {
+    if roll(faults.would_block) {
+        debug!("injecting would block error");
+        Err(io::Error::new(io::ErrorKind::WouldBlock, InjectedFault))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes to write of the `len` bytes, which is less then
+/// `len` for a partial write.
+pub(crate) fn partial_write(len: usize) -> usize {
+    if len > 1 && roll(net_faults().partial_writes) {
+        if let Some(n) = random() {
+            let n = (n as usize % (len - 1)) + 1;
+            debug!("injecting partial write: writing {n}/{len} bytes");
+            return n;
+        }
+    }
+    len
+}
+
+/// Returns the buffers to write for a partial write, or `None` if all of
+/// `bufs` should be written.
+pub(crate) fn partial_write_vectored<'a>(bufs: &'a [IoSlice<'_>]) -> Option<Vec<IoSlice<'a>>> {
+    let len = bufs.iter().map(|buf| buf.len()).sum();
+    let mut left = partial_write(len);
+    if left == len {
+        return None;
+    }
+    let mut partial = Vec::new();
+    for buf in bufs {
+        if left == 0 {
+            break;
+        }
+        let n = min(left, buf.len());
+        partial.push(IoSlice::new(&buf[..n]));
+        left -= n;
+    }
+    Some(partial)
+}
+
+/// Wake the task in `ctx` if `err` is injected, as no readiness event will
+/// wake it.
+pub(crate) fn wake_after_fault(err: &io::Error, ctx: &task::Context<'_>) {
+    if err.get_ref().is_some_and(|err| err.is::<InjectedFault>()) {
+        ctx.waker().wake_by_ref();
+    }
+}
+
+fn random() -> Option<u64> {
+    let mut n = [0; 8];
+    match getrandom(&mut n) {
+        Ok(()) => Some(u64::from_ne_bytes(n)),
+        Err(err) => {
+            warn!("error getting random bytes: {err}");
+            None
+        }
+    }
+}
+
+/// Error used for injected faults.
+#[derive(Debug)]
+struct InjectedFault;
+
+impl fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("injected fault")
+    }
+}
+
+impl error::Error for InjectedFault {}
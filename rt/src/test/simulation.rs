@@ -0,0 +1,207 @@
+//! Deterministic simulations, see [`Simulation`].
+
+use std::time::Instant;
+use std::{env, fmt, thread};
+
+use crossbeam_channel::Receiver;
+use getrandom::getrandom;
+
+use crate::local::waker::WakerId;
+use crate::test::{return_waker, take_waker, SHARED_INTERNAL};
+use crate::worker::Worker;
+use crate::{ProcessId, RuntimeRef};
+
+/// Name of the environment variable used to set the seed of a [`Simulation`].
+const SEED_ENV: &str = "HEPH_SIM_SEED";
+
+/// Deterministic single-threaded runtime.
+///
+/// A simulation runs thread-local actors and futures in an order determined by
+/// a seed, using a virtual clock. The same seed leads to the same scheduling
+/// decisions, which allows a failing (e.g. flaky) test to be reproduced by
+/// running it again with the same seed.
+///
+/// Timers, such as [`Timer`], [`Deadline`] and [`Interval`], use the virtual
+/// clock. The clock only advances when no process is ready to run, at which
+/// point it jumps to the next timer. This means a test using a timeout of an
+/// hour completes without waiting an hour.
+///
+/// [`Timer`]: crate::timer::Timer
+/// [`Deadline`]: crate::timer::Deadline
+/// [`Interval`]: crate::timer::Interval
+///
+/// # Seed
+///
+/// The seed is read from the `HEPH_SIM_SEED` environment variable, if not set
+/// a random seed is used. Alternatively [`Simulation::with_seed`] can be used.
+/// If the simulation is dropped while panicking, e.g. due to a failed
+/// assertion, the seed is printed to standard error.
+///
+/// # Notes
+///
+/// Only thread-local actors and futures are run in the simulation. Thread-safe
+/// actors and futures are spawned on the *test* runtime (see the module
+/// documentation) and thus are not deterministic.
+///
+/// I/O events are checked for, but never waited on. If a process waits on an
+/// I/O event [`Simulation::run`] returns once no other progress can be made,
+/// it can be called again after the I/O event happened.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// #
+/// use std::time::Duration;
+///
+/// use heph::actor;
+/// use heph::supervisor::NoSupervisor;
+/// use heph_rt::spawn::ActorOptions;
+/// use heph_rt::test::Simulation;
+/// use heph_rt::timer::Timer;
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+///     // Doesn't actually wait a day.
+///     Timer::after(&mut ctx, Duration::from_secs(24 * 60 * 60)).await;
+/// }
+///
+/// let mut sim = Simulation::with_seed(123);
+/// let start = sim.now();
+/// let actor = actor as fn(_) -> _;
+/// let _ = sim
+///     .runtime_ref()
+///     .spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+/// sim.run();
+/// assert!(sim.now() - start >= Duration::from_secs(24 * 60 * 60));
+/// ```
+pub struct Simulation {
+    seed: u64,
+    worker: Worker,
+    waker: Option<(WakerId, Receiver<ProcessId>)>,
+}
+
+impl Simulation {
+    /// Create a new simulation.
+    ///
+    /// The seed is read from the `HEPH_SIM_SEED` environment variable, or
+    /// random if not set.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `HEPH_SIM_SEED` is not a valid seed.
+    pub fn new() -> Simulation {
+        let seed = match env::var(SEED_ENV) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|err| panic!("invalid `{SEED_ENV}` ('{seed}'): {err}")),
+            Err(_) => {
+                let mut seed = [0; 8];
+                getrandom(&mut seed).expect("failed to get random seed for simulation");
+                u64::from_ne_bytes(seed)
+            }
+        };
+        Simulation::with_seed(seed)
+    }
+
+    /// Create a new simulation using `seed`.
+    pub fn with_seed(seed: u64) -> Simulation {
+        let (waker_id, waker_events) = take_waker();
+        let worker = Worker::new_simulation(
+            SHARED_INTERNAL.clone(),
+            waker_id,
+            waker_events.clone(),
+            seed,
+            Instant::now(),
+        )
+        .expect("failed to create simulation runtime");
+        Simulation {
+            seed,
+            worker,
+            waker: Some((waker_id, waker_events)),
+        }
+    }
+
+    /// Returns the seed used by the simulation.
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the current time of the virtual clock.
+    pub fn now(&self) -> Instant {
+        self.worker.create_ref().now()
+    }
+
+    /// Returns a reference to the simulated runtime.
+    ///
+    /// This can be used to spawn thread-local actors and futures, which are
+    /// run by calling [`Simulation::run`].
+    pub fn runtime_ref(&self) -> RuntimeRef {
+        self.worker.create_ref()
+    }
+
+    /// Run all processes until no more progress can be made, i.e. all
+    /// processes are done or waiting on something other than a timer.
+    ///
+    /// # Panics
+    ///
+    /// This panics if polling for I/O events fails.
+    pub fn run(&mut self) {
+        self.worker
+            .run_simulation()
+            .expect("failed to poll for events in simulation");
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Simulation {
+        Simulation::new()
+    }
+}
+
+impl fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulation")
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            eprintln!(
+                "simulation failed using seed {}, re-run using {SEED_ENV}={}",
+                self.seed, self.seed
+            );
+        }
+        if let Some(waker) = self.waker.take() {
+            return_waker(waker);
+        }
+    }
+}
+
+/// Pseudo-random number generator used in simulations, using the SplitMix64
+/// algorithm.
+///
+/// This is *not* a cryptographically secure generator.
+#[derive(Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a new generator based on `seed`.
+    pub(crate) const fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns the next random number.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
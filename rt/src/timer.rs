@@ -101,12 +101,14 @@ impl<RT: rt::Access> Timer<RT> {
 
     /// Create a new timer, based on a timeout.
     ///
-    /// Same as calling `Timer::at(&mut ctx, Instant::now() + timeout)`.
+    /// Same as calling `Timer::at(&mut ctx, Instant::now() + timeout)`, but
+    /// using the runtime's clock (which is virtual in a `test::Simulation`).
     pub fn after<M>(ctx: &mut actor::Context<M, RT>, timeout: Duration) -> Timer<RT>
     where
         RT: Clone,
     {
-        Timer::at(ctx, Instant::now() + timeout)
+        let now = ctx.runtime_ref().now();
+        Timer::at(ctx, now + timeout)
     }
 
     /// Returns the deadline set for this `Timer`.
@@ -116,7 +118,7 @@ impl<RT: rt::Access> Timer<RT> {
 
     /// Returns `true` if the deadline has passed.
     pub fn has_passed(&self) -> bool {
-        self.deadline <= Instant::now()
+        self.deadline <= self.rt.now()
     }
 
     /// Wrap a future creating a new `Deadline`.
@@ -263,7 +265,8 @@ impl<Fut, RT: rt::Access> Deadline<Fut, RT> {
     /// Create a new deadline based on a timeout.
     ///
     /// Same as calling `Deadline::at(&mut ctx, Instant::now() + timeout,
-    /// future)`.
+    /// future)`, but using the runtime's clock (which is virtual in a
+    /// `test::Simulation`).
     pub fn after<M>(
         ctx: &mut actor::Context<M, RT>,
         timeout: Duration,
@@ -272,7 +275,8 @@ impl<Fut, RT: rt::Access> Deadline<Fut, RT> {
     where
         RT: Clone,
     {
-        Deadline::at(ctx, Instant::now() + timeout, future)
+        let now = ctx.runtime_ref().now();
+        Deadline::at(ctx, now + timeout, future)
     }
 
     /// Returns the deadline set for this `Deadline`.
@@ -282,7 +286,7 @@ impl<Fut, RT: rt::Access> Deadline<Fut, RT> {
 
     /// Returns `true` if the deadline has passed.
     pub fn has_passed(&self) -> bool {
-        self.deadline <= Instant::now()
+        self.deadline <= self.rt.now()
     }
 
     /// Returns a reference to the wrapped future.
@@ -435,8 +439,8 @@ impl<RT: rt::Access> Interval<RT> {
     where
         RT: Clone,
     {
-        let deadline = Instant::now() + interval;
         let mut rt = ctx.runtime().clone();
+        let deadline = rt.now() + interval;
         rt.add_deadline(deadline);
         Interval {
             deadline,
@@ -455,9 +459,10 @@ impl<RT: rt::Access> AsyncIterator for Interval<RT> {
     type Item = DeadlinePassed;
 
     fn poll_next(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.deadline <= Instant::now() {
+        let now = self.rt.now();
+        if self.deadline <= now {
             // Determine the next deadline.
-            let next_deadline = Instant::now() + self.interval;
+            let next_deadline = now + self.interval;
             let this = Pin::get_mut(self);
             this.deadline = next_deadline;
             this.rt.add_deadline(next_deadline);
@@ -15,21 +15,27 @@
 //! [`Runtime`]: crate::Runtime
 //! [started]: WorkerSetup::start
 
+#[cfg(any(test, feature = "test"))]
+use std::cell::Cell;
 use std::cell::RefMut;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::atomic::{fence, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
-use std::{fmt, io, thread};
+use std::{fmt, io, ptr, slice, str, thread};
 
 use crossbeam_channel::{self, Receiver};
 use heph::actor_ref::{Delivery, SendError};
-use log::{as_debug, debug, info, trace};
+use log::{as_debug, debug, info, trace, warn};
 use mio::{Events, Poll, Registry, Token};
 
 use crate::error::StringError;
 use crate::local::waker::{self, WakerId};
+#[cfg(any(test, feature = "test"))]
+use crate::local::Clock;
 use crate::local::RuntimeInternals;
 use crate::process::{ProcessId, ProcessResult};
 use crate::setup::set_cpu_affinity;
@@ -68,6 +74,7 @@ pub(super) fn setup(id: NonZeroUsize) -> io::Result<(WorkerSetup, &'static Threa
         poll,
         waker_id,
         waker_events,
+        progress: Arc::new(Progress::new()),
     };
     Ok((setup, thread_waker))
 }
@@ -83,6 +90,8 @@ pub(super) struct WorkerSetup {
     waker_id: WakerId,
     /// Receiving side of the channel for `Waker` events.
     waker_events: Receiver<ProcessId>,
+    /// See [`Worker::progress`].
+    progress: Arc<Progress>,
 }
 
 impl WorkerSetup {
@@ -91,10 +100,13 @@ impl WorkerSetup {
         self,
         shared_internals: Arc<shared::RuntimeInternals>,
         auto_cpu_affinity: bool,
+        poll_budget: Option<Duration>,
+        count_slow_polls: bool,
         trace_log: Option<trace::Log>,
     ) -> io::Result<Handle> {
         rt::channel::new().and_then(move |(sender, receiver)| {
             let id = self.id;
+            let progress = self.progress.clone();
             thread::Builder::new()
                 .name(format!("Worker {id}"))
                 .spawn(move || {
@@ -103,6 +115,8 @@ impl WorkerSetup {
                         receiver,
                         shared_internals,
                         auto_cpu_affinity,
+                        poll_budget,
+                        count_slow_polls,
                         trace_log,
                     )
                     .map_err(rt::Error::worker)?;
@@ -112,6 +126,7 @@ impl WorkerSetup {
                     id,
                     channel: sender,
                     handle,
+                    progress,
                 })
         })
     }
@@ -131,6 +146,8 @@ pub(super) struct Handle {
     channel: rt::channel::Sender<Control>,
     /// Handle for the actual thread.
     handle: thread::JoinHandle<Result<(), rt::Error>>,
+    /// Progress of the worker thread, see [`Handle::blocked`].
+    progress: Arc<Progress>,
 }
 
 impl Handle {
@@ -163,12 +180,157 @@ impl Handle {
         self.channel.try_send(Control::Run(f))
     }
 
+    /// Returns the process blocking the worker thread, i.e. the process that
+    /// is running for longer than `budget`.
+    ///
+    /// A process is only returned once per run, so that the coordinator only
+    /// logs a blocked worker once.
+    pub(super) fn blocked(&self, budget: Duration) -> Option<Blocked> {
+        let progress = &*self.progress;
+        let (start, pid, name) = progress.running()?;
+        if progress.reported.load(Ordering::Relaxed) == start {
+            return None;
+        }
+        let elapsed = progress.elapsed(start);
+        // Make sure the process didn't finish in the meantime.
+        if elapsed <= budget || progress.start.load(Ordering::Acquire) != start {
+            return None;
+        }
+        progress.reported.store(start, Ordering::Relaxed);
+        Some(Blocked { pid, name, elapsed })
+    }
+
+    /// Returns the number of slow polls per process name, see
+    /// [`rt::Setup::count_slow_polls`].
+    pub(super) fn slow_polls(&self) -> HashMap<&'static str, usize> {
+        self.progress.slow_polls().clone()
+    }
+
     /// See [`thread::JoinHandle::join`].
     pub(super) fn join(self) -> thread::Result<Result<(), rt::Error>> {
         self.handle.join()
     }
 }
 
+/// Process blocking a worker thread, see [`Handle::blocked`].
+#[derive(Debug)]
+pub(super) struct Blocked {
+    pub(super) pid: ProcessId,
+    pub(super) name: &'static str,
+    /// Time the process has been running.
+    pub(super) elapsed: Duration,
+}
+
+/// Progress of a worker thread.
+///
+/// The worker updates this before and after running a process, which allows
+/// the coordinator to detect processes that block the worker thread. This is
+/// only done if a poll budget is set, see [`rt::Setup::poll_budget`].
+///
+/// The running process is tracked using atomics as it's updated for every
+/// process run.
+#[derive(Debug)]
+struct Progress {
+    /// Base for `start`.
+    epoch: Instant,
+    /// Time the running process started, in nanoseconds since `epoch`, or
+    /// [`NOT_RUNNING`]. Written after `pid` and `name_*`, see
+    /// [`Progress::running`].
+    start: AtomicU64,
+    /// Value of `start` of the last started process, used to keep `start`
+    /// unique. Only used by the worker thread.
+    last_start: AtomicU64,
+    /// Id of the running process.
+    pid: AtomicUsize,
+    /// Pointer and length of the name of the running process.
+    name_ptr: AtomicPtr<u8>,
+    name_len: AtomicUsize,
+    /// Value of `start` of the process last reported as blocking by the
+    /// coordinator.
+    reported: AtomicU64,
+    /// Number of slow polls per process name, only updated for slow polls.
+    slow_polls: Mutex<HashMap<&'static str, usize>>,
+}
+
+/// Value for [`Progress::start`] if no process is running.
+const NOT_RUNNING: u64 = 0;
+
+impl Progress {
+    fn new() -> Progress {
+        Progress {
+            epoch: Instant::now(),
+            start: AtomicU64::new(NOT_RUNNING),
+            last_start: AtomicU64::new(NOT_RUNNING),
+            pid: AtomicUsize::new(0),
+            name_ptr: AtomicPtr::new(ptr::null_mut()),
+            name_len: AtomicUsize::new(0),
+            reported: AtomicU64::new(NOT_RUNNING),
+            slow_polls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Mark the process with `pid` and `name` as running, started at `start`.
+    fn start(&self, pid: ProcessId, name: &'static str, start: Instant) {
+        // Never use `NOT_RUNNING` for a running process and never reuse the
+        // value of a previous process, see `Progress::running`.
+        let start = (start.duration_since(self.epoch).as_nanos() as u64)
+            .max(self.last_start.load(Ordering::Relaxed) + 1);
+        self.last_start.store(start, Ordering::Relaxed);
+        // Don't let the writes below move before the write of `NOT_RUNNING` in
+        // `Progress::finish`.
+        fence(Ordering::Release);
+        self.pid.store(pid.0, Ordering::Relaxed);
+        self.name_ptr
+            .store(name.as_ptr().cast_mut(), Ordering::Relaxed);
+        self.name_len.store(name.len(), Ordering::Relaxed);
+        self.start.store(start, Ordering::Release);
+    }
+
+    /// Returns the `start` value, id and name of the running process, if any.
+    fn running(&self) -> Option<(u64, ProcessId, &'static str)> {
+        // This is a sequence lock with `start` as sequence number: it's set to
+        // `NOT_RUNNING` while the fields of the next process are written and
+        // it's unique per process. So if it didn't change while reading the
+        // fields they all belong to the same process.
+        let start = self.start.load(Ordering::Acquire);
+        if start == NOT_RUNNING {
+            return None;
+        }
+        let pid = ProcessId(self.pid.load(Ordering::Relaxed));
+        let name_ptr = self.name_ptr.load(Ordering::Relaxed);
+        let name_len = self.name_len.load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+        if self.start.load(Ordering::Relaxed) != start {
+            return None;
+        }
+        // SAFETY: per the above `name_ptr` and `name_len` were written by the
+        // same call to `Progress::start` and thus come from the same
+        // `&'static str`.
+        let name = unsafe { str::from_utf8_unchecked(slice::from_raw_parts(name_ptr, name_len)) };
+        Some((start, pid, name))
+    }
+
+    /// Mark the running process as finished.
+    fn finish(&self) {
+        self.start.store(NOT_RUNNING, Ordering::Release);
+    }
+
+    /// Returns the time elapsed since `start`, as stored in [`Progress::start`].
+    fn elapsed(&self, start: u64) -> Duration {
+        self.epoch
+            .elapsed()
+            .saturating_sub(Duration::from_nanos(start))
+    }
+
+    fn slow_polls(&self) -> MutexGuard<'_, HashMap<&'static str, usize>> {
+        match self.slow_polls.lock() {
+            Ok(slow_polls) => slow_polls,
+            // The counts are always valid, so we can ignore the poisoning.
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
 /// Worker that runs thread-local and thread-safe actors and futurers, and
 /// holds and manages everything that is required to run them.
 pub(crate) struct Worker {
@@ -190,6 +352,17 @@ pub(crate) struct Worker {
     ///
     /// [`Runtime::start`]: rt::Runtime::start
     started: bool,
+    /// Deadline after which all remaining processes are stopped, set once the
+    /// runtime is shutting down. See [`RuntimeRef::shutdown`].
+    shutdown: Option<Instant>,
+    /// Maximum time a process may run before its considered slow, see
+    /// [`rt::Setup::poll_budget`]. If `None` processes are not tracked.
+    poll_budget: Option<Duration>,
+    /// Whether or not to count the slow polls per process, see
+    /// [`rt::Setup::count_slow_polls`].
+    count_slow_polls: bool,
+    /// Progress of the worker, shared with the coordinator.
+    progress: Arc<Progress>,
 }
 
 impl Worker {
@@ -199,6 +372,8 @@ impl Worker {
         mut receiver: rt::channel::Receiver<Control>,
         shared_internals: Arc<shared::RuntimeInternals>,
         auto_cpu_affinity: bool,
+        poll_budget: Option<Duration>,
+        count_slow_polls: bool,
         trace_log: Option<trace::Log>,
     ) -> Result<Worker, Error> {
         let timing = trace::start(&trace_log);
@@ -236,6 +411,10 @@ impl Worker {
             waker_events: setup.waker_events,
             channel: receiver,
             started: false,
+            shutdown: None,
+            poll_budget,
+            count_slow_polls,
+            progress: setup.progress,
         };
 
         trace::finish_rt(
@@ -249,33 +428,119 @@ impl Worker {
 
     /// Create a new local `Runtime` for testing.
     ///
+    /// If `waker` is `None` a new waker is created, this is required if the
+    /// worker is [run], as the waker must be registered with the worker's
+    /// `Poll` instance.
+    ///
     /// Used in the [`crate::test`] module.
+    ///
+    /// [run]: Worker::run
     #[cfg(any(test, feature = "test"))]
     pub(crate) fn new_test(
         shared_internals: Arc<shared::RuntimeInternals>,
         mut receiver: rt::channel::Receiver<Control>,
+        waker: Option<(WakerId, Receiver<ProcessId>)>,
     ) -> io::Result<Worker> {
         let poll = Poll::new()?;
 
-        // TODO: this channel will grow unbounded as the waker implementation
-        // sends pids into it.
-        let (waker_sender, waker_events) = crossbeam_channel::unbounded();
-        let waker = mio::Waker::new(poll.registry(), WAKER)?;
-        let waker_id = waker::init(waker, waker_sender);
+        let (waker_id, waker_events) = match waker {
+            Some(waker) => waker,
+            None => {
+                // TODO: this channel will grow unbounded as the waker
+                // implementation sends pids into it.
+                let (waker_sender, waker_events) = crossbeam_channel::unbounded();
+                let waker = mio::Waker::new(poll.registry(), WAKER)?;
+                (waker::init(waker, waker_sender), waker_events)
+            }
+        };
 
         receiver.register(poll.registry(), COMMS)?;
 
         let id = NonZeroUsize::new(usize::MAX).unwrap();
-        let internals = RuntimeInternals::new(id, shared_internals, waker_id, poll, None, None);
+        let mut internals = RuntimeInternals::new(id, shared_internals, waker_id, poll, None, None);
+        // Allow the time to be advanced, see `test::advance_time`.
+        internals.clock = Clock::Offset(Cell::new(Duration::ZERO));
         Ok(Worker {
             internals: Rc::new(internals),
             events: Events::with_capacity(16),
             waker_events,
             channel: receiver,
             started: false,
+            shutdown: None,
+            poll_budget: None,
+            count_slow_polls: false,
+            progress: Arc::new(Progress::new()),
+        })
+    }
+
+    /// Create a new local `Runtime` for a simulation.
+    ///
+    /// Used in [`crate::test::Simulation`].
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn new_simulation(
+        shared_internals: Arc<shared::RuntimeInternals>,
+        waker_id: WakerId,
+        waker_events: Receiver<ProcessId>,
+        seed: u64,
+        start: Instant,
+    ) -> io::Result<Worker> {
+        let poll = Poll::new()?;
+        // NOTE: the channel is never used, but required by `Worker`.
+        let (_, receiver) = rt::channel::new()?;
+        let internals =
+            RuntimeInternals::new_simulation(shared_internals, waker_id, poll, seed, start);
+        Ok(Worker {
+            internals: Rc::new(internals),
+            events: Events::with_capacity(16),
+            waker_events,
+            channel: receiver,
+            started: true,
+            shutdown: None,
+            poll_budget: None,
+            count_slow_polls: false,
+            progress: Arc::new(Progress::new()),
         })
     }
 
+    /// Run the worker in a simulation, see [`Worker::new_simulation`].
+    ///
+    /// Runs all thread-local processes until no more progress can be made. If
+    /// no processes are ready to run the virtual clock is advanced to the
+    /// next timer. OS events are only polled for, never waited on.
+    #[cfg(any(test, feature = "test"))]
+    pub(crate) fn run_simulation(&mut self) -> io::Result<()> {
+        let mut runtime_ref = self.create_ref();
+        loop {
+            let _ = self.schedule_from_waker();
+            let _ = self.schedule_from_local_timers(self.internals.clock.now());
+            self.internals
+                .poll
+                .borrow_mut()
+                .poll(&mut self.events, Some(Duration::ZERO))?;
+            let mut scheduler = self.internals.scheduler.borrow_mut();
+            for event in self.events.iter() {
+                match event.token() {
+                    WAKER | COMMS | SHARED_POLL => {}
+                    token => scheduler.mark_ready(ProcessId::from(token)),
+                }
+            }
+            drop(scheduler);
+
+            if self.run_local_process(&mut runtime_ref) {
+                continue;
+            }
+
+            if !self.waker_events.is_empty() {
+                continue;
+            }
+            let next_timer = self.internals.timers.borrow_mut().next();
+            match next_timer {
+                Some(deadline) => self.internals.clock.advance_to(deadline),
+                None => return Ok(()),
+            }
+        }
+    }
+
     /// Run the worker.
     pub(crate) fn run(mut self) -> Result<(), Error> {
         debug!(worker_id = self.internals.id.get(); "starting worker");
@@ -300,7 +565,17 @@ impl Worker {
                 n += 1;
             }
 
-            if self.started && !self.has_process() {
+            self.check_shutdown();
+            if let Some(deadline) = self.shutdown {
+                if deadline <= self.internals.clock.now() {
+                    self.stop_processes();
+                    debug!(worker_id = self.internals.id.get(); "shutdown deadline passed, stopping worker");
+                    self.internals.shared.wake_all_workers();
+                    return Ok(());
+                }
+            }
+
+            if self.started && !self.has_process() && !self.internals.shared.keep_running() {
                 debug!(worker_id = self.internals.id.get(); "no processes to run, stopping worker");
                 self.internals.shared.wake_all_workers();
                 return Ok(());
@@ -319,8 +594,10 @@ impl Worker {
                 let timing = trace::start(&*self.internals.trace_log.borrow());
                 let pid = process.as_ref().id();
                 let name = process.as_ref().name();
+                let start = self.start_process(pid, name);
                 match process.as_mut().run(runtime_ref) {
                     ProcessResult::Complete => {
+                        self.internals.scheduler.borrow_mut().complete(pid);
                         // Don't want to panic when dropping the process.
                         drop(catch_unwind(AssertUnwindSafe(move || drop(process))));
                     }
@@ -328,10 +605,15 @@ impl Worker {
                         self.internals.scheduler.borrow_mut().add_process(process);
                     }
                 }
+                let description = if self.finish_process(pid, name, start) {
+                    "Slow poll of thread-local process"
+                } else {
+                    "Running thread-local process"
+                };
                 trace::finish_rt(
                     self.internals.trace_log.borrow_mut().as_mut(),
                     timing,
-                    "Running thread-local process",
+                    description,
                     &[("id", &pid.0), ("name", &name)],
                 );
                 true
@@ -343,24 +625,32 @@ impl Worker {
     /// Attempts to run a single shared process. Returns `true` if it ran a
     /// process, `false` otherwise.
     fn run_shared_process(&mut self, runtime_ref: &mut RuntimeRef) -> bool {
-        let process = self.internals.shared.remove_process();
+        let process = self.internals.shared.remove_process(self.internals.id);
         match process {
             Some(mut process) => {
                 let timing = trace::start(&*self.internals.trace_log.borrow());
                 let pid = process.as_ref().id();
                 let name = process.as_ref().name();
+                let start = self.start_process(pid, name);
                 match process.as_mut().run(runtime_ref) {
                     ProcessResult::Complete => {
                         self.internals.shared.complete(process);
                     }
                     ProcessResult::Pending => {
-                        self.internals.shared.add_process(process);
+                        self.internals
+                            .shared
+                            .add_process(process, self.internals.id);
                     }
                 }
+                let description = if self.finish_process(pid, name, start) {
+                    "Slow poll of thread-safe process"
+                } else {
+                    "Running thread-safe process"
+                };
                 trace::finish_rt(
                     self.internals.trace_log.borrow_mut().as_mut(),
                     timing,
-                    "Running thread-safe process",
+                    description,
                     &[("id", &pid.0), ("name", &name)],
                 );
                 true
@@ -369,6 +659,86 @@ impl Worker {
         }
     }
 
+    /// Mark the process with `pid` as running, if we're tracking the progress.
+    /// Returns the time the process started running.
+    fn start_process(&self, pid: ProcessId, name: &'static str) -> Option<Instant> {
+        self.poll_budget.map(|_| {
+            let start = Instant::now();
+            self.progress.start(pid, name, start);
+            start
+        })
+    }
+
+    /// Mark the process with `pid`, started at `start` (see
+    /// [`Worker::start_process`]), as no longer running.
+    ///
+    /// Returns `true` if the process ran longer than the poll budget, logging
+    /// a warning.
+    fn finish_process(&self, pid: ProcessId, name: &'static str, start: Option<Instant>) -> bool {
+        let (Some(budget), Some(start)) = (self.poll_budget, start) else {
+            return false;
+        };
+        let elapsed = start.elapsed();
+        self.progress.finish();
+        if elapsed <= budget {
+            return false;
+        }
+
+        if self.count_slow_polls {
+            *self.progress.slow_polls().entry(name).or_insert(0) += 1;
+        }
+        warn!(
+            worker_id = self.internals.id.get(), pid = pid.0, name = name,
+            elapsed = as_debug!(elapsed), budget = as_debug!(budget);
+            "process ran longer than the poll budget, blocking other processes on the worker thread",
+        );
+        true
+    }
+
+    /// Check if the runtime started shutting down, relaying a terminate signal
+    /// to all actors that want to receive it the first time it does.
+    fn check_shutdown(&mut self) {
+        if self.shutdown.is_some() {
+            return;
+        }
+
+        if let Some(deadline) = self.internals.shared.shutdown_deadline() {
+            debug!(worker_id = self.internals.id.get(); "shutting down worker");
+            self.shutdown = Some(deadline);
+            let mut receivers = self.internals.signal_receivers.borrow_mut();
+            receivers.remove_disconnected();
+            // NOTE: unlike a process signal it's not an error if no actor
+            // wants to receive the signal.
+            let _ = receivers.try_send(Signal::Terminate, Delivery::ToAll);
+        }
+    }
+
+    /// Stop all local and shared processes that are still running after the
+    /// shutdown deadline, recording their names so they can be returned by
+    /// [`Runtime::start`].
+    ///
+    /// [`Runtime::start`]: crate::Runtime::start
+    fn stop_processes(&mut self) {
+        let local = self.internals.scheduler.borrow_mut().drain();
+        for process in local {
+            warn!(
+                worker_id = self.internals.id.get(), pid = process.as_ref().id().0, name = process.as_ref().name();
+                "thread-local process still running after shutdown deadline, stopping it",
+            );
+            self.internals.shared.add_stopped(process.as_ref().name());
+            // Don't want to panic when dropping the process.
+            drop(catch_unwind(AssertUnwindSafe(move || drop(process))));
+        }
+        for process in self.internals.shared.drain_processes() {
+            warn!(
+                worker_id = self.internals.id.get(), pid = process.as_ref().id().0, name = process.as_ref().name();
+                "thread-safe process still running after shutdown deadline, stopping it",
+            );
+            self.internals.shared.add_stopped(process.as_ref().name());
+            drop(catch_unwind(AssertUnwindSafe(move || drop(process))));
+        }
+    }
+
     /// Returns `true` if there are processes in either the local or shared
     /// schedulers.
     fn has_process(&self) -> bool {
@@ -391,9 +761,8 @@ impl Worker {
             0
         };
         local_amount += self.schedule_from_waker();
-        let now = Instant::now();
-        local_amount += self.schedule_from_local_timers(now);
-        shared_amount += self.schedule_from_shared_timers(now);
+        local_amount += self.schedule_from_local_timers(self.internals.clock.now());
+        shared_amount += self.schedule_from_shared_timers(self.internals.shared.now());
 
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
@@ -644,16 +1013,27 @@ impl Worker {
             return Some(Duration::ZERO);
         }
 
-        let now = Instant::now();
-        match self.internals.timers.borrow_mut().next() {
+        let now = self.internals.clock.now();
+        let shared_now = self.internals.shared.now();
+        let timeout = match self.internals.timers.borrow_mut().next() {
             Some(deadline) => match deadline.checked_duration_since(now) {
                 // Deadline has already expired, so no blocking.
                 None => Some(Duration::ZERO),
                 // Check the shared timers with the current deadline.
-                timeout @ Some(..) => self.internals.shared.next_timeout(now, timeout),
+                timeout @ Some(..) => self.internals.shared.next_timeout(shared_now, timeout),
             },
             // If there are no local timers check the shared timers.
-            None => self.internals.shared.next_timeout(now, None),
+            None => self.internals.shared.next_timeout(shared_now, None),
+        };
+
+        // Ensure we wake up to stop the processes once the shutdown deadline
+        // has passed.
+        match self.shutdown {
+            Some(deadline) => {
+                let shutdown_timeout = deadline.saturating_duration_since(now);
+                Some(timeout.map_or(shutdown_timeout, |t| t.min(shutdown_timeout)))
+            }
+            None => timeout,
         }
     }
 
@@ -662,10 +1042,15 @@ impl Worker {
     fn check_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
         // NOTE: we don't have to check local resources as those can't be
         // changed from outside this thread.
-        if !self.waker_events.is_empty() || self.internals.shared.has_ready_process() {
+        if !self.waker_events.is_empty()
+            || self.internals.shared.has_ready_process()
+            || (self.shutdown.is_none() && self.internals.shared.is_shutting_down())
+            || (self.started && !self.has_process() && !self.internals.shared.keep_running())
+        {
             Some(Duration::ZERO)
         } else {
-            self.internals.shared.next_timeout(Instant::now(), timeout)
+            let now = self.internals.shared.now();
+            self.internals.shared.next_timeout(now, timeout)
         }
     }
 
@@ -0,0 +1,27 @@
+#!/bin/sh
+# Generates the certificates used in the TLS tests: a CA and two certificates,
+# for `localhost` and `example.com`, signed by that CA. All files are DER
+# encoded, the private keys use PKCS #8.
+
+set -eu
+
+cd "$(dirname "$0")"
+
+openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes \
+	-days 36500 -subj "/CN=Heph test CA" \
+	-addext "basicConstraints=critical,CA:TRUE" \
+	-addext "keyUsage=critical,keyCertSign,cRLSign" \
+	-keyout ca.key.pem -out ca.pem
+openssl x509 -in ca.pem -outform DER -out ca.der
+
+for name in localhost example.com; do
+	openssl req -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes \
+		-subj "/CN=$name" -keyout "$name.key.pem" -out "$name.csr"
+	printf "basicConstraints=critical,CA:FALSE\nsubjectAltName=DNS:%s\nextendedKeyUsage=serverAuth\n" "$name" > "$name.ext"
+	openssl x509 -req -in "$name.csr" -CA ca.pem -CAkey ca.key.pem \
+		-CAcreateserial -days 36500 -extfile "$name.ext" -outform DER -out "$name.der"
+	openssl pkcs8 -topk8 -nocrypt -in "$name.key.pem" -outform DER -out "$name.key.der"
+	rm "$name.key.pem" "$name.csr" "$name.ext"
+done
+
+rm ca.key.pem ca.pem ca.srl
@@ -19,10 +19,12 @@ mod functional {
     mod actor_context;
     mod actor_group;
     mod actor_ref;
+    mod blocking;
     mod bytes;
     mod from_message;
     mod future;
     mod pipe;
+    mod registry;
     mod restart_supervisor;
     mod runtime;
     mod spawn;
@@ -30,5 +32,8 @@ mod functional {
     mod tcp;
     mod test;
     mod timer;
+    #[cfg(feature = "tls")]
+    mod tls;
     mod udp;
+    mod uds;
 }
@@ -3,10 +3,11 @@
 use std::pin::Pin;
 use std::task::Poll;
 
-use heph::actor::{self, NoMessages, RecvError};
+use heph::actor::{self, NoMessages, RecvError, StashFull};
+use heph::actor_ref::{ActorRef, InboxState, OverflowPolicy};
 use heph::supervisor::NoSupervisor;
 use heph_rt::spawn::{ActorOptions, Spawn};
-use heph_rt::test::{init_local_actor, poll_actor};
+use heph_rt::test::{init_local_actor, poll_actor, probe};
 use heph_rt::{Runtime, ThreadLocal, ThreadSafe};
 
 use crate::util::{assert_send, assert_sync};
@@ -65,6 +66,134 @@ fn actor_ref() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+async fn receive_matching_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    assert_eq!(ctx.try_receive_matching(|msg| *msg == 2), Ok(2));
+    assert_eq!(
+        ctx.try_receive_matching(|msg| *msg >= 10),
+        Err(RecvError::Empty)
+    );
+
+    let msg = ctx.receive_matching(|msg| *msg >= 10).await.unwrap();
+    assert_eq!(msg, 10);
+
+    // The skipped messages should be received in order.
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(ctx.receive_next().await, Ok(3));
+    assert_eq!(ctx.try_receive_next(), Ok(4));
+    assert_eq!(ctx.try_receive_next(), Ok(5));
+
+    assert_eq!(ctx.receive_matching(|_| true).await, Err(NoMessages));
+    assert_eq!(
+        ctx.try_receive_matching(|_| true),
+        Err(RecvError::Disconnected)
+    );
+}
+
+#[test]
+fn receive_matching() {
+    let receive_matching_actor = receive_matching_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receive_matching_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    for msg in [1usize, 2, 3] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    // Non-matching message should be skipped.
+    actor_ref.try_send(4usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    for msg in [10usize, 5] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    drop(actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn receive_matching_bounded() {
+    let (dead_letters, mut probe) = probe();
+    let (sender, receiver) = heph_inbox::new(2);
+    let state = InboxState::new(
+        "receive_matching",
+        Some(dead_letters),
+        OverflowPolicy::Block,
+    );
+    let actor_ref = ActorRef::local(sender, state.clone());
+    let mut ctx = actor::Context::new(receiver, state, ());
+
+    for msg in [1usize, 2] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    let res = ctx.try_receive_matching(|msg| *msg == 10);
+    assert_eq!(res, Err(RecvError::Empty));
+
+    // At most the inbox's capacity of messages are skipped, the remainder are
+    // left in the inbox.
+    for msg in [3usize, 10] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    assert_eq!(
+        ctx.try_receive_matching(|msg| *msg == 10),
+        Err(RecvError::Empty)
+    );
+    // Unless the first message in the inbox matches.
+    assert_eq!(ctx.try_receive_matching(|msg| *msg == 3), Ok(3));
+    assert_eq!(ctx.try_receive_matching(|msg| *msg == 10), Ok(10));
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+    assert_eq!(ctx.try_receive_next(), Err(RecvError::Empty));
+
+    // No messages should be lost.
+    assert!(probe.received().is_empty());
+}
+
+async fn stash_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    ctx.set_stash_capacity(2);
+    assert_eq!(ctx.stash_capacity(), 2);
+
+    // Stash the first two messages.
+    for expected in [1, 2] {
+        let msg = ctx.receive_next().await.unwrap();
+        assert_eq!(msg, expected);
+        ctx.stash(msg).unwrap();
+    }
+    assert_eq!(ctx.stash_len(), 2);
+
+    // Stash should be full.
+    let msg = ctx.receive_next().await.unwrap();
+    assert_eq!(msg, 3);
+    assert_eq!(ctx.stash(msg), Err(StashFull(3)));
+
+    // Unstashed messages should be received first, in order.
+    ctx.unstash_all();
+    assert_eq!(ctx.stash_len(), 0);
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(ctx.receive_next().await, Ok(2));
+    assert_eq!(ctx.try_receive_next(), Ok(4));
+}
+
+#[test]
+fn stash() {
+    let stash_actor = stash_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(stash_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    for msg in [1usize, 2, 3, 4] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn stash_full_format() {
+    assert_eq!(StashFull(()).to_string(), "stash is full");
+}
+
 async fn thread_safe_try_spawn_actor(mut ctx: actor::Context<usize, ThreadSafe>) {
     let actor_ref1 = ctx
         .try_spawn(
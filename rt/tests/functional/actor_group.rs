@@ -1,12 +1,15 @@
 //! Tests related to `ActorGroup`.
 
+use std::cell::RefCell;
 use std::convert::Infallible;
 use std::fmt;
+use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::Poll;
 
 use heph::actor;
-use heph::actor_ref::{ActorGroup, Delivery};
+use heph::actor_ref::{ActorGroup, ActorRef, Collect, Delivery, RpcError, RpcMessage};
 use heph_rt::test::{init_local_actor, poll_actor, poll_future};
 use heph_rt::ThreadLocal;
 
@@ -33,6 +36,9 @@ fn empty() {
     let group = ActorGroup::<()>::empty();
     assert!(group.try_send((), Delivery::ToAll).is_err());
     assert!(group.try_send((), Delivery::ToOne).is_err());
+    assert!(group.try_send((), Delivery::to_key("key")).is_err());
+    assert!(group.try_send((), Delivery::ToRandom).is_err());
+    assert!(group.try_send((), Delivery::ToLeastLoaded).is_err());
     assert_eq!(group.len(), 0);
     assert!(group.is_empty());
 }
@@ -350,6 +356,165 @@ fn send_delivery_to_one() {
     }
 }
 
+/// Messages received by the `record_msgs` actors: `(actor id, message)`.
+type Received = Rc<RefCell<Vec<(usize, usize)>>>;
+
+async fn record_msgs(
+    mut ctx: actor::Context<usize, ThreadLocal>,
+    (id, received): (usize, Received),
+) {
+    while let Ok(msg) = ctx.receive_next().await {
+        received.borrow_mut().push((id, msg));
+    }
+}
+
+/// Creates `n` `record_msgs` actors, all recording into `received`.
+fn record_actors(
+    n: usize,
+    received: &Received,
+) -> (
+    Vec<Pin<Box<impl Future<Output = ()>>>>,
+    Vec<ActorRef<usize>>,
+) {
+    let mut actors = Vec::with_capacity(n);
+    let mut actor_refs = Vec::with_capacity(n);
+    for id in 0..n {
+        let record_msgs = record_msgs as fn(_, _) -> _;
+        let (actor, actor_ref) = init_local_actor(record_msgs, (id, received.clone())).unwrap();
+        actors.push(Box::pin(actor));
+        actor_refs.push(actor_ref);
+    }
+    (actors, actor_refs)
+}
+
+/// Sends the messages `0..n` to `group` using the message as key, returns the
+/// id of the actor that received each message.
+fn send_keyed<F: Future>(
+    group: &ActorGroup<usize>,
+    actors: &mut [Pin<Box<F>>],
+    received: &Received,
+    n: usize,
+) -> Vec<usize> {
+    for msg in 0..n {
+        group.try_send(msg, Delivery::to_key(&msg)).unwrap();
+        for actor in actors.iter_mut() {
+            assert!(poll_future(Pin::as_mut(actor)).is_pending());
+        }
+    }
+    let mut received = received.borrow_mut();
+    assert_eq!(received.len(), n);
+    received.sort_unstable_by_key(|(_, msg)| *msg);
+    received.drain(..).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn send_delivery_to_key() {
+    const N: usize = 100;
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let (mut actors, actor_refs) = record_actors(5, &received);
+    let mut group = actor_refs.iter().cloned().collect::<ActorGroup<_>>();
+
+    let assigned = send_keyed(&group, &mut actors, &received, N);
+    // All actors should get some messages.
+    for id in 0..actor_refs.len() {
+        assert!(assigned.contains(&id));
+    }
+    // Same key, same actor.
+    assert_eq!(send_keyed(&group, &mut actors, &received, N), assigned);
+
+    // Removing an actor should only move the keys of the removed actor.
+    group.remove(&actor_refs[2]);
+    let reassigned = send_keyed(&group, &mut actors, &received, N);
+    for (before, after) in assigned.iter().zip(reassigned.iter()) {
+        if *before == 2 {
+            assert_ne!(*after, 2);
+        } else {
+            assert_eq!(before, after);
+        }
+    }
+
+    // Adding it back should restore the original assignment.
+    group.add(actor_refs[2].clone());
+    assert_eq!(send_keyed(&group, &mut actors, &received, N), assigned);
+}
+
+#[test]
+fn send_delivery_to_random() {
+    const N: usize = 30;
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let (mut actors, actor_refs) = record_actors(3, &received);
+    let group = actor_refs.iter().cloned().collect::<ActorGroup<_>>();
+
+    for msg in 0..N {
+        group.try_send(msg, Delivery::ToRandom).unwrap();
+        for actor in actors.iter_mut() {
+            assert!(poll_future(Pin::as_mut(actor)).is_pending());
+        }
+    }
+    let mut msgs: Vec<usize> = received.borrow().iter().map(|(_, msg)| *msg).collect();
+    msgs.sort_unstable();
+    assert_eq!(msgs, (0..N).collect::<Vec<_>>());
+}
+
+#[test]
+fn send_delivery_to_least_loaded() {
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let (mut actors, actor_refs) = record_actors(3, &received);
+    let group = actor_refs.iter().cloned().collect::<ActorGroup<_>>();
+
+    // Fill the inboxes of the first two actors.
+    actor_refs[0].try_send(0usize).unwrap();
+    actor_refs[0].try_send(1usize).unwrap();
+    actor_refs[0].try_send(2usize).unwrap();
+    actor_refs[1].try_send(3usize).unwrap();
+
+    group.try_send(4usize, Delivery::ToLeastLoaded).unwrap();
+    // Now actor 1 and 2 are equally loaded.
+    group.try_send(5usize, Delivery::ToLeastLoaded).unwrap();
+
+    for actor in actors.iter_mut() {
+        assert!(poll_future(Pin::as_mut(actor)).is_pending());
+    }
+    received.borrow_mut().sort_unstable_by_key(|(_, msg)| *msg);
+    assert_eq!(received.borrow()[4], (2, 4));
+    assert!(matches!(received.borrow()[5], (1 | 2, 5)));
+
+    // Once the actors processed their messages all are equally loaded again.
+    for msg in 6..9usize {
+        group.try_send(msg, Delivery::ToLeastLoaded).unwrap();
+    }
+    for actor in actors.iter_mut() {
+        assert!(poll_future(Pin::as_mut(actor)).is_pending());
+    }
+    let mut ids: Vec<usize> = received.borrow()[6..].iter().map(|(id, _)| *id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[test]
+fn inbox_metrics() {
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let (mut actors, actor_refs) = record_actors(2, &received);
+    let group = actor_refs.iter().cloned().collect::<ActorGroup<_>>();
+
+    actor_refs[0].try_send(0usize).unwrap();
+    actor_refs[0].try_send(1usize).unwrap();
+    actor_refs[1].try_send(2usize).unwrap();
+
+    let metrics: Vec<_> = group.inbox_metrics().map(|m| m.len).collect();
+    assert_eq!(metrics, [2, 1]);
+
+    for actor in actors.iter_mut() {
+        assert!(poll_future(Pin::as_mut(actor)).is_pending());
+    }
+    for metrics in group.inbox_metrics() {
+        assert_eq!(metrics.len, 0);
+        assert_eq!(metrics.capacity, actor_refs[0].capacity());
+    }
+    let high_watermarks: Vec<_> = group.inbox_metrics().map(|m| m.high_watermark).collect();
+    assert_eq!(high_watermarks, [2, 1]);
+}
+
 async fn stop_on_run(ctx: actor::Context<Infallible, ThreadLocal>) {
     drop(ctx);
 }
@@ -453,3 +618,138 @@ fn join_all_before_one_actor_finished() {
     test(true);
     test(false);
 }
+
+async fn double(mut ctx: actor::Context<RpcMessage<usize, usize>, ThreadLocal>) {
+    let msg = ctx.receive_next().await.expect("missing message");
+    msg.handle(|n| n * 2).unwrap();
+}
+
+async fn no_response(mut ctx: actor::Context<RpcMessage<usize, usize>, ThreadLocal>) {
+    let msg = ctx.receive_next().await.expect("missing message");
+    drop(msg);
+}
+
+#[test]
+fn rpc_empty() {
+    let group = ActorGroup::<RpcMessage<usize, usize>>::empty();
+    let mut future = Box::pin(group.rpc(1usize, Collect::All));
+    assert_eq!(
+        poll_future(Pin::as_mut(&mut future)),
+        Poll::Ready(Vec::new())
+    );
+}
+
+#[test]
+fn rpc_all() {
+    let mut actors = Vec::new();
+    let group: ActorGroup<_> = (0..3)
+        .map(|_| {
+            let double = double as fn(_) -> _;
+            let (actor, actor_ref) = init_local_actor(double, ()).unwrap();
+            actors.push(Box::pin(actor));
+            actor_ref
+        })
+        .collect();
+
+    let mut future = Box::pin(group.rpc(2usize, Collect::All));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+
+    let mut last = actors.pop().unwrap();
+    for mut actor in actors {
+        assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    }
+    // Still waiting on the last actor.
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut last)), Poll::Ready(Ok(())));
+    let expected = vec![Ok(4usize), Ok(4), Ok(4)];
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Ready(expected));
+}
+
+#[test]
+fn rpc_first() {
+    let mut actors = Vec::new();
+    let group: ActorGroup<_> = (0..3)
+        .map(|_| {
+            let double = double as fn(_) -> _;
+            let (actor, actor_ref) = init_local_actor(double, ()).unwrap();
+            actors.push(Box::pin(actor));
+            actor_ref
+        })
+        .collect();
+
+    let mut future = Box::pin(group.rpc(2usize, Collect::First(2)));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actors[2])), Poll::Ready(Ok(())));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actors[0])), Poll::Ready(Ok(())));
+    let expected = vec![Ok(4usize), Err(RpcError::Cancelled), Ok(4)];
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Ready(expected));
+}
+
+#[test]
+fn rpc_first_ok() {
+    let no_response = no_response as fn(_) -> _;
+    let (actor1, actor_ref1) = init_local_actor(no_response, ()).unwrap();
+    let double = double as fn(_) -> _;
+    let (actor2, actor_ref2) = init_local_actor(double, ()).unwrap();
+    let (actor3, actor_ref3) = init_local_actor(double, ()).unwrap();
+    let mut actor1 = Box::pin(actor1);
+    let mut actor2 = Box::pin(actor2);
+    let group = ActorGroup::new([actor_ref1, actor_ref2, actor_ref3]);
+
+    let mut future = Box::pin(group.rpc(3usize, Collect::FirstOk));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+
+    // An error shouldn't complete the call.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor1)), Poll::Ready(Ok(())));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor2)), Poll::Ready(Ok(())));
+    let expected = vec![
+        Err(RpcError::NoResponse),
+        Ok(6usize),
+        Err(RpcError::Cancelled),
+    ];
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Ready(expected));
+    drop(actor3);
+}
+
+#[test]
+fn rpc_send_error() {
+    let double = double as fn(_) -> _;
+    let (actor1, actor_ref1) = init_local_actor(double, ()).unwrap();
+    let (actor2, actor_ref2) = init_local_actor(double, ()).unwrap();
+    let mut actor1 = Box::pin(actor1);
+    drop(actor2);
+    let group = ActorGroup::new([actor_ref1, actor_ref2]);
+
+    let mut future = Box::pin(group.rpc(1usize, Collect::All));
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Pending);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor1)), Poll::Ready(Ok(())));
+    let expected = vec![Ok(2usize), Err(RpcError::SendError)];
+    assert_eq!(poll_future(Pin::as_mut(&mut future)), Poll::Ready(expected));
+}
+
+#[test]
+fn rpc_deadline() {
+    let double = double as fn(_) -> _;
+    let (actor1, actor_ref1) = init_local_actor(double, ()).unwrap();
+    let (actor2, actor_ref2) = init_local_actor(double, ()).unwrap();
+    let mut actor1 = Box::pin(actor1);
+    let group = ActorGroup::new([actor_ref1, actor_ref2]);
+
+    // Deadline that has already passed.
+    let future = group
+        .rpc(1usize, Collect::All)
+        .with_deadline(std::future::ready(()));
+    let mut future = Box::pin(future);
+    assert_eq!(
+        poll_future(Pin::as_mut(&mut future)),
+        Poll::Ready(vec![Err(RpcError::TimedOut), Err(RpcError::TimedOut)])
+    );
+    // The actor should be able to receive the request, but not respond to it.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor1)), Poll::Ready(Ok(())));
+    drop(actor2);
+}
@@ -1,5 +1,6 @@
 //! Tests related to `ActorRef`.
 
+use std::any::type_name;
 use std::convert::Infallible;
 use std::fmt;
 use std::num::NonZeroUsize;
@@ -7,7 +8,10 @@ use std::pin::Pin;
 use std::task::Poll;
 
 use heph::actor;
-use heph::actor_ref::{ActorRef, Join, RpcError, RpcMessage, SendError, SendValue};
+use heph::actor_ref::{
+    ActorRef, DeadLetter, DeadLetterReason, InboxState, Join, OverflowPolicy, RpcError, RpcMessage,
+    SendError, SendValue,
+};
 use heph::supervisor::NoSupervisor;
 use heph_rt::spawn::options::Priority;
 use heph_rt::spawn::ActorOptions;
@@ -24,7 +28,7 @@ const MSGS: &[&str] = &["Hello world", "Hello mars", "Hello moon"];
 #[test]
 fn size() {
     assert_size::<ActorRef<()>>(24);
-    assert_size::<SendValue<'_, ()>>(40);
+    assert_size::<SendValue<'_, ()>>(48);
     assert_size::<Join<'_, ()>>(32);
 }
 
@@ -576,6 +580,280 @@ fn send_error_format() {
     assert_eq!(format!("{}", SendError), "unable to send message");
 }
 
+/// Returns an `ActorRef` to use as dead-letter actor and the receiving side of
+/// it.
+fn dead_letters() -> (ActorRef<DeadLetter>, heph_inbox::Receiver<DeadLetter>) {
+    let (sender, receiver) = heph_inbox::new_small();
+    (
+        ActorRef::local(
+            sender,
+            InboxState::new("dead_letters", None, OverflowPolicy::Block),
+        ),
+        receiver,
+    )
+}
+
+/// Returns an `ActorRef` for the actor "target" that sends undeliverable
+/// messages to `dead_letters` and the receiving side of it.
+fn dead_letter_target<M: Send + 'static>(
+    dead_letters: ActorRef<DeadLetter>,
+) -> (ActorRef<M>, heph_inbox::Receiver<M>) {
+    let (sender, receiver) = heph_inbox::new_small();
+    let state = InboxState::new_send::<M>("target", Some(dead_letters), OverflowPolicy::Block);
+    (ActorRef::local(sender, state), receiver)
+}
+
+#[track_caller]
+fn assert_dead_letter<M>(
+    letter: Result<DeadLetter, heph_inbox::RecvError>,
+    target: Option<&'static str>,
+    reason: DeadLetterReason,
+) -> DeadLetter {
+    let letter = letter.unwrap();
+    assert_eq!(letter.message_type, type_name::<M>());
+    assert_eq!(letter.target, target);
+    assert_eq!(letter.reason, reason);
+    letter
+}
+
+/// Returns the message included in `letter`.
+#[track_caller]
+fn letter_message<M: 'static>(letter: DeadLetter) -> M {
+    *letter.message.unwrap().downcast::<M>().unwrap()
+}
+
+#[test]
+fn dead_letters_try_send() {
+    let (dead_letters, mut letters) = dead_letters();
+    let (actor_ref, receiver) = dead_letter_target::<usize>(dead_letters);
+
+    while actor_ref.try_send(1usize).is_ok() {}
+    let letter =
+        assert_dead_letter::<usize>(letters.try_recv(), Some("target"), DeadLetterReason::Full);
+    assert_eq!(letter_message::<usize>(letter), 1);
+
+    drop(receiver);
+    assert_eq!(actor_ref.try_send(2usize), Err(SendError));
+    let letter = assert_dead_letter::<usize>(
+        letters.try_recv(),
+        Some("target"),
+        DeadLetterReason::Disconnected,
+    );
+    assert_eq!(letter_message::<usize>(letter), 2);
+    assert!(letters.try_recv().is_err());
+}
+
+#[test]
+fn dead_letters_send() {
+    let (dead_letters, mut letters) = dead_letters();
+    let (actor_ref, receiver) = dead_letter_target::<usize>(dead_letters);
+    drop(receiver);
+
+    let mut send = Box::pin(actor_ref.send(1usize));
+    assert_eq!(poll_future(send.as_mut()), Poll::Ready(Err(SendError)));
+    let letter = assert_dead_letter::<usize>(
+        letters.try_recv(),
+        Some("target"),
+        DeadLetterReason::Disconnected,
+    );
+    assert_eq!(letter_message::<usize>(letter), 1);
+}
+
+#[test]
+fn dead_letters_mapped() {
+    let (dead_letters, mut letters) = dead_letters();
+    let (actor_ref, receiver) = dead_letter_target::<NonZeroUsize>(dead_letters);
+    let actor_ref: ActorRef<usize> =
+        actor_ref.try_map_fn(|msg| NonZeroUsize::new(msg).ok_or(SendError));
+
+    assert_eq!(actor_ref.try_send(0usize), Err(SendError));
+    let letter = assert_dead_letter::<usize>(
+        letters.try_recv(),
+        Some("target"),
+        DeadLetterReason::MapFailed,
+    );
+    assert!(letter.message.is_none());
+
+    drop(receiver);
+    assert_eq!(actor_ref.try_send(1usize), Err(SendError));
+    let letter = assert_dead_letter::<NonZeroUsize>(
+        letters.try_recv(),
+        Some("target"),
+        DeadLetterReason::Disconnected,
+    );
+    assert_eq!(letter_message::<NonZeroUsize>(letter).get(), 1);
+}
+
+#[test]
+fn dead_letters_rpc_response() {
+    let (dead_letters, mut letters) = dead_letters();
+    let (actor_ref, mut receiver) = dead_letter_target::<RpcMessage<(), usize>>(dead_letters);
+
+    let mut rpc = Box::pin(actor_ref.rpc(()));
+    assert_eq!(poll_future(rpc.as_mut()), Poll::Pending);
+    let msg = receiver.try_recv().unwrap();
+    // Caller stops waiting for the response.
+    drop(rpc);
+
+    assert_eq!(msg.response.respond(1), Err(SendError));
+    let letter =
+        assert_dead_letter::<usize>(letters.try_recv(), None, DeadLetterReason::Disconnected);
+    assert!(letter.message.is_none());
+}
+
+#[test]
+fn dead_letter_reason_format() {
+    assert_eq!(DeadLetterReason::Full.to_string(), "inbox full");
+    assert_eq!(DeadLetterReason::Disconnected.to_string(), "disconnected");
+    assert_eq!(
+        DeadLetterReason::MapFailed.to_string(),
+        "failed to map message"
+    );
+}
+
+/// Creates an inbox with `capacity` and `policy`, sends it the messages
+/// `0..send` and expects to receive `expected` from it. Returns the result of
+/// sending each message.
+fn overflow(
+    capacity: usize,
+    policy: OverflowPolicy,
+    send: usize,
+    expected: &[usize],
+) -> Vec<Result<(), SendError>> {
+    let (sender, receiver) = heph_inbox::new(capacity);
+    let state = InboxState::new("overflow", None, policy);
+    let actor_ref = ActorRef::local(sender, state.clone());
+    assert_eq!(actor_ref.capacity(), capacity);
+    let results = (0..send).map(|msg| actor_ref.try_send(msg)).collect();
+
+    let mut ctx = actor::Context::new(receiver, state, ());
+    for expected in expected {
+        assert_eq!(ctx.try_receive_next(), Ok(*expected));
+    }
+    assert!(ctx.try_receive_next().is_err());
+    results
+}
+
+#[test]
+fn inbox_capacity() {
+    let results = overflow(2, OverflowPolicy::Block, 3, &[0, 1]);
+    assert_eq!(results, [Ok(()), Ok(()), Err(SendError)]);
+}
+
+#[test]
+#[should_panic = "actor inbox capacity must be between 1 and 29"]
+fn inbox_capacity_zero() {
+    let _ = ActorOptions::default().with_inbox_capacity(0);
+}
+
+#[test]
+fn overflow_drop_newest() {
+    let results = overflow(2, OverflowPolicy::DropNewest, 5, &[0, 1]);
+    assert_eq!(results, [Ok(()); 5]);
+}
+
+#[test]
+fn overflow_drop_oldest() {
+    let results = overflow(2, OverflowPolicy::DropOldest, 5, &[3, 4]);
+    assert_eq!(results, [Ok(()); 5]);
+}
+
+#[test]
+fn overflow_grow() {
+    let results = overflow(2, OverflowPolicy::Grow(2), 5, &[0, 1, 2, 3]);
+    assert_eq!(results, [Ok(()), Ok(()), Ok(()), Ok(()), Err(SendError)]);
+}
+
+#[test]
+fn overflow_grow_send_in_order() {
+    let (sender, receiver) = heph_inbox::new(1);
+    let state = InboxState::new("overflow", None, OverflowPolicy::Grow(1));
+    let actor_ref = ActorRef::local(sender, state.clone());
+    let mut ctx = actor::Context::new(receiver, state, ());
+
+    actor_ref.try_send(0usize).unwrap();
+    actor_ref.try_send(1usize).unwrap();
+    // Both the inbox and overflow queue are full.
+    let mut send = Box::pin(actor_ref.send(2usize));
+    assert_eq!(poll_future(send.as_mut()), Poll::Pending);
+
+    // Receiving moves message 1 into the inbox, making space in the queue.
+    assert_eq!(ctx.try_receive_next(), Ok(0));
+    assert_eq!(poll_future(send.as_mut()), Poll::Ready(Ok(())));
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+    assert!(ctx.try_receive_next().is_err());
+}
+
+#[test]
+fn overflow_dead_letters() {
+    let (dead_letters, mut letters) = dead_letters();
+    let (sender, receiver) = heph_inbox::new::<usize>(2);
+    let state =
+        InboxState::new_send::<usize>("target", Some(dead_letters), OverflowPolicy::DropNewest);
+    let actor_ref = ActorRef::local(sender, state);
+
+    for msg in 0..3usize {
+        actor_ref.try_send(msg).unwrap();
+    }
+    let letter =
+        assert_dead_letter::<usize>(letters.try_recv(), Some("target"), DeadLetterReason::Full);
+    assert_eq!(letter_message::<usize>(letter), 2);
+    assert!(letters.try_recv().is_err());
+    drop(receiver);
+}
+
+#[test]
+fn inbox_metrics() {
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let (actor, actor_ref) = init_local_actor(expect_msgs, vec![0usize, 1, 2]).unwrap();
+    let mut actor = Box::pin(actor);
+
+    assert_eq!(actor_ref.inbox_len(), 0);
+    assert_eq!(actor_ref.capacity(), INBOX_SIZE);
+    assert_eq!(actor_ref.inbox_high_watermark(), 0);
+
+    actor_ref.try_send(0usize).unwrap();
+    actor_ref.try_send(1usize).unwrap();
+    assert_eq!(actor_ref.inbox_len(), 2);
+    assert_eq!(actor_ref.inbox_high_watermark(), 2);
+
+    // Mapped actor references share the same inbox.
+    let mapped: ActorRef<u8> = actor_ref.clone().map();
+    mapped.try_send(2u8).unwrap();
+    let metrics = mapped.inbox_metrics();
+    assert_eq!(metrics.len, 3);
+    assert_eq!(metrics.capacity, INBOX_SIZE);
+    assert_eq!(metrics.high_watermark, 3);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(actor_ref.inbox_len(), 0);
+    assert_eq!(actor_ref.inbox_high_watermark(), 3);
+}
+
+#[test]
+fn inbox_len_tracked_from_start() {
+    let (sender, receiver) = heph_inbox::new(4);
+    let state = InboxState::new("target", None, OverflowPolicy::Block);
+    let actor_ref = ActorRef::local(sender, state.clone());
+    let mut ctx = actor::Context::new(receiver, state, ());
+
+    // Messages send before the first query should be counted.
+    actor_ref.try_send(0usize).unwrap();
+    actor_ref.try_send(1usize).unwrap();
+    assert_eq!(actor_ref.inbox_len(), 2);
+    assert_eq!(actor_ref.inbox_high_watermark(), 2);
+
+    assert_eq!(ctx.try_receive_next(), Ok(0));
+    assert_eq!(actor_ref.inbox_len(), 1);
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(actor_ref.inbox_len(), 0);
+    assert!(ctx.try_receive_next().is_err());
+    actor_ref.try_send(2usize).unwrap();
+    assert_eq!(actor_ref.inbox_len(), 1);
+    assert_eq!(actor_ref.inbox_high_watermark(), 2);
+}
+
 async fn wake_on_send(_: actor::Context<usize, ThreadLocal>, relay_ref: ActorRef<usize>) {
     relay_ref
         .send(123usize)
@@ -846,6 +1124,8 @@ fn rpc_error_format() {
     assert_eq!(format!("{}", RpcError::SendError), "unable to send message");
     assert_eq!(format!("{}", RpcError::SendError), format!("{}", SendError));
     assert_eq!(format!("{}", RpcError::NoResponse), "no RPC response");
+    assert_eq!(format!("{}", RpcError::TimedOut), "RPC timed out");
+    assert_eq!(format!("{}", RpcError::Cancelled), "RPC cancelled");
 }
 
 async fn wake_on_response(_: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
@@ -0,0 +1,102 @@
+use std::pin::Pin;
+use std::task::Poll;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use heph::actor::{self, Actor};
+use heph_rt::blocking::Error;
+use heph_rt::test::{init_actor, init_local_actor, poll_actor};
+use heph_rt::{ThreadLocal, ThreadSafe};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Poll `actor` until it's done, or panic after [`TIMEOUT`].
+fn run_actor<A: Actor>(actor: A)
+where
+    A::Error: std::fmt::Debug,
+{
+    let mut actor = Box::pin(actor);
+    let start = Instant::now();
+    loop {
+        match poll_actor(Pin::as_mut(&mut actor)) {
+            Poll::Ready(res) => return res.unwrap(),
+            Poll::Pending if start.elapsed() > TIMEOUT => panic!("actor didn't complete"),
+            Poll::Pending => thread::sleep(Duration::from_millis(1)),
+        }
+    }
+}
+
+#[test]
+fn spawn_blocking_thread_local() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let actor_thread = thread::current().id();
+        let (thread, name) = ctx
+            .runtime()
+            .spawn_blocking(|| {
+                let thread = thread::current();
+                (thread.id(), thread.name().map(str::to_owned))
+            })
+            .await
+            .unwrap();
+        assert_ne!(thread, actor_thread);
+        assert_eq!(name.as_deref(), Some("heph-blocking"));
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    run_actor(actor);
+}
+
+#[test]
+fn spawn_blocking_thread_safe() {
+    async fn actor(mut ctx: actor::Context<!, ThreadSafe>) {
+        let value = ctx.runtime().spawn_blocking(|| 1 + 1).await.unwrap();
+        assert_eq!(value, 2);
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_actor(actor, ()).unwrap();
+    run_actor(actor);
+}
+
+#[test]
+fn spawn_blocking_panic() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let err = ctx
+            .runtime()
+            .spawn_blocking::<_, ()>(|| panic!("oops"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Panic(ref msg) if msg == "oops"));
+        assert_eq!(err.to_string(), "blocking function panicked: oops");
+
+        // The pool should still work after a panic.
+        let value = ctx.runtime().spawn_blocking(|| "ok").await.unwrap();
+        assert_eq!(value, "ok");
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    run_actor(actor);
+}
+
+#[test]
+fn spawn_blocking_many() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let futures: Vec<_> = (0..64)
+            .map(|n| {
+                ctx.runtime().spawn_blocking(move || {
+                    thread::sleep(Duration::from_millis(1));
+                    n * 2
+                })
+            })
+            .collect();
+        for (n, future) in futures.into_iter().enumerate() {
+            assert_eq!(future.await.unwrap(), n * 2);
+        }
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    run_actor(actor);
+}
@@ -0,0 +1,177 @@
+//! Tests for the registry of named actors.
+//!
+//! NOTE: all tests share the same registry, so every test must use unique
+//! names.
+
+use std::pin::Pin;
+use std::task::Poll;
+
+use heph::actor::{self, SyncContext};
+use heph::actor_ref::ActorRef;
+use heph::supervisor::NoSupervisor;
+use heph_rt::registry::{LookupError, RegisterError, Registered};
+use heph_rt::spawn::SyncActorOptions;
+use heph_rt::test::{self, init_actor, init_local_actor, poll_actor, spawn_sync_actor};
+use heph_rt::{Sync, ThreadLocal, ThreadSafe};
+
+async fn pending_actor<RT>(_: actor::Context<String, RT>) {
+    std::future::pending().await
+}
+
+#[test]
+fn register_error_format() {
+    let err = RegisterError::AlreadyRegistered;
+    assert_eq!(err.to_string(), "name already registered");
+}
+
+#[test]
+fn lookup_error_format() {
+    assert_eq!(
+        LookupError::NotRegistered.to_string(),
+        "name not registered"
+    );
+    assert_eq!(
+        LookupError::InvalidType.to_string(),
+        "registered actor has a different message type"
+    );
+}
+
+#[test]
+fn register_and_lookup() {
+    const NAME: &str = "register_and_lookup";
+    let mut runtime_ref = test::runtime();
+    assert_eq!(
+        runtime_ref.lookup::<String>(NAME).unwrap_err(),
+        LookupError::NotRegistered
+    );
+
+    let pending_actor = pending_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(pending_actor, ()).unwrap();
+    runtime_ref.register(NAME, actor_ref.clone()).unwrap();
+
+    let found = runtime_ref.lookup::<String>(NAME).unwrap();
+    assert!(found.sends_to(&actor_ref));
+    // Lookups are type-checked.
+    assert_eq!(
+        runtime_ref.lookup::<usize>(NAME).unwrap_err(),
+        LookupError::InvalidType
+    );
+    drop(actor);
+}
+
+#[test]
+fn register_already_registered() {
+    const NAME: &str = "register_already_registered";
+    let mut runtime_ref = test::runtime();
+
+    let pending_actor = pending_actor as fn(_) -> _;
+    let (actor1, actor_ref1) = init_local_actor(pending_actor, ()).unwrap();
+    let (actor2, actor_ref2) = init_local_actor(pending_actor, ()).unwrap();
+    runtime_ref.register(NAME, actor_ref1.clone()).unwrap();
+    assert_eq!(
+        runtime_ref.register(NAME, actor_ref2).unwrap_err(),
+        RegisterError::AlreadyRegistered
+    );
+    // The same actor can register again, e.g. after a restart.
+    runtime_ref.register(NAME, actor_ref1.clone()).unwrap();
+
+    let found = runtime_ref.lookup::<String>(NAME).unwrap();
+    assert!(found.sends_to(&actor_ref1));
+    drop((actor1, actor2));
+}
+
+#[test]
+fn unregister_stopped_actor() {
+    const NAME: &str = "unregister_stopped_actor";
+    let mut runtime_ref = test::runtime();
+
+    let pending_actor = pending_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(pending_actor, ()).unwrap();
+    runtime_ref.register(NAME, actor_ref).unwrap();
+    assert!(runtime_ref.lookup::<String>(NAME).is_ok());
+
+    // Once the actor stops it should be unregistered.
+    drop(actor);
+    assert_eq!(
+        runtime_ref.lookup::<String>(NAME).unwrap_err(),
+        LookupError::NotRegistered
+    );
+
+    // Allowing other actors to use the name.
+    let (actor, actor_ref) = init_local_actor(pending_actor, ()).unwrap();
+    runtime_ref.register(NAME, actor_ref).unwrap();
+    drop(actor);
+}
+
+async fn watcher_actor(mut ctx: actor::Context<Registered, ThreadLocal>, name: &'static str) {
+    let msg = ctx.receive_next().await.unwrap();
+    assert_eq!(msg.name, name);
+    let msg = ctx.receive_next().await.unwrap();
+    assert_eq!(msg.name, name);
+}
+
+#[test]
+fn watch_registrations() {
+    const NAME: &str = "watch_registrations";
+    let mut runtime_ref = test::runtime();
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, NAME).unwrap();
+    let mut watcher = Box::pin(watcher);
+    runtime_ref.watch_registrations(NAME, watcher_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    let pending_actor = pending_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(pending_actor, ()).unwrap();
+    runtime_ref.register(NAME, actor_ref.clone()).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    // Registering again, e.g. after a restart, should also notify the watcher.
+    runtime_ref.register(NAME, actor_ref).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Ready(Ok(())));
+    drop(actor);
+}
+
+async fn thread_safe_actor(mut ctx: actor::Context<String, ThreadSafe>) {
+    const NAME: &str = "thread_safe_access";
+    let actor_ref = ctx.actor_ref();
+    ctx.runtime().register(NAME, actor_ref.clone()).unwrap();
+    let found = ctx.runtime_ref().lookup::<String>(NAME).unwrap();
+    assert!(found.sends_to(&actor_ref));
+}
+
+#[test]
+fn thread_safe_access() {
+    let thread_safe_actor = thread_safe_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_actor(thread_safe_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    drop(actor_ref);
+}
+
+fn sync_actor(mut ctx: SyncContext<!, Sync>, actor_ref: ActorRef<String>) {
+    const NAME: &str = "sync_access";
+    ctx.runtime().register(NAME, actor_ref.clone()).unwrap();
+    let found = ctx.runtime_ref().lookup::<String>(NAME).unwrap();
+    assert!(found.sends_to(&actor_ref));
+}
+
+#[test]
+fn sync_access() {
+    let pending_actor = pending_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(pending_actor, ()).unwrap();
+    let sync_actor = sync_actor as fn(_, _);
+    let (handle, _) = spawn_sync_actor(
+        NoSupervisor,
+        sync_actor,
+        actor_ref.clone(),
+        SyncActorOptions::default(),
+    )
+    .unwrap();
+    handle.join().unwrap();
+
+    // The registration should be visible to the other threads as well.
+    let found = test::runtime().lookup::<String>("sync_access").unwrap();
+    assert!(found.sends_to(&actor_ref));
+    drop(actor);
+}
@@ -10,6 +10,7 @@ use std::thread::{self, sleep};
 use std::time::Duration;
 
 use heph::actor::{self, Actor, NewActor, SyncContext};
+use heph::actor_ref::{ActorRef, DeadLetterReason, InboxState, OverflowPolicy};
 use heph::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
 use heph_rt::spawn::options::{ActorOptions, FutureOptions, Priority, SyncActorOptions};
 use heph_rt::{Runtime, ThreadLocal, ThreadSafe};
@@ -746,3 +747,48 @@ fn catches_local_future_panics_on_drop() {
     assert!(PANIC_RAN.load(Ordering::SeqCst));
     assert!(OK_RAN.load(Ordering::SeqCst));
 }
+
+async fn stop_actor(_: actor::Context<usize, ThreadSafe>) {}
+
+fn stop_sync_actor(_: SyncContext<usize, heph_rt::Sync>) {}
+
+#[test]
+fn dead_letters() {
+    let (sender, mut letters) = heph_inbox::new_small();
+    let dead_letters = ActorRef::local(
+        sender,
+        InboxState::new("dead_letters", None, OverflowPolicy::Block),
+    );
+
+    let mut runtime = Runtime::setup().dead_letters(dead_letters).build().unwrap();
+    let actor_ref = runtime.spawn(
+        NoSupervisor,
+        stop_actor as fn(_) -> _,
+        (),
+        ActorOptions::default(),
+    );
+    let sync_actor_ref = runtime
+        .spawn_sync_actor(
+            NoSupervisor,
+            stop_sync_actor as fn(_),
+            (),
+            SyncActorOptions::default(),
+        )
+        .unwrap();
+    runtime.start().unwrap();
+
+    // Both actors stopped, so the messages can't be delivered.
+    assert!(actor_ref.try_send(1usize).is_err());
+    assert!(sync_actor_ref.try_send(2usize).is_err());
+    let mut messages = Vec::new();
+    for _ in 0..2 {
+        let letter = letters.try_recv().unwrap();
+        assert_eq!(letter.message_type, "usize");
+        assert!(letter.target.is_some());
+        assert_eq!(letter.reason, DeadLetterReason::Disconnected);
+        messages.push(*letter.message.unwrap().downcast::<usize>().unwrap());
+    }
+    messages.sort_unstable();
+    assert_eq!(messages, [1, 2]);
+    assert!(letters.try_recv().is_err());
+}
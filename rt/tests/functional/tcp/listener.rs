@@ -1,11 +1,12 @@
 use std::io;
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
 use std::task::Poll;
 use std::time::Duration;
 
 use heph::supervisor::NoSupervisor;
 use heph::{actor, ActorRef};
+use heph_rt::net::tcp::listener::TcpListenerOptions;
 use heph_rt::net::{TcpListener, TcpStream};
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{init_local_actor, join_many, poll_actor, try_spawn_local};
@@ -73,6 +74,56 @@ fn ttl() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+#[test]
+fn bind_with_reuse_port() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let options = TcpListenerOptions::default()
+            .with_backlog(16)
+            .with_reuse_port(true);
+        let mut listener1 =
+            TcpListener::bind_with(&mut ctx, any_local_address(), options.clone()).unwrap();
+        let address = listener1.local_addr().unwrap();
+
+        // Without `SO_REUSEPORT` binding to the same address fails.
+        let err = TcpListener::bind(&mut ctx, address).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        let mut listener2 = TcpListener::bind_with(&mut ctx, address, options).unwrap();
+        assert_eq!(listener2.local_addr().unwrap(), address);
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn bind_with_only_v6() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let options = TcpListenerOptions::default()
+            .with_only_v6(true)
+            .with_send_buffer_size(64 * 1024)
+            .with_recv_buffer_size(64 * 1024)
+            .with_linger(Duration::from_secs(1))
+            .with_keepalive_time(Duration::from_secs(60));
+        let address = SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0);
+        let mut listener = TcpListener::bind_with(&mut ctx, address, options).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // As the IPv6 listener only accepts IPv6 connections we can bind an
+        // IPv4 listener to the same port.
+        let address = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port);
+        let mut listener = TcpListener::bind(&mut ctx, address).unwrap();
+        assert_eq!(listener.local_addr().unwrap(), address);
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
 const DATA: &[u8] = b"Hello world";
 
 async fn stream_actor<RT>(mut ctx: actor::Context<SocketAddr, RT>)
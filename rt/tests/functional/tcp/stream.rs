@@ -12,6 +12,9 @@ use std::time::Duration;
 use heph::actor;
 use heph::actor_ref::{ActorRef, RpcMessage};
 use heph::supervisor::NoSupervisor;
+#[cfg(target_os = "linux")]
+use heph_rt::net::tcp::listener::TcpListenerOptions;
+use heph_rt::net::tcp::stream::TcpStreamOptions;
 use heph_rt::net::{TcpListener, TcpStream};
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{join, join_many, try_spawn_local, PanicSupervisor};
@@ -104,6 +107,70 @@ fn connect() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn connect_with_options() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let local_address = any_local_address();
+        let options = TcpStreamOptions::default()
+            .with_local_address(local_address)
+            .with_send_buffer_size(64 * 1024)
+            .with_recv_buffer_size(64 * 1024)
+            .with_linger(Duration::from_secs(1))
+            .with_keepalive_time(Duration::from_secs(60))
+            .with_keepalive_interval(Duration::from_secs(10))
+            .with_keepalive_retries(3);
+        let mut stream = TcpStream::connect_with(&mut ctx, address, options)?.await?;
+        assert_eq!(stream.local_addr()?.ip(), local_address.ip());
+        assert_eq!(stream.peer_addr()?, address);
+        assert!(stream.keepalive()?);
+
+        stream.send_all(DATA).await
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0; DATA.len() + 1];
+    let n = stream.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], DATA);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn connect_with_fast_open() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let options = TcpListenerOptions::default().with_fast_open(16);
+        let mut listener = TcpListener::bind_with(&mut ctx, any_local_address(), options)?;
+        let address = listener.local_addr()?;
+
+        // Fast Open is only used once the client has a cookie from the server,
+        // i.e. on the second connection, but both should work.
+        for _ in 0..2 {
+            let options = TcpStreamOptions::default().with_fast_open(true);
+            let mut stream = TcpStream::connect_with(&mut ctx, address, options)?.await?;
+            stream.send_all(DATA).await?;
+
+            let (stream, _) = listener.accept().await?;
+            let mut stream = stream.bind_to(&mut ctx)?;
+            let mut buf = Vec::with_capacity(DATA.len() + 1);
+            stream.recv_n(&mut buf, DATA.len()).await?;
+            assert_eq!(buf, DATA);
+        }
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 #[cfg_attr(
     target_os = "freebsd",
@@ -1,22 +1,25 @@
 //! Tests for the `test` module.
 
+use std::cell::{Cell, RefCell};
 use std::future::pending;
 use std::future::poll_fn;
 use std::iter::FromIterator;
 use std::mem::size_of;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{self, Poll};
 use std::time::{Duration, Instant};
 
 use heph::actor::{self, Actor, NewActor};
-use heph::actor_ref::ActorGroup;
+use heph::actor_ref::{ActorGroup, ActorRef};
 use heph::supervisor::NoSupervisor;
 use heph_rt::spawn::{ActorOptions, FutureOptions};
 use heph_rt::test::{
-    self, join, join_all, join_many, size_of_actor, size_of_actor_val, spawn_future, try_spawn,
-    try_spawn_local, JoinResult,
+    self, init_local_actor, join, join_all, join_many, poll_actor, size_of_actor,
+    size_of_actor_val, spawn_future, try_spawn, try_spawn_local, JoinResult, Simulation,
 };
-use heph_rt::timer::Timer;
+use heph_rt::timer::{Deadline, DeadlinePassed, Interval, Timer};
+use heph_rt::util::next;
 use heph_rt::{self as rt, ThreadLocal};
 
 #[test]
@@ -33,7 +36,7 @@ fn test_size_of_actor() {
 
     #[allow(trivial_casts)]
     {
-        assert_eq!(size_of_actor_val(&(actor1 as fn(_) -> _)), 40);
+        assert_eq!(size_of_actor_val(&(actor1 as fn(_) -> _)), 56);
     }
 
     struct Na;
@@ -250,3 +253,226 @@ fn assert_within_margin(start: Instant, expected: Duration) {
         "elapsed: {elapsed:?}, expected: {expected:?}",
     );
 }
+
+/// Returns `Poll::Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|ctx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Runs a number of futures in a simulation using `seed`, returns the order
+/// in which they ran.
+fn simulation_order(seed: u64) -> Vec<usize> {
+    let order = Rc::new(RefCell::new(Vec::new()));
+    let mut sim = Simulation::with_seed(seed);
+    let mut runtime_ref = sim.runtime_ref();
+    for n in 0..8 {
+        let order = order.clone();
+        runtime_ref.spawn_local_future(
+            async move {
+                for _ in 0..3 {
+                    order.borrow_mut().push(n);
+                    yield_now().await;
+                }
+            },
+            FutureOptions::default(),
+        );
+    }
+    sim.run();
+    drop(sim);
+    Rc::try_unwrap(order).unwrap().into_inner()
+}
+
+#[test]
+fn simulation_same_seed_same_order() {
+    let order = simulation_order(1);
+    assert_eq!(order.len(), 8 * 3);
+    assert_eq!(order, simulation_order(1));
+    assert_ne!(order, simulation_order(2));
+}
+
+#[test]
+fn simulation_seed() {
+    let sim = Simulation::with_seed(123);
+    assert_eq!(sim.seed(), 123);
+}
+
+#[test]
+fn simulation_timer() {
+    const TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, done: Rc<Cell<bool>>) {
+        let timer = Timer::after(&mut ctx, TIMEOUT);
+        timer.await;
+        done.set(true);
+    }
+
+    let start = Instant::now();
+    let mut sim = Simulation::with_seed(0);
+    let sim_start = sim.now();
+    let done = Rc::new(Cell::new(false));
+    let actor = actor as fn(_, _) -> _;
+    let _ =
+        sim.runtime_ref()
+            .spawn_local(NoSupervisor, actor, done.clone(), ActorOptions::default());
+    sim.run();
+    assert!(done.get());
+    assert!(sim.now() - sim_start >= TIMEOUT);
+    assert_within_margin(start, Duration::ZERO);
+}
+
+#[test]
+fn simulation_interval() {
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, ticks: Rc<RefCell<Vec<Instant>>>) {
+        let mut interval = Interval::every(&mut ctx, INTERVAL);
+        for _ in 0..3 {
+            let _ = next(&mut interval).await;
+            ticks.borrow_mut().push(interval.next_deadline() - INTERVAL);
+        }
+    }
+
+    let mut sim = Simulation::with_seed(0);
+    let sim_start = sim.now();
+    let ticks = Rc::new(RefCell::new(Vec::new()));
+    let actor = actor as fn(_, _) -> _;
+    let _ =
+        sim.runtime_ref()
+            .spawn_local(NoSupervisor, actor, ticks.clone(), ActorOptions::default());
+    sim.run();
+    let ticks = ticks.borrow();
+    assert_eq!(ticks.len(), 3);
+    let mut expected = sim_start;
+    for tick in ticks.iter() {
+        expected += INTERVAL;
+        assert!(*tick >= expected, "tick: {tick:?}, expected: {expected:?}");
+    }
+    assert_eq!(sim.now(), *ticks.last().unwrap());
+}
+
+/// Large timeout used in the time tests, which we never want to wait on.
+const LARGE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Actor that waits for a timer of [`LARGE_TIMEOUT`].
+async fn timer_actor<RT: rt::Access + Clone>(mut ctx: actor::Context<!, RT>) {
+    let _ = Timer::after(&mut ctx, LARGE_TIMEOUT).await;
+}
+
+#[test]
+fn advance_time_local_timer() {
+    let start = Instant::now();
+    let actor = timer_actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    assert_eq!(test::pending_timers(), 1);
+
+    test::advance_time(LARGE_TIMEOUT / 2);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    test::advance_time(LARGE_TIMEOUT / 2);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    drop(actor);
+    test::assert_no_pending_timers();
+    assert_within_margin(start, Duration::ZERO);
+}
+
+#[test]
+fn advance_time_deadline_and_interval() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let future = pending::<Result<(), DeadlinePassed>>();
+        let res = Deadline::after(&mut ctx, LARGE_TIMEOUT, future).await;
+        assert!(res.is_err());
+
+        let mut interval = Interval::every(&mut ctx, LARGE_TIMEOUT);
+        let _ = next(&mut interval).await;
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    test::advance_time(LARGE_TIMEOUT);
+    // Deadline passed, now waiting on the interval.
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    assert_eq!(test::pending_timers(), 1);
+    test::advance_time(LARGE_TIMEOUT);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    drop(actor);
+    test::assert_no_pending_timers();
+}
+
+#[test]
+fn set_time() {
+    let actor = timer_actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+
+    // Can't go back in time.
+    test::set_time(Instant::now() - LARGE_TIMEOUT);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+
+    test::set_time(Instant::now() + 2 * LARGE_TIMEOUT);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+#[should_panic = "expected no pending timers, got 1"]
+fn assert_no_pending_timers() {
+    let actor = timer_actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    test::assert_no_pending_timers();
+}
+
+/// Actor that sends `msg` to `actor_ref`.
+async fn send_actor<RT>(_: actor::Context<!, RT>, actor_ref: ActorRef<usize>, msg: usize) {
+    actor_ref.send(msg).await.unwrap();
+}
+
+#[test]
+fn probe_thread_local_and_thread_safe_actors() {
+    let (actor_ref, mut probe) = test::probe::<usize>();
+
+    let actor = send_actor as fn(_, _, _) -> _;
+    let local_ref = try_spawn_local(
+        NoSupervisor,
+        actor,
+        (actor_ref.clone(), 1usize),
+        ActorOptions::default(),
+    )
+    .unwrap();
+    join(&local_ref, Duration::from_secs(1)).unwrap();
+    assert_eq!(*probe.expect_message(Duration::from_secs(1)), 1);
+
+    let actor = send_actor as fn(_, _, _) -> _;
+    let shared_ref = try_spawn(
+        NoSupervisor,
+        actor,
+        (actor_ref, 2usize),
+        ActorOptions::default(),
+    )
+    .unwrap();
+    join(&shared_ref, Duration::from_secs(1)).unwrap();
+    assert_eq!(*probe.expect_message(Duration::from_secs(1)), 2);
+
+    probe.expect_no_message(Duration::from_millis(10));
+    assert_eq!(probe.received(), [1, 2]);
+}
+
+#[test]
+#[should_panic = "can't shutdown the test runtime"]
+fn shutdown_test_runtime() {
+    test::runtime().shutdown(Instant::now());
+}
@@ -0,0 +1,222 @@
+//! Tests for the TLS types.
+
+use std::io::{self, IoSlice, Read, Write};
+use std::net::{self, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use heph::actor;
+use heph::messages::Terminate;
+use heph::supervisor::SupervisorStrategy;
+use heph_rt::net::tls::rustls::server::ResolvesServerCertUsingSni;
+use heph_rt::net::tls::rustls::sign::{self, CertifiedKey};
+use heph_rt::net::tls::rustls::{Certificate, ClientConnection, PrivateKey, RootCertStore};
+use heph_rt::net::tls::rustls::{ServerConnection, StreamOwned};
+use heph_rt::net::tls::{ClientConfig, ServerConfig, TlsStream};
+use heph_rt::net::{TcpServer, TcpStream};
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::{join, try_spawn_local, PanicSupervisor};
+use heph_rt::ThreadLocal;
+
+use crate::util::any_local_address;
+
+const DATA: &[u8] = b"Hello world";
+const DATAV: &[&[u8]] = &[b"Hello world!", b" ", b"From mars."];
+const DATAV_LEN: usize = DATAV[0].len() + DATAV[1].len() + DATAV[2].len();
+
+// Generated using `tests/data/tls/generate.sh`.
+const CA: &[u8] = include_bytes!("../data/tls/ca.der");
+const LOCALHOST_CERT: &[u8] = include_bytes!("../data/tls/localhost.der");
+const LOCALHOST_KEY: &[u8] = include_bytes!("../data/tls/localhost.key.der");
+const EXAMPLE_CERT: &[u8] = include_bytes!("../data/tls/example.com.der");
+const EXAMPLE_KEY: &[u8] = include_bytes!("../data/tls/example.com.key.der");
+
+fn client_config(alpn: &[&[u8]]) -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add(&Certificate(CA.to_vec())).unwrap();
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    Arc::new(config)
+}
+
+/// Server configuration selecting the certificate for `localhost` or
+/// `example.com` based on SNI.
+fn server_config(alpn: &[&[u8]]) -> Arc<ServerConfig> {
+    let mut resolver = ResolvesServerCertUsingSni::new();
+    for (name, cert, key) in [
+        ("localhost", LOCALHOST_CERT, LOCALHOST_KEY),
+        ("example.com", EXAMPLE_CERT, EXAMPLE_KEY),
+    ] {
+        let key = sign::any_supported_type(&PrivateKey(key.to_vec())).unwrap();
+        let key = CertifiedKey::new(vec![Certificate(cert.to_vec())], key);
+        resolver.add(name, key).unwrap();
+    }
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(resolver));
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    Arc::new(config)
+}
+
+#[test]
+fn client() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let config = client_config(&[b"h2", b"heph"]);
+        let server_name = "localhost".try_into().unwrap();
+        let mut stream = TlsStream::connect(&mut ctx, address, config, server_name)?.await?;
+        assert_eq!(stream.peer_addr().unwrap(), address);
+        assert_eq!(stream.alpn_protocol(), Some(&b"heph"[..]));
+        assert!(stream.protocol_version().is_some());
+        assert_eq!(stream.server_name(), None);
+
+        let bufs = &mut [
+            IoSlice::new(DATAV[0]),
+            IoSlice::new(DATAV[1]),
+            IoSlice::new(DATAV[2]),
+        ];
+        stream.send_vectored_all(bufs).await?;
+
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        stream.recv_n(&mut buf, DATA.len()).await?;
+        assert_eq!(buf, DATA);
+
+        stream.close().await?;
+        // Peer also closes the connection.
+        buf.clear();
+        assert_eq!(stream.recv(&mut buf).await?, 0);
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (stream, _) = listener.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let tls = ServerConnection::new(server_config(&[b"heph"])).unwrap();
+    let mut stream = StreamOwned::new(tls, stream);
+
+    let mut buf = [0; DATAV_LEN];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, &*DATAV.concat());
+    assert_eq!(stream.conn.server_name(), Some("localhost"));
+
+    stream.write_all(DATA).unwrap();
+    // Actor closes the connection.
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    stream.conn.send_close_notify();
+    stream.flush().unwrap();
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn client_handshake_error() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let config = client_config(&[]);
+        // Server doesn't have a certificate for this name.
+        let server_name = "heph.example.com".try_into().unwrap();
+        let err = TlsStream::connect(&mut ctx, address, config, server_name)?
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (stream, _) = listener.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let tls = ServerConnection::new(server_config(&[])).unwrap();
+    let mut stream = StreamOwned::new(tls, stream);
+    let mut buf = [0; 8];
+    assert!(stream.read(&mut buf).is_err());
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn server() {
+    async fn conn_actor(
+        _: actor::Context<!, ThreadLocal>,
+        mut stream: TlsStream,
+        _: SocketAddr,
+    ) -> io::Result<()> {
+        assert_eq!(stream.server_name(), Some("example.com"));
+        assert_eq!(stream.alpn_protocol(), Some(&b"http/1.1"[..]));
+
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        stream.recv_n(&mut buf, DATA.len()).await?;
+        assert_eq!(buf, DATA);
+        stream.send_all(&buf).await?;
+        stream.close().await
+    }
+
+    fn supervisor(err: io::Error) -> SupervisorStrategy<(TcpStream, SocketAddr)> {
+        // The handshake fails for unknown server names.
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let _ = HANDSHAKE_ERRORS.fetch_add(1, Ordering::SeqCst);
+        SupervisorStrategy::Stop
+    }
+
+    static HANDSHAKE_ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+    let server = TcpServer::setup_tls(
+        any_local_address(),
+        supervisor,
+        conn_actor as fn(_, _, _) -> _,
+        server_config(&[b"h2", b"http/1.1"]),
+        ActorOptions::default(),
+    )
+    .unwrap();
+    let address = server.local_addr();
+    let server_ref = try_spawn_local(PanicSupervisor, server, (), ActorOptions::default()).unwrap();
+
+    let connect = |server_name: &str| {
+        let stream = net::TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let config = client_config(&[b"http/1.1"]);
+        let server_name = server_name.try_into().unwrap();
+        let tls = ClientConnection::new(config, server_name).unwrap();
+        StreamOwned::new(tls, stream)
+    };
+
+    // Server should select the certificate for `example.com`, otherwise the
+    // certificate verification fails.
+    let mut stream = connect("example.com");
+    stream.write_all(DATA).unwrap();
+    let mut buf = [0; DATA.len()];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+    assert_eq!(stream.conn.alpn_protocol(), Some(&b"http/1.1"[..]));
+    // Actor closes the connection.
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+
+    // No certificate for this server name, so the handshake should fail and
+    // the supervisor should be called.
+    let mut stream = connect("heph.example.com");
+    assert!(stream.write_all(DATA).is_err());
+
+    server_ref.try_send(Terminate).unwrap();
+    join(&server_ref, Duration::from_secs(1)).unwrap();
+    assert_eq!(HANDSHAKE_ERRORS.load(Ordering::SeqCst), 1);
+}
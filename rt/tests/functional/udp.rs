@@ -1,13 +1,13 @@
 //! Tests related to `UdpSocket`.
 
 use std::io::{self, IoSlice};
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::time::Duration;
 
 use heph::actor::{self, Actor, NewActor};
 use heph::actor_ref::{ActorRef, RpcMessage};
 use heph::supervisor::NoSupervisor;
-use heph_rt::net::udp::{UdpSocket, Unconnected};
+use heph_rt::net::udp::{UdpSocket, UdpSocketOptions, Unconnected};
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{join, try_spawn_local, PanicSupervisor};
 use heph_rt::{self as rt, Bound, Runtime, RuntimeRef, ThreadLocal};
@@ -392,3 +392,247 @@ fn actor_bound() {
 
     runtime.start().unwrap();
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn unconnected_many() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let mut socket1 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket2 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket3 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let address1 = socket1.local_addr()?;
+        let address2 = socket2.local_addr()?;
+        let address3 = socket3.local_addr()?;
+
+        let bufs = &[(DATA, address2), (DATAV[0], address3), (DATAV[2], address2)];
+        let n = socket1.send_to_many(bufs).await?;
+        assert_eq!(n, bufs.len());
+
+        let mut bufs = (0..4)
+            .map(|_| Vec::with_capacity(DATA.len() + 1))
+            .collect::<Vec<_>>();
+        let addresses = socket2.recv_from_many(&mut bufs).await?;
+        assert_eq!(addresses, [address1, address1]);
+        assert_eq!(bufs[0], DATA);
+        assert_eq!(bufs[1], DATAV[2]);
+        assert!(bufs[2].is_empty());
+        assert!(bufs[3].is_empty());
+
+        let mut bufs = [Vec::with_capacity(DATA.len() + 1)];
+        let addresses = socket3.recv_from_many(&mut bufs).await?;
+        assert_eq!(addresses, [address1]);
+        assert_eq!(bufs[0], DATAV[0]);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn connected_many() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let socket1 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket2 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket1 = socket1.connect(socket2.local_addr()?)?;
+        let mut socket2 = socket2.connect(socket1.local_addr()?)?;
+
+        let n = socket1.send_many(DATAV).await?;
+        assert_eq!(n, DATAV.len());
+
+        let mut bufs = (0..4)
+            .map(|_| Vec::with_capacity(DATA.len() + 2))
+            .collect::<Vec<_>>();
+        let n = socket2.recv_many(&mut bufs).await?;
+        assert_eq!(n, DATAV.len());
+        for (buf, expected) in bufs.iter().zip(DATAV) {
+            assert_eq!(buf, expected);
+        }
+        assert!(bufs[3].is_empty());
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn segmentation_offload() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        const SEGMENT_SIZE: usize = 4;
+
+        let socket1 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket2 = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let mut socket1 = socket1.connect(socket2.local_addr()?)?;
+        let mut socket2 = socket2.connect(socket1.local_addr()?)?;
+
+        socket1.set_gso_segment_size(SEGMENT_SIZE as u16)?;
+        assert_eq!(socket1.gso_segment_size()?, SEGMENT_SIZE as u16);
+        assert!(!socket2.gro()?);
+
+        // Without GRO the kernel splits the buffer into multiple datagrams.
+        let n = socket1.send(DATA).await?;
+        assert_eq!(n, DATA.len());
+        let mut bufs = (0..4)
+            .map(|_| Vec::with_capacity(DATA.len()))
+            .collect::<Vec<_>>();
+        let n = socket2.recv_many(&mut bufs).await?;
+        assert_eq!(n, 3);
+        for (buf, expected) in bufs.iter().zip(DATA.chunks(SEGMENT_SIZE)) {
+            assert_eq!(buf, expected);
+        }
+
+        // With GRO the datagrams are received in a single buffer.
+        socket2.set_gro(true)?;
+        assert!(socket2.gro()?);
+        let n = socket1.send(DATA).await?;
+        assert_eq!(n, DATA.len());
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let (n, segment_size) = socket2.recv_segments(&mut buf).await?;
+        assert_eq!(n, DATA.len());
+        assert_eq!(segment_size, SEGMENT_SIZE);
+        assert_eq!(buf, DATA);
+
+        // Disabling GSO sends the buffer as a single datagram.
+        socket1.set_gso_segment_size(0)?;
+        let n = socket1.send(DATA).await?;
+        assert_eq!(n, DATA.len());
+        buf.clear();
+        let (n, segment_size) = socket2.recv_segments(&mut buf).await?;
+        assert_eq!(n, DATA.len());
+        assert_eq!(segment_size, DATA.len());
+        assert_eq!(buf, DATA);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn multicast_ipv4() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        const GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 0, 1);
+
+        let mut receiver = UdpSocket::bind(&mut ctx, (Ipv4Addr::UNSPECIFIED, 0).into())?;
+        let port = receiver.local_addr()?.port();
+        receiver.join_multicast_v4(GROUP, Ipv4Addr::LOCALHOST)?;
+
+        let mut sender = UdpSocket::bind(&mut ctx, any_local_address())?;
+        let sender_address = sender.local_addr()?;
+        sender.set_multicast_if_v4(Ipv4Addr::LOCALHOST)?;
+        assert_eq!(sender.multicast_if_v4()?, Ipv4Addr::LOCALHOST);
+        sender.set_multicast_ttl_v4(0)?;
+        assert_eq!(sender.multicast_ttl_v4()?, 0);
+        sender.set_multicast_loop_v4(true)?;
+        assert!(sender.multicast_loop_v4()?);
+
+        let n = sender.send_to(DATA, (GROUP, port).into()).await?;
+        assert_eq!(n, DATA.len());
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let (n, address) = receiver.recv_from(&mut buf).await?;
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+        assert_eq!(address, sender_address);
+
+        receiver.leave_multicast_v4(GROUP, Ipv4Addr::LOCALHOST)?;
+        // Can't leave a group we're not a member of.
+        assert!(receiver
+            .leave_multicast_v4(GROUP, Ipv4Addr::LOCALHOST)
+            .is_err());
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn multicast_ipv6() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        // Interface-local scope, i.e. only used for loopback.
+        const GROUP: Ipv6Addr = Ipv6Addr::new(0xff01, 0, 0, 0, 0, 0, 0, 0x123);
+        // Index of the loopback interface.
+        const INTERFACE: u32 = 1;
+
+        // NOTE: the loopback interface doesn't support sending IPv6 multicast
+        // datagrams (it doesn't have the `MULTICAST` flag set), so we only
+        // test the options here.
+        let mut socket = UdpSocket::bind(&mut ctx, (Ipv6Addr::UNSPECIFIED, 0).into())?;
+        socket.join_multicast_v6(GROUP, INTERFACE)?;
+        socket.set_multicast_if_v6(INTERFACE)?;
+        assert_eq!(socket.multicast_if_v6()?, INTERFACE);
+        socket.set_multicast_hops_v6(0)?;
+        assert_eq!(socket.multicast_hops_v6()?, 0);
+        socket.set_multicast_loop_v6(false)?;
+        assert!(!socket.multicast_loop_v6()?);
+        socket.set_multicast_loop_v6(true)?;
+        assert!(socket.multicast_loop_v6()?);
+
+        socket.leave_multicast_v6(GROUP, INTERFACE)?;
+        // Can't leave a group we're not a member of.
+        assert!(socket.leave_multicast_v6(GROUP, INTERFACE).is_err());
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn broadcast() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let mut socket = UdpSocket::bind(&mut ctx, any_local_address())?;
+        assert!(!socket.broadcast()?);
+        socket.set_broadcast(true)?;
+        assert!(socket.broadcast()?);
+        socket.set_broadcast(false)?;
+        assert!(!socket.broadcast()?);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn bind_with_options() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let options = UdpSocketOptions::default()
+            .with_reuse_port(true)
+            .with_send_buffer_size(64 * 1024)
+            .with_recv_buffer_size(64 * 1024);
+        let mut socket1 = UdpSocket::bind_with(&mut ctx, any_local_address(), options.clone())?;
+        let address = socket1.local_addr()?;
+
+        // Without `SO_REUSEPORT` binding to the same address fails.
+        let err = UdpSocket::bind(&mut ctx, address).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        let mut socket2 = UdpSocket::bind_with(&mut ctx, address, options)?;
+        assert_eq!(socket2.local_addr()?, address);
+
+        // As the IPv6 socket only receives IPv6 datagrams we can bind an IPv4
+        // socket to the same port.
+        let options = UdpSocketOptions::default().with_only_v6(true);
+        let address = SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0);
+        let mut socket = UdpSocket::bind_with(&mut ctx, address, options)?;
+        let port = socket.local_addr()?.port();
+        let address = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port);
+        let mut socket = UdpSocket::bind(&mut ctx, address)?;
+        assert_eq!(socket.local_addr()?, address);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
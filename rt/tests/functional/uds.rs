@@ -0,0 +1,388 @@
+//! Tests for the Unix socket types.
+
+use std::fs::{self, File};
+use std::io::{self, IoSlice, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use heph::actor::{self, Actor, NewActor};
+use heph::messages::Terminate;
+use heph::ActorRef;
+use heph_rt::net::uds::{server, SocketAddr, UnixDatagram, UnixListener, UnixServer, UnixStream};
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::{join, try_spawn_local, PanicSupervisor};
+use heph_rt::{self as rt, ThreadLocal};
+
+use crate::util::temp_file;
+
+const DATA: &[u8] = b"Hello world";
+const DATAV: &[&[u8]] = &[b"Hello world!", b" ", b"From mars."];
+const DATAV_LEN: usize = DATAV[0].len() + DATAV[1].len() + DATAV[2].len();
+
+fn run_actor<NA>(new_actor: NA, arg: NA::Argument)
+where
+    NA: NewActor<Message = !, Error = !, RuntimeAccess = ThreadLocal> + Send + 'static,
+    NA::Actor: Actor<Error = !> + 'static,
+    NA::Argument: Send,
+{
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, new_actor, arg, ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn socket_addr() {
+    let path = temp_file("uds_socket_addr.sock");
+    let address = SocketAddr::from_pathname(&path).unwrap();
+    assert_eq!(address.as_pathname(), Some(&*path));
+    assert!(!address.is_unnamed());
+    assert_eq!(format!("{address:?}"), format!("{path:?} (pathname)"));
+}
+
+#[test]
+fn stream_pair() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let (mut left, mut right) = UnixStream::pair(&mut ctx).unwrap();
+        assert!(left.local_addr().unwrap().is_unnamed());
+        assert!(left.peer_addr().unwrap().is_unnamed());
+
+        left.send_all(DATA).await.unwrap();
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let n = right.peek(&mut buf).await.unwrap();
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+        buf.clear();
+        right.recv_n(&mut buf, DATA.len()).await.unwrap();
+        assert_eq!(buf, DATA);
+
+        let bufs = &mut [
+            IoSlice::new(DATAV[0]),
+            IoSlice::new(DATAV[1]),
+            IoSlice::new(DATAV[2]),
+        ];
+        right.send_vectored_all(bufs).await.unwrap();
+        let mut buf1 = Vec::with_capacity(DATAV[0].len());
+        let mut buf2 = Vec::with_capacity(DATAV_LEN - DATAV[0].len() + 1);
+        left.recv_n_vectored([&mut buf1, &mut buf2], DATAV_LEN)
+            .await
+            .unwrap();
+        assert_eq!(buf1, DATAV[0]);
+        assert_eq!(&buf2[..DATAV[1].len()], DATAV[1]);
+        assert_eq!(&buf2[DATAV[1].len()..], DATAV[2]);
+
+        drop(right);
+        buf.clear();
+        let n = left.recv(&mut buf).await.unwrap();
+        assert_eq!(n, 0);
+        assert!(left.take_error().unwrap().is_none());
+    }
+
+    run_actor(actor as fn(_) -> _, ());
+}
+
+#[test]
+fn stream_peer_cred() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let (mut left, _right) = UnixStream::pair(&mut ctx).unwrap();
+        let cred = left.peer_cred().unwrap();
+
+        // Compare against the owner of a file we create.
+        let path = temp_file("uds_stream_peer_cred");
+        let metadata = File::create(&path).unwrap().metadata().unwrap();
+        assert_eq!(cred.uid, metadata.uid());
+        assert_eq!(cred.gid, metadata.gid());
+        #[cfg(target_os = "linux")]
+        assert_eq!(cred.pid, Some(std::process::id() as i32));
+    }
+
+    run_actor(actor as fn(_) -> _, ());
+}
+
+#[test]
+fn stream_pass_fds() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let path = temp_file("uds_stream_pass_fds");
+        fs::write(&path, DATA).unwrap();
+        let file = File::open(&path).unwrap();
+
+        let (mut left, mut right) = UnixStream::pair(&mut ctx).unwrap();
+        let n = left.send_fds(b"f", &[file.as_raw_fd()]).await.unwrap();
+        assert_eq!(n, 1);
+        // We keep our own copy of the file descriptor.
+        drop(file);
+
+        let mut buf = Vec::with_capacity(8);
+        let mut fds = Vec::with_capacity(2);
+        let n = right.recv_fds(&mut buf, &mut fds).await.unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(buf, b"f");
+        assert_eq!(fds.len(), 1);
+
+        let mut file = File::from(fds.pop().unwrap());
+        let mut contents = Vec::new();
+        let _ = file.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, DATA);
+
+        // Without any file descriptors.
+        left.send_fds(DATA, &[]).await.unwrap();
+        buf.clear();
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let n = right.recv_fds(&mut buf, &mut fds).await.unwrap();
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+        assert!(fds.is_empty());
+    }
+
+    run_actor(actor as fn(_) -> _, ());
+}
+
+#[test]
+fn stream_recv_fds_truncated() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let path = temp_file("uds_stream_recv_fds_truncated");
+        fs::write(&path, DATA).unwrap();
+        let file = File::open(&path).unwrap();
+
+        let (mut left, mut right) = UnixStream::pair(&mut ctx).unwrap();
+        let fd = file.as_raw_fd();
+        left.send_fds(b"f", &[fd, fd]).await.unwrap();
+
+        let mut buf = Vec::with_capacity(8);
+        let mut fds = Vec::with_capacity(1);
+        let err = right.recv_fds(&mut buf, &mut fds).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(fds.is_empty());
+    }
+
+    run_actor(actor as fn(_) -> _, ());
+}
+
+#[test]
+fn listener_accept() {
+    async fn listener_actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) {
+        let mut listener = UnixListener::bind(&mut ctx, &path).unwrap();
+        assert_eq!(listener.local_addr().unwrap().as_pathname(), Some(&*path));
+
+        let (stream, address) = listener.accept().await.unwrap();
+        assert!(address.is_unnamed());
+        let mut stream = stream.bind_to(&mut ctx).unwrap();
+        assert_eq!(stream.local_addr().unwrap().as_pathname(), Some(&*path));
+        stream.send_all(DATA).await.unwrap();
+        assert!(listener.take_error().unwrap().is_none());
+    }
+
+    let path = temp_file("uds_listener_accept.sock");
+    let listener_ref = try_spawn_local(
+        PanicSupervisor,
+        listener_actor as fn(_, _) -> _,
+        path.clone(),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    let mut stream = loop {
+        match std::os::unix::net::UnixStream::connect(&path) {
+            Ok(stream) => break stream,
+            Err(_) => std::thread::sleep(Duration::from_millis(1)),
+        }
+    };
+    let mut buf = [0; DATA.len()];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+
+    join(&listener_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn stream_connect() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) {
+        let mut stream = UnixStream::connect(&mut ctx, &path).unwrap().await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap().as_pathname(), Some(&*path));
+        stream.send_all(DATA).await.unwrap();
+    }
+
+    let path = temp_file("uds_stream_connect.sock");
+    let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    run_actor(actor as fn(_, _) -> _, path);
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+}
+
+#[test]
+fn datagram_send_to() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) {
+        let mut socket = UnixDatagram::unbound(&mut ctx).unwrap();
+        let target = SocketAddr::from_pathname(&path).unwrap();
+        let n = socket.send_to(DATA, &target).await.unwrap();
+        assert_eq!(n, DATA.len());
+
+        socket.connect(&path).unwrap();
+        assert_eq!(socket.peer_addr().unwrap().as_pathname(), Some(&*path));
+        let bufs = &mut [
+            IoSlice::new(DATAV[0]),
+            IoSlice::new(DATAV[1]),
+            IoSlice::new(DATAV[2]),
+        ];
+        let n = socket.send_vectored(bufs).await.unwrap();
+        assert_eq!(n, DATAV_LEN);
+    }
+
+    let path = temp_file("uds_datagram_send_to.sock");
+    let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+    run_actor(actor as fn(_, _) -> _, path);
+
+    let mut buf = [0; DATAV_LEN + 1];
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], DATA);
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], DATAV.concat());
+}
+
+#[test]
+fn datagram_recv_from() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) {
+        let mut socket = UnixDatagram::bind(&mut ctx, &path).unwrap();
+        assert_eq!(socket.local_addr().unwrap().as_pathname(), Some(&*path));
+
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let (n, address) = socket.recv_from(&mut buf).await.unwrap();
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+        let peer = address.as_pathname().unwrap().to_owned();
+
+        // Reply to the sender.
+        let n = socket.send_to(DATA, &address).await.unwrap();
+        assert_eq!(n, DATA.len());
+
+        socket.connect(peer).unwrap();
+        let mut buf1 = Vec::with_capacity(DATAV[0].len());
+        let mut buf2 = Vec::with_capacity(DATAV_LEN);
+        let n = socket.recv_vectored([&mut buf1, &mut buf2]).await.unwrap();
+        assert_eq!(n, DATAV_LEN);
+        assert_eq!(buf1, DATAV[0]);
+        assert_eq!(&buf2[..DATAV[1].len()], DATAV[1]);
+        assert_eq!(&buf2[DATAV[1].len()..], DATAV[2]);
+        assert!(socket.take_error().unwrap().is_none());
+    }
+
+    let path = temp_file("uds_datagram_recv_from.sock");
+    let actor_ref = try_spawn_local(
+        PanicSupervisor,
+        actor as fn(_, _) -> _,
+        path.clone(),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    let peer_path = temp_file("uds_datagram_recv_from_peer.sock");
+    let socket = std::os::unix::net::UnixDatagram::bind(&peer_path).unwrap();
+    loop {
+        match socket.send_to(DATA, &path) {
+            Ok(_) => break,
+            Err(_) => std::thread::sleep(Duration::from_millis(1)),
+        }
+    }
+    let mut buf = [0; DATA.len() + 1];
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], DATA);
+    let _ = socket.send_to(&DATAV.concat(), &path).unwrap();
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn datagram_pass_fds() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let path = temp_file("uds_datagram_pass_fds");
+        let mut file = File::create(&path).unwrap();
+
+        let (mut left, mut right) = UnixDatagram::pair(&mut ctx).unwrap();
+        left.send(DATA).await.unwrap();
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let n = right.recv(&mut buf).await.unwrap();
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+
+        let n = left.send_fds(b"f", &[file.as_raw_fd()]).await.unwrap();
+        assert_eq!(n, 1);
+        let mut buf = Vec::with_capacity(8);
+        let mut fds = Vec::with_capacity(1);
+        let (n, address) = right.recv_fds(&mut buf, &mut fds).await.unwrap();
+        assert_eq!(n, 1);
+        assert!(address.is_unnamed());
+        assert_eq!(fds.len(), 1);
+
+        // Write using the received file descriptor, read using the original.
+        let mut received = File::from(fds.pop().unwrap());
+        received.write_all(DATA).unwrap();
+        file.flush().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), DATA);
+    }
+
+    run_actor(actor as fn(_) -> _, ());
+}
+
+async fn conn_actor<RT>(_: actor::Context<!, RT>, mut stream: UnixStream, address: SocketAddr)
+where
+    RT: rt::Access,
+{
+    assert!(address.is_unnamed());
+    let mut buf = Vec::with_capacity(DATA.len() + 1);
+    stream.recv_n(&mut buf, DATA.len()).await.unwrap();
+    assert_eq!(buf, DATA);
+}
+
+async fn client_actor<RT>(
+    mut ctx: actor::Context<!, RT>,
+    path: PathBuf,
+    server_ref: ActorRef<server::Message>,
+) where
+    RT: rt::Access,
+{
+    let mut stream = UnixStream::connect(&mut ctx, path).unwrap().await.unwrap();
+    stream.send_all(DATA).await.unwrap();
+    // Send a message to stop the server.
+    server_ref.send(Terminate).await.unwrap();
+}
+
+#[test]
+fn server() {
+    let path = temp_file("uds_server.sock");
+    let server = UnixServer::setup(
+        &path,
+        |err| panic!("unexpect error: {err}"),
+        conn_actor as fn(_, _, _) -> _,
+        ActorOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(server.local_addr().as_pathname(), Some(&*path));
+
+    // Binding to the same path again should fail.
+    let res = UnixServer::setup(
+        &path,
+        |err| panic!("unexpect error: {err}"),
+        conn_actor as fn(actor::Context<!, ThreadLocal>, _, _) -> _,
+        ActorOptions::default(),
+    );
+    match res {
+        Ok(_) => panic!("unexpected success binding to the same path"),
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::AddrInUse),
+    }
+
+    let server_ref = try_spawn_local(PanicSupervisor, server, (), ActorOptions::default()).unwrap();
+    let client_ref = try_spawn_local(
+        PanicSupervisor,
+        client_actor as fn(_, _, _) -> _,
+        (path, server_ref.clone()),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    join(&client_ref, Duration::from_secs(1)).unwrap();
+    join(&server_ref, Duration::from_secs(1)).unwrap();
+}
@@ -0,0 +1,151 @@
+//! Tests for the [`set_net_faults`] function.
+//!
+//! # Notes
+//!
+//! These tests need to be in their own binary since `set_net_faults` is set
+//! globally.
+
+#![feature(async_iterator, never_type)]
+
+use std::io::{self, Read, Write};
+use std::net::{self, Shutdown, SocketAddr};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use heph::actor;
+use heph_rt::net::{TcpStream, UdpSocket};
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::{join, set_net_faults, try_spawn_local, NetFaults, PanicSupervisor};
+use heph_rt::ThreadLocal;
+
+#[path = "util/mod.rs"] // rustfmt can't find the file.
+#[macro_use]
+mod util;
+
+use util::any_local_address;
+
+const DATA: &[u8] = b"Hello world! Hello mars! Hello jupiter! Hello saturn!";
+
+/// Tests can't run in parallel as the faults are set globally.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Lock [`LOCK`] and reset the faults once done.
+fn lock() -> FaultsGuard {
+    let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_net_faults(NetFaults::new());
+    FaultsGuard(guard)
+}
+
+struct FaultsGuard(#[allow(dead_code)] MutexGuard<'static, ()>);
+
+impl Drop for FaultsGuard {
+    fn drop(&mut self) {
+        set_net_faults(NetFaults::new());
+    }
+}
+
+#[test]
+fn tcp_stream_faults() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let mut stream = TcpStream::connect(&mut ctx, address)?.await?;
+
+        set_net_faults(NetFaults::new().would_block(100));
+        let err = stream.try_send(DATA).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let err = stream.try_recv(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        set_net_faults(NetFaults::new().connection_reset(100));
+        let err = stream.try_send(DATA).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        let err = stream.recv(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+
+        set_net_faults(NetFaults::new().partial_writes(100));
+        let n = stream.send(DATA).await?;
+        assert!(n < DATA.len());
+
+        // The futures should retry after `WouldBlock` errors and partial writes.
+        set_net_faults(NetFaults::new().partial_writes(50).would_block(50));
+        stream.send_all(&DATA[n..]).await?;
+        stream.shutdown(Shutdown::Write)?;
+        loop {
+            if stream.recv(&mut buf).await? == 0 {
+                break;
+            }
+            buf.reserve(DATA.len());
+        }
+        assert_eq!(buf, DATA);
+        Ok(())
+    }
+
+    let _guard = lock();
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+    stream.write_all(DATA).unwrap();
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn udp_socket_faults() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let local_address = SocketAddr::new(address.ip(), 0);
+        let socket = UdpSocket::bind(&mut ctx, local_address)?;
+        let mut socket = socket.connect(address)?;
+
+        set_net_faults(NetFaults::new().would_block(100));
+        let err = socket.try_send(DATA).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        // Datagrams are never partially send and the connection is never
+        // reset.
+        set_net_faults(
+            NetFaults::new()
+                .partial_writes(100)
+                .connection_reset(100)
+                .would_block(50),
+        );
+        let n = socket.send(DATA).await?;
+        assert_eq!(n, DATA.len());
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        let n = socket.recv(&mut buf).await?;
+        assert_eq!(n, DATA.len());
+        assert_eq!(buf, DATA);
+        Ok(())
+    }
+
+    let _guard = lock();
+    let socket = net::UdpSocket::bind(any_local_address()).unwrap();
+    let address = socket.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let mut buf = [0; DATA.len() + 1];
+    let (n, peer_address) = socket.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..n], DATA);
+    let _ = socket.send_to(&buf[..n], peer_address).unwrap();
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn net_faults_max_percent() {
+    assert_eq!(
+        NetFaults::new().would_block(200),
+        NetFaults::new().would_block(100)
+    );
+}
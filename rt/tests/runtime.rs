@@ -0,0 +1,486 @@
+//! Tests that require a complete runtime, e.g. for stopping the runtime using
+//! [`RuntimeRef::shutdown`] and [`RuntimeHandle`] or for the poll budget.
+//!
+//! # Notes
+//!
+//! These tests are in their own binary as only a limited number of runtimes
+//! can be created per process. It also holds tests that would interfere with
+//! the tests in the functional binary, e.g. by adding timers that are counted
+//! by [`test::pending_shared_timers`].
+//!
+//! [`RuntimeRef::shutdown`]: heph_rt::RuntimeRef::shutdown
+//! [`test::pending_shared_timers`]: heph_rt::test::pending_shared_timers
+
+#![feature(async_iterator, never_type)]
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::task::Poll;
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use heph::actor::{self, SyncContext};
+use heph::actor_ref::{DeadLetter, DeadLetterReason};
+use heph::supervisor::NoSupervisor;
+use heph_rt::net::{TcpServer, TcpStream};
+use heph_rt::spawn::{ActorOptions, FutureOptions, SpawnError, SyncActorOptions};
+use heph_rt::test::{self, init_actor, poll_actor, PanicSupervisor};
+use heph_rt::timer::Timer;
+use heph_rt::{self as rt, Runtime, RuntimeHandle, Signal, ThreadSafe};
+
+#[path = "util/mod.rs"] // rustfmt can't find the file.
+#[macro_use]
+mod util;
+
+use util::{any_local_address, assert_send, assert_sync};
+
+async fn terminate_actor<RT>(mut ctx: actor::Context<Signal, RT>, mark: &'static AtomicUsize) {
+    let signal = ctx.receive_next().await.unwrap();
+    assert_eq!(signal, Signal::Terminate);
+    let _ = mark.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn shutdown_terminates_actors() {
+    static TERMINATED: AtomicUsize = AtomicUsize::new(0);
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    let mut runtime = Runtime::setup().build().unwrap();
+    runtime
+        .run_on_workers::<_, !>(|mut runtime_ref| {
+            let actor_ref = runtime_ref.spawn_local(
+                NoSupervisor,
+                terminate_actor as fn(_, _) -> _,
+                &TERMINATED,
+                ActorOptions::default(),
+            );
+            runtime_ref.receive_signals(actor_ref);
+            runtime_ref.shutdown(Instant::now() + TIMEOUT);
+            Ok(())
+        })
+        .unwrap();
+    let actor_ref = runtime.spawn(
+        NoSupervisor,
+        terminate_actor as fn(_, _) -> _,
+        &TERMINATED,
+        ActorOptions::default(),
+    );
+    runtime.receive_signals(actor_ref);
+
+    let start = Instant::now();
+    runtime.start().unwrap();
+    // Actors stopped on their own, so we shouldn't have to wait for the
+    // deadline.
+    assert!(start.elapsed() < TIMEOUT);
+    assert_eq!(TERMINATED.load(Ordering::SeqCst), 2);
+}
+
+struct DropMark(&'static AtomicUsize);
+
+impl Drop for DropMark {
+    fn drop(&mut self) {
+        let _ = self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+async fn never_stop_actor<RT>(_: actor::Context<!, RT>, mark: &'static AtomicUsize) {
+    let _mark = DropMark(mark);
+    std::future::pending::<()>().await;
+}
+
+#[test]
+fn shutdown_deadline_stops_processes() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    let mut runtime = Runtime::setup().build().unwrap();
+    runtime
+        .run_on_workers::<_, !>(|mut runtime_ref| {
+            let _ = runtime_ref.spawn_local(
+                NoSupervisor,
+                never_stop_actor as fn(_, _) -> _,
+                &DROPPED,
+                ActorOptions::default(),
+            );
+            runtime_ref.spawn_local_future(
+                async {
+                    let _mark = DropMark(&DROPPED);
+                    std::future::pending::<()>().await;
+                },
+                FutureOptions::default(),
+            );
+            Ok(())
+        })
+        .unwrap();
+    let _ = runtime.spawn(
+        NoSupervisor,
+        never_stop_actor as fn(_, _) -> _,
+        &DROPPED,
+        ActorOptions::default(),
+    );
+    runtime.spawn_future(
+        async {
+            let _mark = DropMark(&DROPPED);
+            std::future::pending::<()>().await;
+        },
+        FutureOptions::default(),
+    );
+    runtime
+        .run_on_workers::<_, !>(|mut runtime_ref| {
+            runtime_ref.shutdown(Instant::now() + TIMEOUT);
+            Ok(())
+        })
+        .unwrap();
+
+    let start = Instant::now();
+    let err = runtime.start().unwrap_err();
+    assert!(start.elapsed() >= TIMEOUT - Duration::from_millis(10));
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 4);
+    let mut stopped = err.stopped_processes().to_vec();
+    stopped.sort_unstable();
+    assert_eq!(stopped.len(), 4, "{stopped:?}");
+    assert_eq!(
+        stopped
+            .iter()
+            .filter(|name| name.contains("never_stop_actor"))
+            .count(),
+        2,
+        "{stopped:?}"
+    );
+}
+
+async fn conn_actor<RT>(_: actor::Context<!, RT>, _: TcpStream, _: SocketAddr)
+where
+    RT: rt::Access,
+{
+}
+
+#[test]
+fn shutdown_stops_tcp_servers() {
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    let server = TcpServer::setup(
+        any_local_address(),
+        |err| panic!("unexpect error: {err}"),
+        conn_actor as fn(_, _, _) -> _,
+        ActorOptions::default(),
+    )
+    .unwrap();
+    let local_server = TcpServer::setup(
+        any_local_address(),
+        |err| panic!("unexpect error: {err}"),
+        conn_actor as fn(_, _, _) -> _,
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    let mut runtime = Runtime::setup().build().unwrap();
+    let _ = runtime
+        .try_spawn(PanicSupervisor, server, (), ActorOptions::default())
+        .unwrap();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let _ = runtime_ref
+                .try_spawn_local(PanicSupervisor, local_server, (), ActorOptions::default())
+                .unwrap();
+            runtime_ref.shutdown(Instant::now() + TIMEOUT);
+            Ok(())
+        })
+        .unwrap();
+
+    let start = Instant::now();
+    runtime.start().unwrap();
+    // The servers should stop before the deadline.
+    assert!(start.elapsed() < TIMEOUT);
+}
+
+async fn ok_actor<RT>(_: actor::Context<!, RT>, mark: &'static AtomicBool) {
+    mark.store(true, Ordering::SeqCst);
+}
+
+async fn ok_future(mark: &'static AtomicBool) {
+    mark.store(true, Ordering::SeqCst);
+}
+
+#[test]
+fn runtime_handle_is_send_sync() {
+    assert_send::<RuntimeHandle>();
+    assert_sync::<RuntimeHandle>();
+}
+
+#[test]
+fn runtime_handle_spawn_after_start() {
+    static ACTOR_RAN: AtomicBool = AtomicBool::new(false);
+    static FUTURE_RAN: AtomicBool = AtomicBool::new(false);
+
+    let runtime = Runtime::new().unwrap();
+    let mut handle = runtime.handle();
+    let thread = thread::spawn(move || {
+        // Give the runtime some time to start.
+        sleep(Duration::from_millis(50));
+        handle
+            .spawn(
+                NoSupervisor,
+                ok_actor as fn(_, _) -> _,
+                &ACTOR_RAN,
+                ActorOptions::default(),
+            )
+            .unwrap();
+        handle
+            .spawn_future(ok_future(&FUTURE_RAN), FutureOptions::default())
+            .unwrap();
+        // Allow the runtime to stop.
+        drop(handle);
+    });
+
+    // Without any actors the runtime would stop right away, but the handle
+    // keeps it running.
+    runtime.start().unwrap();
+    thread.join().unwrap();
+    assert!(ACTOR_RAN.load(Ordering::SeqCst));
+    assert!(FUTURE_RAN.load(Ordering::SeqCst));
+}
+
+#[test]
+fn runtime_handle_spawn_after_stop() {
+    static ACTOR_RAN: AtomicBool = AtomicBool::new(false);
+    static FUTURE_RAN: AtomicBool = AtomicBool::new(false);
+
+    let runtime = Runtime::new().unwrap();
+    let mut handle = runtime.handle();
+    let shutdown_handle = handle.clone();
+    let thread = thread::spawn(move || {
+        sleep(Duration::from_millis(50));
+        shutdown_handle.shutdown(Instant::now());
+    });
+
+    runtime.start().unwrap();
+    thread.join().unwrap();
+
+    let res = handle.spawn(
+        NoSupervisor,
+        ok_actor as fn(_, _) -> _,
+        &ACTOR_RAN,
+        ActorOptions::default(),
+    );
+    assert!(matches!(res, Err(SpawnError::Stopped)));
+    let res = handle.spawn_future(ok_future(&FUTURE_RAN), FutureOptions::default());
+    assert!(matches!(res, Err(SpawnError::Stopped)));
+    assert!(!ACTOR_RAN.load(Ordering::SeqCst));
+    assert!(!FUTURE_RAN.load(Ordering::SeqCst));
+}
+
+#[test]
+fn runtime_handle_send_signal() {
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+    async fn signal_actor(mut ctx: actor::Context<Signal, ThreadSafe>) {
+        let signal = ctx.receive_next().await.unwrap();
+        assert_eq!(signal, Signal::User1);
+        let _ = RECEIVED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let mut runtime = Runtime::new().unwrap();
+    let actor_ref = runtime.spawn(
+        NoSupervisor,
+        signal_actor as fn(_) -> _,
+        (),
+        ActorOptions::default(),
+    );
+    runtime.receive_signals(actor_ref);
+    let handle = runtime.handle();
+    let thread = thread::spawn(move || handle.send_signal(Signal::User1));
+
+    runtime.start().unwrap();
+    thread.join().unwrap();
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn runtime_handle_shutdown() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut runtime = Runtime::new().unwrap();
+    let _ = runtime.spawn(
+        NoSupervisor,
+        never_stop_actor as fn(_, _) -> _,
+        &DROPPED,
+        ActorOptions::default(),
+    );
+    let handle = runtime.handle();
+    let thread = thread::spawn(move || {
+        sleep(Duration::from_millis(50));
+        // NOTE: not dropping the handle, the shutdown should stop the runtime.
+        handle.shutdown(Instant::now());
+        handle
+    });
+
+    let err = runtime.start().unwrap_err();
+    let handle = thread.join().unwrap();
+    drop(handle);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+    assert_eq!(err.stopped_processes().len(), 1);
+}
+
+#[test]
+fn setup_blocking_threads() {
+    let setup = Runtime::setup();
+    assert_eq!(setup.get_blocking_threads(), 16);
+    let setup = setup.blocking_threads(4);
+    assert_eq!(setup.get_blocking_threads(), 4);
+}
+
+#[test]
+#[should_panic = "Can't create zero blocking threads, one is the minimum"]
+fn setup_zero_blocking_threads() {
+    let _ = Runtime::setup().blocking_threads(0);
+}
+
+#[test]
+fn spawn_blocking_bounded_pool() {
+    static DONE: AtomicBool = AtomicBool::new(false);
+
+    async fn blocking_actor(mut ctx: actor::Context<!, ThreadSafe>) {
+        let futures: Vec<_> = (0..4)
+            .map(|_| {
+                ctx.runtime().spawn_blocking(|| {
+                    sleep(Duration::from_millis(5));
+                    thread::current().id()
+                })
+            })
+            .collect();
+        let mut threads = Vec::with_capacity(futures.len());
+        for future in futures {
+            threads.push(future.await.unwrap());
+        }
+        // With a single thread in the pool all functions run on it.
+        assert!(threads.iter().all(|thread| *thread == threads[0]));
+        DONE.store(true, Ordering::SeqCst);
+    }
+
+    let mut runtime = Runtime::setup().blocking_threads(1).build().unwrap();
+    let _ = runtime.spawn(
+        PanicSupervisor,
+        blocking_actor as fn(_) -> _,
+        (),
+        ActorOptions::default(),
+    );
+    runtime.start().unwrap();
+    assert!(DONE.load(Ordering::SeqCst));
+}
+
+#[test]
+fn setup_poll_budget() {
+    let setup = Runtime::setup();
+    assert_eq!(setup.get_poll_budget(), None);
+    let setup = setup.poll_budget(Some(Duration::from_millis(10)));
+    assert_eq!(setup.get_poll_budget(), Some(Duration::from_millis(10)));
+    let setup = setup.poll_budget(None);
+    assert_eq!(setup.get_poll_budget(), None);
+}
+
+#[test]
+fn slow_polls() {
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+
+    async fn blocking_actor<RT>(_: actor::Context<!, RT>) {
+        // Block the worker thread for longer than the poll budget, long enough
+        // for the coordinator to detect it.
+        sleep(Duration::from_millis(50));
+        let _ = RAN.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let mut runtime = Runtime::setup()
+        .poll_budget(Some(Duration::from_millis(10)))
+        .count_slow_polls()
+        .build()
+        .unwrap();
+    let _ = runtime.spawn(
+        PanicSupervisor,
+        blocking_actor as fn(_) -> _,
+        (),
+        ActorOptions::default(),
+    );
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let _ = runtime_ref.spawn_local(
+                PanicSupervisor,
+                blocking_actor as fn(_) -> _,
+                (),
+                ActorOptions::default(),
+            );
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(RAN.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn spawn_dead_letters_actor() {
+    fn dead_letters(mut ctx: SyncContext<DeadLetter, rt::Sync>, sender: mpsc::Sender<DeadLetter>) {
+        while let Ok(letter) = ctx.receive_next() {
+            if sender.send(letter).is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn stop_actor(_: actor::Context<usize, ThreadSafe>) {}
+
+    let (sender, letters) = mpsc::channel::<DeadLetter>();
+    let mut runtime = Runtime::new().unwrap();
+    let _ = runtime
+        .spawn_dead_letters_actor(
+            NoSupervisor,
+            dead_letters as fn(_, _),
+            sender,
+            SyncActorOptions::default(),
+        )
+        .unwrap();
+    let actor_ref = runtime.spawn(
+        NoSupervisor,
+        stop_actor as fn(_) -> _,
+        (),
+        ActorOptions::default(),
+    );
+    // The runtime shouldn't wait for the dead-letter actor to stop.
+    runtime.start().unwrap();
+
+    // The actor stopped, so the message is send to the dead-letter actor.
+    assert!(actor_ref.try_send(123usize).is_err());
+    let letter = letters.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(letter.reason, DeadLetterReason::Disconnected);
+    assert_eq!(*letter.message.unwrap().downcast::<usize>().unwrap(), 123);
+}
+
+#[test]
+fn advance_time_thread_safe_timer() {
+    const TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+    async fn timer_actor(mut ctx: actor::Context<!, ThreadSafe>) {
+        let _ = Timer::after(&mut ctx, TIMEOUT).await;
+    }
+
+    let start = Instant::now();
+    let actor = timer_actor as fn(_) -> _;
+    let (actor, _) = init_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+    assert_eq!(test::pending_shared_timers(), 1);
+    // Not a timer of the runtime of this thread.
+    assert_eq!(test::pending_timers(), 0);
+
+    // Advancing the time on another thread doesn't affect this thread.
+    thread::spawn(|| test::advance_time(TIMEOUT))
+        .join()
+        .unwrap();
+    assert!(poll_actor(Pin::as_mut(&mut actor)).is_pending());
+
+    test::advance_time(TIMEOUT);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(test::pending_shared_timers(), 0);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
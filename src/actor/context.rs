@@ -1,13 +1,15 @@
 //! Module containing the `Context` and related types.
 
+use std::collections::VecDeque;
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{self, Poll};
 
-use heph_inbox::{self as inbox, Receiver, RecvValue};
+use heph_inbox::{self as inbox, Receiver};
 
-use crate::actor_ref::ActorRef;
+use crate::actor_ref::{ActorRef, InboxState};
 
 /// The context in which an actor is executed.
 ///
@@ -29,6 +31,12 @@ pub struct Context<M, RT> {
     /// This field is public because it is used by `TcpServer`, as we don't need
     /// entire context there.
     pub(crate) inbox: Receiver<M>,
+    /// State of the inbox, shared with the actor references.
+    inbox_state: Arc<InboxState>,
+    /// Messages removed from the inbox, but not yet received by the actor, e.g.
+    /// skipped by [`Context::receive_matching`]. These are received before any
+    /// messages still in the inbox.
+    pending: Pending<M>,
     /// Runtime access.
     rt: RT,
 }
@@ -36,8 +44,13 @@ pub struct Context<M, RT> {
 impl<M, RT> Context<M, RT> {
     /// Create a new `actor::Context`.
     #[doc(hidden)] // Not part of the stable API.
-    pub const fn new(inbox: Receiver<M>, rt: RT) -> Context<M, RT> {
-        Context { inbox, rt }
+    pub const fn new(inbox: Receiver<M>, inbox_state: Arc<InboxState>, rt: RT) -> Context<M, RT> {
+        Context {
+            inbox,
+            inbox_state,
+            pending: Pending::new(),
+            rt,
+        }
     }
 
     /// Attempt to receive the next message.
@@ -68,7 +81,10 @@ impl<M, RT> Context<M, RT> {
     /// # drop(greeter_actor);
     /// ```
     pub fn try_receive_next(&mut self) -> Result<M, RecvError> {
-        self.inbox.try_recv().map_err(RecvError::from)
+        match self.pending.pop_front() {
+            Some(msg) => Ok(msg),
+            None => try_recv(&mut self.inbox, &self.inbox_state),
+        }
     }
 
     /// Receive the next message.
@@ -123,14 +139,184 @@ impl<M, RT> Context<M, RT> {
     /// # drop(print_actor);
     /// ```
     pub fn receive_next<'ctx>(&'ctx mut self) -> ReceiveMessage<'ctx, M> {
-        ReceiveMessage {
-            recv: self.inbox.recv(),
-        }
+        ReceiveMessage::new(&mut self.inbox, &self.inbox_state, &mut self.pending)
+    }
+
+    /// Attempt to receive the first message for which `predicate` returns
+    /// true.
+    ///
+    /// Messages for which `predicate` returns false are left queued, in the
+    /// order they were send, and will be returned by later calls to receive
+    /// messages. If the actor wants to wait until a matching message is
+    /// received [`receive_matching`] can be used.
+    ///
+    /// [`receive_matching`]: Context::receive_matching
+    ///
+    /// # Notes
+    ///
+    /// Skipped messages are moved out of the actor's inbox into the
+    /// `actor::Context`, which means they no longer count towards its
+    /// capacity. At most [`ActorRef::capacity`] messages are kept this way,
+    /// once that limit is reached the remaining messages are left in the inbox
+    /// and only a matching message at the front of the inbox can be received.
+    /// In that case the actor has to receive the skipped messages, e.g. using
+    /// [`Context::try_receive_next`], before it can receive further matching
+    /// messages.
+    ///
+    /// The skipped messages are part of the actor's state, i.e. when the actor
+    /// is restarted by its supervisor they're lost, the same as the stashed
+    /// messages (see [`Context::stash`]).
+    pub fn try_receive_matching<F>(&mut self, mut predicate: F) -> Result<M, RecvError>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        try_receive_matching(
+            &mut self.inbox,
+            &self.inbox_state,
+            &mut self.pending,
+            &mut predicate,
+            &mut 0,
+        )
+    }
+
+    /// Receive the first message for which `predicate` returns true.
+    ///
+    /// This returns a [`Future`] that will complete once a matching message is
+    /// ready. Messages for which `predicate` returns false are left queued, in
+    /// the order they were send, and will be returned by later calls to
+    /// receive messages.
+    ///
+    /// See [`Context::try_receive_matching`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// An actor that waits for a specific response, while other messages are
+    /// handled afterwards.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// enum Message {
+    ///     Request(String),
+    ///     Response(usize),
+    /// }
+    ///
+    /// async fn protocol_actor(mut ctx: actor::Context<Message, ThreadLocal>) {
+    ///     // Wait for the response, leaving all requests in the inbox.
+    ///     let msg = ctx.receive_matching(|msg| matches!(msg, Message::Response(_))).await;
+    ///     if let Ok(Message::Response(value)) = msg {
+    ///         println!("Got response: {value}");
+    ///     }
+    ///
+    ///     // Now handle the requests, in the order they were send.
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         if let Message::Request(request) = msg {
+    ///             println!("Got request: {request}");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # _ = (Message::Request(String::new()), Message::Response(0));
+    /// # _ = protocol_actor; // Silence dead code warnings.
+    /// ```
+    pub fn receive_matching<'ctx, F>(&'ctx mut self, predicate: F) -> ReceiveMatching<'ctx, M, F>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        ReceiveMatching::new(
+            &mut self.inbox,
+            &self.inbox_state,
+            &mut self.pending,
+            predicate,
+        )
+    }
+
+    /// Stash `msg` to receive it again later, see [`Context::unstash_all`].
+    ///
+    /// This is useful for actors that can't handle certain messages in their
+    /// current state, e.g. while they're still connecting, but want to handle
+    /// them once their state changes.
+    ///
+    /// The stash has a limited capacity, see [`Context::set_stash_capacity`].
+    /// If the stash is full this returns an error containing `msg`.
+    ///
+    /// Like all other state of the actor the stashed messages are lost if the
+    /// actor is restarted by its supervisor.
+    ///
+    /// # Examples
+    ///
+    /// An actor that defers messages until it's connected.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// enum Message {
+    ///     Connected,
+    ///     Request(String),
+    /// }
+    ///
+    /// async fn actor(mut ctx: actor::Context<Message, ThreadLocal>) {
+    ///     // Not connected yet, stash all requests.
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         match msg {
+    ///             Message::Connected => break,
+    ///             msg => {
+    ///                 if let Err(err) = ctx.stash(msg) {
+    ///                     eprintln!("dropping request: {err}");
+    ///                 }
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     // Connected, now we can handle the stashed requests (in the order
+    ///     // they were received) before any new requests.
+    ///     ctx.unstash_all();
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         if let Message::Request(request) = msg {
+    ///             println!("Handling request: {request}");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # _ = (Message::Connected, Message::Request(String::new()));
+    /// # _ = actor; // Silence dead code warnings.
+    /// ```
+    pub fn stash(&mut self, msg: M) -> Result<(), StashFull<M>> {
+        self.pending.stash(msg).map_err(StashFull)
+    }
+
+    /// Move all stashed messages to the front of the inbox, in the order they
+    /// were stashed, see [`Context::stash`].
+    ///
+    /// This means the stashed messages are received before any other messages.
+    pub fn unstash_all(&mut self) {
+        self.pending.unstash_all()
+    }
+
+    /// Returns the number of stashed messages.
+    pub fn stash_len(&self) -> usize {
+        self.pending.stash_len()
+    }
+
+    /// Returns the maximum number of messages that can be stashed.
+    pub fn stash_capacity(&self) -> usize {
+        self.pending.stash_capacity()
+    }
+
+    /// Set the maximum number of messages that can be stashed, defaults to 64.
+    ///
+    /// If the stash already contains more messages than `capacity` those
+    /// messages are kept, but no new messages can be stashed until the stash
+    /// is unstashed.
+    pub fn set_stash_capacity(&mut self, capacity: usize) {
+        self.pending.set_stash_capacity(capacity)
     }
 
     /// Returns a reference to this actor.
     pub fn actor_ref(&self) -> ActorRef<M> {
-        ActorRef::local(self.inbox.new_sender())
+        ActorRef::local(self.inbox.new_sender(), self.inbox_state.clone())
     }
 
     /// Get mutable access to the runtime this actor is running in.
@@ -176,16 +362,334 @@ impl RecvError {
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct ReceiveMessage<'ctx, M> {
-    recv: RecvValue<'ctx, M>,
+    inbox: &'ctx mut Receiver<M>,
+    inbox_state: &'ctx InboxState,
+    pending: &'ctx mut Pending<M>,
+}
+
+impl<'ctx, M> ReceiveMessage<'ctx, M> {
+    pub(crate) fn new(
+        inbox: &'ctx mut Receiver<M>,
+        inbox_state: &'ctx InboxState,
+        pending: &'ctx mut Pending<M>,
+    ) -> ReceiveMessage<'ctx, M> {
+        ReceiveMessage {
+            inbox,
+            inbox_state,
+            pending,
+        }
+    }
+
+    fn try_receive(&mut self) -> Result<M, RecvError> {
+        match self.pending.pop_front() {
+            Some(msg) => Ok(msg),
+            None => try_recv(self.inbox, self.inbox_state),
+        }
+    }
 }
 
 impl<'ctx, M> Future for ReceiveMessage<'ctx, M> {
     type Output = Result<M, NoMessages>;
 
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.recv)
-            .poll(ctx)
-            .map(|r| r.ok_or(NoMessages))
+        match self.try_receive() {
+            Ok(msg) => Poll::Ready(Ok(msg)),
+            Err(RecvError::Empty) => {
+                // The inbox is empty, we'll set the waker.
+                if !self.inbox.register_waker(ctx.waker()) {
+                    // Waker already set.
+                    return Poll::Pending;
+                }
+
+                // But it could be the case that a message was send in the time
+                // between we last checked and we actually marked ourselves as
+                // needing a wake up, so we need to check again.
+                match self.try_receive() {
+                    Ok(msg) => Poll::Ready(Ok(msg)),
+                    Err(RecvError::Empty) => Poll::Pending,
+                    Err(RecvError::Disconnected) => Poll::Ready(Err(NoMessages)),
+                }
+            }
+            Err(RecvError::Disconnected) => Poll::Ready(Err(NoMessages)),
+        }
+    }
+}
+
+/// Future to receive a single message matching a predicate.
+///
+/// The implementation behind [`actor::Context::receive_matching`].
+///
+/// [`actor::Context::receive_matching`]: crate::actor::Context::receive_matching
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveMatching<'ctx, M, F> {
+    inbox: &'ctx mut Receiver<M>,
+    inbox_state: &'ctx InboxState,
+    pending: &'ctx mut Pending<M>,
+    predicate: F,
+    /// Number of messages in `pending` already checked.
+    checked: usize,
+}
+
+impl<'ctx, M, F> ReceiveMatching<'ctx, M, F> {
+    pub(crate) fn new(
+        inbox: &'ctx mut Receiver<M>,
+        inbox_state: &'ctx InboxState,
+        pending: &'ctx mut Pending<M>,
+        predicate: F,
+    ) -> ReceiveMatching<'ctx, M, F> {
+        ReceiveMatching {
+            inbox,
+            inbox_state,
+            pending,
+            predicate,
+            checked: 0,
+        }
+    }
+
+    fn try_receive(&mut self) -> Result<M, RecvError>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        try_receive_matching(
+            self.inbox,
+            self.inbox_state,
+            self.pending,
+            &mut self.predicate,
+            &mut self.checked,
+        )
+    }
+}
+
+impl<'ctx, M, F> Future for ReceiveMatching<'ctx, M, F>
+where
+    F: FnMut(&M) -> bool,
+{
+    type Output = Result<M, NoMessages>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match self.try_receive() {
+            Ok(msg) => Poll::Ready(Ok(msg)),
+            Err(RecvError::Empty) => {
+                // The inbox is empty, we'll set the waker.
+                if !self.inbox.register_waker(ctx.waker()) {
+                    // Waker already set.
+                    return Poll::Pending;
+                }
+
+                // But it could be the case that a message was send in the time
+                // between we last checked and we actually marked ourselves as
+                // needing a wake up, so we need to check again.
+                match self.try_receive() {
+                    Ok(msg) => Poll::Ready(Ok(msg)),
+                    Err(RecvError::Empty) => Poll::Pending,
+                    Err(RecvError::Disconnected) => Poll::Ready(Err(NoMessages)),
+                }
+            }
+            Err(RecvError::Disconnected) => Poll::Ready(Err(NoMessages)),
+        }
+    }
+}
+
+// We never create a `Pin<&mut F>`, so `F` doesn't have to be `Unpin`.
+impl<'ctx, M, F> Unpin for ReceiveMatching<'ctx, M, F> {}
+
+impl<'ctx, M: fmt::Debug, F> fmt::Debug for ReceiveMatching<'ctx, M, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiveMatching")
+            .field("inbox", &self.inbox)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+/// Receive a message from `inbox`, marking it as received in `inbox_state`.
+pub(crate) fn try_recv<M>(
+    inbox: &mut Receiver<M>,
+    inbox_state: &InboxState,
+) -> Result<M, RecvError> {
+    loop {
+        match inbox.try_recv() {
+            Ok(msg) => {
+                if let Some(msg) = inbox_state.received(inbox, msg) {
+                    return Ok(msg);
+                }
+                // Message was dropped, try the next one.
+            }
+            Err(inbox::RecvError::Empty) => {
+                inbox_state.mark_empty();
+                return Err(RecvError::Empty);
+            }
+            Err(err) => return Err(RecvError::from(err)),
+        }
+    }
+}
+
+/// Receive the first message for which `predicate` returns true, first checking
+/// the messages in `pending` (skipping the first `checked` messages) and then
+/// the messages in `inbox`. Non-matching messages from `inbox` are added to
+/// `pending`, updating `checked`. Once `pending` holds the inbox's capacity of
+/// messages the remaining messages are left in `inbox`, only receiving a
+/// matching message at the front of it.
+pub(crate) fn try_receive_matching<M, F>(
+    inbox: &mut Receiver<M>,
+    inbox_state: &InboxState,
+    pending: &mut Pending<M>,
+    predicate: &mut F,
+    checked: &mut usize,
+) -> Result<M, RecvError>
+where
+    F: FnMut(&M) -> bool,
+{
+    if let Some(msg) = pending.remove_matching(*checked, &mut *predicate) {
+        return Ok(msg);
+    }
+    *checked = pending.len();
+
+    loop {
+        if pending.len() >= inbox.capacity() {
+            // Don't let the skipped messages bypass the inbox's capacity, leave
+            // them in the inbox (in order) instead.
+            match inbox.try_peek() {
+                Ok(msg) if !predicate(msg) => return Err(RecvError::Empty),
+                Ok(_) => match inbox.try_recv() {
+                    Ok(msg) => match inbox_state.received(inbox, msg) {
+                        Some(msg) => return Ok(msg),
+                        // Message was dropped, try the next one.
+                        None => continue,
+                    },
+                    Err(err) => return Err(RecvError::from(err)),
+                },
+                // Let `try_recv` handle the error.
+                Err(_) => {}
+            }
+        }
+
+        match try_recv(inbox, inbox_state) {
+            Ok(msg) if predicate(&msg) => return Ok(msg),
+            Ok(msg) => {
+                pending.push_back(msg);
+                *checked += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Default capacity of the stash, see [`Context::set_stash_capacity`].
+const DEFAULT_STASH_CAPACITY: usize = 64;
+
+/// Messages removed from the inbox, but not yet received by the actor.
+///
+/// This is allocated lazily to not increase the size of actors that don't use
+/// it.
+#[derive(Debug)]
+pub(crate) struct Pending<M>(Option<Box<PendingInner<M>>>);
+
+#[derive(Debug)]
+struct PendingInner<M> {
+    /// Messages to receive before any messages in the inbox.
+    messages: VecDeque<M>,
+    /// Stashed messages, see [`Context::stash`].
+    stash: Vec<M>,
+    /// Maximum number of messages in `stash`.
+    stash_capacity: usize,
+}
+
+impl<M> Pending<M> {
+    pub(crate) const fn new() -> Pending<M> {
+        Pending(None)
+    }
+
+    /// Returns the number of pending messages.
+    fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |pending| pending.messages.len())
+    }
+
+    /// Remove the first message.
+    pub(crate) fn pop_front(&mut self) -> Option<M> {
+        self.0.as_mut()?.messages.pop_front()
+    }
+
+    /// Add `msg` to the back of the queue.
+    fn push_back(&mut self, msg: M) {
+        self.inner().messages.push_back(msg);
+    }
+
+    /// Remove the first message, skipping the first `skip` messages, for
+    /// which `predicate` returns true.
+    fn remove_matching<F>(&mut self, skip: usize, predicate: F) -> Option<M>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        let messages = &mut self.0.as_mut()?.messages;
+        let idx = messages.iter().skip(skip).position(predicate)?;
+        messages.remove(skip + idx)
+    }
+
+    /// Add `msg` to the stash, returning it if the stash is full.
+    fn stash(&mut self, msg: M) -> Result<(), M> {
+        let inner = self.inner();
+        if inner.stash.len() >= inner.stash_capacity {
+            return Err(msg);
+        }
+        inner.stash.push(msg);
+        Ok(())
+    }
+
+    /// Move all stashed messages to the front of the queue, in the order they
+    /// were stashed.
+    fn unstash_all(&mut self) {
+        if let Some(inner) = self.0.as_mut() {
+            for msg in inner.stash.drain(..).rev() {
+                inner.messages.push_front(msg);
+            }
+        }
+    }
+
+    /// Returns the number of stashed messages.
+    fn stash_len(&self) -> usize {
+        self.0.as_ref().map_or(0, |pending| pending.stash.len())
+    }
+
+    /// Returns the stash capacity.
+    fn stash_capacity(&self) -> usize {
+        self.0
+            .as_ref()
+            .map_or(DEFAULT_STASH_CAPACITY, |pending| pending.stash_capacity)
+    }
+
+    /// Set the stash capacity.
+    fn set_stash_capacity(&mut self, capacity: usize) {
+        self.inner().stash_capacity = capacity;
+    }
+
+    fn inner(&mut self) -> &mut PendingInner<M> {
+        self.0.get_or_insert_with(|| {
+            Box::new(PendingInner {
+                messages: VecDeque::new(),
+                stash: Vec::new(),
+                stash_capacity: DEFAULT_STASH_CAPACITY,
+            })
+        })
+    }
+}
+
+/// Error returned by [`actor::Context::stash`] when the stash is full,
+/// contains the message that couldn't be stashed.
+///
+/// [`actor::Context::stash`]: crate::actor::Context::stash
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct StashFull<M>(pub M);
+
+impl<M> fmt::Debug for StashFull<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StashFull")
+    }
+}
+
+impl<M> fmt::Display for StashFull<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("stash is full")
     }
 }
 
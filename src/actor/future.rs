@@ -5,14 +5,18 @@ use std::fmt;
 use std::future::Future;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{self, Poll, Waker};
+use std::time::{Duration, Instant};
 
 use heph_inbox::{Manager, ReceiverConnected};
 use log::error;
 
 use crate::actor::{self, Actor, NewActor};
-use crate::actor_ref::ActorRef;
+use crate::actor_ref::monitor::ActorMonitors;
+use crate::actor_ref::{ActorRef, DownReason, InboxState, OverflowPolicy};
 use crate::supervisor::{Supervisor, SupervisorStrategy};
+use crate::timer;
 
 /// A [`Future`] that represent an [`Actor`].
 pub struct ActorFuture<S, NA: NewActor, RT> {
@@ -24,10 +28,20 @@ pub struct ActorFuture<S, NA: NewActor, RT> {
     /// The inbox of the actor, used in creating a new [`actor::Context`]
     /// if the actor is restarted.
     inbox: Manager<NA::Message>,
+    /// State of the inbox, shared with the actor references.
+    inbox_state: Arc<InboxState>,
     /// The running actor.
     actor: NA::Actor,
+    /// If the actor is restarted after a delay, see
+    /// [`SupervisorStrategy::RestartAfter`], this is the time at which to
+    /// create the new actor and the argument to create it with.
+    restart: Option<(Instant, NA::Argument)>,
     /// Runtime access.
     rt: RT,
+    /// Monitors of the actor.
+    ///
+    /// NOTE: must be the last field, see [`ActorMonitors`].
+    monitors: ActorMonitors,
 }
 
 impl<S, NA, RT> ActorFuture<S, NA, RT>
@@ -53,18 +67,23 @@ where
         rt: RT,
     ) -> Result<(ActorFuture<S, NA, RT>, ActorRef<NA::Message>), NA::Error> {
         let (inbox, sender, receiver) = heph_inbox::Manager::new_small_channel();
-        let actor_ref = ActorRef::local(sender);
-        let ctx = actor::Context::new(receiver, rt.clone());
+        let inbox_state = InboxState::new(NA::name(), None, OverflowPolicy::Block);
+        let actor_ref = ActorRef::local(sender, inbox_state.clone());
+        let ctx = actor::Context::new(receiver, inbox_state.clone(), rt.clone());
         let actor = match new_actor.new(ctx, argument) {
             Ok(actor) => actor,
             Err(err) => return Err(err),
         };
+        let monitors = ActorMonitors::new(inbox_state.clone());
         let future = ActorFuture {
             supervisor,
             new_actor,
             inbox,
+            inbox_state,
             actor,
+            restart: None,
             rt,
+            monitors,
         };
         Ok((future, actor_ref))
     }
@@ -85,7 +104,11 @@ where
                     Err(err) => self.handle_restart_error(waker, err),
                 }
             }
-            SupervisorStrategy::Stop => Poll::Ready(()),
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(waker, arg, delay);
+                Poll::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Error),
         }
     }
 
@@ -96,6 +119,7 @@ where
         waker: &Waker,
         panic: Box<dyn Any + Send + 'static>,
     ) -> Poll<()> {
+        let msg = panic_message(&*panic).to_owned();
         match self.supervisor.decide_on_panic(panic) {
             SupervisorStrategy::Restart(arg) => {
                 match self.create_new_actor(arg) {
@@ -108,7 +132,11 @@ where
                     Err(err) => self.handle_restart_error(waker, err),
                 }
             }
-            SupervisorStrategy::Stop => Poll::Ready(()),
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(waker, arg, delay);
+                Poll::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Panic(msg)),
         }
     }
 
@@ -126,22 +154,52 @@ where
                     Err(err) => {
                         // Let the supervisor know.
                         self.supervisor.second_restart_error(err);
-                        Poll::Ready(())
+                        self.stop(DownReason::Error)
                     }
                 }
             }
-            SupervisorStrategy::Stop => Poll::Ready(()),
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                self.restart_after(waker, arg, delay);
+                Poll::Pending
+            }
+            SupervisorStrategy::Stop => self.stop(DownReason::Error),
         }
     }
 
+    /// Marks the actor as stopped because of `reason`.
+    fn stop(&mut self, reason: DownReason) -> Poll<()> {
+        self.monitors.stopped(reason);
+        Poll::Ready(())
+    }
+
+    /// Restarts the actor, like [`ActorFuture::create_new_actor`], but only
+    /// after `delay` has passed.
+    ///
+    /// The new actor is created once the delay has passed, any errors creating
+    /// it are handled at that point. As the `ActorFuture` doesn't have access
+    /// to a runtime it uses a timer shared by all `ActorFuture`s to wake
+    /// itself.
+    fn restart_after(&mut self, waker: &Waker, arg: NA::Argument, delay: Duration) {
+        let timer_waker = waker.clone();
+        let mut restart_at = Instant::now() + delay;
+        if let Err(err) = timer::call_after(delay, move || timer_waker.wake()) {
+            let name = NA::name();
+            error!("failed to delay restart of actor '{name}', restarting it now: {err}");
+            restart_at = Instant::now();
+            waker.wake_by_ref();
+        }
+        self.restart = Some((restart_at, arg));
+    }
+
     /// Creates a new actor and, if successful, replaces the old actor with it.
     fn create_new_actor(&mut self, arg: NA::Argument) -> Result<(), NA::Error> {
         let receiver = self.inbox.new_receiver().unwrap_or_else(inbox_failure);
-        let ctx = actor::Context::new(receiver, self.rt.clone());
+        let ctx = actor::Context::new(receiver, self.inbox_state.clone(), self.rt.clone());
         self.new_actor.new(ctx, arg).map(|actor| {
             // We pin the actor here to ensure its dropped in place when
             // replacing it with out new actor.
-            unsafe { Pin::new_unchecked(&mut self.actor) }.set(actor)
+            unsafe { Pin::new_unchecked(&mut self.actor) }.set(actor);
+            self.monitors.restarted();
         })
     }
 }
@@ -157,12 +215,24 @@ where
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         // This is safe because we're not moving the actor.
         let this = unsafe { Pin::get_unchecked_mut(self) };
+
+        // Check if the actor should be restarted after a delay.
+        if let Some((restart_at, _)) = this.restart {
+            if restart_at > Instant::now() {
+                return Poll::Pending;
+            }
+            let (_, arg) = this.restart.take().unwrap();
+            if let Err(err) = this.create_new_actor(arg) {
+                return this.handle_restart_error(ctx.waker(), err);
+            }
+        }
+
         // The actor need to be called with `Pin`. So we're undoing the previous
         // operation, still ensuring that the actor is not moved.
         let mut actor = unsafe { Pin::new_unchecked(&mut this.actor) };
 
         match catch_unwind(AssertUnwindSafe(|| actor.as_mut().try_poll(ctx))) {
-            Ok(Poll::Ready(Ok(()))) => Poll::Ready(()),
+            Ok(Poll::Ready(Ok(()))) => this.stop(DownReason::Normal),
             Ok(Poll::Ready(Err(err))) => this.handle_actor_error(ctx.waker(), err),
             Ok(Poll::Pending) => Poll::Pending,
             Err(panic) => {
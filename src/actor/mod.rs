@@ -138,8 +138,10 @@ mod sync;
 #[cfg(test)]
 mod tests;
 
+#[cfg(any(test, feature = "test"))]
+pub(crate) use context::Pending;
 #[doc(inline)]
-pub use context::{Context, NoMessages, ReceiveMessage, RecvError};
+pub use context::{Context, NoMessages, ReceiveMatching, ReceiveMessage, RecvError, StashFull};
 #[doc(inline)]
 pub use future::ActorFuture;
 #[doc(hidden)] // Not part of the stable API.
@@ -12,8 +12,12 @@ use heph_inbox::Receiver;
 use heph_inbox::{self as inbox, ReceiverConnected};
 use log::trace;
 
-use crate::actor::{NoMessages, RecvError};
-use crate::actor_ref::ActorRef;
+use crate::actor::context::{
+    try_receive_matching, try_recv, Pending, ReceiveMatching, ReceiveMessage,
+};
+use crate::actor::{name, NoMessages, RecvError};
+use crate::actor_ref::monitor::ActorMonitors;
+use crate::actor_ref::{ActorRef, DownReason, InboxState, OverflowPolicy};
 use crate::supervisor::{SupervisorStrategy, SyncSupervisor};
 
 /// Synchronous actor.
@@ -166,6 +170,11 @@ impl_sync_actor!(
 #[derive(Debug)]
 pub struct SyncContext<M, RT> {
     inbox: Receiver<M>,
+    /// State of the inbox, shared with the actor references.
+    inbox_state: Arc<InboxState>,
+    /// Messages removed from the inbox, but not yet received by the actor, see
+    /// [`SyncContext::receive_matching`].
+    pending: Pending<M>,
     future_waker: Option<Arc<SyncWaker>>,
     /// Runtime access.
     rt: RT,
@@ -174,9 +183,15 @@ pub struct SyncContext<M, RT> {
 impl<M, RT> SyncContext<M, RT> {
     /// Create a new `SyncContext`.
     #[doc(hidden)] // Not part of the stable API.
-    pub const fn new(inbox: Receiver<M>, rt: RT) -> SyncContext<M, RT> {
+    pub const fn new(
+        inbox: Receiver<M>,
+        inbox_state: Arc<InboxState>,
+        rt: RT,
+    ) -> SyncContext<M, RT> {
         SyncContext {
             inbox,
+            inbox_state,
+            pending: Pending::new(),
             future_waker: None,
             rt,
         }
@@ -210,7 +225,10 @@ impl<M, RT> SyncContext<M, RT> {
     /// # assert_sync_actor(greeter_actor as fn(_) -> _);
     /// ```
     pub fn try_receive_next(&mut self) -> Result<M, RecvError> {
-        self.inbox.try_recv().map_err(RecvError::from)
+        match self.pending.pop_front() {
+            Some(msg) => Ok(msg),
+            None => try_recv(&mut self.inbox, &self.inbox_state),
+        }
     }
 
     /// Receive the next message.
@@ -239,7 +257,74 @@ impl<M, RT> SyncContext<M, RT> {
     /// ```
     pub fn receive_next(&mut self) -> Result<M, NoMessages> {
         let waker = self.future_waker();
-        waker.block_on(self.inbox.recv()).ok_or(NoMessages)
+        waker.block_on(ReceiveMessage::new(
+            &mut self.inbox,
+            &self.inbox_state,
+            &mut self.pending,
+        ))
+    }
+
+    /// Attempt to receive the first message for which `predicate` returns
+    /// true.
+    ///
+    /// See [`actor::Context::try_receive_matching`] for more information.
+    ///
+    /// [`actor::Context::try_receive_matching`]: crate::actor::Context::try_receive_matching
+    pub fn try_receive_matching<F>(&mut self, mut predicate: F) -> Result<M, RecvError>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        try_receive_matching(
+            &mut self.inbox,
+            &self.inbox_state,
+            &mut self.pending,
+            &mut predicate,
+            &mut 0,
+        )
+    }
+
+    /// Receive the first message for which `predicate` returns true.
+    ///
+    /// If no matching message is currently available it will block until one
+    /// becomes available or until all actor references (that reference this
+    /// actor) are dropped. Messages for which `predicate` returns false are
+    /// left queued, in the order they were send.
+    ///
+    /// See [`actor::Context::receive_matching`] for more information.
+    ///
+    /// [`actor::Context::receive_matching`]: crate::actor::Context::receive_matching
+    ///
+    /// # Examples
+    ///
+    /// A synchronous actor that handles high priority messages first.
+    ///
+    /// ```
+    /// use heph::actor::SyncContext;
+    ///
+    /// fn print_actor<RT>(mut ctx: SyncContext<(bool, String), RT>) {
+    ///     if let Ok((_, msg)) = ctx.receive_matching(|(high_priority, _)| *high_priority) {
+    ///         println!("Got a high priority message: {msg}");
+    ///     }
+    ///
+    ///     while let Ok((_, msg)) = ctx.receive_next() {
+    ///         println!("Got a message: {msg}");
+    ///     }
+    /// }
+    ///
+    /// # fn assert_sync_actor<A: heph::actor::SyncActor<RuntimeAccess = ()>>(_: A) { }
+    /// # assert_sync_actor(print_actor as fn(_) -> _);
+    /// ```
+    pub fn receive_matching<F>(&mut self, predicate: F) -> Result<M, NoMessages>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        let waker = self.future_waker();
+        waker.block_on(ReceiveMatching::new(
+            &mut self.inbox,
+            &self.inbox_state,
+            &mut self.pending,
+            predicate,
+        ))
     }
 
     /// Block on a [`Future`] waiting for it's completion.
@@ -381,11 +466,13 @@ where
     RT: Clone + Send + 'static,
 {
     let (inbox, sender, ..) = heph_inbox::Manager::new_small_channel();
-    let actor_ref = ActorRef::local(sender);
+    let inbox_state = InboxState::new(name::<A>(), None, OverflowPolicy::Block);
+    let actor_ref = ActorRef::local(sender, inbox_state.clone());
     let sync_worker = SyncWorker {
         supervisor,
         actor,
         inbox,
+        inbox_state,
     };
     thread::Builder::new()
         .name("Sync actor".to_owned())
@@ -399,6 +486,7 @@ struct SyncWorker<S, A: SyncActor> {
     supervisor: S,
     actor: A,
     inbox: inbox::Manager<A::Message>,
+    inbox_state: Arc<InboxState>,
 }
 
 impl<S, A> SyncWorker<S, A>
@@ -412,22 +500,39 @@ where
         let thread = thread::current();
         let name = thread.name().unwrap();
         trace!(name = name; "running synchronous actor");
+        let mut monitors = ActorMonitors::new(self.inbox_state.clone());
         loop {
             let receiver = self.inbox.new_receiver().unwrap_or_else(inbox_failure);
-            let ctx = SyncContext::new(receiver, rt.clone());
+            let ctx = SyncContext::new(receiver, self.inbox_state.clone(), rt.clone());
             match self.actor.run(ctx, arg) {
-                Ok(()) => break,
+                Ok(()) => {
+                    monitors.stopped(DownReason::Normal);
+                    break;
+                }
                 Err(err) => match self.supervisor.decide(err) {
                     SupervisorStrategy::Restart(new_arg) => {
                         trace!(name = name; "restarting synchronous actor");
+                        monitors.restarted();
+                        arg = new_arg;
+                    }
+                    SupervisorStrategy::RestartAfter(new_arg, delay) => {
+                        trace!(name = name; "restarting synchronous actor after {delay:?}");
+                        thread::sleep(delay);
+                        monitors.restarted();
                         arg = new_arg;
                     }
-                    SupervisorStrategy::Stop => break,
+                    SupervisorStrategy::Stop => {
+                        monitors.stopped(DownReason::Error);
+                        break;
+                    }
                 },
             }
         }
 
         trace!(name = name; "stopping synchronous actor");
+        // Notify the monitors after the inbox is dropped.
+        drop(self);
+        drop(monitors);
     }
 }
 
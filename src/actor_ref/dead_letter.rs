@@ -0,0 +1,141 @@
+//! Types related to dead letters.
+//!
+//! A dead letter is a message that couldn't be delivered, e.g. because the
+//! receiving actor's inbox is full or the actor stopped running. Normally such
+//! messages are dropped, and only the sender learns about it (if it checks the
+//! returned error). Using a dead-letter actor these failures can be observed
+//! in a central location, e.g. to log them or to keep metrics.
+//!
+//! The runtime can be configured to send a [`DeadLetter`] for every message
+//! that couldn't be delivered, see `heph_rt::Runtime::spawn_dead_letters_actor`
+//! and `heph_rt::Setup::dead_letters`. This covers
+//! sending messages using [`ActorRef::try_send`] and [`ActorRef::send`]
+//! (including mapped actor references and [`ActorGroup`]s) as well as
+//! [`RpcResponse::respond`].
+//!
+//! The dead letter always contains the message's type. As messages are not
+//! required to implement [`Send`] the message itself is only included if the
+//! inbox of the actor was created for messages that are [`Send`], which is the
+//! case for the thread-safe and synchronous actors spawned by the runtime, see
+//! [`DeadLetter::message`]. The message is never included for failed mappings
+//! ([`DeadLetterReason::MapFailed`]) and RPC responses.
+//!
+//! [`ActorRef::try_send`]: crate::actor_ref::ActorRef::try_send
+//! [`ActorRef::send`]: crate::actor_ref::ActorRef::send
+//! [`ActorGroup`]: crate::actor_ref::ActorGroup
+//! [`RpcResponse::respond`]: crate::actor_ref::RpcResponse::respond
+//!
+//! # Examples
+//!
+//! Logging all dead letters.
+//!
+//! ```
+//! use heph::actor;
+//! use heph::actor_ref::DeadLetter;
+//! use heph_rt::Sync;
+//!
+//! fn dead_letters(mut ctx: actor::SyncContext<DeadLetter, Sync>) {
+//!     while let Ok(letter) = ctx.receive_next() {
+//!         let target = letter.target.unwrap_or("RPC caller");
+//!         println!(
+//!             "failed to deliver message of type '{}' to '{target}': {}",
+//!             letter.message_type, letter.reason
+//!         );
+//!         // The message itself can be retrieved by downcasting it.
+//!         if let Some(msg) = letter.message.and_then(|msg| msg.downcast::<String>().ok()) {
+//!             println!("undelivered message: {msg}");
+//!         }
+//!     }
+//! }
+//! # let _ = dead_letters;
+//! ```
+
+use std::any::{type_name, Any};
+use std::fmt;
+
+use crate::actor_ref::{ActorRef, ActorRefKind};
+
+/// Message send to the dead-letter actor when a message couldn't be
+/// delivered.
+///
+/// See the [`dead_letter`] module for more information.
+///
+/// [`dead_letter`]: crate::actor_ref::dead_letter
+#[derive(Debug)]
+pub struct DeadLetter {
+    /// Type of the message that couldn't be delivered, as returned by
+    /// [`type_name`].
+    pub message_type: &'static str,
+    /// The message that couldn't be delivered, if available. Use
+    /// [`Box::downcast`] with the type in `message_type` to get the message.
+    ///
+    /// See the [`dead_letter`] module for when the message is available.
+    ///
+    /// [`dead_letter`]: crate::actor_ref::dead_letter
+    pub message: Option<Box<dyn Any + Send>>,
+    /// Name of the actor the message was send to, or `None` if the message was
+    /// a RPC response (as the caller is not necessarily an actor).
+    pub target: Option<&'static str>,
+    /// Reason why the message couldn't be delivered.
+    pub reason: DeadLetterReason,
+}
+
+/// Reason why a message couldn't be delivered, see [`DeadLetter`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DeadLetterReason {
+    /// The inbox of the actor was full.
+    Full,
+    /// The actor is no longer running, or for RPC responses the caller is no
+    /// longer waiting for the response.
+    Disconnected,
+    /// Mapping the message to the message type of the actor failed, see
+    /// [`ActorRef::try_map`].
+    MapFailed,
+}
+
+impl fmt::Display for DeadLetterReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeadLetterReason::Full => f.pad("inbox full"),
+            DeadLetterReason::Disconnected => f.pad("disconnected"),
+            DeadLetterReason::MapFailed => f.pad("failed to map message"),
+        }
+    }
+}
+
+impl<M> From<&heph_inbox::SendError<M>> for DeadLetterReason {
+    fn from(err: &heph_inbox::SendError<M>) -> DeadLetterReason {
+        match err {
+            heph_inbox::SendError::Full(_) => DeadLetterReason::Full,
+            heph_inbox::SendError::Disconnected(_) => DeadLetterReason::Disconnected,
+        }
+    }
+}
+
+/// Send a [`DeadLetter`] for a message of type `M` to `dead_letters`.
+pub(super) fn send<M>(
+    dead_letters: &ActorRef<DeadLetter>,
+    message: Option<Box<dyn Any + Send>>,
+    target: Option<&'static str>,
+    reason: DeadLetterReason,
+) {
+    let letter = DeadLetter {
+        message_type: type_name::<M>(),
+        message,
+        target,
+        reason,
+    };
+    match &dead_letters.kind {
+        // NOTE: not using `ActorRef::try_send` as that would send a dead letter
+        // about the dead letter if the dead-letter actor's inbox is full.
+        ActorRefKind::Local(sender, state) => {
+            if sender.try_send(letter).is_ok() {
+                state.mark_sent();
+            }
+        }
+        ActorRefKind::Mapped(actor_ref) => {
+            let _ = actor_ref.try_mapped_send(letter);
+        }
+    }
+}
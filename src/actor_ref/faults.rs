@@ -0,0 +1,150 @@
+//! Actor references that inject faults, see [`crate::test::inject_faults`].
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use heph_inbox as inbox;
+
+use crate::actor_ref::{
+    ActorRef, ActorRefKind, InboxState, MappedActorRef, MappedJoin, MappedSendValue, SendError,
+};
+use crate::test::{self, MessageFaults};
+use crate::timer;
+
+impl<M> ActorRef<M> {
+    /// Wrap the actor reference in one that injects `faults`, or the shared
+    /// faults (see [`test::set_message_faults`]) if `None`.
+    pub(crate) fn with_faults(self, faults: Option<MessageFaults>) -> ActorRef<M>
+    where
+        M: Clone + Send + 'static,
+    {
+        let faulty_ref = FaultyActorRef {
+            actor_ref: self,
+            faults,
+            held: Mutex::new(None),
+        };
+        ActorRef {
+            kind: ActorRefKind::Mapped(Arc::new(faulty_ref)),
+        }
+    }
+}
+
+/// Wrapper around an [`ActorRef`] to inject faults.
+struct FaultyActorRef<M> {
+    actor_ref: ActorRef<M>,
+    /// If `None` the shared faults are used, see [`test::set_message_faults`].
+    faults: Option<MessageFaults>,
+    /// Message held back to be send after the next message, used to reorder
+    /// messages.
+    held: Mutex<Option<M>>,
+}
+
+impl<M> FaultyActorRef<M>
+where
+    M: Clone + Send + 'static,
+{
+    /// Send `msg` after applying the faults, except for loss and duplication.
+    fn deliver(&self, faults: &MessageFaults, msg: M) -> Result<(), SendError> {
+        if test::roll(faults.reorder) {
+            let mut held = self.lock();
+            if held.is_none() {
+                log::debug!("holding message on purpose");
+                *held = Some(msg);
+                return Ok(());
+            }
+        }
+
+        let res = self.delay_or_send(faults, msg);
+        // Send the held message after this message, reordering the two.
+        let held = self.lock().take();
+        if let Some(msg) = held {
+            let _ = self.delay_or_send(faults, msg);
+        }
+        res
+    }
+
+    fn delay_or_send(&self, faults: &MessageFaults, msg: M) -> Result<(), SendError> {
+        if test::roll(faults.delay) {
+            log::debug!("delaying message on purpose");
+            let actor_ref = self.actor_ref.clone();
+            timer::call_after(faults.delay_duration, move || {
+                let _ = actor_ref.try_send(msg);
+            })
+            .expect("failed to spawn thread to delay messages");
+            Ok(())
+        } else {
+            self.actor_ref.try_send(msg)
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<M>> {
+        match self.held.lock() {
+            Ok(held) => held,
+            // The held message is always in a valid state, so we can ignore
+            // the poisoning.
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+impl<M> MappedActorRef<M> for FaultyActorRef<M>
+where
+    M: Clone + Send + 'static,
+{
+    fn try_mapped_send(&self, msg: M) -> Result<(), SendError> {
+        let faults = match self.faults {
+            Some(faults) => faults,
+            None => test::message_faults(),
+        };
+        if test::roll(faults.loss) {
+            log::debug!("dropping message on purpose");
+            return Ok(());
+        }
+        if test::roll(faults.duplicate) {
+            log::debug!("duplicating message on purpose");
+            self.deliver(&faults, msg.clone())?;
+        }
+        self.deliver(&faults, msg)
+    }
+
+    fn mapped_send<'r>(&'r self, msg: M) -> MappedSendValue<'r> {
+        // NOTE: this doesn't wait for space in the inbox as that would defeat
+        // reordering the messages.
+        match self.try_mapped_send(msg) {
+            Ok(()) => MappedSendValue::Send,
+            Err(SendError) => MappedSendValue::SendErr,
+        }
+    }
+
+    fn mapped_join<'r>(&'r self) -> MappedJoin<'r> {
+        MappedActorRef::<M>::mapped_join(&self.actor_ref)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.actor_ref.is_connected()
+    }
+
+    fn id(&self) -> inbox::Id {
+        self.actor_ref.id()
+    }
+
+    fn capacity(&self) -> usize {
+        self.actor_ref.capacity()
+    }
+
+    fn inbox_state(&self) -> &InboxState {
+        self.actor_ref.inbox_state()
+    }
+}
+
+impl<M> Drop for FaultyActorRef<M> {
+    fn drop(&mut self) {
+        // Don't lose the held message.
+        let held = match self.held.get_mut() {
+            Ok(held) => held,
+            Err(err) => err.into_inner(),
+        };
+        if let Some(msg) = held.take() {
+            let _ = self.actor_ref.try_send(msg);
+        }
+    }
+}
@@ -104,22 +104,37 @@
 //! }
 //! ```
 
-use std::any::TypeId;
+use std::any::{Any, TypeId};
+use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
 use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::pin::Pin;
+use std::ptr::{self, NonNull};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 
-use heph_inbox::{self as inbox, Sender};
+use heph_inbox::{self as inbox, Receiver, Sender};
 
+pub mod dead_letter;
+#[cfg(any(test, feature = "test"))]
+mod faults;
+pub mod monitor;
+mod overflow;
 pub mod rpc;
 #[doc(no_inline)]
-pub use rpc::{Rpc, RpcError, RpcMessage, RpcResponse};
+pub use dead_letter::{DeadLetter, DeadLetterReason};
+#[doc(no_inline)]
+pub use monitor::{Down, DownReason};
+pub use overflow::OverflowPolicy;
+#[doc(no_inline)]
+pub use rpc::{Collect, GroupRpc, Rpc, RpcError, RpcMessage, RpcResponse};
+
+use overflow::Overflow;
 
 /// Actor reference.
 ///
@@ -133,7 +148,7 @@ pub struct ActorRef<M> {
 
 enum ActorRefKind<M> {
     /// Reference to an actor running on the same node.
-    Local(Sender<M>),
+    Local(Sender<M>, Arc<InboxState>),
     /// Reference that attempts to map the message to a different type first.
     Mapped(Arc<dyn MappedActorRef<M>>),
 }
@@ -153,9 +168,9 @@ impl<M> Unpin for ActorRefKind<M> {}
 impl<M> ActorRef<M> {
     /// Create a new `ActorRef` for an actor using `sender`.
     #[doc(hidden)] // Not part of the stable API.
-    pub const fn local(sender: Sender<M>) -> ActorRef<M> {
+    pub const fn local(sender: Sender<M>, inbox_state: Arc<InboxState>) -> ActorRef<M> {
         ActorRef {
-            kind: ActorRefKind::Local(sender),
+            kind: ActorRefKind::Local(sender, inbox_state),
         }
     }
 
@@ -180,7 +195,22 @@ impl<M> ActorRef<M> {
         let msg = msg.into();
         SendValue {
             kind: match &self.kind {
-                Local(sender) => SendValueKind::Local(sender.send(msg)),
+                Local(sender, state) if state.overflow.is_some() => {
+                    match state.try_send(sender, msg) {
+                        Ok(()) => SendValueKind::Done(Ok(())),
+                        // Overflow queue is full, wait for space in it. We
+                        // can't wait for space in the inbox as the message
+                        // would overtake the queued messages.
+                        Err(inbox::SendError::Full(msg)) => {
+                            SendValueKind::Overflow(sender, state, Some(msg))
+                        }
+                        Err(inbox::SendError::Disconnected(msg)) => {
+                            state.undeliverable(msg, DeadLetterReason::Disconnected);
+                            SendValueKind::Done(Err(SendError))
+                        }
+                    }
+                }
+                Local(sender, state) => SendValueKind::Local(sender.send(msg), state),
                 Mapped(actor_ref) => SendValueKind::Mapped(actor_ref.mapped_send(msg)),
             },
         }
@@ -209,7 +239,15 @@ impl<M> ActorRef<M> {
 
         let msg = msg.into();
         match &self.kind {
-            Local(sender) => sender.try_send(msg).map_err(|_| SendError),
+            Local(sender, state) => match state.try_send(sender, msg) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    let reason = (&err).into();
+                    let (inbox::SendError::Full(msg) | inbox::SendError::Disconnected(msg)) = err;
+                    state.undeliverable(msg, reason);
+                    Err(SendError)
+                }
+            },
             Mapped(actor_ref) => actor_ref.try_mapped_send(msg),
         }
     }
@@ -346,12 +384,33 @@ impl<M> ActorRef<M> {
         use ActorRefKind::*;
         Join {
             kind: match &self.kind {
-                Local(sender) => JoinKind::Local(sender.join()),
+                Local(sender, _) => JoinKind::Local(sender.join()),
                 Mapped(actor_ref) => JoinKind::Mapped(actor_ref.mapped_join()),
             },
         }
     }
 
+    /// Monitor the actor.
+    ///
+    /// Once the actor stops `watcher` is send a [`Down`] message containing
+    /// the reason why the actor stopped. If the actor is restarted `watcher` is
+    /// send a [`Down`] message with [`DownReason::Restarted`], but it keeps
+    /// monitoring the actor. If the actor is not running when this is called
+    /// `watcher` is send [`DownReason::NotRunning`] immediately.
+    ///
+    /// Calling this multiple times with the same `watcher` results in multiple
+    /// messages being send. Use [`ActorRef::demonitor`] to stop monitoring.
+    ///
+    /// See the [`monitor`] module for more information.
+    pub fn monitor(&self, watcher: ActorRef<Down>) {
+        monitor::add(self.inbox_state(), || self.is_connected(), watcher);
+    }
+
+    /// Stop `watcher` from monitoring the actor, see [`ActorRef::monitor`].
+    pub fn demonitor(&self, watcher: &ActorRef<Down>) {
+        monitor::remove(self.inbox_state(), watcher);
+    }
+
     /// Returns `true` if the actor to which this reference sends to is still
     /// connected.
     ///
@@ -369,7 +428,7 @@ impl<M> ActorRef<M> {
     pub fn is_connected(&self) -> bool {
         use ActorRefKind::*;
         match &self.kind {
-            Local(sender) => sender.is_connected(),
+            Local(sender, _) => sender.is_connected(),
             Mapped(actor_ref) => actor_ref.is_connected(),
         }
     }
@@ -382,10 +441,51 @@ impl<M> ActorRef<M> {
     fn id(&self) -> inbox::Id {
         use ActorRefKind::*;
         match &self.kind {
-            Local(sender) => sender.id(),
+            Local(sender, _) => sender.id(),
             Mapped(actor_ref) => actor_ref.id(),
         }
     }
+
+    /// Returns the (approximate) number of messages in the actor's inbox.
+    ///
+    /// This includes messages queued because of the actor's
+    /// [`OverflowPolicy`], so it can be larger than [`ActorRef::capacity`].
+    pub fn inbox_len(&self) -> usize {
+        self.inbox_state().len()
+    }
+
+    /// Returns the capacity of the actor's inbox.
+    pub fn capacity(&self) -> usize {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(sender, _) => sender.capacity(),
+            Mapped(actor_ref) => actor_ref.capacity(),
+        }
+    }
+
+    /// Returns the (approximate) highest number of messages that were in the
+    /// actor's inbox at the same time, see [`ActorRef::inbox_len`].
+    pub fn inbox_high_watermark(&self) -> usize {
+        self.inbox_state().high_watermark()
+    }
+
+    /// Returns the metrics of the actor's inbox.
+    pub fn inbox_metrics(&self) -> InboxMetrics {
+        InboxMetrics {
+            len: self.inbox_len(),
+            capacity: self.capacity(),
+            high_watermark: self.inbox_high_watermark(),
+        }
+    }
+
+    /// Returns the state of the actor's inbox.
+    fn inbox_state(&self) -> &InboxState {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(_, state) => state,
+            Mapped(actor_ref) => actor_ref.inbox_state(),
+        }
+    }
 }
 
 impl<M> Clone for ActorRef<M> {
@@ -393,7 +493,7 @@ impl<M> Clone for ActorRef<M> {
         use ActorRefKind::*;
         ActorRef {
             kind: match &self.kind {
-                Local(sender) => Local(sender.clone()),
+                Local(sender, state) => Local(sender.clone(), state.clone()),
                 Mapped(actor_ref) => Mapped(actor_ref.clone()),
             },
         }
@@ -422,6 +522,10 @@ trait MappedActorRef<M> {
     fn is_connected(&self) -> bool;
 
     fn id(&self) -> inbox::Id;
+
+    fn capacity(&self) -> usize;
+
+    fn inbox_state(&self) -> &InboxState;
 }
 
 impl<M, Msg> MappedActorRef<Msg> for ActorRef<M>
@@ -429,30 +533,42 @@ where
     M: TryFrom<Msg>,
 {
     fn try_mapped_send(&self, msg: Msg) -> Result<(), SendError> {
-        M::try_from(msg)
-            .map_err(|_| SendError)
-            .and_then(|msg| self.try_send(msg))
+        match M::try_from(msg) {
+            Ok(msg) => self.try_send(msg),
+            Err(..) => {
+                self.inbox_state()
+                    .dead_letter::<Msg>(DeadLetterReason::MapFailed);
+                Err(SendError)
+            }
+        }
     }
 
     fn mapped_send<'r>(&'r self, msg: Msg) -> MappedSendValue<'r> {
         match M::try_from(msg) {
             Ok(msg) => match &self.kind {
-                ActorRefKind::Local(sender) => match sender.try_send(msg) {
+                ActorRefKind::Local(sender, state) => match state.try_send(sender, msg) {
                     Ok(()) => MappedSendValue::Send,
                     Err(heph_inbox::SendError::Full(msg)) => {
                         MappedSendValue::Sending(Box::pin(self.send(msg)))
                     }
-                    Err(heph_inbox::SendError::Disconnected(_)) => MappedSendValue::SendErr,
+                    Err(heph_inbox::SendError::Disconnected(msg)) => {
+                        state.undeliverable(msg, DeadLetterReason::Disconnected);
+                        MappedSendValue::SendErr
+                    }
                 },
                 ActorRefKind::Mapped(sender) => sender.mapped_send(msg),
             },
-            Err(..) => MappedSendValue::SendErr,
+            Err(..) => {
+                self.inbox_state()
+                    .dead_letter::<Msg>(DeadLetterReason::MapFailed);
+                MappedSendValue::SendErr
+            }
         }
     }
 
     fn mapped_join<'r>(&'r self) -> MappedJoin<'r> {
         match &self.kind {
-            ActorRefKind::Local(sender) => match sender.is_connected() {
+            ActorRefKind::Local(sender, _) => match sender.is_connected() {
                 false => MappedJoin::Disconnected,
                 true => MappedJoin::Join(Box::pin(self.join())),
             },
@@ -467,6 +583,14 @@ where
     fn id(&self) -> inbox::Id {
         self.id()
     }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn inbox_state(&self) -> &InboxState {
+        self.inbox_state()
+    }
 }
 
 /// Wrapper around an [`ActorRef`] to change the message type.
@@ -482,29 +606,40 @@ where
     fn try_mapped_send(&self, msg: Msg) -> Result<(), SendError> {
         match (self.map)(msg) {
             Ok(msg) => self.actor_ref.try_send(msg),
-            Err(..) => Err(SendError),
+            Err(..) => {
+                self.inbox_state()
+                    .dead_letter::<Msg>(DeadLetterReason::MapFailed);
+                Err(SendError)
+            }
         }
     }
 
     fn mapped_send<'r>(&'r self, msg: Msg) -> MappedSendValue<'r> {
         match (self.map)(msg) {
             Ok(msg) => match &self.actor_ref.kind {
-                ActorRefKind::Local(sender) => match sender.try_send(msg) {
+                ActorRefKind::Local(sender, state) => match state.try_send(sender, msg) {
                     Ok(()) => MappedSendValue::Send,
                     Err(heph_inbox::SendError::Full(msg)) => {
                         MappedSendValue::Sending(Box::pin(self.actor_ref.send(msg)))
                     }
-                    Err(heph_inbox::SendError::Disconnected(_)) => MappedSendValue::SendErr,
+                    Err(heph_inbox::SendError::Disconnected(msg)) => {
+                        state.undeliverable(msg, DeadLetterReason::Disconnected);
+                        MappedSendValue::SendErr
+                    }
                 },
                 ActorRefKind::Mapped(sender) => sender.mapped_send(msg),
             },
-            Err(..) => MappedSendValue::SendErr,
+            Err(..) => {
+                self.inbox_state()
+                    .dead_letter::<Msg>(DeadLetterReason::MapFailed);
+                MappedSendValue::SendErr
+            }
         }
     }
 
     fn mapped_join<'r>(&'r self) -> MappedJoin<'r> {
         match &self.actor_ref.kind {
-            ActorRefKind::Local(sender) => match sender.is_connected() {
+            ActorRefKind::Local(sender, _) => match sender.is_connected() {
                 false => MappedJoin::Disconnected,
                 true => MappedJoin::Join(Box::pin(self.actor_ref.join())),
             },
@@ -519,6 +654,14 @@ where
     fn id(&self) -> inbox::Id {
         self.actor_ref.id()
     }
+
+    fn capacity(&self) -> usize {
+        self.actor_ref.capacity()
+    }
+
+    fn inbox_state(&self) -> &InboxState {
+        self.actor_ref.inbox_state()
+    }
 }
 
 /// Future used in `MappedActorRef::mapped_send`
@@ -573,8 +716,12 @@ pub struct SendValue<'r, M> {
 }
 
 enum SendValueKind<'r, M> {
-    Local(inbox::SendValue<'r, M>),
+    Local(inbox::SendValue<'r, M>, &'r InboxState),
+    /// Waiting for space in the overflow queue, see [`OverflowPolicy::Grow`].
+    Overflow(&'r Sender<M>, &'r InboxState, Option<M>),
     Mapped(MappedSendValue<'r>),
+    /// Already send (or failed to), see [`OverflowPolicy`].
+    Done(Result<(), SendError>),
 }
 
 // We know that the `Local` variant is `Send` and `Sync`. Since the `Mapped`
@@ -601,11 +748,34 @@ impl<'r, M> Future for SendValue<'r, M> {
         let this = unsafe { self.get_unchecked_mut() };
         match &mut this.kind {
             // Safety: we're not moving `send_value` so this is safe.
-            Local(fut) => unsafe { Pin::new_unchecked(fut) }
-                .poll(ctx)
-                .map_err(|_| SendError),
+            Local(fut, state) => match unsafe { Pin::new_unchecked(fut) }.poll(ctx) {
+                Poll::Ready(Ok(())) => {
+                    state.mark_sent();
+                    Poll::Ready(Ok(()))
+                }
+                Poll::Ready(Err(msg)) => {
+                    state.undeliverable(msg, DeadLetterReason::Disconnected);
+                    Poll::Ready(Err(SendError))
+                }
+                Poll::Pending => Poll::Pending,
+            },
+            Overflow(sender, state, msg) => {
+                let m = msg.take().expect("polled `SendValue` after completion");
+                match state.send_overflow(sender, m, ctx.waker()) {
+                    Ok(()) => Poll::Ready(Ok(())),
+                    Err(inbox::SendError::Full(m)) => {
+                        *msg = Some(m);
+                        Poll::Pending
+                    }
+                    Err(inbox::SendError::Disconnected(m)) => {
+                        state.undeliverable(m, DeadLetterReason::Disconnected);
+                        Poll::Ready(Err(SendError))
+                    }
+                }
+            }
             // Safety: we're not moving `fut` so this is safe.
             Mapped(fut) => unsafe { Pin::new_unchecked(fut) }.poll(ctx),
+            Done(result) => Poll::Ready(*result),
         }
     }
 }
@@ -673,6 +843,224 @@ impl<'r, M> fmt::Debug for Join<'r, M> {
     }
 }
 
+/// Metrics of an actor's inbox, see [`ActorRef::inbox_metrics`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct InboxMetrics {
+    /// Number of messages in the inbox, see [`ActorRef::inbox_len`].
+    pub len: usize,
+    /// Capacity of the inbox, see [`ActorRef::capacity`].
+    pub capacity: usize,
+    /// Highest number of messages in the inbox so far, see
+    /// [`ActorRef::inbox_high_watermark`].
+    pub high_watermark: usize,
+}
+
+/// State of an actor's inbox, shared between the actor and its references.
+#[doc(hidden)] // Not part of the stable API.
+#[derive(Debug)]
+pub struct InboxState {
+    /// Number of messages in the inbox (including the overflow queue).
+    len: AtomicUsize,
+    /// Highest number of messages in the inbox so far.
+    high_watermark: AtomicUsize,
+    /// Name of the actor, used in [`DeadLetter`]s.
+    name: &'static str,
+    /// Actor to send messages to that couldn't be delivered.
+    dead_letters: Option<ActorRef<DeadLetter>>,
+    /// Converts an undeliverable message into [`DeadLetter::message`], `None`
+    /// if the messages are not included, see [`InboxState::new_send`].
+    into_any: Option<IntoAny>,
+    /// Overflow state, `None` if the policy is [`OverflowPolicy::Block`].
+    overflow: Option<Overflow>,
+    /// Watchers of the actor, see [`ActorRef::monitor`].
+    monitors: monitor::Monitors,
+}
+
+/// See [`InboxState::into_any`].
+///
+/// # Safety
+///
+/// The pointer must be created by [`Box::into_raw`] for the message type the
+/// function is created for, see [`into_any`].
+type IntoAny = unsafe fn(NonNull<()>) -> Box<dyn Any + Send>;
+
+/// # Safety
+///
+/// See [`IntoAny`].
+unsafe fn into_any<M: Send + 'static>(ptr: NonNull<()>) -> Box<dyn Any + Send> {
+    Box::from_raw(ptr.cast::<M>().as_ptr())
+}
+
+impl InboxState {
+    /// Create a new `InboxState` for the actor with `name`.
+    pub fn new(
+        name: &'static str,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        overflow: OverflowPolicy,
+    ) -> Arc<InboxState> {
+        InboxState::create(name, dead_letters, None, overflow)
+    }
+
+    /// Create a new `InboxState` for the actor with `name` and message type
+    /// `M`, including the messages in the [`DeadLetter`]s send to
+    /// `dead_letters`.
+    ///
+    /// The returned state must only be used for an inbox with message type
+    /// `M`.
+    pub fn new_send<M: Send + 'static>(
+        name: &'static str,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        overflow: OverflowPolicy,
+    ) -> Arc<InboxState> {
+        InboxState::create(name, dead_letters, Some(into_any::<M>), overflow)
+    }
+
+    fn create(
+        name: &'static str,
+        dead_letters: Option<ActorRef<DeadLetter>>,
+        into_any: Option<IntoAny>,
+        overflow: OverflowPolicy,
+    ) -> Arc<InboxState> {
+        Arc::new(InboxState {
+            len: AtomicUsize::new(0),
+            high_watermark: AtomicUsize::new(0),
+            name,
+            dead_letters,
+            into_any,
+            overflow: Overflow::new(overflow),
+            monitors: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Attempt to send `msg` to the inbox using `sender`, following the
+    /// [`OverflowPolicy`] if the inbox is full.
+    pub(crate) fn try_send<M>(
+        &self,
+        sender: &Sender<M>,
+        msg: M,
+    ) -> Result<(), inbox::SendError<M>> {
+        match &self.overflow {
+            Some(overflow) => overflow.try_send(self, sender, msg),
+            None => self.try_send_inbox(sender, msg),
+        }
+    }
+
+    /// Same as [`InboxState::try_send`], but wakes `waker` once there is space
+    /// in the overflow queue if it's full, see [`OverflowPolicy::Grow`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is [`OverflowPolicy::Block`].
+    fn send_overflow<M>(
+        &self,
+        sender: &Sender<M>,
+        msg: M,
+        waker: &task::Waker,
+    ) -> Result<(), inbox::SendError<M>> {
+        let overflow = self.overflow.as_ref().expect("no overflow queue");
+        overflow.send(self, sender, msg, Some(waker))
+    }
+
+    /// Attempt to send `msg` to the inbox using `sender`, ignoring the
+    /// [`OverflowPolicy`].
+    fn try_send_inbox<M>(&self, sender: &Sender<M>, msg: M) -> Result<(), inbox::SendError<M>> {
+        let res = sender.try_send(msg);
+        if res.is_ok() {
+            self.mark_sent();
+        }
+        res
+    }
+
+    /// Mark `msg` as received from `inbox`. Returns `None` if the message must
+    /// be dropped, see [`OverflowPolicy::DropOldest`].
+    pub(crate) fn received<M>(&self, inbox: &Receiver<M>, msg: M) -> Option<M> {
+        self.mark_received();
+        match &self.overflow {
+            Some(overflow) => overflow.received(self, inbox, msg),
+            None => Some(msg),
+        }
+    }
+
+    /// Returns the actor to send [`DeadLetter`]s to, if any.
+    pub(crate) const fn dead_letters(&self) -> Option<&ActorRef<DeadLetter>> {
+        self.dead_letters.as_ref()
+    }
+
+    /// Send a [`DeadLetter`] for a message of type `M` that couldn't be
+    /// delivered to the inbox, without including the message.
+    pub(crate) fn dead_letter<M>(&self, reason: DeadLetterReason) {
+        if let Some(dead_letters) = &self.dead_letters {
+            dead_letter::send::<M>(dead_letters, None, Some(self.name), reason);
+        }
+    }
+
+    /// Send a [`DeadLetter`] for `msg` that couldn't be delivered to the
+    /// inbox, including the message if possible (see
+    /// [`InboxState::new_send`]).
+    ///
+    /// `M` must be the message type of the inbox.
+    pub(crate) fn undeliverable<M>(&self, msg: M, reason: DeadLetterReason) {
+        let Some(dead_letters) = &self.dead_letters else {
+            return;
+        };
+        let message = self.into_any.map(|into_any| {
+            // SAFETY: `Box::into_raw` never returns a null pointer.
+            let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(msg))) };
+            // SAFETY: `M` is the message type of the inbox as an inbox (and
+            // thus its `InboxState`) is only used with a single message type,
+            // which is the type `into_any` is created for.
+            unsafe { into_any(ptr.cast()) }
+        });
+        dead_letter::send::<M>(dead_letters, message, Some(self.name), reason);
+    }
+
+    /// Mark a message as send to the inbox.
+    pub(crate) fn mark_sent(&self) {
+        let len = self.len.fetch_add(1, Ordering::Relaxed) + 1;
+        // Avoid the read-modify-write if we can.
+        if len > self.high_watermark.load(Ordering::Relaxed) {
+            let _ = self.high_watermark.fetch_max(len, Ordering::Relaxed);
+        }
+    }
+
+    /// Mark a message as received from (or dropped in) the inbox.
+    fn mark_received(&self) {
+        // NOTE: messages are marked as send after they're added to the inbox,
+        // so the actor can receive a message before it's marked as send.
+        let _ = self
+            .len
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |len| {
+                len.checked_sub(1)
+            });
+    }
+
+    /// Mark the inbox as empty, called by the actor if it finds its inbox
+    /// empty. This corrects the length for the messages not counted in
+    /// [`InboxState::mark_received`].
+    pub(crate) fn mark_empty(&self) {
+        if self.len.load(Ordering::Relaxed) != 0 {
+            self.len.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the (approximate) number of messages in the inbox.
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Returns the (approximate) highest number of messages in the inbox so
+    /// far.
+    fn high_watermark(&self) -> usize {
+        self.high_watermark.load(Ordering::Relaxed)
+    }
+
+    /// Returns a key uniquely identifying the inbox.
+    fn key(&self) -> usize {
+        ptr::addr_of!(*self) as usize
+    }
+}
+
 /// A group of [`ActorRef`]s used to send a message to multiple actors.
 ///
 /// # Notes
@@ -693,11 +1081,54 @@ pub struct ActorGroup<M> {
 
 /// The kind of delivery to use in [`ActorGroup::try_send`].
 #[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub enum Delivery {
     /// Delivery a copy of the message to all actors in the group.
     ToAll,
     /// Delivery the message to one of the actors.
     ToOne,
+    /// Delivery the message to the actor selected by the key, see
+    /// [`Delivery::to_key`].
+    ///
+    /// Messages with the same key are delivered to the same actor. When actors
+    /// are added to or removed from the group only the keys of those actors
+    /// move to another actor, the keys of all other actors are unaffected.
+    ///
+    /// Note that the mapping from key to actor is based on the identity of
+    /// the actors' inboxes, which is local to the process. This means the same
+    /// key maps to different actors in different runs of the program.
+    ToKey(u64),
+    /// Delivery the message to a random actor.
+    ToRandom,
+    /// Delivery the message to the actor with the fewest messages in its
+    /// inbox, see [`ActorRef::inbox_len`].
+    ToLeastLoaded,
+}
+
+impl Delivery {
+    /// Create a [`Delivery::ToKey`] by hashing `key`, e.g. a field of the
+    /// message.
+    ///
+    /// The hash is stable for the lifetime of the process, but see
+    /// [`Delivery::ToKey`] for the stability of the key to actor mapping.
+    pub fn to_key<K>(key: &K) -> Delivery
+    where
+        K: Hash + ?Sized,
+    {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        Delivery::ToKey(hasher.finish())
+    }
+}
+
+/// Mixes the bits of `value`, used in [`Delivery::ToKey`].
+///
+/// This is the finaliser of the SplitMix64 generator, which is a lot cheaper
+/// than hashing `value` with [`DefaultHasher`].
+const fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
 }
 
 impl<M> ActorGroup<M> {
@@ -730,6 +1161,12 @@ impl<M> ActorGroup<M> {
         self.actor_refs.is_empty()
     }
 
+    /// Returns the inbox metrics of all actors in the group, in the same order
+    /// as the actor references in the group. See [`ActorRef::inbox_metrics`].
+    pub fn inbox_metrics<'a>(&'a self) -> impl Iterator<Item = InboxMetrics> + 'a {
+        self.actor_refs.iter().map(ActorRef::inbox_metrics)
+    }
+
     /// Add an `ActorRef` to the group.
     pub fn add(&mut self, actor_ref: ActorRef<M>) {
         self.actor_refs.push(actor_ref)
@@ -781,9 +1218,9 @@ impl<M> ActorGroup<M> {
 
     /// Attempts to send a message to all the actors in the group.
     ///
-    /// This can either send the message to a single actor, by using
-    /// [`Delivery::ToOne`], or to all actors in the group by using
-    /// [`Delivery::ToAll`].
+    /// This can either send the message to all actors in the group by using
+    /// [`Delivery::ToAll`], or to a single actor using one of the other
+    /// [`Delivery`] strategies.
     ///
     /// When deliverying to all actors this will first `clone` the message and
     /// then [`try_send`]ing it to each actor in the group. Note that this means
@@ -822,9 +1259,55 @@ impl<M> ActorGroup<M> {
                 // TODO: try to send it to another actor on send failure?
                 actor_ref.try_send(msg)
             }
+            Delivery::ToKey(key) => {
+                // Rendezvous hashing: send to the actor with the highest hash
+                // of the key and the actor's inbox. Because the hash doesn't
+                // depend on the other actors in the group, adding or removing
+                // an actor only moves the keys of that actor.
+                let actor_ref = self
+                    .actor_refs
+                    .iter()
+                    .max_by_key(|actor_ref| mix(key ^ actor_ref.inbox_state().key() as u64));
+                // NOTE: checked if the group is empty above.
+                actor_ref.unwrap().try_send(msg)
+            }
+            Delivery::ToRandom => {
+                let idx = (crate::random() % self.actor_refs.len() as u64) as usize;
+                self.actor_refs[idx].try_send(msg)
+            }
+            Delivery::ToLeastLoaded => {
+                // Start at a different actor each time, so that the messages
+                // are spread over the actors with an equal load.
+                let start = self.send_next.fetch_add(1, Ordering::AcqRel);
+                let len = self.actor_refs.len();
+                let idx = (0..len)
+                    .map(|n| start.wrapping_add(n) % len)
+                    .filter(|idx| self.actor_refs[*idx].is_connected())
+                    .min_by_key(|idx| self.actor_refs[*idx].inbox_len())
+                    .unwrap_or(start % len);
+                self.actor_refs[idx].try_send(msg)
+            }
         }
     }
 
+    /// Make a Remote Procedure Call (RPC) to all actors in the group.
+    ///
+    /// This will send a copy of the `request` to all actors in the group, see
+    /// [`ActorRef::rpc`], and returns a [`GroupRpc`] [`Future`] that collects
+    /// the responses. Using `collect` it's possible to wait for the responses
+    /// of all actors, the first `n` responses or the first successful
+    /// response. A deadline for the call can be set using
+    /// [`GroupRpc::with_deadline`].
+    ///
+    /// See the [`rpc`] module for more details.
+    pub fn rpc<'r, Req, Res>(&'r self, request: Req, collect: Collect) -> GroupRpc<'r, M, Res>
+    where
+        M: From<RpcMessage<Req, Res>>,
+        Req: Clone,
+    {
+        GroupRpc::new(self, request, collect)
+    }
+
     /// Wait for all actors in this group to finish running.
     ///
     /// This works the same way as [`ActorRef::join`], but waits on a group of
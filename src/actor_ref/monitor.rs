@@ -0,0 +1,213 @@
+//! Types related to monitoring actors.
+//!
+//! An actor can be monitored using [`ActorRef::monitor`]. Once the monitored
+//! actor stops, or is restarted, the watcher receives a [`Down`] message
+//! containing the reason ([`DownReason`]).
+//!
+//! Unlike [`ActorRef::join`] this doesn't require a future to be polled and it
+//! tells the watcher why the actor stopped, e.g. whether it stopped normally or
+//! due to an error or panic.
+//!
+//! # Examples
+//!
+//! Monitoring an actor that returns an error.
+//!
+//! ```
+//! use heph::actor;
+//! use heph::actor_ref::{ActorRef, Down, DownReason};
+//! use heph_rt::ThreadLocal;
+//!
+//! /// Message type of the watcher.
+//! enum Message {
+//!     Down(Down),
+//! }
+//!
+//! impl From<Down> for Message {
+//!     fn from(down: Down) -> Message {
+//!         Message::Down(down)
+//!     }
+//! }
+//!
+//! async fn watcher(mut ctx: actor::Context<Message, ThreadLocal>, actor_ref: ActorRef<String>) {
+//!     // Monitor `actor_ref`, using a mapped actor reference to our own
+//!     // message type.
+//!     actor_ref.monitor(ctx.actor_ref().map());
+//!
+//!     while let Ok(Message::Down(down)) = ctx.receive_next().await {
+//!         match down.reason {
+//!             DownReason::Normal => println!("actor stopped"),
+//!             DownReason::Panic(msg) => println!("actor panicked: {msg}"),
+//!             reason => println!("actor stopped: {reason}"),
+//!         }
+//!     }
+//! }
+//! # let _ = watcher;
+//! ```
+//!
+//! [`ActorRef::monitor`]: crate::actor_ref::ActorRef::monitor
+//! [`ActorRef::join`]: crate::actor_ref::ActorRef::join
+
+use std::fmt;
+use std::mem::take;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::actor_ref::{ActorRef, InboxState};
+
+/// Watchers of a single actor, stored in its [`InboxState`].
+pub(super) type Monitors = Mutex<Vec<ActorRef<Down>>>;
+
+/// Message send to the watcher of a monitored actor.
+///
+/// See [`ActorRef::monitor`].
+///
+/// [`ActorRef::monitor`]: crate::actor_ref::ActorRef::monitor
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Down {
+    /// Reason why the actor went down.
+    pub reason: DownReason,
+}
+
+/// Reason why a monitored actor went down, see [`Down`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DownReason {
+    /// The actor stopped normally, i.e. it returned `Ok`.
+    Normal,
+    /// The actor returned an error and its supervisor decided to stop it. This
+    /// includes errors returned when restarting the actor.
+    Error,
+    /// The actor panicked and its supervisor decided to stop it, contains the
+    /// panic message.
+    Panic(String),
+    /// The actor failed and its supervisor restarted it.
+    ///
+    /// Unlike the other reasons the actor is still running and the monitor
+    /// remains active.
+    Restarted,
+    /// The actor was stopped without failing, e.g. by a [supervision tree] or
+    /// because the runtime was shut down.
+    ///
+    /// [supervision tree]: crate::supervisor::tree
+    Stopped,
+    /// The actor wasn't running when the monitor was created.
+    NotRunning,
+}
+
+impl fmt::Display for DownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownReason::Normal => f.pad("stopped normally"),
+            DownReason::Error => f.pad("stopped after an error"),
+            DownReason::Panic(msg) => write!(f, "stopped after panicking: {msg}"),
+            DownReason::Restarted => f.pad("restarted"),
+            DownReason::Stopped => f.pad("stopped"),
+            DownReason::NotRunning => f.pad("not running"),
+        }
+    }
+}
+
+/// Add a monitor for the actor with inbox `state`.
+///
+/// `is_connected` must return `true` if the actor is still running, if it
+/// returns `false` `watcher` is send [`DownReason::NotRunning`] immediately.
+pub(super) fn add<F>(state: &InboxState, is_connected: F, watcher: ActorRef<Down>)
+where
+    F: FnOnce() -> bool,
+{
+    let mut monitors = lock(&state.monitors);
+    // NOTE: we check `is_connected` with the lock held to ensure we don't race
+    // with `ActorMonitors::drop`.
+    if is_connected() {
+        monitors.push(watcher);
+    } else {
+        drop(monitors);
+        let _ = watcher.try_send(Down {
+            reason: DownReason::NotRunning,
+        });
+    }
+}
+
+/// Remove the monitor(s) of `watcher` for the actor with inbox `state`.
+pub(super) fn remove(state: &InboxState, watcher: &ActorRef<Down>) {
+    lock(&state.monitors).retain(|w| !w.sends_to(watcher));
+}
+
+/// Notify the `watchers` of an actor.
+fn notify(watchers: Vec<ActorRef<Down>>, reason: &DownReason) {
+    // NOTE: sending the messages without holding the lock as mapped actor
+    // references run user code.
+    for watcher in watchers {
+        let _ = watcher.try_send(Down {
+            reason: reason.clone(),
+        });
+    }
+}
+
+fn lock(monitors: &Monitors) -> MutexGuard<'_, Vec<ActorRef<Down>>> {
+    // If a thread panicked while holding the lock the list of monitors is
+    // still valid, so we can ignore the poisoning.
+    monitors.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Notifies the monitors of an actor, see [`ActorRef::monitor`].
+///
+/// Used by the implementations of actors (e.g. [`ActorFuture`]) to notify the
+/// monitors once the actor is restarted or stopped. The monitors are notified
+/// of the actor stopping when this is dropped, which must be *after* the
+/// actor's inbox (i.e. its `Manager` and `Receiver`) is dropped.
+///
+/// [`ActorRef::monitor`]: crate::actor_ref::ActorRef::monitor
+/// [`ActorFuture`]: crate::actor::ActorFuture
+#[doc(hidden)] // Not part of the stable API.
+pub struct ActorMonitors {
+    /// State of the actor's inbox, which holds the monitors.
+    state: Arc<InboxState>,
+    /// Reason the actor stopped, defaults to [`DownReason::Stopped`].
+    reason: Option<DownReason>,
+}
+
+impl ActorMonitors {
+    /// Create a new `ActorMonitors` for the actor with inbox `state`.
+    pub const fn new(state: Arc<InboxState>) -> ActorMonitors {
+        ActorMonitors {
+            state,
+            reason: None,
+        }
+    }
+
+    /// Notify the monitors the actor was restarted.
+    pub fn restarted(&self) {
+        let watchers = {
+            let monitors = lock(&self.state.monitors);
+            if monitors.is_empty() {
+                return;
+            }
+            monitors.clone()
+        };
+        notify(watchers, &DownReason::Restarted);
+    }
+
+    /// Set the `reason` the actor stopped.
+    pub fn stopped(&mut self, reason: DownReason) {
+        self.reason = Some(reason);
+    }
+}
+
+impl Drop for ActorMonitors {
+    fn drop(&mut self) {
+        // At this point the actor's `Manager` and `Receiver` are dropped, which
+        // means that `ActorRef::monitor` will no longer add new monitors (see
+        // `add`).
+        let watchers = take(&mut *lock(&self.state.monitors));
+        let reason = self.reason.take().unwrap_or(DownReason::Stopped);
+        notify(watchers, &reason);
+    }
+}
+
+impl fmt::Debug for ActorMonitors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActorMonitors")
+            .field("reason", &self.reason)
+            .finish()
+    }
+}
@@ -0,0 +1,308 @@
+//! Handling of full inboxes, see [`OverflowPolicy`].
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::task;
+
+use heph_inbox::{Receiver, SendError, Sender};
+
+use crate::actor_ref::{DeadLetterReason, InboxState};
+
+/// What to do when a message is send to an actor with a full inbox.
+///
+/// Messages that are dropped because of the policy are send to the dead-letter
+/// actor, if any, using [`DeadLetterReason::Full`]. See the [`dead_letter`]
+/// module.
+///
+/// [`dead_letter`]: crate::actor_ref::dead_letter
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Block the sender: [`ActorRef::send`] waits until the message can be
+    /// send and [`ActorRef::try_send`] returns an error. This is the default.
+    ///
+    /// [`ActorRef::send`]: crate::actor_ref::ActorRef::send
+    /// [`ActorRef::try_send`]: crate::actor_ref::ActorRef::try_send
+    #[default]
+    Block,
+    /// Drop the message that is being send.
+    DropNewest,
+    /// Drop the oldest message in the inbox to make room for the message that
+    /// is being send.
+    DropOldest,
+    /// Queue messages that don't fit in the inbox, up to the limit of queued
+    /// messages. Once the queue is full as well this acts like
+    /// [`OverflowPolicy::Block`], with [`ActorRef::send`] waiting for space in
+    /// the queue so that messages are delivered in order.
+    ///
+    /// [`ActorRef::send`]: crate::actor_ref::ActorRef::send
+    Grow(usize),
+}
+
+/// Overflow state of an inbox, used for all policies except
+/// [`OverflowPolicy::Block`].
+#[derive(Debug)]
+pub(super) struct Overflow {
+    policy: OverflowPolicy,
+    /// Messages that didn't fit in the inbox, oldest first.
+    queue: Mutex<VecDeque<ErasedMsg>>,
+    /// Senders waiting for space in `queue`, see [`Overflow::send`]. Only
+    /// locked while holding the `queue` lock.
+    blocked: Mutex<Vec<task::Waker>>,
+    /// Number of messages in `queue`, to avoid locking it when it's empty.
+    queued: AtomicUsize,
+    /// Number of messages in the inbox to drop once they're received, see
+    /// [`OverflowPolicy::DropOldest`].
+    to_drop: AtomicUsize,
+}
+
+impl Overflow {
+    /// Returns `None` for [`OverflowPolicy::Block`] as it's handled by the
+    /// inbox itself. The same is true for `Grow(0)` as it never queues a
+    /// message.
+    pub(super) fn new(policy: OverflowPolicy) -> Option<Overflow> {
+        match policy {
+            OverflowPolicy::Block | OverflowPolicy::Grow(0) => None,
+            policy => Some(Overflow {
+                policy,
+                queue: Mutex::new(VecDeque::new()),
+                blocked: Mutex::new(Vec::new()),
+                queued: AtomicUsize::new(0),
+                to_drop: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Attempt to send `msg` using `sender`, following the policy if the inbox
+    /// is full.
+    pub(super) fn try_send<M>(
+        &self,
+        state: &InboxState,
+        sender: &Sender<M>,
+        msg: M,
+    ) -> Result<(), SendError<M>> {
+        self.send(state, sender, msg, None)
+    }
+
+    /// Same as [`Overflow::try_send`], but if the queue is full (only possible
+    /// for [`OverflowPolicy::Grow`]) `waker` is woken once there is space in
+    /// it again.
+    ///
+    /// # Notes
+    ///
+    /// Senders must wait for space in the queue, not in the inbox, as a message
+    /// send directly to the inbox would overtake the queued messages.
+    pub(super) fn send<M>(
+        &self,
+        state: &InboxState,
+        sender: &Sender<M>,
+        msg: M,
+        waker: Option<&task::Waker>,
+    ) -> Result<(), SendError<M>> {
+        // Only send to the inbox directly if no messages are queued, otherwise
+        // the message would overtake the queued messages.
+        let msg = if self.queued.load(Ordering::Acquire) == 0 {
+            match state.try_send_inbox(sender, msg) {
+                Err(SendError::Full(msg)) => msg,
+                res => return res,
+            }
+        } else if sender.is_connected() {
+            msg
+        } else {
+            return Err(SendError::Disconnected(msg));
+        };
+
+        match self.policy {
+            OverflowPolicy::Block => Err(SendError::Full(msg)),
+            OverflowPolicy::DropNewest => {
+                state.undeliverable(msg, DeadLetterReason::Full);
+                Ok(())
+            }
+            OverflowPolicy::DropOldest => {
+                let mut queue = self.lock();
+                if queue.len() >= sender.capacity() {
+                    // The queue holds an entire inbox worth of newer messages,
+                    // all messages in the inbox are already marked to be
+                    // dropped, so the oldest message is the first queued one.
+                    if let Some(oldest) = queue.pop_front() {
+                        // SAFETY: all messages in the queue are of type `M`,
+                        // see `Overflow::pump`.
+                        let oldest = unsafe { oldest.into_inner::<M>() };
+                        state.undeliverable(oldest, DeadLetterReason::Full);
+                    }
+                    state.mark_received();
+                } else {
+                    // We can't remove the oldest message from the inbox, so
+                    // we mark it to be dropped once it's received.
+                    let _ = self.to_drop.fetch_add(1, Ordering::AcqRel);
+                }
+                // SAFETY: see `ErasedMsg`, the queue is only used with message
+                // type `M`.
+                queue.push_back(unsafe { ErasedMsg::new(msg) });
+                state.mark_sent();
+                self.pump(&mut queue, sender);
+                Ok(())
+            }
+            OverflowPolicy::Grow(limit) => {
+                let mut queue = self.lock();
+                if queue.len() >= limit {
+                    if let Some(waker) = waker {
+                        let mut blocked = lock(&self.blocked);
+                        if !blocked.iter().any(|w| w.will_wake(waker)) {
+                            blocked.push(waker.clone());
+                        }
+                    }
+                    return Err(SendError::Full(msg));
+                }
+                // SAFETY: see `ErasedMsg`, the queue is only used with message
+                // type `M`.
+                queue.push_back(unsafe { ErasedMsg::new(msg) });
+                state.mark_sent();
+                self.pump(&mut queue, sender);
+                Ok(())
+            }
+        }
+    }
+
+    /// Process `msg` received from `inbox`. Returns `None` if the message must
+    /// be dropped.
+    pub(super) fn received<M>(&self, state: &InboxState, inbox: &Receiver<M>, msg: M) -> Option<M> {
+        let drop_msg = self.to_drop.load(Ordering::Acquire) != 0
+            && self
+                .to_drop
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+                .is_ok();
+        let msg = if drop_msg {
+            state.undeliverable(msg, DeadLetterReason::Full);
+            None
+        } else {
+            Some(msg)
+        };
+
+        // Now that there is space in the inbox, move a queued message into it.
+        if self.queued.load(Ordering::Acquire) != 0 {
+            let mut queue = self.lock();
+            self.pump(&mut queue, &inbox.new_sender());
+        }
+        msg
+    }
+
+    /// Move messages from `queue` into the inbox, for as long as it has space.
+    ///
+    /// # Notes
+    ///
+    /// Both the senders (after queueing a message) and the receiver (after
+    /// receiving a message) call this. This ensures queued messages are never
+    /// left behind in the queue while the inbox is empty.
+    fn pump<M>(&self, queue: &mut VecDeque<ErasedMsg>, sender: &Sender<M>) {
+        while let Some(msg) = queue.pop_front() {
+            // SAFETY: all messages in the queue are of type `M` as an inbox
+            // (and thus its `InboxState`) is only used with a single message
+            // type.
+            let msg = unsafe { msg.into_inner::<M>() };
+            match sender.try_send(msg) {
+                Ok(()) => {}
+                Err(SendError::Full(msg)) => {
+                    // SAFETY: see above.
+                    queue.push_front(unsafe { ErasedMsg::new(msg) });
+                    break;
+                }
+                Err(SendError::Disconnected(_)) => {
+                    // The actor stopped, no one is going to receive the
+                    // messages.
+                    queue.clear();
+                    break;
+                }
+            }
+        }
+        self.queued.store(queue.len(), Ordering::Release);
+
+        // Wake the senders waiting for space in the queue, see `Overflow::send`.
+        if let OverflowPolicy::Grow(limit) = self.policy {
+            if queue.len() < limit {
+                for waker in lock(&self.blocked).drain(..) {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<ErasedMsg>> {
+        lock(&self.queue)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        // The queue and wakers are always in a valid state, so we can ignore
+        // the poisoning.
+        Err(err) => err.into_inner(),
+    }
+}
+
+/// Type-erased, heap allocated message.
+///
+/// # Invariant
+///
+/// The messages are stored in the [`Overflow`] queue of an [`InboxState`],
+/// which is always `Send` and `Sync`, regardless of the message type. However
+/// an inbox (and thus its `InboxState`) is only used with a single message
+/// type `M` and the queue is only accessed using the `ActorRef<M>` and
+/// `actor::Context<M>` of that inbox, which are only `Send` if `M` is `Send`.
+/// Thus a message is never send to, or dropped on, another thread unless its
+/// type allows it. [`ErasedMsg::new`] is `unsafe` to uphold this.
+struct ErasedMsg {
+    ptr: NonNull<()>,
+    /// Drops the message pointed to by `ptr`.
+    drop: unsafe fn(NonNull<()>),
+}
+
+// SAFETY: see the invariant on `ErasedMsg`.
+unsafe impl Send for ErasedMsg {}
+unsafe impl Sync for ErasedMsg {}
+
+impl ErasedMsg {
+    /// # Safety
+    ///
+    /// The message must only be stored in the queue of an inbox with message
+    /// type `M`, see the invariant on [`ErasedMsg`].
+    unsafe fn new<M>(msg: M) -> ErasedMsg {
+        ErasedMsg {
+            // SAFETY: `Box::into_raw` never returns a null pointer.
+            ptr: unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(msg))) }.cast(),
+            drop: drop_msg::<M>,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `M` must be the same type as used in [`ErasedMsg::new`].
+    unsafe fn into_inner<M>(self) -> M {
+        let this = ManuallyDrop::new(self);
+        *Box::from_raw(this.ptr.cast::<M>().as_ptr())
+    }
+}
+
+/// # Safety
+///
+/// `ptr` must be created by [`ErasedMsg::new`] using type `M`.
+unsafe fn drop_msg<M>(ptr: NonNull<()>) {
+    drop(Box::from_raw(ptr.cast::<M>().as_ptr()));
+}
+
+impl Drop for ErasedMsg {
+    fn drop(&mut self) {
+        // SAFETY: `drop` is created for the type of the message in `new`.
+        unsafe { (self.drop)(self.ptr) }
+    }
+}
+
+impl fmt::Debug for ErasedMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErasedMsg")
+    }
+}
@@ -16,6 +16,10 @@
 //!
 //! [`from_message`]: crate::from_message
 //!
+//! RPC is also supported for a group of actors using [`ActorGroup::rpc`], which
+//! sends the request to all actors in the group and collects their responses
+//! (see [`Collect`]) in a [`GroupRpc`] future.
+//!
 //! # Examples
 //!
 //! Using RPC to communicate with another actor.
@@ -158,13 +162,16 @@
 
 use std::error::Error;
 use std::fmt;
-use std::future::Future;
+use std::future::{pending, Future, Pending};
+use std::mem::take;
 use std::pin::Pin;
 use std::task::{self, Poll};
 
 use heph_inbox::oneshot::{new_oneshot, RecvOnce, Sender};
 
-use crate::actor_ref::{ActorRef, SendError, SendValue};
+use crate::actor_ref::{
+    dead_letter, ActorGroup, ActorRef, DeadLetter, DeadLetterReason, SendError, SendValue,
+};
 
 /// [`Future`] that resolves to a Remote Procedure Call (RPC) response.
 ///
@@ -183,7 +190,11 @@ impl<'r, M, Res> Rpc<'r, M, Res> {
         M: From<RpcMessage<Req, Res>>,
     {
         let (sender, receiver) = new_oneshot();
-        let response = RpcResponse { sender };
+        let dead_letters = actor_ref.inbox_state().dead_letters().cloned();
+        let response = RpcResponse {
+            sender,
+            dead_letters,
+        };
         let msg = RpcMessage { request, response };
         let send = actor_ref.send(msg);
         Rpc {
@@ -227,6 +238,12 @@ pub enum RpcError {
     SendError,
     /// Returned when the other side returned no response.
     NoResponse,
+    /// Returned by [`GroupRpc`] if the deadline passed before the actor
+    /// responded, see [`GroupRpc::with_deadline`].
+    TimedOut,
+    /// Returned by [`GroupRpc`] if enough responses were collected before the
+    /// actor responded, see [`Collect`].
+    Cancelled,
 }
 
 impl From<SendError> for RpcError {
@@ -240,6 +257,8 @@ impl fmt::Display for RpcError {
         match self {
             RpcError::SendError => SendError.fmt(f),
             RpcError::NoResponse => f.write_str("no RPC response"),
+            RpcError::TimedOut => f.write_str("RPC timed out"),
+            RpcError::Cancelled => f.write_str("RPC cancelled"),
         }
     }
 }
@@ -294,12 +313,19 @@ impl<Req, Res> RpcMessage<Req, Res> {
 #[derive(Debug)]
 pub struct RpcResponse<Res> {
     sender: Sender<Res>,
+    /// Actor to send a [`DeadLetter`] to if the response can't be delivered.
+    dead_letters: Option<ActorRef<DeadLetter>>,
 }
 
 impl<Res> RpcResponse<Res> {
     /// Respond to a RPC request.
     pub fn respond(self, response: Res) -> Result<(), SendError> {
-        self.sender.try_send(response).map_err(|_| SendError)
+        self.sender.try_send(response).map_err(|_| {
+            if let Some(dead_letters) = &self.dead_letters {
+                dead_letter::send::<Res>(dead_letters, None, None, DeadLetterReason::Disconnected);
+            }
+            SendError
+        })
     }
 
     /// Returns `false` if the receiving side is disconnected.
@@ -313,3 +339,167 @@ impl<Res> RpcResponse<Res> {
         self.sender.is_connected()
     }
 }
+
+/// What responses to collect in a [`GroupRpc`], see [`ActorGroup::rpc`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Collect {
+    /// Collect the responses from all actors in the group.
+    All,
+    /// Collect the first `n` responses, this includes errors.
+    First(usize),
+    /// Collect responses until the first successful response.
+    FirstOk,
+}
+
+impl Collect {
+    /// Returns `true` if enough responses are collected.
+    const fn is_done(self, received: usize, successful: usize, total: usize) -> bool {
+        let enough = match self {
+            Collect::All => false,
+            Collect::First(n) => received >= n,
+            Collect::FirstOk => successful >= 1,
+        };
+        enough || received >= total
+    }
+}
+
+/// [`Future`] behind [`ActorGroup::rpc`].
+///
+/// This resolves to the response of each actor in the group, in the same
+/// order as the actors in the group. If the actor didn't respond before the
+/// call completed the response is [`RpcError::Cancelled`] if enough responses
+/// were already collected (see [`Collect`]), or [`RpcError::TimedOut`] if the
+/// deadline passed (see [`GroupRpc::with_deadline`]).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct GroupRpc<'r, M, Res, D = Pending<()>> {
+    /// The RPCs to the actors in the group, `None` once the RPC completed.
+    ///
+    /// NOTE: the `Rpc`s are pinned in the allocation, they must not be moved.
+    rpcs: Box<[Option<Rpc<'r, M, Res>>]>,
+    /// Response of each actor, `None` if not (yet) received.
+    responses: Vec<Option<Result<Res, RpcError>>>,
+    collect: Collect,
+    /// Number of responses received.
+    received: usize,
+    /// Number of successful responses received.
+    successful: usize,
+    /// Deadline for the entire call.
+    deadline: D,
+}
+
+impl<'r, M, Res> GroupRpc<'r, M, Res> {
+    /// Create a new group RPC.
+    pub(super) fn new<Req>(
+        group: &'r ActorGroup<M>,
+        request: Req,
+        collect: Collect,
+    ) -> GroupRpc<'r, M, Res>
+    where
+        M: From<RpcMessage<Req, Res>>,
+        Req: Clone,
+    {
+        let rpcs = group
+            .actor_refs
+            .iter()
+            .map(|actor_ref| Some(Rpc::new(actor_ref, request.clone())))
+            .collect();
+        let responses = group.actor_refs.iter().map(|_| None).collect();
+        GroupRpc {
+            rpcs,
+            responses,
+            collect,
+            received: 0,
+            successful: 0,
+            deadline: pending(),
+        }
+    }
+
+    /// Set a deadline for the call.
+    ///
+    /// Once the `deadline` future completes the call completes with the
+    /// responses collected so far. For example using a `Timer` from the
+    /// runtime.
+    pub fn with_deadline<D>(self, deadline: D) -> GroupRpc<'r, M, Res, D>
+    where
+        D: Future,
+    {
+        GroupRpc {
+            rpcs: self.rpcs,
+            responses: self.responses,
+            collect: self.collect,
+            received: self.received,
+            successful: self.successful,
+            deadline,
+        }
+    }
+}
+
+impl<'r, M, Res, D> Future for GroupRpc<'r, M, Res, D>
+where
+    D: Future,
+{
+    type Output = Vec<Result<Res, RpcError>>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: we're not moving `deadline` and the `Rpc`s are pinned in
+        // their own allocation.
+        let this = unsafe { Pin::get_unchecked_mut(self) };
+        let total = this.rpcs.len();
+
+        let mut done = this.collect.is_done(this.received, this.successful, total);
+        if !done {
+            for (rpc, response) in this.rpcs.iter_mut().zip(this.responses.iter_mut()) {
+                let result = match rpc.as_mut() {
+                    // Safety: see above.
+                    Some(r) => match unsafe { Pin::new_unchecked(r) }.poll(ctx) {
+                        Poll::Ready(result) => result,
+                        Poll::Pending => continue,
+                    },
+                    None => continue,
+                };
+                *rpc = None;
+                this.received += 1;
+                if result.is_ok() {
+                    this.successful += 1;
+                }
+                *response = Some(result);
+
+                if this.collect.is_done(this.received, this.successful, total) {
+                    done = true;
+                    break;
+                }
+            }
+        }
+
+        let missing = if done {
+            RpcError::Cancelled
+        } else {
+            // Safety: see above.
+            let deadline = unsafe { Pin::new_unchecked(&mut this.deadline) };
+            if deadline.poll(ctx).is_pending() {
+                return Poll::Pending;
+            }
+            RpcError::TimedOut
+        };
+
+        // Cancel the remaining calls.
+        for rpc in this.rpcs.iter_mut() {
+            *rpc = None;
+        }
+        let responses = take(&mut this.responses)
+            .into_iter()
+            .map(|response| response.unwrap_or(Err(missing)))
+            .collect();
+        Poll::Ready(responses)
+    }
+}
+
+impl<'r, M, Res, D> fmt::Debug for GroupRpc<'r, M, Res, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GroupRpc")
+            .field("collect", &self.collect)
+            .field("received", &self.received)
+            .field("left", &(self.rpcs.len() - self.received))
+            .finish()
+    }
+}
@@ -95,6 +95,7 @@ pub mod quick_start;
 pub mod supervisor;
 #[cfg(any(test, feature = "test"))]
 pub mod test;
+mod timer;
 
 #[doc(no_inline)]
 pub use actor::{Actor, NewActor};
@@ -102,3 +103,15 @@ pub use actor::{Actor, NewActor};
 pub use actor_ref::ActorRef;
 #[doc(no_inline)]
 pub use supervisor::{Supervisor, SupervisorStrategy};
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Returns a random number, good enough for jitter and load balancing but not
+/// for anything security related.
+pub(crate) fn random() -> u64 {
+    // `RandomState` is seeded with random keys (which are incremented each
+    // time `RandomState::new` is called), hashing nothing gives us a random
+    // number without requiring a dependency.
+    RandomState::new().build_hasher().finish()
+}
@@ -29,8 +29,15 @@
 //! heph-rt. In those cases the supervisor should still log the error
 //! encountered.
 //!
+//! When an actor fails because of an external resource, e.g. a database that
+//! is down, restarting it immediately will often lead to the same error. In
+//! those cases the actor can be [restarted after a delay], giving the resource
+//! time to recover. The [`Backoff`] type can be used to calculate increasing
+//! delays between restarts.
+//!
 //! [stopped]: crate::supervisor::SupervisorStrategy::Stop
 //! [restarted]: crate::supervisor::SupervisorStrategy::Restart
+//! [restarted after a delay]: crate::supervisor::SupervisorStrategy::RestartAfter
 //!
 //! # Actors and sync actors
 //!
@@ -53,6 +60,13 @@
 //! Second, the [`restart_supervisor!`] macro, which can be used to easily
 //! create a supervisor implementation that restarts the actor.
 //!
+//! # Supervision trees
+//!
+//! The supervisors described above only decide about a single actor. When a
+//! group of actors depend on each other a [`SupervisorTree`] can be used to
+//! restart the entire group, or part of it, when one of them fails. See the
+//! [`tree`] module for more information.
+//!
 //! # Examples
 //!
 //! Supervisor that logs the errors of a badly behaving actor and stops it.
@@ -96,12 +110,19 @@
 
 use std::any::Any;
 use std::fmt;
+use std::task::{self, Poll};
+use std::time::Duration;
 
 use log::warn;
 
 use crate::actor::SyncActor;
 use crate::actor::{Actor, NewActor};
 
+pub mod tree;
+
+#[doc(no_inline)]
+pub use tree::{ChildSupervisor, RestartStrategy, SupervisorTree};
+
 /// The supervisor of an [actor].
 ///
 /// For more information about supervisors see the [module documentation], here
@@ -190,6 +211,27 @@ where
         drop(panic);
         SupervisorStrategy::Stop
     }
+
+    /// Poll for a decision about the actor that wasn't caused by the actor
+    /// itself.
+    ///
+    /// Supervisors that are part of a [supervision tree] can decide to restart,
+    /// or stop, the actor because one of its siblings failed. The runtime calls
+    /// this method before running the actor, if this returns a strategy the
+    /// runtime will apply it before running the actor. If this returns
+    /// [`Poll::Pending`] the supervisor must ensure the actor is woken, using
+    /// the waker in `ctx`, once a decision is made.
+    ///
+    /// # Default
+    ///
+    /// By default this always returns [`Poll::Pending`] as supervisors outside
+    /// of a supervision tree only make decisions when the actor fails.
+    ///
+    /// [supervision tree]: crate::supervisor::tree
+    fn poll_tree(&mut self, ctx: &mut task::Context<'_>) -> Poll<SupervisorStrategy<NA::Argument>> {
+        let _ = ctx;
+        Poll::Pending
+    }
 }
 
 impl<F, NA> Supervisor<NA> for F
@@ -222,10 +264,116 @@ where
 pub enum SupervisorStrategy<Arg> {
     /// Restart the actor with the provided argument `Arg`.
     Restart(Arg),
+    /// Restart the actor with the provided argument `Arg` after the provided
+    /// delay.
+    ///
+    /// While waiting for the delay to pass the actor isn't running, but
+    /// messages can still be send to it. See [`Backoff`] to calculate the
+    /// delay.
+    RestartAfter(Arg, Duration),
     /// Stop the actor.
     Stop,
 }
 
+/// Exponential backoff for restarting actors.
+///
+/// Calculates the delay before restarting an actor, to be used in
+/// [`SupervisorStrategy::RestartAfter`]. The first restart is delayed by the
+/// initial delay, each following restart by the previous delay times the
+/// multiplier, up to the maximum delay. To prevent a group of actors
+/// restarting at the same time (e.g. after a shared resource failed) a random
+/// jitter can be applied to the delay.
+///
+/// The [`restart_supervisor!`] macro can use this type to delay restarts.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::supervisor::Backoff;
+///
+/// // Start with a delay of 100 milliseconds, doubling it each restart up to
+/// // 10 seconds, without jitter.
+/// let backoff = Backoff::new(Duration::from_millis(100), 2.0, Duration::from_secs(10), 0.0);
+///
+/// assert_eq!(backoff.delay(0), Duration::from_millis(100));
+/// assert_eq!(backoff.delay(1), Duration::from_millis(200));
+/// assert_eq!(backoff.delay(2), Duration::from_millis(400));
+/// assert_eq!(backoff.delay(10), Duration::from_secs(10));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Backoff {
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter: f64,
+}
+
+impl Backoff {
+    /// Create a new `Backoff`.
+    ///
+    /// Arguments:
+    ///  * `initial_delay`: delay before the first restart.
+    ///  * `multiplier`: the delay is multiplied by this each restart, should be
+    ///    at least `1.0`.
+    ///  * `max_delay`: maximum delay between restarts, jitter included.
+    ///  * `jitter`: fraction of the delay that is randomly added or removed,
+    ///    e.g. `0.1` means the delay is changed by up to 10%. Should be between
+    ///    `0.0` (no jitter) and `1.0`.
+    pub const fn new(
+        initial_delay: Duration,
+        multiplier: f64,
+        max_delay: Duration,
+        jitter: f64,
+    ) -> Backoff {
+        Backoff {
+            initial_delay,
+            multiplier,
+            max_delay,
+            jitter,
+        }
+    }
+
+    /// Returns the initial delay.
+    pub const fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Returns the multiplier.
+    pub const fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Returns the maximum delay.
+    pub const fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Returns the jitter.
+    pub const fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    /// Returns the delay for restart `attempt`, starting at zero.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        // NOTE: we calculate in nanoseconds to avoid rounding errors.
+        let max = self.max_delay.as_nanos() as f64;
+        let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+        let mut delay = self.initial_delay.as_nanos() as f64 * self.multiplier.powi(exponent);
+        if self.jitter > 0.0 {
+            // Random number in the range -1.0..=1.0.
+            let random = (crate::random() as f64 / u64::MAX as f64).mul_add(2.0, -1.0);
+            delay += delay * self.jitter.min(1.0) * random;
+        }
+        if delay.is_nan() || delay > max {
+            delay = max;
+        }
+        // NOTE: `as` casts saturate, so this can't overflow.
+        Duration::from_nanos(delay.round() as u64)
+    }
+}
+
 /// Supervisor for [synchronous actors].
 ///
 /// For more information about supervisors see the [module documentation], here
@@ -418,6 +566,12 @@ where
 /// * Maximum number of restarts (*optional*), defaults to 5.
 /// * Maximum duration before the restart counter get reset (*optional*),
 ///   defaults to 5 seconds.
+/// * Backoff (*optional*), in the form `backoff(initial_delay, multiplier,
+///   max_delay, jitter)`. If provided the actor is restarted after a delay
+///   rather than immediately, see [`Backoff`] for the meaning of the arguments.
+///   The delay is based on the number of restarts within the maximum duration,
+///   i.e. it's reset together with the restart counter. Can only be used if the
+///   maximum number of restarts and duration are provided.
 /// * Additional logging message, defaults to nothing extra. This uses normal
 ///   [rust formatting rules] and is added at the end of the default message,
 ///   after the error. The `args` keyword gives access to the arguments. See
@@ -447,6 +601,14 @@ where
 /// my_actor failed, stopping it (no restarts left): some I/O error: actor arguments (true, 0): (true, 0)
 /// ```
 ///
+/// If a backoff is used the delay is logged as well.
+///
+/// ```text
+/// $actor_name failed, restarting it in $delay ($left/$max restarts left): ${error}$log_extra
+/// # For example, using the supervisor created in the backoff example below.
+/// my_actor failed, restarting it in 100ms (2/3 restarts left): some I/O error
+/// ```
+///
 /// Similar messages will be logged if the actor fails to restart.
 ///
 /// # Examples
@@ -481,48 +643,97 @@ where
 /// let supervisor = MySupervisor::new(true, 23);
 /// # drop(supervisor);
 /// ```
+///
+/// Using a backoff to delay restarts, useful when the actor depends on an
+/// external resource that might need some time to recover.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::restart_supervisor;
+///
+/// restart_supervisor!(
+///     MySupervisor,
+///     "my actor",
+///     (),
+///     3,
+///     Duration::from_secs(60),
+///     // Start with a delay of 100 milliseconds, doubling it on each restart,
+///     // up to a maximum of 5 seconds, applying a jitter of 10%.
+///     backoff(Duration::from_millis(100), 2.0, Duration::from_secs(5), 0.1),
+/// );
+///
+/// assert!(MySupervisor::BACKOFF.is_some());
+/// # drop(MySupervisor::new());
+/// ```
 #[macro_export]
 macro_rules! restart_supervisor {
     // No non-optional arguments, unit `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), 5, std::time::Duration::from_secs(5), "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), 5, std::time::Duration::from_secs(5), std::option::Option::None, "",);
     };
     ($vis: vis $supervisor_name: ident, $actor_name: expr, () $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), 5, std::time::Duration::from_secs(5), "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), 5, std::time::Duration::from_secs(5), std::option::Option::None, "",);
     };
     // No non-optional arguments, tuple `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, ( $( $arg: ty),* ) $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), 5, std::time::Duration::from_secs(5), "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), 5, std::time::Duration::from_secs(5), std::option::Option::None, "",);
     };
     // No non-optional arguments, single `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, $arg: ty $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), 5, std::time::Duration::from_secs(5), "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), 5, std::time::Duration::from_secs(5), std::option::Option::None, "",);
     };
 
     // No log extra, unit `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, (), $max_restarts: expr, $max_duration: expr $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, std::option::Option::None, "",);
     };
     // No log extra, tuple `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, ( $( $arg: ty ),* ), $max_restarts: expr, $max_duration: expr $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, std::option::Option::None, "",);
     };
     // No log extra, single `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, $arg: ty, $max_restarts: expr, $max_duration: expr $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, "",);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, std::option::Option::None, "",);
+    };
+
+    // Backoff, no log extra, unit `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, (), $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?) $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), "",);
+    };
+    // Backoff, no log extra, tuple `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, ( $( $arg: ty ),* ), $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?) $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), "",);
+    };
+    // Backoff, no log extra, single `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, $arg: ty, $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?) $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), "",);
+    };
+
+    // Backoff and log extra, unit `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, (), $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?), $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), $log_extra, $( args $(. $log_arg_field )* ),*);
+    };
+    // Backoff and log extra, tuple `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, ( $( $arg: ty ),* ), $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?), $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), $log_extra, $( args $(. $log_arg_field )* ),*);
+    };
+    // Backoff and log extra, single `NewActor::Argument`.
+    ($vis: vis $supervisor_name: ident, $actor_name: expr, $arg: ty, $max_restarts: expr, $max_duration: expr, backoff($initial_delay: expr, $multiplier: expr, $max_delay: expr, $jitter: expr $(,)?), $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, std::option::Option::Some($crate::supervisor::Backoff::new($initial_delay, $multiplier, $max_delay, $jitter)), $log_extra, $( args $(. $log_arg_field )* ),*);
     };
 
     // All arguments, unit `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, (), $max_restarts: expr, $max_duration: expr, $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, $log_extra, $( args $(. $log_arg_field )* ),*);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, (), $max_restarts, $max_duration, std::option::Option::None, $log_extra, $( args $(. $log_arg_field )* ),*);
     };
     // All arguments, tuple `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, ( $( $arg: ty ),* ), $max_restarts: expr, $max_duration: expr, $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, $log_extra, $( args $(. $log_arg_field )* ),*);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $( $arg ),* ), $max_restarts, $max_duration, std::option::Option::None, $log_extra, $( args $(. $log_arg_field )* ),*);
     };
     // All arguments, single `NewActor::Argument`.
     ($vis: vis $supervisor_name: ident, $actor_name: expr, $arg: ty, $max_restarts: expr, $max_duration: expr, $log_extra: expr, $( args $(. $log_arg_field: tt )* ),* $(,)?) => {
-        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, $log_extra, $( args $(. $log_arg_field )* ),*);
+        $crate::__heph_restart_supervisor_impl!($vis $supervisor_name, $actor_name, ( $arg ), $max_restarts, $max_duration, std::option::Option::None, $log_extra, $( args $(. $log_arg_field )* ),*);
     };
 }
 
@@ -537,6 +748,7 @@ macro_rules! __heph_restart_supervisor_impl {
         ( $( $arg: ty ),* ),
         $max_restarts: expr,
         $max_duration: expr,
+        $backoff: expr,
         $log_extra: expr,
         $( args $(. $log_arg_field: tt )* ),*
         $(,)?
@@ -572,7 +784,21 @@ macro_rules! __heph_restart_supervisor_impl {
             /// [`MAX_RESTARTS`]: Self::MAX_RESTARTS
             $vis const MAX_DURATION: std::time::Duration = $max_duration;
 
+            /// Backoff used to delay restarting the actor, if any.
+            $vis const BACKOFF: std::option::Option<$crate::supervisor::Backoff> = $backoff;
+
             $crate::__heph_restart_supervisor_impl!(impl_new $vis $supervisor_name, ( $( $arg ),* ));
+
+            /// Returns the delay before restarting the actor, based on
+            /// [`Self::BACKOFF`] and the number of restarts used.
+            fn restart_delay(&self) -> std::option::Option<std::time::Duration> {
+                let attempt = Self::MAX_RESTARTS - self.restarts_left - 1;
+                let attempt = std::primitive::u32::try_from(attempt).unwrap_or(std::primitive::u32::MAX);
+                match Self::BACKOFF {
+                    std::option::Option::Some(backoff) => std::option::Option::Some(backoff.delay(attempt)),
+                    std::option::Option::None => std::option::Option::None,
+                }
+            }
         }
 
         impl<NA> $crate::supervisor::Supervisor<NA> for $supervisor_name
@@ -590,6 +816,13 @@ macro_rules! __heph_restart_supervisor_impl {
 
                 if self.restarts_left >= 1 {
                     self.restarts_left -= 1;
+                    if let std::option::Option::Some(delay) = self.restart_delay() {
+                        ::log::warn!(
+                            std::concat!($actor_name, " actor failed to restart, trying again in {:?} ({}/{} restarts left): {}", $log_extra),
+                            delay, self.restarts_left, $max_restarts, err, $( self.args $(. $log_arg_field )* ),*
+                        );
+                        return $crate::SupervisorStrategy::RestartAfter(self.args.clone(), delay);
+                    }
                     ::log::warn!(
                         std::concat!($actor_name, " actor failed to restart, trying again ({}/{} restarts left): {}", $log_extra),
                         self.restarts_left, $max_restarts, err, $( self.args $(. $log_arg_field )* ),*
@@ -648,6 +881,13 @@ macro_rules! __heph_restart_supervisor_impl {
 
         if $self.restarts_left >= 1 {
             $self.restarts_left -= 1;
+            if let std::option::Option::Some(delay) = $self.restart_delay() {
+                ::log::warn!(
+                    std::concat!($actor_name, " failed, restarting it in {:?} ({}/{} restarts left): {}", $log_extra),
+                    delay, $self.restarts_left, $max_restarts, $err, $( $self.args $(. $log_arg_field )* ),*
+                );
+                return $crate::SupervisorStrategy::RestartAfter($self.args.clone(), delay);
+            }
             ::log::warn!(
                 std::concat!($actor_name, " failed, restarting it ({}/{} restarts left): {}", $log_extra),
                 $self.restarts_left, $max_restarts, $err, $( $self.args $(. $log_arg_field )* ),*
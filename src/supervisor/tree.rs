@@ -0,0 +1,593 @@
+//! Supervision trees.
+//!
+//! A [`SupervisorTree`] supervises a group of children, which can be actors or
+//! other (nested) supervision trees. When one of the children fails the tree
+//! decides, based on its [`RestartStrategy`], which other children are
+//! restarted along with it:
+//!
+//! * [`RestartStrategy::OneForOne`]: only the failed child is restarted.
+//! * [`RestartStrategy::OneForAll`]: all children are restarted.
+//! * [`RestartStrategy::RestForOne`]: the failed child and all children added
+//!   to the tree after it are restarted.
+//!
+//! Actors are added to a tree using a [`ChildSupervisor`], created by
+//! [`SupervisorTree::child`]. It wraps a regular [`Supervisor`], which still
+//! decides whether or not the actor should be restarted after an error. Only if
+//! that supervisor decides to restart the actor the tree gets involved. The
+//! runtime (heph-rt) provides `Spawn::spawn_child` to spawn an actor as child
+//! of a tree in a single call.
+//!
+//! # Restart limits
+//!
+//! Each tree has a restart limit: a maximum number of restarts within a
+//! duration, much like the [`restart_supervisor!`] macro. Once one of the
+//! children fails more often than that the tree gives up and escalates the
+//! failure to its parent tree, which handles it as if the (nested) tree itself
+//! failed. If the parent decides to restart the tree all children of the tree
+//! are restarted. If the tree has no parent, or the parent gives up as well,
+//! all children of the tree are stopped.
+//!
+//! [`restart_supervisor!`]: crate::restart_supervisor
+//!
+//! # Notes
+//!
+//! Only asynchronous actors can be part of a supervision tree, synchronous
+//! actors are not supported.
+//!
+//! # Examples
+//!
+//! Two actors that depend on each other, if one fails both are restarted.
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::time::Duration;
+//!
+//! use heph::actor;
+//! use heph::supervisor::{RestartStrategy, SupervisorStrategy, SupervisorTree};
+//! use heph_rt::spawn::{ActorOptions, Spawn};
+//! use heph_rt::{self as rt, Runtime, ThreadLocal};
+//!
+//! fn main() -> Result<(), rt::Error> {
+//!     let mut runtime = Runtime::new()?;
+//!     runtime.run_on_workers(|mut runtime_ref| -> Result<(), !> {
+//!         // Restart both actors if one of them fails, at most 3 times
+//!         // within a minute.
+//!         let tree = SupervisorTree::new(
+//!             "connection",
+//!             RestartStrategy::OneForAll,
+//!             3,
+//!             Duration::from_secs(60),
+//!         );
+//!         let reader = reader as fn(_) -> _;
+//!         let writer = writer as fn(_) -> _;
+//!         runtime_ref.spawn_child(&tree, supervisor, reader, (), ActorOptions::default());
+//!         runtime_ref.spawn_child(&tree, supervisor, writer, (), ActorOptions::default());
+//!         Ok(())
+//!     })?;
+//!     runtime.start()
+//! }
+//!
+//! /// The error returned by our actors.
+//! struct Error;
+//!
+//! /// Supervisor for both actors, always restarting the actor. The tree
+//! /// limits the number of restarts.
+//! fn supervisor(err: Error) -> SupervisorStrategy<()> {
+//! #   drop(err); // Silence dead code warnings.
+//!     SupervisorStrategy::Restart(())
+//! }
+//!
+//! async fn reader(_: actor::Context<!, ThreadLocal>) -> Result<(), Error> {
+//!     // Read stuff...
+//!     Ok(())
+//! }
+//!
+//! async fn writer(_: actor::Context<!, ThreadLocal>) -> Result<(), Error> {
+//!     // Write stuff...
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{self, Poll};
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+use crate::actor::{Actor, NewActor};
+use crate::supervisor::{Supervisor, SupervisorStrategy};
+
+/// The strategy a [`SupervisorTree`] uses to restart its children.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::supervisor::tree
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RestartStrategy {
+    /// Only restart the failed child.
+    OneForOne,
+    /// Restart all children of the tree when one of them fails.
+    OneForAll,
+    /// Restart the failed child and all children added to the tree after it.
+    RestForOne,
+}
+
+/// A supervision tree.
+///
+/// See the [module documentation] for more information.
+///
+/// Cloning a `SupervisorTree` is cheap, all clones refer to the same tree.
+///
+/// [module documentation]: crate::supervisor::tree
+#[derive(Clone)]
+pub struct SupervisorTree {
+    inner: Arc<Inner>,
+}
+
+/// Internals of the [`SupervisorTree`].
+struct Inner {
+    /// Log friendly name of the tree.
+    name: &'static str,
+    strategy: RestartStrategy,
+    /// Maximum number of restarts within `max_duration`.
+    max_restarts: usize,
+    max_duration: Duration,
+    /// Parent tree and the index of this tree in the parent's children.
+    parent: Option<(SupervisorTree, usize)>,
+    state: Mutex<State>,
+}
+
+/// Mutable state of the [`SupervisorTree`].
+struct State {
+    /// All children of the tree, in the order they were added. Removed
+    /// children are kept (as [`Child::Removed`]) to keep the indices stable.
+    children: Vec<Child>,
+    /// The number of restarts left.
+    restarts_left: usize,
+    /// Time of the last restart.
+    last_restart: Option<Instant>,
+}
+
+/// A child of a [`SupervisorTree`].
+enum Child {
+    /// Actor supervised by a [`ChildSupervisor`].
+    Actor {
+        /// Request made by the tree, not yet seen by the actor.
+        request: Option<Request>,
+        /// Waker to wake the actor once a request is made.
+        waker: Option<task::Waker>,
+    },
+    /// Nested supervision tree.
+    Tree(Weak<Inner>),
+    /// Child was removed, e.g. the actor stopped.
+    Removed,
+}
+
+/// Request made by a [`SupervisorTree`] for one of its children.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Request {
+    Restart,
+    Stop,
+}
+
+impl SupervisorTree {
+    /// Create a new top-level supervision tree.
+    ///
+    /// The tree restarts its children using `strategy`, at most `max_restarts`
+    /// times within `max_duration`. See the [module documentation] for more
+    /// information.
+    ///
+    /// [module documentation]: crate::supervisor::tree
+    pub fn new(
+        name: &'static str,
+        strategy: RestartStrategy,
+        max_restarts: usize,
+        max_duration: Duration,
+    ) -> SupervisorTree {
+        SupervisorTree::new_tree(name, strategy, max_restarts, max_duration, None)
+    }
+
+    /// Create a new supervision tree nested inside of this tree.
+    ///
+    /// The returned tree is a child of this tree. If the returned tree exceeds
+    /// its restart limit the failure is escalated to this tree.
+    pub fn subtree(
+        &self,
+        name: &'static str,
+        strategy: RestartStrategy,
+        max_restarts: usize,
+        max_duration: Duration,
+    ) -> SupervisorTree {
+        let mut state = self.lock();
+        let index = state.children.len();
+        let parent = Some((self.clone(), index));
+        let tree = SupervisorTree::new_tree(name, strategy, max_restarts, max_duration, parent);
+        state
+            .children
+            .push(Child::Tree(Arc::downgrade(&tree.inner)));
+        tree
+    }
+
+    fn new_tree(
+        name: &'static str,
+        strategy: RestartStrategy,
+        max_restarts: usize,
+        max_duration: Duration,
+        parent: Option<(SupervisorTree, usize)>,
+    ) -> SupervisorTree {
+        SupervisorTree {
+            inner: Arc::new(Inner {
+                name,
+                strategy,
+                max_restarts,
+                max_duration,
+                parent,
+                state: Mutex::new(State {
+                    children: Vec::new(),
+                    restarts_left: max_restarts,
+                    last_restart: None,
+                }),
+            }),
+        }
+    }
+
+    /// Create a new [`ChildSupervisor`] for an actor that is part of this
+    /// tree.
+    ///
+    /// The `supervisor` decides whether or not the actor is restarted after an
+    /// error, see [`ChildSupervisor`]. The `arg`ument is used to restart the
+    /// actor when the tree restarts it, e.g. because a sibling failed. It must
+    /// be the same argument as used to spawn the actor.
+    pub fn child<S, Arg>(&self, supervisor: S, arg: Arg) -> ChildSupervisor<S, Arg> {
+        let mut state = self.lock();
+        let index = state.children.len();
+        state.children.push(Child::Actor {
+            request: None,
+            waker: None,
+        });
+        ChildSupervisor {
+            tree: self.clone(),
+            index,
+            supervisor,
+            arg,
+        }
+    }
+
+    /// Returns the name of the tree.
+    pub fn name(&self) -> &'static str {
+        self.inner.name
+    }
+
+    /// Returns the restart strategy of the tree.
+    pub fn strategy(&self) -> RestartStrategy {
+        self.inner.strategy
+    }
+
+    /// Returns the number of children (actors and nested trees) in the tree.
+    pub fn len(&self) -> usize {
+        self.lock().children.iter().filter(|c| c.is_alive()).count()
+    }
+
+    /// Returns `true` if the tree has no children.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stop all children of the tree, including the children of nested trees.
+    pub fn stop(&self) {
+        self.request_all(Request::Stop, None);
+    }
+
+    /// Called when the child at `index` failed and its supervisor wants to
+    /// restart it. Returns `true` if the child should be restarted, `false` if
+    /// it should be stopped.
+    fn child_failed(&self, index: usize) -> bool {
+        let mut state = self.lock();
+        if !state.try_restart(self.inner.max_restarts, self.inner.max_duration) {
+            drop(state);
+            return self.give_up(index);
+        }
+
+        let trees = match self.inner.strategy {
+            RestartStrategy::OneForOne => Vec::new(),
+            RestartStrategy::OneForAll => state.request(Request::Restart, 0, Some(index)),
+            RestartStrategy::RestForOne => state.request(Request::Restart, index + 1, None),
+        };
+        drop(state);
+        for tree in trees {
+            tree.restart_all(None);
+        }
+        true
+    }
+
+    /// Handle the tree reaching its restart limit, caused by the child at
+    /// `index`. Returns `true` if the child should be restarted (because the
+    /// parent tree restarted this tree), `false` otherwise.
+    fn give_up(&self, index: usize) -> bool {
+        match &self.inner.parent {
+            Some((parent, parent_index)) => {
+                warn!(
+                    "supervision tree '{}' reached its restart limit, escalating to tree '{}'",
+                    self.inner.name, parent.inner.name
+                );
+                if parent.child_failed(*parent_index) {
+                    self.restart_all(Some(index));
+                    true
+                } else {
+                    // NOTE: the parent stopped its other children, but not
+                    // this tree as it caused the failure.
+                    self.request_all(Request::Stop, Some(index));
+                    false
+                }
+            }
+            None => {
+                error!(
+                    "supervision tree '{}' reached its restart limit, stopping all children",
+                    self.inner.name
+                );
+                self.request_all(Request::Stop, Some(index));
+                false
+            }
+        }
+    }
+
+    /// Restart all children, except the child at index `except`, resetting the
+    /// restart limit.
+    fn restart_all(&self, except: Option<usize>) {
+        let mut state = self.lock();
+        state.restarts_left = self.inner.max_restarts;
+        state.last_restart = None;
+        drop(state);
+        self.request_all(Request::Restart, except);
+    }
+
+    /// Make `request` for all children, except the child at index `except`.
+    fn request_all(&self, request: Request, except: Option<usize>) {
+        let trees = self.lock().request(request, 0, except);
+        for tree in trees {
+            match request {
+                Request::Restart => tree.restart_all(None),
+                Request::Stop => tree.request_all(Request::Stop, None),
+            }
+        }
+    }
+
+    /// Poll the request for the actor at `index`.
+    fn poll_request(&self, index: usize, waker: &task::Waker) -> Poll<Request> {
+        let mut state = self.lock();
+        match state.children.get_mut(index) {
+            Some(Child::Actor {
+                request,
+                waker: actor_waker,
+            }) => match request.take() {
+                Some(request) => Poll::Ready(request),
+                None => {
+                    match actor_waker {
+                        Some(actor_waker) if actor_waker.will_wake(waker) => {}
+                        _ => *actor_waker = Some(waker.clone()),
+                    }
+                    Poll::Pending
+                }
+            },
+            // Removed actors can't be restarted.
+            Some(Child::Tree(..) | Child::Removed) | None => Poll::Ready(Request::Stop),
+        }
+    }
+
+    /// Remove the child at `index`.
+    fn remove_child(&self, index: usize) {
+        if let Some(child) = self.lock().children.get_mut(index) {
+            *child = Child::Removed;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().unwrap()
+    }
+}
+
+impl fmt::Debug for SupervisorTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SupervisorTree")
+            .field("name", &self.inner.name)
+            .field("strategy", &self.inner.strategy)
+            .field("max_restarts", &self.inner.max_restarts)
+            .field("max_duration", &self.inner.max_duration)
+            .field("children", &self.len())
+            .finish()
+    }
+}
+
+impl State {
+    /// Returns `true` if a restart is allowed within the restart limit, which
+    /// counts as a restart.
+    fn try_restart(&mut self, max_restarts: usize, max_duration: Duration) -> bool {
+        let now = Instant::now();
+        let last_restart = self.last_restart.replace(now);
+
+        // If enough time has passed between the last restart and now we reset
+        // the `restarts_left` left counter.
+        if let Some(last_restart) = last_restart {
+            if now - last_restart > max_duration {
+                self.restarts_left = max_restarts;
+            }
+        }
+
+        if self.restarts_left >= 1 {
+            self.restarts_left -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Make `request` for all actors starting at index `start`, skipping the
+    /// child at index `except`. Returns all nested trees the request should be
+    /// made for, which must be done after unlocking the state.
+    fn request(
+        &mut self,
+        request: Request,
+        start: usize,
+        except: Option<usize>,
+    ) -> Vec<SupervisorTree> {
+        let mut trees = Vec::new();
+        for (index, child) in self.children.iter_mut().enumerate().skip(start) {
+            if Some(index) == except {
+                continue;
+            }
+            match child {
+                Child::Actor {
+                    request: actor_request,
+                    waker,
+                } => {
+                    // Stopping takes precedence over restarting.
+                    if *actor_request != Some(Request::Stop) {
+                        *actor_request = Some(request);
+                    }
+                    if let Some(waker) = waker.take() {
+                        waker.wake();
+                    }
+                }
+                Child::Tree(tree) => match tree.upgrade() {
+                    Some(inner) => trees.push(SupervisorTree { inner }),
+                    None => *child = Child::Removed,
+                },
+                Child::Removed => {}
+            }
+        }
+        trees
+    }
+}
+
+impl Child {
+    /// Returns `false` if the child is removed.
+    fn is_alive(&self) -> bool {
+        match self {
+            Child::Actor { .. } => true,
+            Child::Tree(tree) => tree.strong_count() != 0,
+            Child::Removed => false,
+        }
+    }
+}
+
+/// Supervisor for an actor that is part of a [`SupervisorTree`].
+///
+/// Created by [`SupervisorTree::child`].
+///
+/// The wrapped supervisor (`S`) decides whether or not the actor should be
+/// restarted after an error (or panic). If it decides to restart the actor the
+/// [`SupervisorTree`] is informed, which can decide to restart the actor's
+/// siblings as well, or to stop the actor if the tree's restart limit is
+/// reached.
+///
+/// When the tree decides to restart the actor, because one of its siblings
+/// failed, the actor is restarted using the last argument it was (re)started
+/// with (`Arg`).
+pub struct ChildSupervisor<S, Arg> {
+    tree: SupervisorTree,
+    /// Index of the actor in the tree's children.
+    index: usize,
+    supervisor: S,
+    /// Argument used to restart the actor.
+    arg: Arg,
+}
+
+impl<S, Arg> ChildSupervisor<S, Arg> {
+    /// Returns the tree the actor is part of.
+    pub fn tree(&self) -> &SupervisorTree {
+        &self.tree
+    }
+
+    /// Let the tree decide about the `strategy` of the wrapped supervisor.
+    fn tree_decide(&mut self, strategy: SupervisorStrategy<Arg>) -> SupervisorStrategy<Arg>
+    where
+        Arg: Clone,
+    {
+        match strategy {
+            SupervisorStrategy::Restart(arg) => {
+                if self.tree.child_failed(self.index) {
+                    self.arg = arg.clone();
+                    SupervisorStrategy::Restart(arg)
+                } else {
+                    SupervisorStrategy::Stop
+                }
+            }
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                if self.tree.child_failed(self.index) {
+                    self.arg = arg.clone();
+                    SupervisorStrategy::RestartAfter(arg, delay)
+                } else {
+                    SupervisorStrategy::Stop
+                }
+            }
+            SupervisorStrategy::Stop => SupervisorStrategy::Stop,
+        }
+    }
+}
+
+impl<S, NA> Supervisor<NA> for ChildSupervisor<S, NA::Argument>
+where
+    S: Supervisor<NA>,
+    NA: NewActor,
+    NA::Argument: Clone,
+{
+    fn decide(&mut self, err: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
+        let strategy = self.supervisor.decide(err);
+        self.tree_decide(strategy)
+    }
+
+    fn decide_on_restart_error(&mut self, err: NA::Error) -> SupervisorStrategy<NA::Argument> {
+        // NOTE: the tree already counted the restart.
+        let strategy = self.supervisor.decide_on_restart_error(err);
+        match &strategy {
+            SupervisorStrategy::Restart(arg) | SupervisorStrategy::RestartAfter(arg, _) => {
+                self.arg = arg.clone();
+            }
+            SupervisorStrategy::Stop => {}
+        }
+        strategy
+    }
+
+    fn second_restart_error(&mut self, err: NA::Error) {
+        self.supervisor.second_restart_error(err);
+    }
+
+    fn decide_on_panic(
+        &mut self,
+        panic: Box<dyn std::any::Any + Send + 'static>,
+    ) -> SupervisorStrategy<NA::Argument> {
+        let strategy = self.supervisor.decide_on_panic(panic);
+        self.tree_decide(strategy)
+    }
+
+    fn poll_tree(&mut self, ctx: &mut task::Context<'_>) -> Poll<SupervisorStrategy<NA::Argument>> {
+        self.tree
+            .poll_request(self.index, ctx.waker())
+            .map(|request| match request {
+                Request::Restart => SupervisorStrategy::Restart(self.arg.clone()),
+                Request::Stop => SupervisorStrategy::Stop,
+            })
+    }
+}
+
+impl<S, Arg> Drop for ChildSupervisor<S, Arg> {
+    fn drop(&mut self) {
+        // The actor is no longer running, so the tree can't restart it.
+        self.tree.remove_child(self.index);
+    }
+}
+
+impl<S, Arg> fmt::Debug for ChildSupervisor<S, Arg>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildSupervisor")
+            .field("tree", &self.tree.name())
+            .field("index", &self.index)
+            .field("supervisor", &self.supervisor)
+            .finish()
+    }
+}
@@ -1,5 +1,16 @@
 //! Testing facilities.
 //!
+//! Available utilities:
+//!  * [`probe`]: create an actor reference that records the messages it
+//!    receives, allowing assertions about them using [`Probe`].
+//!  * [`size_of_actor`], [`size_of_actor_val`]: returns the size of an actor.
+//!  * [`set_message_loss`]: set the percentage of messages lost on purpose.
+//!  * [`inject_faults`], [`inject_shared_faults`] and [`set_message_faults`]:
+//!    lose, duplicate, reorder or delay messages on purpose, see
+//!    [`MessageFaults`].
+//!  * [`PanicSupervisor`]: supervisor that panics when it receives an actor's
+//!    error.
+//!
 //! # Notes
 //!
 //! *This module is only available when the `test` feature is enabled*. It
@@ -14,14 +25,19 @@
 //! features = ["test"]
 //! ```
 
+use std::any::type_name;
 use std::mem::size_of;
 use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fmt, slice};
 
 use getrandom::getrandom;
+use heph_inbox::Receiver;
 use log::warn;
 
-use crate::actor::{self, Actor, NewActor};
+use crate::actor::{self, Actor, NewActor, Pending, ReceiveMessage, SyncWaker};
+use crate::actor_ref::{ActorRef, InboxState, OverflowPolicy};
 use crate::supervisor::{Supervisor, SupervisorStrategy};
 
 /// Percentage of messages lost on purpose.
@@ -64,6 +80,158 @@ fn random_percentage() -> u8 {
     }
 }
 
+/// Returns `true` with a chance of `percent`.
+#[doc(hidden)] // Shared with `heph_rt::test`, not part of the stable API.
+pub fn roll(percent: u8) -> bool {
+    percent != 0 && random_percentage() < percent
+}
+
+/// Faults to inject into the sending of messages.
+///
+/// All faults are set as a percentage of the messages send, a number between
+/// `0` (the default, disabling the fault) and `100`. Larger numbers are
+/// treated as `100`.
+///
+/// Faults are injected by actor references wrapped using [`inject_faults`],
+/// or using [`inject_shared_faults`] for the faults set using
+/// [`set_message_faults`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::test::{inject_faults, probe, MessageFaults};
+///
+/// let (actor_ref, mut probe) = probe::<String>();
+/// // Duplicate all messages.
+/// let actor_ref = inject_faults(actor_ref, MessageFaults::new().duplicate(100));
+///
+/// actor_ref.try_send("Hello").unwrap();
+/// assert_eq!(probe.expect_message(Duration::from_secs(1)), "Hello");
+/// assert_eq!(probe.expect_message(Duration::from_secs(1)), "Hello");
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageFaults {
+    pub(crate) loss: u8,
+    pub(crate) duplicate: u8,
+    pub(crate) reorder: u8,
+    pub(crate) delay: u8,
+    pub(crate) delay_duration: Duration,
+}
+
+impl MessageFaults {
+    /// Create a new configuration without any faults.
+    pub const fn new() -> MessageFaults {
+        MessageFaults {
+            loss: 0,
+            duplicate: 0,
+            reorder: 0,
+            delay: 0,
+            delay_duration: Duration::ZERO,
+        }
+    }
+
+    /// Set the percentage of messages lost.
+    ///
+    /// Same as [`set_message_loss`], but only for the wrapped actor reference.
+    pub const fn loss(mut self, percent: u8) -> MessageFaults {
+        self.loss = max_percent(percent);
+        self
+    }
+
+    /// Set the percentage of messages send twice.
+    pub const fn duplicate(mut self, percent: u8) -> MessageFaults {
+        self.duplicate = max_percent(percent);
+        self
+    }
+
+    /// Set the percentage of messages held back and send after the next
+    /// message, reordering the two messages.
+    ///
+    /// A held back message is send once the actor reference is dropped, if no
+    /// other message is send before that.
+    pub const fn reorder(mut self, percent: u8) -> MessageFaults {
+        self.reorder = max_percent(percent);
+        self
+    }
+
+    /// Set the percentage of messages delivered after `duration`.
+    ///
+    /// # Notes
+    ///
+    /// Delayed messages are delivered by a background thread, using the
+    /// system clock (not the clock of a test runtime). If the inbox of the
+    /// actor is full at the time of delivery the message is lost.
+    pub const fn delay(mut self, percent: u8, duration: Duration) -> MessageFaults {
+        self.delay = max_percent(percent);
+        self.delay_duration = duration;
+        self
+    }
+}
+
+/// Returns `percent`, capped at `100`.
+#[doc(hidden)] // Shared with `heph_rt::test`, not part of the stable API.
+pub const fn max_percent(percent: u8) -> u8 {
+    if percent > 100 {
+        100
+    } else {
+        percent
+    }
+}
+
+/// Faults used by actor references wrapped using [`inject_shared_faults`].
+static MSG_FAULTS: Mutex<MessageFaults> = Mutex::new(MessageFaults::new());
+
+/// Set the faults to inject for all actor references wrapped using
+/// [`inject_shared_faults`].
+///
+/// # Notes
+///
+/// Unlike [`set_message_loss`] this does *not* apply to all actor references,
+/// as duplicating and delaying messages requires the message to be [`Clone`]
+/// and [`Send`], which can't be required for all messages.
+pub fn set_message_faults(faults: MessageFaults) {
+    match MSG_FAULTS.lock() {
+        Ok(mut f) => *f = faults,
+        Err(err) => *err.into_inner() = faults,
+    }
+}
+
+/// Returns the faults set using [`set_message_faults`].
+pub(crate) fn message_faults() -> MessageFaults {
+    match MSG_FAULTS.lock() {
+        Ok(faults) => *faults,
+        Err(err) => *err.into_inner(),
+    }
+}
+
+/// Wrap `actor_ref` in an actor reference that injects `faults` when sending
+/// messages.
+///
+/// # Notes
+///
+/// Sending using [`ActorRef::send`] doesn't wait for space in the inbox, it
+/// returns an error if the inbox is full (just like [`ActorRef::try_send`]).
+pub fn inject_faults<M>(actor_ref: ActorRef<M>, faults: MessageFaults) -> ActorRef<M>
+where
+    M: Clone + Send + 'static,
+{
+    actor_ref.with_faults(Some(faults))
+}
+
+/// Same as [`inject_faults`], but uses the faults set using
+/// [`set_message_faults`] at the time of sending a message.
+///
+/// The faults are shared by all actor references wrapped using this function,
+/// they are *not* applied to other actor references.
+pub fn inject_shared_faults<M>(actor_ref: ActorRef<M>) -> ActorRef<M>
+where
+    M: Clone + Send + 'static,
+{
+    actor_ref.with_faults(None)
+}
+
 /// Returns the size of the actor.
 ///
 /// When using asynchronous function for actors see [`size_of_actor_val`].
@@ -91,7 +259,7 @@ where
 ///     }
 /// }
 ///
-/// assert_eq!(size_of_actor_val(&(actor as fn(_) -> _)), 88);
+/// assert_eq!(size_of_actor_val(&(actor as fn(_) -> _)), 120);
 /// ```
 pub const fn size_of_actor_val<NA>(_: &NA) -> usize
 where
@@ -100,6 +268,147 @@ where
     size_of_actor::<NA>()
 }
 
+/// Create a new probe.
+///
+/// Returns an actor reference and a [`Probe`]. All messages send using the
+/// actor reference (or its clones) are received by the probe, which can be used
+/// to make assertions about them. This can be used in place of an actor that
+/// only collects the messages it receives.
+///
+/// The actor reference can be send to both thread-local and thread-safe
+/// actors, in the latter case the message type `M` must be [`Send`]. The inbox
+/// of the probe has no capacity limit.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::test::probe;
+///
+/// let (actor_ref, mut probe) = probe::<String>();
+///
+/// // Normally the actor reference is given to an actor that is being tested.
+/// actor_ref.try_send("Hello").unwrap();
+/// actor_ref.try_send("World").unwrap();
+///
+/// assert_eq!(probe.expect_message(Duration::from_secs(1)), "Hello");
+/// probe.expect_matching(Duration::from_secs(1), |msg| msg.starts_with('W'));
+/// probe.expect_no_message(Duration::from_millis(10));
+/// assert_eq!(probe.received(), ["Hello", "World"]);
+/// ```
+pub fn probe<M>() -> (ActorRef<M>, Probe<M>) {
+    let (sender, inbox) = heph_inbox::new_small();
+    // NOTE: using `Grow` so that sending to the probe never fails.
+    let inbox_state = InboxState::new("probe", None, OverflowPolicy::Grow(usize::MAX));
+    let actor_ref = ActorRef::local(sender, inbox_state.clone());
+    let probe = Probe {
+        inbox,
+        inbox_state,
+        pending: Pending::new(),
+        received: Vec::new(),
+    };
+    (actor_ref, probe)
+}
+
+/// Probe that receives and records messages, see [`probe`].
+///
+/// All messages received by the probe are kept, see [`Probe::received`].
+#[derive(Debug)]
+pub struct Probe<M> {
+    inbox: Receiver<M>,
+    inbox_state: Arc<InboxState>,
+    /// Always empty, but required by [`ReceiveMessage`].
+    pending: Pending<M>,
+    /// All messages received so far.
+    received: Vec<M>,
+}
+
+impl<M> Probe<M> {
+    /// Expect a message to be received within `timeout`, returning it.
+    ///
+    /// # Panics
+    ///
+    /// This panics if no message is received within `timeout`, or if all actor
+    /// references to the probe are dropped without sending a message.
+    #[track_caller]
+    pub fn expect_message(&mut self, timeout: Duration) -> &M {
+        match self.receive(timeout) {
+            Some(Some(msg)) => msg,
+            Some(None) => panic!(
+                "expected a message of type '{}', but all actor references are dropped",
+                type_name::<M>()
+            ),
+            None => panic!(
+                "expected a message of type '{}' within {timeout:?}, but received none",
+                type_name::<M>()
+            ),
+        }
+    }
+
+    /// Expect no message to be received for `duration`.
+    ///
+    /// # Notes
+    ///
+    /// This blocks for `duration`, unless all actor references to the probe
+    /// are dropped.
+    ///
+    /// # Panics
+    ///
+    /// This panics if a message is received.
+    #[track_caller]
+    pub fn expect_no_message(&mut self, duration: Duration) {
+        if let Some(Some(_)) = self.receive(duration) {
+            panic!(
+                "expected no message of type '{}' within {duration:?}, but received one",
+                type_name::<M>()
+            );
+        }
+    }
+
+    /// Expect a message to be received within `timeout` for which `predicate`
+    /// returns `true`, returning it.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the received message doesn't match `predicate`, and for
+    /// the same reasons as [`Probe::expect_message`].
+    #[track_caller]
+    pub fn expect_matching<F>(&mut self, timeout: Duration, predicate: F) -> &M
+    where
+        F: FnOnce(&M) -> bool,
+    {
+        let msg = self.expect_message(timeout);
+        assert!(
+            predicate(msg),
+            "received message of type '{}' doesn't match the predicate",
+            type_name::<M>()
+        );
+        msg
+    }
+
+    /// Returns all messages received so far, including messages that are
+    /// ready to be received but not yet checked by one of the `expect_*`
+    /// methods.
+    pub fn received(&mut self) -> &[M] {
+        while let Some(Some(_)) = self.receive(Duration::ZERO) {}
+        &self.received
+    }
+
+    /// Receive a message within `timeout`. Returns `None` if no message was
+    /// received in time and `Some(None)` if all actor references are dropped.
+    fn receive(&mut self, timeout: Duration) -> Option<Option<&M>> {
+        let future = ReceiveMessage::new(&mut self.inbox, &self.inbox_state, &mut self.pending);
+        match SyncWaker::new().block_for(future, timeout)? {
+            Ok(msg) => {
+                self.received.push(msg);
+                Some(self.received.last())
+            }
+            Err(_) => Some(None),
+        }
+    }
+}
+
 /// Quick and dirty supervisor that panics whenever it receives an error.
 #[derive(Copy, Clone, Debug)]
 pub struct PanicSupervisor;
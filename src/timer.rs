@@ -0,0 +1,93 @@
+//! Module with a single, shared timer thread.
+//!
+//! Used by the parts of Heph that don't have access to a runtime, e.g.
+//! [`ActorFuture`] to delay restarts, and by the fault injection in the `test`
+//! module to delay messages.
+//!
+//! [`ActorFuture`]: crate::actor::ActorFuture
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Timers waiting to expire, see [`call_after`].
+static TIMERS: Mutex<Option<mpsc::Sender<Timer>>> = Mutex::new(None);
+
+/// Call `f` after `delay` on the timer thread.
+///
+/// Returns an error if the timer thread can't be spawned.
+pub(crate) fn call_after<F>(delay: Duration, f: F) -> io::Result<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let timer = Timer {
+        deadline: Instant::now() + delay,
+        f: Box::new(f),
+    };
+    let mut sender = match TIMERS.lock() {
+        Ok(sender) => sender,
+        Err(err) => err.into_inner(),
+    };
+    if sender.is_none() {
+        let (s, receiver) = mpsc::channel();
+        let _ = thread::Builder::new()
+            .name("heph-timer".to_owned())
+            .spawn(move || run_timers(&receiver))?;
+        *sender = Some(s);
+    }
+    // The thread never stops, so this can't fail.
+    let _ = sender.as_ref().unwrap().send(timer);
+    Ok(())
+}
+
+/// Runs the [`Timer`]s received on `receiver` once they're expired.
+fn run_timers(receiver: &mpsc::Receiver<Timer>) {
+    let mut timers = BinaryHeap::new();
+    loop {
+        let now = Instant::now();
+        while timers.peek().is_some_and(|t: &Timer| t.deadline <= now) {
+            (timers.pop().unwrap().f)();
+        }
+
+        let res = match timers.peek() {
+            Some(next) => receiver.recv_timeout(next.deadline - now),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match res {
+            Ok(timer) => timers.push(timer),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// Function called once the `deadline` has passed.
+struct Timer {
+    deadline: Instant,
+    f: Box<dyn FnOnce() + Send>,
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed to make the `BinaryHeap` a min-heap.
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
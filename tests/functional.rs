@@ -21,6 +21,7 @@ mod functional {
     mod actor_group;
     mod actor_ref;
     mod restart_supervisor;
+    mod supervisor_tree;
     mod sync_actor;
     mod test;
 }
@@ -1,13 +1,16 @@
 //! Tests related to `ActorRef`.
 
-use heph::actor_ref::{ActorRef, Join, RpcError, SendError, SendValue};
+use heph::actor::{spawn_sync_actor, SyncContext};
+use heph::actor_ref::{ActorRef, Down, DownReason, Join, RpcError, SendError, SendValue};
+use heph::supervisor::SupervisorStrategy;
+use heph::test::probe;
 
 use crate::util::{assert_send, assert_size, assert_sync};
 
 #[test]
 fn size() {
     assert_size::<ActorRef<()>>(24);
-    assert_size::<SendValue<'_, ()>>(40);
+    assert_size::<SendValue<'_, ()>>(48);
     assert_size::<Join<'_, ()>>(32);
 }
 
@@ -38,4 +41,132 @@ fn rpc_error_format() {
     assert_eq!(format!("{}", RpcError::SendError), "unable to send message");
     assert_eq!(format!("{}", RpcError::SendError), format!("{}", SendError));
     assert_eq!(format!("{}", RpcError::NoResponse), "no RPC response");
+    assert_eq!(format!("{}", RpcError::TimedOut), "RPC timed out");
+    assert_eq!(format!("{}", RpcError::Cancelled), "RPC cancelled");
+}
+
+#[test]
+fn down_reason_format() {
+    assert_eq!(DownReason::Normal.to_string(), "stopped normally");
+    assert_eq!(DownReason::Error.to_string(), "stopped after an error");
+    assert_eq!(
+        DownReason::Panic("oops".to_owned()).to_string(),
+        "stopped after panicking: oops"
+    );
+    assert_eq!(DownReason::Restarted.to_string(), "restarted");
+    assert_eq!(DownReason::Stopped.to_string(), "stopped");
+    assert_eq!(DownReason::NotRunning.to_string(), "not running");
+}
+
+/// Synchronous actor that returns the first message it receives.
+fn result_actor<RT>(mut ctx: SyncContext<Result<(), ()>, RT>) -> Result<(), ()> {
+    ctx.receive_next().unwrap_or(Ok(()))
+}
+
+#[test]
+fn monitor_actor_stopped_normally() {
+    let (handle, actor_ref) = spawn_sync_actor(
+        |_| SupervisorStrategy::Stop,
+        result_actor as fn(_) -> _,
+        (),
+        (),
+    )
+    .unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    actor_ref.try_send(Ok(())).unwrap();
+    handle.join().unwrap();
+
+    let reason = DownReason::Normal;
+    assert_eq!(probe.received(), [Down { reason }]);
+}
+
+#[test]
+fn monitor_actor_stopped_after_error() {
+    let (handle, actor_ref) = spawn_sync_actor(
+        |_| SupervisorStrategy::Stop,
+        result_actor as fn(_) -> _,
+        (),
+        (),
+    )
+    .unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    actor_ref.try_send(Err(())).unwrap();
+    handle.join().unwrap();
+
+    let reason = DownReason::Error;
+    assert_eq!(probe.received(), [Down { reason }]);
+}
+
+#[test]
+fn monitor_actor_restarted() {
+    let mut restarts_left = 1;
+    let supervisor = move |()| {
+        if restarts_left > 0 {
+            restarts_left -= 1;
+            SupervisorStrategy::Restart(())
+        } else {
+            SupervisorStrategy::Stop
+        }
+    };
+    let (handle, actor_ref) =
+        spawn_sync_actor(supervisor, result_actor as fn(_) -> _, (), ()).unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    actor_ref.try_send(Err(())).unwrap();
+    actor_ref.try_send(Err(())).unwrap();
+    handle.join().unwrap();
+
+    let expected = [
+        Down {
+            reason: DownReason::Restarted,
+        },
+        Down {
+            reason: DownReason::Error,
+        },
+    ];
+    assert_eq!(probe.received(), expected);
+}
+
+#[test]
+fn monitor_actor_not_running() {
+    let (handle, actor_ref) = spawn_sync_actor(
+        |_| SupervisorStrategy::Stop,
+        result_actor as fn(_) -> _,
+        (),
+        (),
+    )
+    .unwrap();
+    actor_ref.try_send(Ok(())).unwrap();
+    handle.join().unwrap();
+
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher);
+
+    let reason = DownReason::NotRunning;
+    assert_eq!(probe.received(), [Down { reason }]);
+}
+
+#[test]
+fn demonitor() {
+    let (handle, actor_ref) = spawn_sync_actor(
+        |_| SupervisorStrategy::Stop,
+        result_actor as fn(_) -> _,
+        (),
+        (),
+    )
+    .unwrap();
+    let (watcher, mut probe) = probe();
+    actor_ref.monitor(watcher.clone());
+    actor_ref.demonitor(&watcher);
+    drop(watcher);
+
+    actor_ref.try_send(Ok(())).unwrap();
+    handle.join().unwrap();
+
+    assert!(probe.received().is_empty());
 }
@@ -10,6 +10,7 @@ use std::task::{self, Poll};
 use std::thread::sleep;
 use std::time::Duration;
 
+use heph::supervisor::Backoff;
 use heph::{actor, restart_supervisor, Actor, NewActor, Supervisor, SupervisorStrategy};
 
 // NOTE: keep in sync with the documentation.
@@ -324,3 +325,205 @@ fn decide_on_second_restart_error() {
     let mut supervisor = Supervisor::new(arg);
     decide_for_restart_second(&NEW_ACTOR, &mut supervisor, ERROR2);
 }
+
+#[test]
+fn no_backoff() {
+    restart_supervisor!(Supervisor, "my actor", bool, 1, Duration::from_secs(60));
+    assert_eq!(Supervisor::BACKOFF, None);
+}
+
+#[test]
+fn backoff_unit_argument() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        (),
+        2,
+        Duration::from_secs(10),
+        backoff(Duration::from_millis(10), 2.0, Duration::from_secs(1), 0.0),
+    );
+    let _supervisor = Supervisor::new();
+    assert_eq!(Supervisor::MAX_RESTARTS, 2);
+    assert_eq!(Supervisor::MAX_DURATION, Duration::from_secs(10));
+    assert_eq!(
+        Supervisor::BACKOFF,
+        Some(Backoff::new(
+            Duration::from_millis(10),
+            2.0,
+            Duration::from_secs(1),
+            0.0
+        ))
+    );
+}
+
+#[test]
+fn backoff_single_argument() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        usize,
+        2,
+        Duration::from_secs(10),
+        backoff(Duration::from_millis(10), 2.0, Duration::from_secs(1), 0.0)
+    );
+    let _supervisor = Supervisor::new(123);
+    assert!(Supervisor::BACKOFF.is_some());
+}
+
+#[test]
+fn backoff_tuple_argument() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        (u8, u16),
+        2,
+        Duration::from_secs(10),
+        backoff(Duration::from_millis(10), 2.0, Duration::from_secs(1), 0.0),
+    );
+    let _supervisor = Supervisor::new(123, 456);
+    assert!(Supervisor::BACKOFF.is_some());
+}
+
+#[test]
+fn backoff_log_extra() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        (u8, u16),
+        2,
+        Duration::from_secs(10),
+        backoff(Duration::from_millis(10), 2.0, Duration::from_secs(1), 0.1),
+        ": log extra: {}, {}",
+        args.0,
+        args.1,
+    );
+    let _supervisor = Supervisor::new(123, 456);
+    assert!(Supervisor::BACKOFF.is_some());
+}
+
+#[test]
+fn decide_backoff() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        bool,
+        3,
+        Duration::from_secs(60),
+        backoff(
+            Duration::from_millis(100),
+            2.0,
+            Duration::from_millis(300),
+            0.0
+        ),
+    );
+
+    let arg = true;
+    let mut supervisor = Supervisor::new(arg);
+
+    let expected = [
+        Duration::from_millis(100),
+        Duration::from_millis(200),
+        Duration::from_millis(300), // Maximum delay.
+    ];
+    for delay in expected {
+        assert_eq!(
+            decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+            SupervisorStrategy::RestartAfter(arg, delay)
+        );
+    }
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::Stop
+    );
+}
+
+#[test]
+fn decide_backoff_max_duration_elapsed() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        bool,
+        2,
+        Duration::from_millis(100),
+        backoff(Duration::from_millis(10), 2.0, Duration::from_secs(1), 0.0),
+    );
+
+    let arg = true;
+    let mut supervisor = Supervisor::new(arg);
+
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(10))
+    );
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(20))
+    );
+
+    // After waiting for a while the delay should be reset.
+    sleep(Supervisor::MAX_DURATION);
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(10))
+    );
+}
+
+#[test]
+fn decide_on_restart_error_backoff() {
+    restart_supervisor!(
+        Supervisor,
+        "my actor",
+        bool,
+        2,
+        Duration::from_secs(60),
+        backoff(Duration::from_millis(10), 3.0, Duration::from_secs(1), 0.0),
+    );
+
+    let arg = true;
+    let mut supervisor = Supervisor::new(arg);
+
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(10))
+    );
+    assert_eq!(
+        decide_for_restart(&NEW_ACTOR, &mut supervisor, ERROR2),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(30))
+    );
+    assert_eq!(
+        decide_for_restart(&NEW_ACTOR, &mut supervisor, ERROR2),
+        SupervisorStrategy::Stop
+    );
+}
+
+#[test]
+fn backoff_delay() {
+    let backoff = Backoff::new(Duration::from_millis(10), 1.5, Duration::from_secs(1), 0.0);
+    assert_eq!(backoff.initial_delay(), Duration::from_millis(10));
+    assert_eq!(backoff.multiplier(), 1.5);
+    assert_eq!(backoff.max_delay(), Duration::from_secs(1));
+    assert_eq!(backoff.jitter(), 0.0);
+
+    assert_eq!(backoff.delay(0), Duration::from_millis(10));
+    assert_eq!(backoff.delay(1), Duration::from_millis(15));
+    assert_eq!(backoff.delay(2), Duration::from_micros(22_500));
+    assert_eq!(backoff.delay(100), Duration::from_secs(1));
+    assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+}
+
+#[test]
+fn backoff_delay_jitter() {
+    let backoff = Backoff::new(Duration::from_millis(100), 2.0, Duration::from_secs(1), 0.5);
+    for attempt in 0..3 {
+        let base = Duration::from_millis(100) * 2u32.pow(attempt);
+        for _ in 0..100 {
+            let delay = backoff.delay(attempt);
+            assert!(delay >= base / 2, "delay too short: {delay:?}");
+            assert!(delay <= base + base / 2, "delay too long: {delay:?}");
+        }
+    }
+    // Jitter shouldn't exceed the maximum delay.
+    for _ in 0..100 {
+        assert!(backoff.delay(10) <= Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,291 @@
+//! Tests for supervision trees.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{self, Poll, Wake};
+use std::time::Duration;
+
+use heph::supervisor::{ChildSupervisor, RestartStrategy, SupervisorTree};
+use heph::{actor, Actor, NewActor, Supervisor, SupervisorStrategy};
+
+const ERROR: &str = "error";
+const MAX_DURATION: Duration = Duration::from_secs(60);
+
+struct NewActorImpl;
+
+impl NewActor for NewActorImpl {
+    type Message = !;
+    type Argument = usize;
+    type Actor = ActorImpl;
+    type Error = !;
+    type RuntimeAccess = ();
+
+    fn new(
+        &mut self,
+        _: actor::Context<Self::Message, Self::RuntimeAccess>,
+        _: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        unimplemented!()
+    }
+}
+
+struct ActorImpl;
+
+impl Actor for ActorImpl {
+    type Error = &'static str;
+
+    fn try_poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        unimplemented!()
+    }
+}
+
+/// Supervisor for `NewActorImpl` that always restarts the actor.
+fn restart(_: &'static str) -> SupervisorStrategy<usize> {
+    SupervisorStrategy::Restart(100)
+}
+
+/// Supervisor for `NewActorImpl` that always stops the actor.
+fn stop(_: &'static str) -> SupervisorStrategy<usize> {
+    SupervisorStrategy::Stop
+}
+
+type Child = ChildSupervisor<fn(&'static str) -> SupervisorStrategy<usize>, usize>;
+
+/// Create a tree with `n` children (using the `restart` supervisor), the
+/// children are started with their index as argument.
+fn tree(strategy: RestartStrategy, max_restarts: usize, n: usize) -> (SupervisorTree, Vec<Child>) {
+    let tree = SupervisorTree::new("test", strategy, max_restarts, MAX_DURATION);
+    let children = (0..n)
+        .map(|i| tree.child(restart as fn(_) -> _, i))
+        .collect();
+    (tree, children)
+}
+
+/// Calls `supervisor.decide` with `NewActorImpl` as `NewActor` implementation.
+fn decide<S: Supervisor<NewActorImpl>>(supervisor: &mut S) -> SupervisorStrategy<usize> {
+    supervisor.decide(ERROR)
+}
+
+/// Calls `supervisor.poll_tree` with `NewActorImpl` as `NewActor`
+/// implementation, returning the number of times the waker was woken.
+fn poll<S: Supervisor<NewActorImpl>>(
+    supervisor: &mut S,
+    waker: &Arc<WakeCounter>,
+) -> Poll<SupervisorStrategy<usize>> {
+    let waker = task::Waker::from(waker.clone());
+    let mut ctx = task::Context::from_waker(&waker);
+    supervisor.poll_tree(&mut ctx)
+}
+
+#[derive(Default)]
+struct WakeCounter(AtomicUsize);
+
+impl WakeCounter {
+    fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Wake for WakeCounter {
+    fn wake(self: Arc<Self>) {
+        let _ = self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn tree_properties() {
+    let (tree, children) = tree(RestartStrategy::OneForAll, 1, 3);
+    assert_eq!(tree.name(), "test");
+    assert_eq!(tree.strategy(), RestartStrategy::OneForAll);
+    assert_eq!(tree.len(), 3);
+    assert!(!tree.is_empty());
+    drop(children);
+    assert_eq!(tree.len(), 0);
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn no_requests() {
+    let (_tree, mut children) = tree(RestartStrategy::OneForAll, 1, 2);
+    let waker = Arc::new(WakeCounter::default());
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+    assert_eq!(waker.count(), 0);
+}
+
+#[test]
+fn one_for_one() {
+    let (_tree, mut children) = tree(RestartStrategy::OneForOne, 1, 3);
+    let waker = Arc::new(WakeCounter::default());
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+
+    assert_eq!(decide(&mut children[1]), SupervisorStrategy::Restart(100));
+    assert_eq!(waker.count(), 0);
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+}
+
+#[test]
+fn one_for_all() {
+    let (_tree, mut children) = tree(RestartStrategy::OneForAll, 1, 3);
+    let waker = Arc::new(WakeCounter::default());
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+
+    assert_eq!(decide(&mut children[1]), SupervisorStrategy::Restart(100));
+    assert_eq!(waker.count(), 2);
+    let expected = [
+        Poll::Ready(SupervisorStrategy::Restart(0)),
+        Poll::Pending,
+        Poll::Ready(SupervisorStrategy::Restart(2)),
+    ];
+    for (child, expected) in children.iter_mut().zip(expected) {
+        assert_eq!(poll(child, &waker), expected);
+    }
+    // Requests are only returned once.
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+}
+
+#[test]
+fn rest_for_one() {
+    let (_tree, mut children) = tree(RestartStrategy::RestForOne, 1, 4);
+    let waker = Arc::new(WakeCounter::default());
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Pending);
+    }
+
+    assert_eq!(decide(&mut children[1]), SupervisorStrategy::Restart(100));
+    assert_eq!(waker.count(), 2);
+    let expected = [
+        Poll::Pending,
+        Poll::Pending,
+        Poll::Ready(SupervisorStrategy::Restart(2)),
+        Poll::Ready(SupervisorStrategy::Restart(3)),
+    ];
+    for (child, expected) in children.iter_mut().zip(expected) {
+        assert_eq!(poll(child, &waker), expected);
+    }
+}
+
+#[test]
+fn restart_uses_latest_argument() {
+    let (_tree, mut children) = tree(RestartStrategy::OneForAll, 2, 2);
+    let waker = Arc::new(WakeCounter::default());
+
+    // First child is restarted with the argument returned by its supervisor.
+    assert_eq!(decide(&mut children[0]), SupervisorStrategy::Restart(100));
+    assert_eq!(poll(&mut children[0], &waker), Poll::Pending);
+    assert_eq!(
+        poll(&mut children[1], &waker),
+        Poll::Ready(SupervisorStrategy::Restart(1))
+    );
+
+    // Second child failing should restart the first using the latest
+    // argument.
+    assert_eq!(decide(&mut children[1]), SupervisorStrategy::Restart(100));
+    assert_eq!(
+        poll(&mut children[0], &waker),
+        Poll::Ready(SupervisorStrategy::Restart(100))
+    );
+}
+
+#[test]
+fn supervisor_stops_child() {
+    let tree = SupervisorTree::new("test", RestartStrategy::OneForAll, 1, MAX_DURATION);
+    let mut child1 = tree.child(stop as fn(_) -> _, 0);
+    let mut child2 = tree.child(restart as fn(_) -> _, 1);
+    let waker = Arc::new(WakeCounter::default());
+
+    // If the child's own supervisor stops the actor the tree isn't involved.
+    assert_eq!(decide(&mut child1), SupervisorStrategy::Stop);
+    assert_eq!(poll(&mut child2, &waker), Poll::Pending);
+}
+
+#[test]
+fn restart_limit() {
+    let (tree, mut children) = tree(RestartStrategy::OneForAll, 1, 3);
+    let waker = Arc::new(WakeCounter::default());
+
+    assert_eq!(decide(&mut children[0]), SupervisorStrategy::Restart(100));
+    for child in children.iter_mut().skip(1) {
+        assert!(poll(child, &waker).is_ready());
+    }
+
+    // Restart limit reached, all children should be stopped.
+    assert_eq!(decide(&mut children[0]), SupervisorStrategy::Stop);
+    for child in children.iter_mut().skip(1) {
+        assert_eq!(poll(child, &waker), Poll::Ready(SupervisorStrategy::Stop));
+    }
+
+    drop(children);
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn stop_tree() {
+    let (tree, mut children) = tree(RestartStrategy::OneForOne, 1, 2);
+    let subtree = tree.subtree("subtree", RestartStrategy::OneForOne, 1, MAX_DURATION);
+    let mut child = subtree.child(restart as fn(_) -> _, 0);
+    let waker = Arc::new(WakeCounter::default());
+    assert_eq!(tree.len(), 3);
+
+    tree.stop();
+    for child in children.iter_mut() {
+        assert_eq!(poll(child, &waker), Poll::Ready(SupervisorStrategy::Stop));
+    }
+    assert_eq!(
+        poll(&mut child, &waker),
+        Poll::Ready(SupervisorStrategy::Stop)
+    );
+}
+
+#[test]
+fn escalate_to_parent() {
+    let tree = SupervisorTree::new("parent", RestartStrategy::OneForAll, 1, MAX_DURATION);
+    let mut sibling = tree.child(restart as fn(_) -> _, 0);
+    let subtree = tree.subtree("child", RestartStrategy::OneForOne, 1, MAX_DURATION);
+    let mut child1 = subtree.child(restart as fn(_) -> _, 1);
+    let mut child2 = subtree.child(restart as fn(_) -> _, 2);
+    let waker = Arc::new(WakeCounter::default());
+
+    // Within the subtree's limit, only `child1` is restarted.
+    assert_eq!(decide(&mut child1), SupervisorStrategy::Restart(100));
+    assert_eq!(poll(&mut child2, &waker), Poll::Pending);
+    assert_eq!(poll(&mut sibling, &waker), Poll::Pending);
+
+    // Subtree reached its limit, escalating to the parent, which restarts all
+    // its children (including the entire subtree).
+    assert_eq!(decide(&mut child1), SupervisorStrategy::Restart(100));
+    assert_eq!(
+        poll(&mut sibling, &waker),
+        Poll::Ready(SupervisorStrategy::Restart(0))
+    );
+    assert_eq!(
+        poll(&mut child2, &waker),
+        Poll::Ready(SupervisorStrategy::Restart(2))
+    );
+
+    // The subtree's restart limit is reset after the parent restarted it.
+    assert_eq!(decide(&mut child2), SupervisorStrategy::Restart(100));
+    assert_eq!(poll(&mut child1, &waker), Poll::Pending);
+
+    // Subtree reaches its limit again, but the parent also reached its
+    // limit, stopping everything.
+    assert_eq!(decide(&mut child2), SupervisorStrategy::Stop);
+    assert_eq!(
+        poll(&mut sibling, &waker),
+        Poll::Ready(SupervisorStrategy::Stop)
+    );
+    assert_eq!(
+        poll(&mut child1, &waker),
+        Poll::Ready(SupervisorStrategy::Stop)
+    );
+}
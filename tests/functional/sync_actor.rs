@@ -6,7 +6,7 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use heph::actor::spawn_sync_actor;
-use heph::actor::{RecvError, SyncContext};
+use heph::actor::{NoMessages, RecvError, SyncContext};
 use heph::supervisor::{NoSupervisor, SupervisorStrategy};
 
 #[derive(Clone, Debug)]
@@ -127,6 +127,30 @@ fn context_try_receive_next() {
     handle.join().unwrap();
 }
 
+fn receive_matching_actor<RT>(mut ctx: SyncContext<usize, RT>) {
+    assert_eq!(ctx.receive_matching(|msg| *msg == 3), Ok(3));
+    // The skipped messages should be received in order.
+    assert_eq!(ctx.try_receive_next(), Ok(1));
+    assert_eq!(ctx.receive_next(), Ok(2));
+    assert_eq!(ctx.receive_matching(|_| true), Err(NoMessages));
+    assert_eq!(
+        ctx.try_receive_matching(|_| true),
+        Err(RecvError::Disconnected)
+    );
+}
+
+#[test]
+fn context_receive_matching() {
+    let (handle, actor_ref) =
+        spawn_sync_actor(NoSupervisor, receive_matching_actor as fn(_) -> _, (), ()).unwrap();
+
+    for msg in [1usize, 2, 3] {
+        actor_ref.try_send(msg).unwrap();
+    }
+    drop(actor_ref);
+    handle.join().unwrap();
+}
+
 #[test]
 fn supervision() {
     let (handle, _) =
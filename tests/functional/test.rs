@@ -3,9 +3,11 @@
 use std::mem::size_of;
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use heph::actor::{self, Actor, NewActor};
-use heph::test::{size_of_actor, size_of_actor_val};
+use heph::test::{inject_faults, probe, size_of_actor, size_of_actor_val, MessageFaults};
 
 #[test]
 fn test_size_of_actor() {
@@ -15,7 +17,7 @@ fn test_size_of_actor() {
 
     #[allow(trivial_casts)]
     {
-        assert_eq!(size_of_actor_val(&(actor1 as fn(_) -> _)), 24);
+        assert_eq!(size_of_actor_val(&(actor1 as fn(_) -> _)), 40);
     }
 
     struct Na;
@@ -52,3 +54,135 @@ fn test_size_of_actor() {
     assert_eq!(size_of_actor::<Na>(), 0);
     assert_eq!(size_of_actor_val(&Na), 0);
 }
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+#[test]
+fn probe_expect_message() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    actor_ref.try_send(1usize).unwrap();
+    actor_ref.try_send(2usize).unwrap();
+    assert_eq!(*probe.expect_message(TIMEOUT), 1);
+    assert_eq!(*probe.expect_matching(TIMEOUT, |msg| *msg == 2), 2);
+    assert_eq!(probe.received(), [1, 2]);
+}
+
+#[test]
+fn probe_expect_message_from_other_thread() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        actor_ref.try_send(1usize).unwrap();
+    });
+    assert_eq!(*probe.expect_message(TIMEOUT), 1);
+    handle.join().unwrap();
+}
+
+#[test]
+fn probe_no_capacity_limit() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    for n in 0..100usize {
+        actor_ref.try_send(n).unwrap();
+    }
+    assert_eq!(probe.received(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn probe_expect_no_message() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let start = Instant::now();
+    probe.expect_no_message(Duration::from_millis(10));
+    assert!(start.elapsed() >= Duration::from_millis(10));
+    drop(actor_ref);
+    assert!(probe.received().is_empty());
+}
+
+#[test]
+#[should_panic = "expected no message of type 'usize' within 10ms, but received one"]
+fn probe_expect_no_message_panics() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    actor_ref.try_send(1usize).unwrap();
+    probe.expect_no_message(Duration::from_millis(10));
+}
+
+#[test]
+#[should_panic = "expected a message of type 'usize' within 10ms, but received none"]
+fn probe_expect_message_timeout() {
+    let (_actor_ref, mut probe) = probe::<usize>();
+    let _ = probe.expect_message(Duration::from_millis(10));
+}
+
+#[test]
+#[should_panic = "expected a message of type 'usize', but all actor references are dropped"]
+fn probe_expect_message_disconnected() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    drop(actor_ref);
+    let _ = probe.expect_message(TIMEOUT);
+}
+
+#[test]
+#[should_panic = "received message of type 'usize' doesn't match the predicate"]
+fn probe_expect_matching_no_match() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    actor_ref.try_send(1usize).unwrap();
+    let _ = probe.expect_matching(TIMEOUT, |msg| *msg == 2);
+}
+
+#[test]
+fn inject_faults_loss() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_faults(actor_ref, MessageFaults::new().loss(100));
+    actor_ref.try_send(1usize).unwrap();
+    drop(actor_ref);
+    assert!(probe.received().is_empty());
+}
+
+#[test]
+fn inject_faults_duplicate() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_faults(actor_ref, MessageFaults::new().duplicate(100));
+    actor_ref.try_send(1usize).unwrap();
+    actor_ref.try_send(2usize).unwrap();
+    assert_eq!(probe.received(), [1, 1, 2, 2]);
+}
+
+#[test]
+fn inject_faults_reorder() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_faults(actor_ref, MessageFaults::new().reorder(100));
+    for n in 1..=5usize {
+        actor_ref.try_send(n).unwrap();
+    }
+    assert_eq!(probe.received(), [2, 1, 4, 3]);
+    // The held message is send when the actor reference is dropped.
+    drop(actor_ref);
+    assert_eq!(probe.received(), [2, 1, 4, 3, 5]);
+}
+
+#[test]
+fn inject_faults_delay() {
+    const DELAY: Duration = Duration::from_millis(50);
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_faults(actor_ref, MessageFaults::new().delay(100, DELAY));
+    let start = Instant::now();
+    actor_ref.try_send(1usize).unwrap();
+    probe.expect_no_message(Duration::from_millis(10));
+    assert_eq!(*probe.expect_message(TIMEOUT), 1);
+    assert!(start.elapsed() >= DELAY);
+}
+
+#[test]
+fn inject_faults_send() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_faults(actor_ref, MessageFaults::new().duplicate(100));
+    heph_rt::test::block_on(async move { actor_ref.send(1usize).await.unwrap() });
+    assert_eq!(probe.received(), [1, 1]);
+}
+
+#[test]
+fn message_faults_max_percent() {
+    assert_eq!(
+        MessageFaults::new().loss(200),
+        MessageFaults::new().loss(100)
+    );
+}
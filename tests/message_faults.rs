@@ -0,0 +1,31 @@
+//! Test the [`set_message_faults`] function.
+//!
+//! # Notes
+//!
+//! This function needs to be in it's own binary since `set_message_faults` is
+//! set globally.
+
+use std::time::Duration;
+
+use heph::test::{inject_shared_faults, probe, set_message_faults, MessageFaults};
+
+#[test]
+fn global_message_faults() {
+    let (actor_ref, mut probe) = probe::<usize>();
+    let actor_ref = inject_shared_faults(actor_ref);
+
+    // Without faults all messages should arrive.
+    actor_ref.try_send(1usize).unwrap();
+    assert_eq!(*probe.expect_message(Duration::from_secs(1)), 1);
+
+    // The faults are picked up by existing actor references.
+    set_message_faults(MessageFaults::new().duplicate(100));
+    actor_ref.try_send(2usize).unwrap();
+    set_message_faults(MessageFaults::new().loss(100));
+    actor_ref.try_send(3usize).unwrap();
+    set_message_faults(MessageFaults::new());
+    actor_ref.try_send(4usize).unwrap();
+
+    drop(actor_ref);
+    assert_eq!(probe.received(), [1, 2, 2, 4]);
+}